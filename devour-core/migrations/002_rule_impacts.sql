@@ -0,0 +1,17 @@
+-- Measured distortion impact of rewrite rules, accumulated over runs
+
+CREATE TABLE IF NOT EXISTS rule_impacts (
+    rule TEXT NOT NULL PRIMARY KEY,
+    runs INTEGER NOT NULL,
+    genes INTEGER NOT NULL,
+    rewritten INTEGER NOT NULL,
+    -- Sums of (after - before) per run
+    unpredictability REAL NOT NULL,
+    instability REAL NOT NULL,
+    nonlocality REAL NOT NULL,
+    redundancy REAL NOT NULL,
+    reduction REAL NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_rule_impacts_reduction ON rule_impacts(reduction);
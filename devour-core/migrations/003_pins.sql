@@ -0,0 +1,6 @@
+-- Objects pinned by hand (devour pin <cid>) - gc roots
+
+CREATE TABLE IF NOT EXISTS pins (
+    cid TEXT NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
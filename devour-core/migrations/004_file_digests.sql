@@ -0,0 +1,39 @@
+-- Incremental digest: what each file of a source digested to last time
+
+CREATE TABLE IF NOT EXISTS file_digests (
+    source TEXT NOT NULL,              -- Provenance without the commit
+    path TEXT NOT NULL,
+    hash TEXT NOT NULL,                -- blake3 of the file bytes
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (source, path)
+);
+
+-- One gene per function: a file yields many souls
+CREATE TABLE IF NOT EXISTS file_genes (
+    source TEXT NOT NULL,
+    path TEXT NOT NULL,
+    soul TEXT NOT NULL,
+    PRIMARY KEY (source, path, soul)
+);
+
+CREATE INDEX idx_file_genes_soul ON file_genes(soul);
+
+-- Genes whose every file disappeared
+CREATE TABLE IF NOT EXISTS tombstones (
+    soul TEXT NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Source objects already digested
+CREATE TABLE IF NOT EXISTS digested_sources (
+    cid TEXT NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Exported function name and where it sits
+ALTER TABLE genes ADD COLUMN name TEXT;
+ALTER TABLE genes ADD COLUMN file TEXT;
+ALTER TABLE genes ADD COLUMN start_line INTEGER;
+ALTER TABLE genes ADD COLUMN end_line INTEGER;
+
+CREATE INDEX idx_genes_name ON genes(name);
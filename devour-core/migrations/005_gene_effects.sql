@@ -0,0 +1,2 @@
+-- Effect set per gene, as a JSON list; purity is derived from it
+ALTER TABLE genes ADD COLUMN effects TEXT;
@@ -2,8 +2,13 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
+use lambda_core::protein::ProteinVector;
+
 use crate::storage::Store;
 
+/// Proteins closer than this are near-duplicates
+pub const NEAR_DUPLICATE: f32 = 0.95;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AlignmentRules {
     pub mappings: HashMap<String, EquivalenceClass>,
@@ -50,6 +55,17 @@ pub async fn align(store: &Store, rules_path: Option<&str>) -> Result<usize> {
         }
     }
     
+    // Near-duplicates by protein vector
+    let corpus: Vec<(String, String)> = genes.iter()
+        .map(|(soul, gene)| (soul.clone(), gene.ir.clone()))
+        .collect();
+    for (soul1, soul2, similarity) in near_duplicates(&corpus, NEAR_DUPLICATE) {
+        let (lo, hi) = if soul1 < soul2 { (soul1, soul2) } else { (soul2, soul1) };
+        if classes.entry(lo.clone()).or_default().insert(hi.clone()) {
+            store.add_equivalence(&lo, &hi, similarity).await?;
+        }
+    }
+    
     // Merge transitive equivalences
     let merged = merge_equivalence_classes(classes);
     
@@ -74,11 +90,7 @@ fn are_equivalent(gene1: &GeneInfo, gene2: &GeneInfo, rules: &AlignmentRules) ->
         return true;
     }
     
-    // Check IR similarity
-    if ir_similarity(&gene1.ir, &gene2.ir) > 0.95 {
-        return true;
-    }
-    
+    // IR similarity is handled by the near-duplicate pass
     false
 }
 
@@ -143,24 +155,14 @@ fn normalize_type(typ: &str) -> String {
         .replace("Function", "->")
 }
 
-/// Calculate IR similarity
+/// Calculate IR similarity - cosine of protein vectors
 fn ir_similarity(ir1: &str, ir2: &str) -> f32 {
-    // Normalize IRs
     let norm1 = normalize_ir(ir1);
     let norm2 = normalize_ir(ir2);
     
     if norm1 == norm2 { return 1.0; }
     
-    // Token-based similarity
-    let tokens1: HashSet<&str> = norm1.split_whitespace().collect();
-    let tokens2: HashSet<&str> = norm2.split_whitespace().collect();
-    
-    let intersection = tokens1.intersection(&tokens2).count();
-    let union = tokens1.union(&tokens2).count();
-    
-    if union == 0 { return 0.0; }
-    
-    intersection as f32 / union as f32
+    ProteinVector::from_sexpr(&norm1).cosine(&ProteinVector::from_sexpr(&norm2))
 }
 
 fn normalize_ir(ir: &str) -> String {
@@ -171,6 +173,27 @@ fn normalize_ir(ir: &str) -> String {
         .join(" ")
 }
 
+/// Near-duplicate gene search: pairs of souls whose proteins are within `threshold`
+pub fn near_duplicates(genes: &[(String, String)], threshold: f32) -> Vec<(String, String, f32)> {
+    let proteins: Vec<(&String, ProteinVector)> = genes.iter()
+        .map(|(soul, ir)| (soul, ProteinVector::from_sexpr(&normalize_ir(ir))))
+        .collect();
+    
+    let mut pairs = Vec::new();
+    for (i, (soul1, p1)) in proteins.iter().enumerate() {
+        for (soul2, p2) in &proteins[i + 1..] {
+            let similarity = p1.cosine(p2);
+            if soul1 != soul2 && similarity >= threshold {
+                pairs.push(((*soul1).clone(), (*soul2).clone(), similarity));
+            }
+        }
+    }
+    
+    // Most similar first, ties by soul for a stable order
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+    pairs
+}
+
 /// Merge transitive equivalence classes
 fn merge_equivalence_classes(classes: HashMap<String, HashSet<String>>) -> Vec<HashSet<String>> {
     let mut merged = Vec::new();
@@ -234,17 +257,26 @@ struct GeneInfo {
 }
 
 async fn get_all_genes(store: &Store) -> Result<HashMap<String, GeneInfo>> {
-    // Query all genes from store
-    // Simplified - would query the database
-    Ok(HashMap::new())
+    let mut genes = HashMap::new();
+    for (soul, _) in store.gene_cids().await? {
+        if let Some(gene) = store.get_gene(&soul).await? {
+            genes.insert(soul.clone(), GeneInfo {
+                soul,
+                name: gene.name,
+                signature: gene.signatures.first().cloned().unwrap_or_default(),
+                ir: gene.ir,
+            });
+        }
+    }
+    Ok(genes)
 }
 
-fn load_rules(path: &str) -> Result<AlignmentRules> {
+pub(crate) fn load_rules(path: &str) -> Result<AlignmentRules> {
     let content = std::fs::read_to_string(path)?;
     Ok(serde_yaml::from_str(&content)?)
 }
 
-fn default_rules() -> AlignmentRules {
+pub(crate) fn default_rules() -> AlignmentRules {
     AlignmentRules {
         mappings: vec![
             ("map", vec!["map", "collect", "transform"], vec!["map"], vec!["map", "collect"]),
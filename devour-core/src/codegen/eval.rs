@@ -0,0 +1,224 @@
+// IR interpreter the backends' tests run generated code through
+//
+// Call-by-value over JSON-shaped values: vectors go in and come out as
+// `serde_json::Value`, functions in a vector are JS sources extracted
+// first. Fuel bounds recursion.
+
+use anyhow::{bail, Result};
+use serde_json::Value as Json;
+
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+const FUEL: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Num(i64),
+    Bool(bool),
+    Str(String),
+    List(Vec<Value>),
+    Fn(Box<Func>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Func {
+    Lam(String, IR, Vec<(String, Value)>),
+    Id,
+    Const(Value),
+    /// Applies the second first
+    Compose(Value, Value),
+    Pipe(Value, Value),
+}
+
+impl Value {
+    /// Integers, booleans, strings and arrays of them - `None` for anything else
+    pub fn from_json(json: &Json) -> Option<Value> {
+        Some(match json {
+            Json::Number(n) => Value::Num(n.as_i64()?),
+            Json::Bool(b) => Value::Bool(*b),
+            Json::String(s) => Value::Str(s.clone()),
+            Json::Array(items) => Value::List(items.iter().map(Value::from_json).collect::<Option<_>>()?),
+            _ => return None,
+        })
+    }
+
+    /// A function from JS source, e.g. `x => x * 2` - `None` when it doesn't lower
+    pub fn from_js(source: &str) -> Option<Value> {
+        let function = crate::extract::js::extract(&format!("export const f = {};", source)).ok()?.pop()?;
+        let ir = crate::extract::decode(&function.ir)?;
+        if opaque(&ir) {
+            return None;
+        }
+        Interpreter::new(&ir).eval(&ir, &mut vec![]).ok()
+    }
+
+    fn truthy(&self) -> Result<bool> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            Value::Num(n) => Ok(*n != 0),
+            v => bail!("{:?} as a condition", v),
+        }
+    }
+}
+
+fn opaque(ir: &IR) -> bool {
+    matches!(ir, IR::Opaque(_)) || ir.children().into_iter().any(opaque)
+}
+
+/// `gene` applied to `args`
+pub fn run(gene: &IR, args: Vec<Value>) -> Result<Value> {
+    let mut interpreter = Interpreter::new(gene);
+    let mut f = interpreter.eval(gene, &mut vec![])?;
+    for arg in args {
+        f = interpreter.apply(f, arg)?;
+    }
+    Ok(f)
+}
+
+struct Interpreter<'a> {
+    /// What `rec` refers to
+    gene: &'a IR,
+    fuel: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(gene: &'a IR) -> Self {
+        Interpreter { gene, fuel: FUEL }
+    }
+
+    fn eval(&mut self, ir: &IR, env: &mut Vec<(String, Value)>) -> Result<Value> {
+        if self.fuel == 0 {
+            bail!("out of fuel");
+        }
+        self.fuel -= 1;
+
+        Ok(match ir {
+            IR::Var(x) => match env.iter().rev().find(|(name, _)| name == x) {
+                Some((_, v)) => v.clone(),
+                None if x == SELF => {
+                    let gene = self.gene;
+                    self.eval(gene, &mut vec![])?
+                }
+                None => bail!("free variable `{}`", x),
+            },
+            IR::Lam(x, body) => Value::Fn(Box::new(Func::Lam(x.clone(), (**body).clone(), env.clone()))),
+            IR::App(f, x) => {
+                let f = self.eval(f, env)?;
+                let x = self.eval(x, env)?;
+                self.apply(f, x)?
+            }
+            IR::Map(xs, f) => {
+                let f = self.eval(f, env)?;
+                let mut out = vec![];
+                for x in self.list(xs, env)? {
+                    out.push(self.apply(f.clone(), x)?);
+                }
+                Value::List(out)
+            }
+            IR::Filter(xs, p) => {
+                let p = self.eval(p, env)?;
+                let mut out = vec![];
+                for x in self.list(xs, env)? {
+                    if self.apply(p.clone(), x.clone())?.truthy()? {
+                        out.push(x);
+                    }
+                }
+                Value::List(out)
+            }
+            IR::Focus(xs, w, inside, outside) => {
+                let w = self.eval(w, env)?;
+                let inside = self.eval(inside, env)?;
+                let outside = match **outside {
+                    IR::Drop => None,
+                    _ => Some(self.eval(outside, env)?),
+                };
+                let mut out = vec![];
+                for x in self.list(xs, env)? {
+                    if self.apply(w.clone(), x.clone())?.truthy()? {
+                        out.push(self.apply(inside.clone(), x)?);
+                    } else if let Some(outside) = &outside {
+                        out.push(self.apply(outside.clone(), x)?);
+                    }
+                }
+                Value::List(out)
+            }
+            IR::Reduce(xs, f, init) => {
+                let f = self.eval(f, env)?;
+                let mut acc = self.eval(init, env)?;
+                for x in self.list(xs, env)? {
+                    let partial = self.apply(f.clone(), acc)?;
+                    acc = self.apply(partial, x)?;
+                }
+                acc
+            }
+            IR::Nil => Value::List(vec![]),
+            IR::Cons(h, t) => {
+                let mut items = vec![self.eval(h, env)?];
+                items.extend(self.list(t, env)?);
+                Value::List(items)
+            }
+            IR::Num(n) => Value::Num(*n),
+            IR::Bool(b) => Value::Bool(*b),
+            IR::Str(s) => Value::Str(s.clone()),
+            IR::If(c, t, e) => {
+                if self.eval(c, env)?.truthy()? {
+                    self.eval(t, env)?
+                } else {
+                    self.eval(e, env)?
+                }
+            }
+            IR::Eq(a, b) => Value::Bool(self.eval(a, env)? == self.eval(b, env)?),
+            IR::Add(a, b) | IR::Sub(a, b) | IR::Mul(a, b) | IR::Div(a, b) | IR::Mod(a, b) | IR::Lt(a, b) | IR::Le(a, b) => {
+                match (ir, self.eval(a, env)?, self.eval(b, env)?) {
+                    (IR::Add(..), Value::Str(a), Value::Str(b)) => Value::Str(a + &b),
+                    (_, Value::Num(a), Value::Num(b)) => match ir {
+                        IR::Add(..) => Value::Num(a.wrapping_add(b)),
+                        IR::Sub(..) => Value::Num(a.wrapping_sub(b)),
+                        IR::Mul(..) => Value::Num(a.wrapping_mul(b)),
+                        IR::Div(..) if b == 0 => bail!("division by zero"),
+                        IR::Div(..) => Value::Num(a / b),
+                        IR::Mod(..) if b == 0 => bail!("division by zero"),
+                        IR::Mod(..) => Value::Num(a % b),
+                        IR::Lt(..) => Value::Bool(a < b),
+                        _ => Value::Bool(a <= b),
+                    },
+                    (ir, a, b) => bail!("`{}` on {:?} and {:?}", ir.head(), a, b),
+                }
+            }
+            IR::Id => Value::Fn(Box::new(Func::Id)),
+            IR::Const(x) => Value::Fn(Box::new(Func::Const(self.eval(x, env)?))),
+            IR::Compose(f, g) => Value::Fn(Box::new(Func::Compose(self.eval(f, env)?, self.eval(g, env)?))),
+            IR::Pipe(f, g) => Value::Fn(Box::new(Func::Pipe(self.eval(f, env)?, self.eval(g, env)?))),
+            IR::Drop => bail!("drop outside a focus"),
+            IR::Opaque(reason) => bail!("opaque construct: {}", reason),
+        })
+    }
+
+    fn list(&mut self, ir: &IR, env: &mut Vec<(String, Value)>) -> Result<Vec<Value>> {
+        match self.eval(ir, env)? {
+            Value::List(items) => Ok(items),
+            v => bail!("{:?} as a list", v),
+        }
+    }
+
+    fn apply(&mut self, f: Value, arg: Value) -> Result<Value> {
+        let Value::Fn(f) = f else { bail!("{:?} applied", f) };
+        match *f {
+            Func::Lam(x, body, mut env) => {
+                env.push((x, arg));
+                self.eval(&body, &mut env)
+            }
+            Func::Id => Ok(arg),
+            Func::Const(v) => Ok(v),
+            Func::Compose(f, g) => {
+                let inner = self.apply(g, arg)?;
+                self.apply(f, inner)
+            }
+            Func::Pipe(f, g) => {
+                let inner = self.apply(f, arg)?;
+                self.apply(g, inner)
+            }
+        }
+    }
+}
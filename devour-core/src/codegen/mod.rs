@@ -0,0 +1,173 @@
+// Code generators: champion IR to the bodies forge writes per target
+
+use std::collections::HashSet;
+
+use crate::surgeon::egraph::IR;
+
+pub mod python;
+pub mod rust;
+pub mod signature;
+pub mod typescript;
+pub mod wasm;
+
+#[cfg(test)]
+mod eval;
+
+/// A gene to generate, under the name it's exported as
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub name: String,
+    pub soul: String,
+    pub ir: IR,
+}
+
+/// Exports of `genes`, decoded - textual IR has nothing to generate from,
+/// and a name taken by an earlier gene gets its soul appended
+pub fn exports<'a>(genes: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>) -> Vec<Export> {
+    let mut exports: Vec<Export> = vec![];
+    for (name, soul, ir) in genes {
+        let Some(ir) = crate::extract::decode(ir) else {
+            tracing::warn!("Skipping {}: textual IR", name);
+            continue;
+        };
+        let name = if exports.iter().any(|e| e.name == name) {
+            format!("{}_{}", name, soul.trim_start_matches('λ'))
+        } else {
+            name.to_string()
+        };
+        exports.push(Export { name, soul: soul.to_string(), ir });
+    }
+    exports
+}
+
+/// Leading lambdas' parameters and what's under them
+pub fn params(ir: &IR) -> (Vec<&str>, &IR) {
+    let mut names = vec![];
+    let mut body = ir;
+    while let IR::Lam(x, inner) = body {
+        names.push(x.as_str());
+        body = inner;
+    }
+    (names, body)
+}
+
+/// `ir` with free `from` renamed to `to` - `to` must not be bound inside
+pub fn rename(ir: &IR, from: &str, to: &str) -> IR {
+    match ir {
+        IR::Var(x) if x == from => IR::Var(to.to_string()),
+        IR::Lam(x, _) if x == from => ir.clone(),
+        _ => ir.map_children(|child| rename(child, from, to)),
+    }
+}
+
+fn mentions(ir: &IR, name: &str) -> bool {
+    match ir {
+        IR::Var(x) | IR::Lam(x, _) if x == name => true,
+        _ => ir.children().into_iter().any(|child| mentions(child, name)),
+    }
+}
+
+/// Every variable and binder in `ir` - fresh names avoid these
+pub fn names(ir: &IR) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut stack = vec![ir];
+    while let Some(ir) = stack.pop() {
+        if let IR::Var(x) | IR::Lam(x, _) = ir {
+            names.insert(x.clone());
+        }
+        stack.extend(ir.children());
+    }
+    names
+}
+
+/// Parameter every lambda among `fs` binds, when a loop over `xs` can bind it
+/// once for all of them - nothing else may mention it
+pub fn shared_param<'a>(xs: &IR, fs: &[&'a IR]) -> Option<&'a str> {
+    let params: Vec<&str> = fs.iter().filter_map(|f| match f {
+        IR::Lam(p, _) => Some(p.as_str()),
+        _ => None,
+    }).collect();
+    let p = *params.first()?;
+    let others_free = fs.iter().all(|f| matches!(f, IR::Lam(..)) || !mentions(f, p));
+    let shared = p != "_" && params.iter().all(|q| *q == p) && !mentions(xs, p) && others_free;
+    shared.then_some(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::Value as Json;
+
+    use super::*;
+    use crate::codegen::eval::{self, Value};
+    use crate::extract::js;
+    use crate::runtime::ExtractedFunction;
+
+    /// A language's extractor - what each backend reads its output back with
+    pub type Extract = fn(&str) -> Result<Vec<ExtractedFunction>>;
+
+    /// A backend's generator, one gene to one module
+    pub type Module = fn(&Export) -> Result<String>;
+
+    /// Every source backend with the extractor for what it writes
+    const BACKENDS: [(&str, Module, Extract); 3] = [
+        ("typescript", typescript::module, js::extract),
+        ("python", python::module, crate::extract::python::extract),
+        ("rust", |export| rust::module(export, None), crate::extract::rust::extract),
+    ];
+
+    /// First function of `source`, under its own name
+    pub fn gene(extract: Extract, source: &str) -> Export {
+        let function = extract(source).unwrap().remove(0);
+        Export { name: function.name, soul: "λ0".to_string(), ir: crate::extract::decode(&function.ir).unwrap() }
+    }
+
+    /// `export` as `module` generates it and `extract` reads it back
+    pub fn round_trip(module: Module, extract: Extract, export: &Export) -> IR {
+        gene(extract, &module(export).unwrap()).ir
+    }
+
+    pub fn map() -> Export {
+        gene(js::extract, "export function map(xs, f) { return xs.map(f); }")
+    }
+
+    pub fn map_vectors() -> Json {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../genes/map/tests/vectors.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// A binding, a filter, a map and a fold - 10 for `score_args`
+    pub fn score() -> Export {
+        gene(js::extract, "export function score(xs, k) { const ys = xs.filter(x => x > 0).map(x => x * k); return ys.reduce((a, y) => a + y, 0); }")
+    }
+
+    pub fn score_args() -> Vec<Value> {
+        vec![Value::List(vec![Value::Num(-1), Value::Num(2), Value::Num(3)]), Value::Num(2)]
+    }
+
+    #[test]
+    fn test_map_vectors_through_the_interpreter() {
+        for (backend, module, extract) in BACKENDS {
+            let generated = round_trip(module, extract, &map());
+            let mut ran = 0;
+            for vector in map_vectors()["vectors"].as_array().unwrap() {
+                let xs = Value::from_json(&vector["input"]["xs"]);
+                let f = Value::from_js(vector["input"]["f"].as_str().unwrap());
+                let (Some(xs), Some(f), Some(expected)) = (xs, f, Value::from_json(&vector["output"])) else {
+                    continue;
+                };
+                assert_eq!(eval::run(&generated, vec![xs, f]).ok(), Some(expected), "{} {}", backend, vector["name"]);
+                ran += 1;
+            }
+            assert!(ran >= 3, "{}", backend);
+        }
+    }
+
+    #[test]
+    fn test_score_through_the_interpreter() {
+        for (backend, module, extract) in BACKENDS {
+            let generated = round_trip(module, extract, &score());
+            assert_eq!(eval::run(&generated, score_args()).unwrap(), Value::Num(10), "{}", backend);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+// Python backend - champion IR as a typed Python module
+//
+// Traversals are comprehensions - a hard focus is `[f for x in xs if w]`, or
+// a conditional element when it keeps both sides - folds go through
+// `functools.reduce`, and a binding the extractor lowered to `(λx.rest) e`
+// comes back as an assignment. Hints come from the inferred signature and
+// are left out where there is none. Output depends on the IR alone: one
+// layout, imports sorted, identical IR prints identical bytes.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use super::signature::{self, generic, Signature, Type};
+use super::{names, params, rename, shared_param, Export};
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+// Binding strength, loosest first
+const LAMBDA: u8 = 1;
+const CONDITIONAL: u8 = 2;
+const OR: u8 = 3;
+const COMPARISON: u8 = 6;
+const ADDITIVE: u8 = 10;
+const MULTIPLICATIVE: u8 = 11;
+const UNARY: u8 = 12;
+const PRIMARY: u8 = 14;
+const ATOM: u8 = 15;
+
+/// `<name>.py` for one gene
+pub fn module(export: &Export) -> Result<String> {
+    let signature = signature::infer(&export.ir);
+    let (names, body) = params(&export.ir);
+    let mut printer = Printer::new(export, &names);
+    let mut body_lines = String::new();
+    printer.statements(body, &names, &mut body_lines)?;
+
+    let mut imports = vec![];
+    if printer.reduces {
+        imports.push("import functools".to_string());
+    }
+    let typing: Vec<&str> = match &signature {
+        Some(s) => [("Callable", s.params.iter().map(|(_, t)| t).chain([&s.result]).any(callable)), ("TypeVar", s.generics > 0)]
+            .into_iter()
+            .filter_map(|(name, used)| used.then_some(name))
+            .collect(),
+        None => vec![],
+    };
+    if !typing.is_empty() {
+        imports.push(format!("from typing import {}", typing.join(", ")));
+    }
+
+    let mut out = format!("# Gene: {}\n# Soul: {}\n", export.name, export.soul);
+    if !imports.is_empty() {
+        out.push_str(&format!("\n{}\n", imports.join("\n")));
+    }
+    if let Some(s) = signature.as_ref().filter(|s| s.generics > 0) {
+        out.push('\n');
+        for name in s.generic_names() {
+            out.push_str(&format!("{} = TypeVar(\"{}\")\n", name, name));
+        }
+    }
+    out.push_str(&format!("\n\ndef {}{}:\n", ident(&export.name), head(&names, &signature)));
+    out.push_str(&body_lines);
+    Ok(out)
+}
+
+fn callable(ty: &Type) -> bool {
+    match ty {
+        Type::Fn(..) => true,
+        Type::List(item) => callable(item),
+        _ => false,
+    }
+}
+
+/// Parameters and return hint
+fn head(names: &[&str], signature: &Option<Signature>) -> String {
+    match signature {
+        _ if names == ["_"] => match signature {
+            Some(s) => format!("() -> {}", hint(&s.result)),
+            None => "()".to_string(),
+        },
+        Some(s) => {
+            let params: Vec<String> = s.params.iter().map(|(name, ty)| format!("{}: {}", ident(name), hint(ty))).collect();
+            format!("({}) -> {}", params.join(", "), hint(&s.result))
+        }
+        None => format!("({})", names.iter().map(|name| ident(name)).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn hint(ty: &Type) -> String {
+    match ty {
+        Type::Num => "int".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Str => "str".to_string(),
+        Type::List(item) => format!("list[{}]", hint(item)),
+        Type::Fn(param, result) => format!("Callable[[{}], {}]", hint(param), hint(result)),
+        Type::Var(v) => generic(*v),
+    }
+}
+
+/// Python keywords get a trailing underscore
+pub fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def",
+        "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+    ];
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Parenthesized when it binds looser than its position needs
+fn wrap(code: String, own: u8, prec: u8) -> String {
+    if own < prec {
+        format!("({})", code)
+    } else {
+        code
+    }
+}
+
+struct Printer {
+    name: String,
+    /// Parameters of the gene, 1 for a nullary gene taking `_`
+    arity: usize,
+    nullary: bool,
+    /// Every name in the gene - fresh ones avoid these
+    taken: HashSet<String>,
+    /// Whether the body needs `functools`
+    reduces: bool,
+}
+
+impl Printer {
+    fn new(export: &Export, params: &[&str]) -> Self {
+        let mut taken = names(&export.ir);
+        taken.insert(export.name.clone());
+        Printer { name: ident(&export.name), arity: params.len(), nullary: params == ["_"], taken, reduces: false }
+    }
+
+    fn fresh(&mut self, base: &str) -> String {
+        let name = (1..).map(|i| if i == 1 { base.to_string() } else { format!("{}{}", base, i) })
+            .find(|name| !self.taken.contains(name))
+            .unwrap();
+        self.taken.insert(name.clone());
+        name
+    }
+
+    /// Function body: leading bindings as assignments, then the return
+    fn statements(&mut self, mut body: &IR, params: &[&str], out: &mut String) -> Result<()> {
+        let mut declared: HashSet<&str> = params.iter().copied().collect();
+        while let IR::App(f, value) = body {
+            let IR::Lam(x, rest) = &**f else { break };
+            // Rebinding would be seen by closures made earlier
+            if x == "_" || !declared.insert(x) {
+                break;
+            }
+            out.push_str(&format!("    {} = {}\n", ident(x), self.expr(value, LAMBDA)?));
+            body = rest;
+        }
+        out.push_str(&format!("    return {}\n", self.expr(body, LAMBDA)?));
+        Ok(())
+    }
+
+    /// Name a comprehension binds - the lambdas' own when they agree
+    fn element(&mut self, xs: &IR, fs: &[&IR]) -> String {
+        match shared_param(xs, fs) {
+            Some(p) => p.to_string(),
+            None => self.fresh("x"),
+        }
+    }
+
+    /// `f` applied to the variable `x`, a lambda's body inlined
+    fn applied(&mut self, f: &IR, x: &str, prec: u8) -> Result<String> {
+        match f {
+            IR::Lam(p, body) if p == x => self.expr(body, prec),
+            IR::Lam(p, body) => self.expr(&rename(body, p, x), prec),
+            _ => {
+                let code = format!("{}({})", self.expr(f, PRIMARY)?, ident(x));
+                Ok(wrap(code, PRIMARY, prec))
+            }
+        }
+    }
+
+    /// `ir` as an expression binding at least as tight as `prec`
+    fn expr(&mut self, ir: &IR, prec: u8) -> Result<String> {
+        let (code, own) = self.print(ir)?;
+        Ok(wrap(code, own, prec))
+    }
+
+    fn print(&mut self, ir: &IR) -> Result<(String, u8)> {
+        Ok(match ir {
+            IR::Var(x) if x == SELF => {
+                if self.arity != 1 || self.nullary {
+                    bail!("`{}` used as a value, takes {} arguments", SELF, self.arity);
+                }
+                (self.name.clone(), ATOM)
+            }
+            IR::Var(x) => (ident(x), ATOM),
+            IR::Num(n) if *n < 0 => (n.to_string(), UNARY),
+            IR::Num(n) => (n.to_string(), ATOM),
+            IR::Bool(true) => ("True".to_string(), ATOM),
+            IR::Bool(false) => ("False".to_string(), ATOM),
+            IR::Str(s) => (serde_json::to_string(s)?, ATOM),
+            IR::Nil => ("[]".to_string(), ATOM),
+            IR::Cons(..) => {
+                let mut items = vec![];
+                let mut rest = ir;
+                while let IR::Cons(h, t) = rest {
+                    items.push(self.expr(h, LAMBDA)?);
+                    rest = t;
+                }
+                if !matches!(rest, IR::Nil) {
+                    items.push(format!("*{}", self.expr(rest, OR)?));
+                }
+                (format!("[{}]", items.join(", ")), ATOM)
+            }
+            IR::Lam(x, body) => (format!("lambda {}: {}", ident(x), self.expr(body, LAMBDA)?), LAMBDA),
+            IR::App(..) => self.call(ir)?,
+            IR::If(c, t, e) => {
+                let code = format!("{} if {} else {}", self.expr(t, OR)?, self.expr(c, OR)?, self.expr(e, CONDITIONAL)?);
+                (code, CONDITIONAL)
+            }
+            IR::Add(a, b) => self.binary(a, "+", b, ADDITIVE)?,
+            IR::Sub(a, b) => self.binary(a, "-", b, ADDITIVE)?,
+            IR::Mul(a, b) => self.binary(a, "*", b, MULTIPLICATIVE)?,
+            IR::Div(a, b) => self.binary(a, "//", b, MULTIPLICATIVE)?,
+            IR::Mod(a, b) => self.binary(a, "%", b, MULTIPLICATIVE)?,
+            // Comparisons chain in Python, so neither side may be one
+            IR::Eq(a, b) => self.binary(a, "==", b, COMPARISON)?,
+            IR::Lt(a, b) => self.binary(a, "<", b, COMPARISON)?,
+            IR::Le(a, b) => self.binary(a, "<=", b, COMPARISON)?,
+            IR::Map(xs, f) => {
+                let x = self.element(xs, &[f]);
+                let element = self.applied(f, &x, LAMBDA)?;
+                (format!("[{} for {} in {}]", element, ident(&x), self.expr(xs, OR)?), ATOM)
+            }
+            IR::Filter(xs, p) => {
+                let x = self.element(xs, &[p]);
+                let cond = self.applied(p, &x, OR)?;
+                (format!("[{} for {} in {} if {}]", ident(&x), ident(&x), self.expr(xs, OR)?, cond), ATOM)
+            }
+            IR::Focus(xs, w, inside, outside) => {
+                let x = self.element(xs, &[w, inside, outside]);
+                let source = format!("for {} in {}", ident(&x), self.expr(xs, OR)?);
+                let code = match **outside {
+                    IR::Drop => {
+                        let element = self.applied(inside, &x, LAMBDA)?;
+                        format!("[{} {} if {}]", element, source, self.applied(w, &x, OR)?)
+                    }
+                    _ => {
+                        let kept = self.applied(inside, &x, OR)?;
+                        let test = self.applied(w, &x, OR)?;
+                        let rest = self.applied(outside, &x, CONDITIONAL)?;
+                        format!("[{} if {} else {} {}]", kept, test, rest, source)
+                    }
+                };
+                (code, ATOM)
+            }
+            IR::Reduce(xs, f, init) => {
+                self.reduces = true;
+                // The IR's step is curried, reduce passes both at once
+                let step = match f.as_ref() {
+                    IR::Lam(acc, inner) => match inner.as_ref() {
+                        IR::Lam(x, body) if acc != "_" && x != "_" => {
+                            format!("lambda {}, {}: {}", ident(acc), ident(x), self.expr(body, LAMBDA)?)
+                        }
+                        _ => self.curried(f)?,
+                    },
+                    _ => self.curried(f)?,
+                };
+                let code = format!("functools.reduce({}, {}, {})", step, self.expr(xs, LAMBDA)?, self.expr(init, LAMBDA)?);
+                (code, PRIMARY)
+            }
+            IR::Compose(f, g) | IR::Pipe(g, f) => {
+                let x = self.fresh("x");
+                let inner = self.applied(g, &x, LAMBDA)?;
+                (format!("lambda {}: {}({})", x, self.expr(f, PRIMARY)?, inner), LAMBDA)
+            }
+            IR::Id => {
+                let x = self.fresh("x");
+                (format!("lambda {}: {}", x, x), LAMBDA)
+            }
+            IR::Const(c) => (format!("lambda _: {}", self.expr(c, LAMBDA)?), LAMBDA),
+            IR::Drop => bail!("drop outside a focus"),
+            IR::Opaque(reason) => bail!("opaque construct: {}", reason),
+        })
+    }
+
+    fn binary(&mut self, a: &IR, op: &str, b: &IR, prec: u8) -> Result<(String, u8)> {
+        let left = if prec == COMPARISON { prec + 1 } else { prec };
+        Ok((format!("{} {} {}", self.expr(a, left)?, op, self.expr(b, prec + 1)?), prec))
+    }
+
+    /// A reduce step for a function that isn't a two-lambda literal
+    fn curried(&mut self, f: &IR) -> Result<String> {
+        let (acc, x) = (self.fresh("acc"), self.fresh("x"));
+        Ok(format!("lambda {}, {}: {}({})({})", acc, x, self.expr(f, PRIMARY)?, acc, x))
+    }
+
+    /// Curried application - a full call of `rec` calls the gene by name
+    fn call(&mut self, ir: &IR) -> Result<(String, u8)> {
+        let mut args = vec![];
+        let mut head = ir;
+        while let IR::App(f, x) = head {
+            args.push(&**x);
+            head = f;
+        }
+        args.reverse();
+
+        if matches!(head, IR::Var(x) if x == SELF) {
+            if args.len() != self.arity {
+                bail!("`{}` applied to {} arguments, takes {}", SELF, args.len(), self.arity);
+            }
+            if self.nullary {
+                return Ok((format!("{}()", self.name), PRIMARY));
+            }
+            let args = args.into_iter().map(|a| self.expr(a, LAMBDA)).collect::<Result<Vec<_>>>()?;
+            return Ok((format!("{}({})", self.name, args.join(", ")), PRIMARY));
+        }
+
+        let mut code = self.expr(head, PRIMARY)?;
+        for arg in args {
+            code = format!("{}({})", code, self.expr(arg, LAMBDA)?);
+        }
+        Ok((code, PRIMARY))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::eval::{self, Value};
+    use crate::codegen::tests::{gene, round_trip, score};
+    use crate::extract::python;
+
+    #[test]
+    fn test_typed_module_layout() {
+        let map = gene(python::extract, "def map(xs, f):\n    return [f(x) for x in xs]\n");
+        let code = module(&map).unwrap();
+        assert_eq!(
+            code,
+            "# Gene: map\n# Soul: λ0\n\n\
+             from typing import Callable, TypeVar\n\n\
+             A = TypeVar(\"A\")\n\
+             B = TypeVar(\"B\")\n\n\n\
+             def map(xs: list[A], f: Callable[[A], B]) -> list[B]:\n    return [f(x) for x in xs]\n"
+        );
+        assert_eq!(module(&map).unwrap(), code);
+
+        let odd = gene(python::extract, "def odd(xs):\n    return [x for x in xs if x % 2]\n");
+        assert_eq!(module(&odd).unwrap(), "# Gene: odd\n# Soul: λ0\n\n\ndef odd(xs):\n    return [x for x in xs if x % 2]\n");
+    }
+
+    #[test]
+    fn test_focus_comprehensions_and_folds() {
+        let code = module(&score()).unwrap();
+        assert!(code.starts_with("# Gene: score\n# Soul: λ0\n\nimport functools\n\n\ndef score(xs: list[int], k: int) -> int:\n"), "{}", code);
+        assert!(code.ends_with("    return functools.reduce(lambda a, y: a + y, ys, 0)\n"), "{}", code);
+
+        // A comprehension read as a focus prints as one again
+        let scaled = gene(python::extract, "def scaled(xs, k):\n    return [x * k for x in xs if x > 0]\n");
+        assert!(module(&scaled).unwrap().ends_with("    return [x * k for x in xs if 0 < x]\n"));
+
+        // A focus keeping both sides is one conditional element
+        let ir = r#"{"Lam":["xs",{"Focus":[{"Var":"xs"},{"Lam":["x",{"Lt":[{"Var":"x"},{"Num":3}]}]},{"Lam":["x",{"Mul":[{"Var":"x"},{"Num":10}]}]},{"Lam":["x",{"Sub":[{"Num":0},{"Var":"x"}]}]}]}]}"#;
+        let both = Export { name: "both".to_string(), soul: "λ0".to_string(), ir: serde_json::from_str(ir).unwrap() };
+        assert!(module(&both).unwrap().contains("    return [x * 10 if x < 3 else 0 - x for x in xs]\n"));
+        let xs = Value::List(vec![Value::Num(1), Value::Num(5)]);
+        assert_eq!(eval::run(&round_trip(module, python::extract, &both), vec![xs]).unwrap(), Value::List(vec![Value::Num(10), Value::Num(-5)]));
+
+        let fib = gene(python::extract, "def fib(n):\n    return n if n < 2 else fib(n - 1) + fib(n - 2)\n");
+        assert!(module(&fib).unwrap().ends_with("def fib(n: int) -> int:\n    return n if n < 2 else fib(n - 1) + fib(n - 2)\n"));
+        assert_eq!(eval::run(&round_trip(module, python::extract, &fib), vec![Value::Num(15)]).unwrap(), Value::Num(610));
+    }
+}
@@ -0,0 +1,900 @@
+// Rust backend - champion IR as generic Rust functions
+//
+// Traversals are iterator chains: `map`, `filter`, `filter_map` for a hard
+// focus (a conditional `map` when it keeps both sides) and `fold`, collected
+// only where a vector is needed. Every node is typed by signature inference:
+// function parameters become uncurried `Fn` bounds, a value moves on its one
+// use and is cloned otherwise, and `Clone`/`PartialEq` bounds follow from
+// where that happens. A gene without a signature has nothing to generate.
+// Vectors from the gene's vectors.json become `#[test]`s.
+
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value as Json;
+
+use super::signature::{self, generic, Signature, Type, Typing, Unifier};
+use super::{names, params, rename, shared_param, Export};
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+// Binding strength, loosest first
+const CLOSURE: u8 = 1;
+const COMPARISON: u8 = 4;
+const ADDITIVE: u8 = 6;
+const MULTIPLICATIVE: u8 = 7;
+const UNARY: u8 = 8;
+const POSTFIX: u8 = 9;
+const ATOM: u8 = 10;
+
+/// `<name>.rs` for one gene, with tests from its vectors.json when given
+pub fn module(export: &Export, vectors: Option<&Json>) -> Result<String> {
+    let ir = normalize(&export.ir, &mut names(&export.ir));
+    let typing = signature::typing(&ir).context("no signature to type it with")?;
+    let mut out = format!("// Gene: {}\n// Soul: {}\n\n", export.name, export.soul);
+    out.push_str(&Printer::new(Some(&export.name), &ir, &typing).function()?);
+
+    let tests = vectors.map(|vectors| tests(&export.name, &ir, &typing.signature, vectors)).unwrap_or_default();
+    if !tests.is_empty() {
+        out.push_str("\n#[cfg(test)]\nmod tests {\n    use super::*;\n");
+        for test in tests {
+            out.push('\n');
+            out.push_str(&test);
+        }
+        out.push_str("}\n");
+    }
+    Ok(out)
+}
+
+/// Rust keywords get a trailing underscore
+pub fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+        "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static",
+        "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+        "where", "while", "yield",
+    ];
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn fresh(taken: &mut HashSet<String>, base: &str) -> String {
+    let name = (1..).map(|i| if i == 1 { base.to_string() } else { format!("{}{}", base, i) })
+        .find(|name| !taken.contains(name))
+        .unwrap();
+    taken.insert(name.clone());
+    name
+}
+
+/// `f` applied to the variable `x`, a lambda's body renamed
+fn applied(f: &IR, x: &str) -> IR {
+    match f {
+        IR::Lam(p, body) if p == x => (**body).clone(),
+        IR::Lam(p, body) => rename(body, p, x),
+        _ => IR::App(Box::new(f.clone()), Box::new(IR::Var(x.to_string()))),
+    }
+}
+
+/// `ir` with combinators as lambdas, and the callbacks of `filter`, a focus
+/// and `fold` as the lambdas their closures print from - one parameter
+/// shared by a focus's functions, two for a fold step
+fn normalize(ir: &IR, taken: &mut HashSet<String>) -> IR {
+    let ir = ir.map_children(|child| normalize(child, taken));
+    let lam = |x: String, body: IR| IR::Lam(x, Box::new(body));
+    match ir {
+        IR::Compose(f, g) | IR::Pipe(g, f) => {
+            let x = fresh(taken, "x");
+            let inner = applied(&g, &x);
+            lam(x, IR::App(f, Box::new(inner)))
+        }
+        IR::Id => {
+            let x = fresh(taken, "x");
+            lam(x.clone(), IR::Var(x))
+        }
+        IR::Const(c) => lam(fresh(taken, "x"), *c),
+        IR::Filter(xs, p) if !matches!(*p, IR::Lam(..)) => {
+            let x = fresh(taken, "x");
+            let body = applied(&p, &x);
+            IR::Filter(xs, Box::new(lam(x, body)))
+        }
+        IR::Focus(xs, w, inside, outside) => {
+            let mut fs = vec![&*w, &*inside];
+            if !matches!(*outside, IR::Drop) {
+                fs.push(&*outside);
+            }
+            let x = match shared_param(&xs, &fs) {
+                Some(p) => p.to_string(),
+                None => fresh(taken, "x"),
+            };
+            let each = |f: &IR| Box::new(lam(x.clone(), applied(f, &x)));
+            let outside = match *outside {
+                IR::Drop => outside,
+                _ => each(&outside),
+            };
+            IR::Focus(xs, each(&w), each(&inside), outside)
+        }
+        IR::Reduce(xs, f, init) => {
+            let step = match *f {
+                IR::Lam(ref acc, ref inner) => match &**inner {
+                    IR::Lam(x, _) if x != acc || x == "_" => *f,
+                    _ => {
+                        let x = fresh(taken, "x");
+                        lam(acc.clone(), lam(x.clone(), applied(inner, &x)))
+                    }
+                },
+                _ => {
+                    let (acc, x) = (fresh(taken, "acc"), fresh(taken, "x"));
+                    let call = IR::App(Box::new(IR::App(f, Box::new(IR::Var(acc.clone())))), Box::new(IR::Var(x.clone())));
+                    lam(acc, lam(x, call))
+                }
+            };
+            IR::Reduce(xs, Box::new(step), init)
+        }
+        ir => ir,
+    }
+}
+
+/// Free occurrences of a variable
+#[derive(Default)]
+struct Uses {
+    count: usize,
+    /// Whether some are inside a closure
+    captured: bool,
+}
+
+fn uses(ir: &IR, x: &str, uses: &mut Uses, depth: usize) {
+    match ir {
+        IR::Var(y) if y == x => {
+            uses.count += 1;
+            uses.captured |= depth > 0;
+        }
+        IR::Lam(y, _) if y == x => {}
+        // A binding prints as a `let`, not a closure
+        IR::App(f, value) if matches!(&**f, IR::Lam(y, _) if y != "_") => {
+            self::uses(value, x, uses, depth);
+            if let IR::Lam(y, rest) = &**f {
+                if y != x {
+                    self::uses(rest, x, uses, depth);
+                }
+            }
+        }
+        IR::Lam(_, body) => self::uses(body, x, uses, depth + 1),
+        _ => ir.children().into_iter().for_each(|child| self::uses(child, x, uses, depth)),
+    }
+}
+
+/// Whether `x` is passed on to `rec` - a function parameter that is gets
+/// borrowed, or each level of recursion would borrow it once more
+fn passed_on(ir: &IR, x: &str) -> bool {
+    match ir {
+        IR::Lam(y, _) if y == x => false,
+        IR::App(f, arg) if matches!(&**arg, IR::Var(y) if y == x) && matches!(head(f), IR::Var(g) if g == SELF) => true,
+        _ => ir.children().into_iter().any(|child| passed_on(child, x)),
+    }
+}
+
+/// What a curried application applies
+fn head(ir: &IR) -> &IR {
+    match ir {
+        IR::App(f, _) => head(f),
+        _ => ir,
+    }
+}
+
+/// `Fn` bound of a function parameter - its arguments and result
+fn uncurry(ty: &Type) -> (Vec<&Type>, &Type) {
+    let mut args = vec![];
+    let mut rest = ty;
+    while let Type::Fn(arg, result) = rest {
+        args.push(&**arg);
+        rest = result;
+    }
+    (args, rest)
+}
+
+/// `ty` in Rust - generics past the first `generics` are ones nothing
+/// constrains, and `()` stands in for them
+fn rust_type(ty: &Type, generics: u32) -> Result<String> {
+    Ok(match ty {
+        Type::Num => "i64".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Str => "String".to_string(),
+        Type::List(item) => format!("Vec<{}>", rust_type(item, generics)?),
+        Type::Var(v) if *v < generics => generic(*v),
+        Type::Var(_) => "()".to_string(),
+        Type::Fn(..) => bail!("function type outside a parameter"),
+    })
+}
+
+/// Generics `ty` mentions, out of the first `generics`
+fn generics_in(ty: &Type, generics: u32, out: &mut BTreeSet<u32>) {
+    match ty {
+        Type::Var(v) if *v < generics => {
+            out.insert(*v);
+        }
+        Type::List(item) => generics_in(item, generics, out),
+        Type::Fn(param, result) => {
+            generics_in(param, generics, out);
+            generics_in(result, generics, out);
+        }
+        _ => {}
+    }
+}
+
+/// Parenthesized when it binds looser than its position needs
+fn wrap(code: String, own: u8, prec: u8) -> String {
+    if own < prec {
+        format!("({})", code)
+    } else {
+        code
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Used as it is
+    Copy,
+    /// Used once, outside closures
+    Move,
+    /// Cloned, or borrowed when it's a function
+    Clone,
+    /// `&T` a `filter` closure gets
+    Borrowed,
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    name: String,
+    ty: Type,
+    mode: Mode,
+    /// Arguments of a function parameter's uncurried bound
+    arity: Option<usize>,
+}
+
+struct Printer<'a> {
+    /// The gene's name, `None` for a function out of a vector
+    name: Option<String>,
+    ir: &'a IR,
+    typing: &'a Typing,
+    generics: u32,
+    /// Parameters of the gene, 1 for a nullary gene taking `_`
+    arity: usize,
+    nullary: bool,
+    /// Variables in scope, innermost last
+    scope: Vec<Binding>,
+    /// Generics cloned somewhere, and compared
+    cloned: BTreeSet<u32>,
+    compared: BTreeSet<u32>,
+}
+
+impl<'a> Printer<'a> {
+    fn new(name: Option<&str>, ir: &'a IR, typing: &'a Typing) -> Self {
+        let (names, _) = params(ir);
+        Printer {
+            name: name.map(ident),
+            ir,
+            typing,
+            generics: typing.signature.generics,
+            arity: names.len(),
+            nullary: names == ["_"],
+            scope: vec![],
+            cloned: BTreeSet::new(),
+            compared: BTreeSet::new(),
+        }
+    }
+
+    fn copy(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Num | Type::Bool => true,
+            Type::Var(v) => *v >= self.generics,
+            _ => false,
+        }
+    }
+
+    /// How `x` of type `ty` is used over `bodies`, its scope
+    fn bind(&self, x: &str, ty: &Type, bodies: &[&IR]) -> Binding {
+        let mut found = Uses::default();
+        for body in bodies {
+            uses(body, x, &mut found, 0);
+        }
+        let mode = match () {
+            _ if self.copy(ty) => Mode::Copy,
+            _ if found.count <= 1 && !found.captured => Mode::Move,
+            _ => Mode::Clone,
+        };
+        Binding { name: x.to_string(), ty: ty.clone(), mode, arity: None }
+    }
+
+    /// Pattern for a binder nothing may use - named ones keep their name
+    /// behind an underscore
+    fn pattern(x: &str, bodies: &[&IR], named: bool) -> String {
+        let mut found = Uses::default();
+        for body in bodies {
+            uses(body, x, &mut found, 0);
+        }
+        match found.count {
+            _ if x == "_" => "_".to_string(),
+            0 if named => format!("_{}", ident(x)),
+            0 => "_".to_string(),
+            _ => ident(x),
+        }
+    }
+
+    fn lookup(&self, x: &str) -> Option<&Binding> {
+        self.scope.iter().rev().find(|b| b.name == x)
+    }
+
+    /// `fn` item with its generics and bounds
+    fn function(&mut self) -> Result<String> {
+        let (_, body) = params(self.ir);
+        let signature = &self.typing.signature;
+        let taken = signature.generic_names();
+        let mut fn_names = ["F", "G", "H", "I", "J", "K"].into_iter().map(str::to_string)
+            .chain((1..).map(|i| format!("F{}", i)))
+            .filter(|name| !taken.contains(name));
+
+        let mut type_params = taken.clone();
+        let mut fn_bounds = vec![];
+        let mut args = vec![];
+        for (name, ty) in &signature.params {
+            let mut binding = self.bind(name, ty, &[body]);
+            let pattern = Self::pattern(name, &[body], true);
+            match ty {
+                Type::Fn(..) => {
+                    let (params, result) = uncurry(ty);
+                    let params = params.iter().map(|p| rust_type(p, self.generics)).collect::<Result<Vec<_>>>()?;
+                    let f = fn_names.next().unwrap();
+                    fn_bounds.push(format!("{}: Fn({}) -> {}", f, params.join(", "), rust_type(result, self.generics)?));
+                    if passed_on(body, name) {
+                        args.push(format!("{}: &{}", pattern, f));
+                        binding.mode = Mode::Copy;
+                    } else {
+                        args.push(format!("{}: {}", pattern, f));
+                    }
+                    type_params.push(f);
+                    binding.arity = Some(params.len());
+                }
+                _ => args.push(format!("{}: {}", pattern, rust_type(ty, self.generics)?)),
+            }
+            self.scope.push(binding);
+        }
+        let result = match &signature.result {
+            Type::Fn(param, result) => {
+                format!("impl Fn({}) -> {}", rust_type(param, self.generics)?, rust_type(result, self.generics)?)
+            }
+            ty => rust_type(ty, self.generics)?,
+        };
+
+        let mut code = String::new();
+        self.statements(body, &mut code)?;
+
+        let mut bounds = vec![];
+        for v in 0..self.generics {
+            let traits: Vec<&str> = [("Clone", self.cloned.contains(&v)), ("PartialEq", self.compared.contains(&v))]
+                .into_iter()
+                .filter_map(|(name, used)| used.then_some(name))
+                .collect();
+            if !traits.is_empty() {
+                bounds.push(format!("{}: {}", generic(v), traits.join(" + ")));
+            }
+        }
+        bounds.extend(fn_bounds);
+
+        let generics = match type_params.is_empty() {
+            true => String::new(),
+            false => format!("<{}>", type_params.join(", ")),
+        };
+        let mut out = format!("pub fn {}{}({}) -> {}", self.name.as_deref().unwrap(), generics, args.join(", "), result);
+        if bounds.is_empty() {
+            out.push_str(" {\n");
+        } else {
+            out.push_str("\nwhere\n");
+            for bound in bounds {
+                out.push_str(&format!("    {},\n", bound));
+            }
+            out.push_str("{\n");
+        }
+        out.push_str(&code);
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// A function out of a vector as a closure, its parameters typed
+    fn literal(&mut self, types: &[Type]) -> Result<String> {
+        let (names, body) = params(self.ir);
+        if names.len() != types.len() {
+            bail!("takes {} arguments, not {}", names.len(), types.len());
+        }
+        let mut patterns = vec![];
+        for (name, ty) in names.iter().zip(types) {
+            let binding = self.bind(name, ty, &[body]);
+            patterns.push(format!("{}: {}", Self::pattern(name, &[body], false), rust_type(ty, 0)?));
+            self.scope.push(binding);
+        }
+        let code = self.expr(body, CLOSURE)?;
+        Ok(format!("|{}| {}", patterns.join(", "), code))
+    }
+
+    /// Function body: leading bindings as `let`s, then the value
+    fn statements(&mut self, mut body: &IR, out: &mut String) -> Result<()> {
+        let depth = self.scope.len();
+        while let IR::App(f, value) = body {
+            let IR::Lam(x, rest) = &**f else { break };
+            if x == "_" {
+                break;
+            }
+            let code = self.expr(value, CLOSURE)?;
+            out.push_str(&format!("    let {} = {};\n", Self::pattern(x, &[rest], true), code));
+            let binding = self.bind(x, self.typing.of(value), &[rest]);
+            self.scope.push(binding);
+            body = rest;
+        }
+        out.push_str(&format!("    {}\n", self.tail(body)?));
+        self.scope.truncate(depth);
+        Ok(())
+    }
+
+    /// What the function returns - nothing is evaluated after it, so a
+    /// variable moves out and a closure moves its captures in
+    fn tail(&mut self, ir: &IR) -> Result<String> {
+        Ok(match ir {
+            IR::Var(x) if self.lookup(x).is_some() => ident(x),
+            IR::If(c, t, e) => format!("if {} {{ {} }} else {{ {} }}", self.expr(c, CLOSURE + 1)?, self.tail(t)?, self.tail(e)?),
+            IR::App(f, value) if matches!(&**f, IR::Lam(x, _) if x != "_") => {
+                let IR::Lam(x, rest) = &**f else { unreachable!() };
+                let code = self.expr(value, CLOSURE)?;
+                let binding = self.bind(x, self.typing.of(value), &[rest]);
+                self.scope.push(binding);
+                let rest_code = self.tail(rest);
+                self.scope.pop();
+                format!("{{ let {} = {}; {} }}", Self::pattern(x, &[rest], true), code, rest_code?)
+            }
+            IR::Lam(..) => format!("move {}", self.closure(ir, false)?),
+            IR::Map(..) | IR::Filter(..) | IR::Focus(..) => format!("{}.collect()", self.chain(ir)?),
+            _ => self.expr(ir, CLOSURE)?,
+        })
+    }
+
+    /// `ir` as an expression binding at least as tight as `prec`
+    fn expr(&mut self, ir: &IR, prec: u8) -> Result<String> {
+        let (code, own) = self.print(ir)?;
+        Ok(wrap(code, own, prec))
+    }
+
+    fn print(&mut self, ir: &IR) -> Result<(String, u8)> {
+        Ok(match ir {
+            IR::Var(x) if x == SELF && self.lookup(x).is_none() => {
+                let Some(name) = self.name.clone() else { bail!("`{}` outside a gene", SELF) };
+                if self.arity != 1 || self.nullary {
+                    bail!("`{}` used as a value, takes {} arguments", SELF, self.arity);
+                }
+                (name, ATOM)
+            }
+            IR::Var(x) => self.var(x)?,
+            IR::Num(n) if *n < 0 => (n.to_string(), UNARY),
+            IR::Num(n) => (n.to_string(), ATOM),
+            IR::Bool(b) => (b.to_string(), ATOM),
+            IR::Str(s) => (format!("{:?}.to_owned()", s), POSTFIX),
+            IR::Nil => ("vec![]".to_string(), ATOM),
+            IR::Cons(..) => {
+                let mut items = vec![];
+                let mut rest = ir;
+                while let IR::Cons(h, t) = rest {
+                    items.push(self.expr(h, CLOSURE)?);
+                    rest = t;
+                }
+                let list = format!("vec![{}]", items.join(", "));
+                match rest {
+                    IR::Nil => (list, ATOM),
+                    _ => (format!("{}.into_iter().chain({}).collect::<Vec<_>>()", list, self.expr(rest, CLOSURE)?), POSTFIX),
+                }
+            }
+            IR::Lam(..) => (self.closure(ir, true)?, CLOSURE),
+            IR::App(f, value) if matches!(&**f, IR::Lam(x, _) if x != "_") => {
+                let IR::Lam(x, rest) = &**f else { unreachable!() };
+                let code = self.expr(value, CLOSURE)?;
+                let binding = self.bind(x, self.typing.of(value), &[rest]);
+                self.scope.push(binding);
+                let rest_code = self.expr(rest, CLOSURE);
+                self.scope.pop();
+                (format!("{{ let {} = {}; {} }}", Self::pattern(x, &[rest], true), code, rest_code?), CLOSURE)
+            }
+            IR::App(..) => self.call(ir)?,
+            IR::If(c, t, e) => {
+                let code = format!("if {} {{ {} }} else {{ {} }}", self.expr(c, CLOSURE + 1)?, self.expr(t, CLOSURE)?, self.expr(e, CLOSURE)?);
+                (code, CLOSURE)
+            }
+            IR::Add(a, b) => self.binary(a, "+", b, ADDITIVE)?,
+            IR::Sub(a, b) => self.binary(a, "-", b, ADDITIVE)?,
+            IR::Mul(a, b) => self.binary(a, "*", b, MULTIPLICATIVE)?,
+            IR::Div(a, b) => self.binary(a, "/", b, MULTIPLICATIVE)?,
+            IR::Mod(a, b) => self.binary(a, "%", b, MULTIPLICATIVE)?,
+            IR::Lt(a, b) => self.binary(a, "<", b, COMPARISON)?,
+            IR::Le(a, b) => self.binary(a, "<=", b, COMPARISON)?,
+            IR::Eq(a, b) => {
+                let mut generics = BTreeSet::new();
+                generics_in(self.typing.of(a), self.generics, &mut generics);
+                self.compared.extend(generics);
+                (format!("{} == {}", self.operand(a)?, self.operand(b)?), COMPARISON)
+            }
+            IR::Map(..) | IR::Filter(..) | IR::Focus(..) => (format!("{}.collect::<Vec<_>>()", self.chain(ir)?), POSTFIX),
+            IR::Reduce(xs, f, init) => {
+                let IR::Lam(acc, inner) = &**f else { unreachable!("normalized") };
+                let IR::Lam(x, body) = &**inner else { unreachable!("normalized") };
+                let Type::Fn(acc_ty, step) = self.typing.of(f).clone() else { unreachable!() };
+                let Type::Fn(x_ty, _) = *step else { unreachable!() };
+                let (chain, init) = (self.chain(xs)?, self.expr(init, CLOSURE)?);
+                let patterns = (Self::pattern(acc, &[body], false), Self::pattern(x, &[body], false));
+                let bindings = [self.bind(acc, &acc_ty, &[body]), self.bind(x, &x_ty, &[body])];
+                self.scope.extend(bindings);
+                let body = self.expr(body, CLOSURE);
+                self.scope.truncate(self.scope.len() - 2);
+                (format!("{}.fold({}, |{}, {}| {})", chain, init, patterns.0, patterns.1, body?), POSTFIX)
+            }
+            IR::Compose(..) | IR::Pipe(..) | IR::Id | IR::Const(_) => unreachable!("normalized"),
+            IR::Drop => bail!("drop outside a focus"),
+            IR::Opaque(reason) => bail!("opaque construct: {}", reason),
+        })
+    }
+
+    fn var(&mut self, x: &str) -> Result<(String, u8)> {
+        let Some(binding) = self.lookup(x).cloned() else { bail!("free variable `{}`", x) };
+        if binding.arity.is_some_and(|arity| arity != 1) {
+            bail!("`{}` takes {} arguments, used as a value", x, binding.arity.unwrap());
+        }
+        let function = matches!(binding.ty, Type::Fn(..));
+        Ok(match binding.mode {
+            Mode::Copy | Mode::Move => (ident(x), ATOM),
+            Mode::Clone if function => (format!("&{}", ident(x)), UNARY),
+            Mode::Clone | Mode::Borrowed => {
+                generics_in(&binding.ty, self.generics, &mut self.cloned);
+                (format!("{}.clone()", ident(x)), POSTFIX)
+            }
+        })
+    }
+
+    /// Side of `==`, which borrows - a variable isn't cloned for it, nor
+    /// a string made owned
+    fn operand(&mut self, ir: &IR) -> Result<String> {
+        match ir {
+            IR::Str(s) => Ok(format!("{:?}", s)),
+            IR::Var(x) => match self.lookup(x).map(|b| b.mode) {
+                Some(Mode::Borrowed) => Ok(format!("*{}", ident(x))),
+                Some(_) => Ok(ident(x)),
+                None => self.expr(ir, COMPARISON + 1),
+            },
+            _ => self.expr(ir, COMPARISON + 1),
+        }
+    }
+
+    fn binary(&mut self, a: &IR, op: &str, b: &IR, prec: u8) -> Result<(String, u8)> {
+        // Comparisons don't chain, so neither side may be one
+        let left = if prec == COMPARISON { prec + 1 } else { prec };
+        Ok((format!("{} {} {}", self.expr(a, left)?, op, self.expr(b, prec + 1)?), prec))
+    }
+
+    /// A lambda as a closure - typed parameters where it's a value of its
+    /// own, not where an adaptor or the return type types it
+    fn closure(&mut self, ir: &IR, typed: bool) -> Result<String> {
+        let IR::Lam(x, body) = ir else { unreachable!() };
+        let Type::Fn(param, _) = self.typing.of(ir).clone() else { unreachable!() };
+        let mut pattern = Self::pattern(x, &[body], false);
+        if let (true, Ok(ty)) = (typed, rust_type(&param, self.generics)) {
+            pattern = format!("{}: {}", pattern, ty);
+        }
+        let binding = self.bind(x, &param, &[body]);
+        self.scope.push(binding);
+        let body = self.expr(body, CLOSURE);
+        self.scope.pop();
+        Ok(format!("|{}| {}", pattern, body?))
+    }
+
+    /// Traversal as an iterator - adaptors chain onto the inner one
+    fn chain(&mut self, ir: &IR) -> Result<String> {
+        Ok(match ir {
+            IR::Map(xs, f) => {
+                let xs = self.chain(xs)?;
+                let f = match &**f {
+                    IR::Lam(..) => self.closure(f, false)?,
+                    _ => self.expr(f, CLOSURE)?,
+                };
+                format!("{}.map({})", xs, f)
+            }
+            IR::Filter(xs, p) => {
+                let xs = self.chain(xs)?;
+                let IR::Lam(x, body) = &**p else { unreachable!("normalized") };
+                let Type::Fn(item, _) = self.typing.of(p).clone() else { unreachable!() };
+                let mut binding = self.bind(x, &item, &[body]);
+                let mut pattern = Self::pattern(x, &[body], false);
+                if binding.mode == Mode::Copy {
+                    pattern = format!("&{}", pattern);
+                } else {
+                    binding.mode = Mode::Borrowed;
+                }
+                self.scope.push(binding);
+                let cond = self.expr(body, CLOSURE);
+                self.scope.pop();
+                format!("{}.filter(|{}| {})", xs, pattern, cond?)
+            }
+            IR::Focus(xs, w, inside, outside) => {
+                let xs = self.chain(xs)?;
+                let lams: Vec<&IR> = [w, inside, outside].into_iter().map(|f| &**f).filter(|f| !matches!(f, IR::Drop)).collect();
+                let bodies: Vec<&IR> = lams.iter().map(|f| match f {
+                    IR::Lam(_, body) => &**body,
+                    _ => unreachable!("normalized"),
+                }).collect();
+                let IR::Lam(x, _) = lams[0] else { unreachable!() };
+                let Type::Fn(item, _) = self.typing.of(lams[0]).clone() else { unreachable!() };
+                let pattern = Self::pattern(x, &bodies, false);
+                let binding = self.bind(x, &item, &bodies);
+                self.scope.push(binding);
+                let printed = bodies.iter().enumerate()
+                    .map(|(i, body)| self.expr(body, if i == 0 { CLOSURE + 1 } else { CLOSURE }))
+                    .collect::<Result<Vec<_>>>();
+                self.scope.pop();
+                let printed = printed?;
+                match printed.get(2) {
+                    None => format!("{}.filter_map(|{}| if {} {{ Some({}) }} else {{ None }})", xs, pattern, printed[0], printed[1]),
+                    Some(otherwise) => format!("{}.map(|{}| if {} {{ {} }} else {{ {} }})", xs, pattern, printed[0], printed[1], otherwise),
+                }
+            }
+            _ => format!("{}.into_iter()", self.expr(ir, POSTFIX)?),
+        })
+    }
+
+    /// Curried application - `rec` and function parameters take all their
+    /// arguments in one call
+    fn call(&mut self, ir: &IR) -> Result<(String, u8)> {
+        let mut args = vec![];
+        let mut head = ir;
+        while let IR::App(f, x) = head {
+            args.push(&**x);
+            head = f;
+        }
+        args.reverse();
+
+        if let IR::Var(f) = head {
+            if f == SELF && self.lookup(f).is_none() {
+                let Some(name) = self.name.clone() else { bail!("`{}` outside a gene", SELF) };
+                if args.len() != self.arity {
+                    bail!("`{}` applied to {} arguments, takes {}", SELF, args.len(), self.arity);
+                }
+                if self.nullary {
+                    return Ok((format!("{}()", name), POSTFIX));
+                }
+                let args = args.into_iter().map(|a| self.expr(a, CLOSURE)).collect::<Result<Vec<_>>>()?;
+                return Ok((format!("{}({})", name, args.join(", ")), POSTFIX));
+            }
+            if let Some(arity) = self.lookup(f).and_then(|b| b.arity) {
+                if args.len() != arity {
+                    bail!("`{}` applied to {} arguments, takes {}", f, args.len(), arity);
+                }
+                let args = args.into_iter().map(|a| self.expr(a, CLOSURE)).collect::<Result<Vec<_>>>()?;
+                return Ok((format!("{}({})", ident(f), args.join(", ")), POSTFIX));
+            }
+        }
+
+        // Calling borrows, so a variable is called as it is
+        let mut code = match head {
+            IR::Var(f) if self.lookup(f).is_some() => ident(f),
+            _ => self.expr(head, POSTFIX)?,
+        };
+        for arg in args {
+            code = format!("{}({})", code, self.expr(arg, CLOSURE)?);
+        }
+        Ok((code, POSTFIX))
+    }
+}
+
+/// Type of a JSON input or output, `None` for what Rust can't take (null,
+/// floats, objects, mixed arrays)
+fn json_type(json: &Json, unifier: &mut Unifier) -> Option<Type> {
+    Some(match json {
+        Json::Number(n) => {
+            n.as_i64()?;
+            Type::Num
+        }
+        Json::Bool(_) => Type::Bool,
+        Json::String(_) => Type::Str,
+        Json::Array(items) => {
+            let item = unifier.fresh();
+            for json in items {
+                let ty = json_type(json, unifier)?;
+                unifier.unify(&item, &ty)?;
+            }
+            Type::List(Box::new(item))
+        }
+        _ => return None,
+    })
+}
+
+/// `ty` with its generics replaced by `vars`
+fn substitute(ty: &Type, vars: &[Type]) -> Type {
+    match ty {
+        Type::Var(v) => vars[*v as usize].clone(),
+        Type::List(item) => Type::List(Box::new(substitute(item, vars))),
+        Type::Fn(param, result) => Type::Fn(Box::new(substitute(param, vars)), Box::new(substitute(result, vars))),
+        ty => ty.clone(),
+    }
+}
+
+/// Generics nothing in a vector pins down are taken as numbers
+fn ground(ty: &Type) -> Type {
+    match ty {
+        Type::Var(_) => Type::Num,
+        Type::List(item) => Type::List(Box::new(ground(item))),
+        Type::Fn(param, result) => Type::Fn(Box::new(ground(param)), Box::new(ground(result))),
+        ty => ty.clone(),
+    }
+}
+
+fn literal(json: &Json, ty: &Type) -> Option<String> {
+    Some(match (ty, json) {
+        (Type::Num, Json::Number(n)) => n.as_i64()?.to_string(),
+        (Type::Bool, Json::Bool(b)) => b.to_string(),
+        (Type::Str, Json::String(s)) => format!("{:?}.to_owned()", s),
+        (Type::List(item), Json::Array(items)) if items.is_empty() => format!("Vec::<{}>::new()", rust_type(item, 0).ok()?),
+        (Type::List(item), Json::Array(items)) => {
+            let items = items.iter().map(|json| literal(json, item)).collect::<Option<Vec<_>>>()?;
+            format!("vec![{}]", items.join(", "))
+        }
+        _ => return None,
+    })
+}
+
+/// A vector's function, from its JS source
+fn function(source: &str) -> Option<IR> {
+    let function = crate::extract::js::extract(&format!("export const f = {};", source)).ok()?.pop()?;
+    let ir = crate::extract::decode(&function.ir)?;
+    Some(normalize(&ir, &mut names(&ir)))
+}
+
+/// `#[test]`s for the vectors the gene's signature can take - mixed types,
+/// floats and functions that don't lower leave a vector out
+fn tests(name: &str, ir: &IR, signature: &Signature, vectors: &Json) -> Vec<String> {
+    let mut taken = HashSet::new();
+    let mut tests = vec![];
+    for vector in vectors["vectors"].as_array().into_iter().flatten() {
+        let Some(assertion) = assertion(name, ir, signature, vector) else { continue };
+        let base: String = vector["name"].as_str().unwrap_or("vector").to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let base = match base.chars().next() {
+            Some(c) if !c.is_ascii_digit() => ident(&base),
+            _ => format!("vector_{}", base),
+        };
+        let test = fresh(&mut taken, &base);
+        tests.push(format!("    #[test]\n    fn {}() {{\n        {}\n    }}\n", test, assertion));
+    }
+    tests
+}
+
+fn assertion(name: &str, ir: &IR, signature: &Signature, vector: &Json) -> Option<String> {
+    // The gene's generics are the first variables, so its types unify as
+    // they are
+    let mut unifier = Unifier::default();
+    for _ in 0..signature.generics {
+        unifier.fresh();
+    }
+
+    let mut functions = vec![];
+    for (param, ty) in &signature.params {
+        let input = &vector["input"][param];
+        if let Type::Fn(..) = ty {
+            let ir = function(input.as_str()?)?;
+            let typing = signature::typing(&ir)?;
+            let vars: Vec<Type> = (0..typing.signature.generics).map(|_| unifier.fresh()).collect();
+            let own = typing.signature.params.iter().rev()
+                .fold(typing.signature.result.clone(), |result, (_, param)| Type::Fn(Box::new(param.clone()), Box::new(result)));
+            unifier.unify(ty, &substitute(&own, &vars))?;
+            functions.push((ir, typing));
+        } else {
+            let input = json_type(input, &mut unifier)?;
+            unifier.unify(ty, &input)?;
+        }
+    }
+    let output = json_type(&vector["output"], &mut unifier)?;
+    unifier.unify(&signature.result, &output)?;
+
+    let mut functions = functions.iter();
+    let mut args = vec![];
+    for (param, ty) in &signature.params {
+        let ty = ground(&unifier.zonk(ty));
+        let input = &vector["input"][param];
+        args.push(match ty {
+            Type::Fn(..) => {
+                let (function, typing) = functions.next()?;
+                let types: Vec<Type> = uncurry(&ty).0.into_iter().cloned().collect();
+                let closure = Printer::new(None, function, typing).literal(&types).ok()?;
+                match passed_on(params(ir).1, param) {
+                    true => format!("&{}", closure),
+                    false => closure,
+                }
+            }
+            _ => literal(input, &ty)?,
+        });
+    }
+    let expected = literal(&vector["output"], &ground(&unifier.zonk(&signature.result)))?;
+    Some(format!("assert_eq!({}({}), {});", ident(name), args.join(", "), expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::{gene, map, map_vectors, score};
+    use crate::extract::js;
+
+    #[test]
+    fn test_chains_bounds_and_vector_tests() {
+        let code = module(&map(), Some(&map_vectors())).unwrap();
+        assert!(code.starts_with(
+            "// Gene: map\n// Soul: λ0\n\n\
+             pub fn map<A, B, F>(xs: Vec<A>, f: F) -> Vec<B>\nwhere\n    F: Fn(A) -> B,\n{\n    xs.into_iter().map(f).collect()\n}\n\n\
+             #[cfg(test)]\nmod tests {\n    use super::*;\n\n"
+        ), "{}", code);
+        assert!(code.contains("    fn basic_numbers() {\n        assert_eq!(map(vec![1, 2, 3, 4, 5], |x: i64| x * 2), vec![2, 4, 6, 8, 10]);\n    }\n"));
+        assert!(code.contains("assert_eq!(map(Vec::<i64>::new(), |x: i64| x * 2), Vec::<i64>::new());"));
+        // Mixed arrays and functions that don't lower have no test
+        assert!(!code.contains("identity_function") && !code.contains("string_transformation"));
+
+        assert!(module(&score(), None).unwrap().ends_with(
+            "pub fn score(xs: Vec<i64>, k: i64) -> i64 {\n    \
+             let ys = xs.into_iter().filter(|&x| 0 < x).map(|x| x * k).collect::<Vec<_>>();\n    \
+             ys.into_iter().fold(0, |a, y| a + y)\n}\n"
+        ));
+
+        // A hard focus is a `filter_map`; values used twice are cloned
+        let ir = r#"{"Lam":["xs",{"Lam":["y",{"Focus":[{"Var":"xs"},{"Lam":["x",{"Eq":[{"Var":"x"},{"Var":"y"}]}]},{"Lam":["x",{"Cons":[{"Var":"x"},{"Cons":[{"Var":"x"},"Nil"]}]}]},"Drop"]}]}]}"#;
+        let pairs = Export { name: "pairs".to_string(), soul: "λ0".to_string(), ir: serde_json::from_str(ir).unwrap() };
+        assert!(module(&pairs, None).unwrap().ends_with(
+            "pub fn pairs<A>(xs: Vec<A>, y: A) -> Vec<Vec<A>>\nwhere\n    A: Clone + PartialEq,\n{\n    \
+             xs.into_iter().filter_map(|x| if x == y { Some(vec![x.clone(), x.clone()]) } else { None }).collect()\n}\n"
+        ));
+
+        // Passed on to itself, a function parameter is borrowed
+        let times = gene(js::extract, "export function times(f, x, n) { return n === 0 ? x : times(f, f(x), n - 1); }");
+        assert!(module(&times, None).unwrap().contains("pub fn times<A, F>(f: &F, x: A, n: i64) -> A\n"));
+
+        let opaque = gene(js::extract, "export function shout(s) { return s.toUpperCase(); }");
+        assert!(module(&opaque, None).is_err());
+    }
+
+    #[test]
+    fn test_forged_crate_passes_its_tests() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let times_vectors = serde_json::json!({"vectors": [
+            {"name": "doubled thrice", "input": {"f": "x => x * 2", "x": 1, "n": 3}, "output": 8},
+            {"name": "appended", "input": {"f": "xs => [0, ...xs]", "x": [], "n": 2}, "output": [0, 0]},
+        ]});
+        let genes = [
+            ("export function map(xs, f) { return xs.map(f); }", Some(map_vectors())),
+            ("export function times(f, x, n) { return n === 0 ? x : times(f, f(x), n - 1); }", Some(times_vectors)),
+            ("export function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }", None),
+            ("export function only(xs, s) { return xs.filter(x => x === s).map(x => x === 'a' ? 'b' : x); }", None),
+        ];
+        let mut lib = String::from("#![allow(non_snake_case)]\n\n");
+        for (source, vectors) in &genes {
+            let gene = gene(js::extract, source);
+            std::fs::write(dir.path().join("src").join(format!("{}.rs", gene.name)), module(&gene, vectors.as_ref()).unwrap()).unwrap();
+            lib.push_str(&format!("pub mod {};\n", gene.name));
+        }
+        std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"forged\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n").unwrap();
+
+        let output = std::process::Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+            .args(["test", "--offline", "--quiet"])
+            .env("RUSTFLAGS", "-D warnings")
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&output.stderr));
+        assert!(stdout.contains("6 passed"), "{}", stdout);
+    }
+}
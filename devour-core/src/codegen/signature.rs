@@ -0,0 +1,361 @@
+// Gene signatures inferred from IR - what typed backends annotate with
+//
+// Plain unification over the core IR: no let-polymorphism and recursion is
+// monomorphic. A gene some rule can't type (an opaque node, a numeric
+// weight, `+` on strings) has no signature and backends leave it untyped.
+
+use std::collections::HashMap;
+
+use super::params;
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Num,
+    Bool,
+    Str,
+    List(Box<Type>),
+    Fn(Box<Type>, Box<Type>),
+    /// Generic, numbered by first appearance in the signature
+    Var(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub params: Vec<(String, Type)>,
+    pub result: Type,
+    /// Generics used, `Var(0)` to `Var(generics - 1)`
+    pub generics: u32,
+}
+
+impl Signature {
+    /// Generics by name, in order
+    pub fn generic_names(&self) -> Vec<String> {
+        (0..self.generics).map(generic).collect()
+    }
+}
+
+/// `A` to `Z`, then `T26`, `T27`...
+pub fn generic(n: u32) -> String {
+    match char::from_u32('A' as u32 + n) {
+        Some(c) if n < 26 => c.to_string(),
+        _ => format!("T{}", n),
+    }
+}
+
+/// Every node's type, by address - valid while the IR it was inferred over
+/// stays put
+pub struct Typing {
+    pub signature: Signature,
+    nodes: HashMap<*const IR, Type>,
+}
+
+impl Typing {
+    /// Type of a node of the typed IR - generics past the signature's are
+    /// ones the gene leaves open, like the items of a `[]` nothing reads
+    pub fn of(&self, node: &IR) -> &Type {
+        &self.nodes[&(node as *const IR)]
+    }
+}
+
+/// Signature of a gene over its leading lambdas
+pub fn infer(ir: &IR) -> Option<Signature> {
+    typing(ir).map(|typing| typing.signature)
+}
+
+/// Signature of a gene and the type of each of its nodes
+pub fn typing(ir: &IR) -> Option<Typing> {
+    let mut cx = Infer { unifier: Unifier::default(), nodes: vec![] };
+    let whole = cx.unifier.fresh();
+    let ty = cx.infer(&mut vec![(SELF.to_string(), whole.clone())], ir)?;
+    cx.unifier.unify(&whole, &ty)?;
+
+    let mut rest = cx.unifier.zonk(&ty);
+    let mut typed = vec![];
+    for name in params(ir).0 {
+        let Type::Fn(param, result) = rest else { unreachable!("a lambda types as a function") };
+        typed.push((name.to_string(), *param));
+        rest = *result;
+    }
+
+    // A nullary function's `_` is nothing callers pass
+    if typed.len() == 1 && typed[0].0 == "_" {
+        typed.clear();
+    }
+
+    // Renumber what's left generic by first appearance
+    let mut order = vec![];
+    for ty in typed.iter().map(|(_, ty)| ty).chain([&rest]) {
+        collect(ty, &mut order);
+    }
+    let generics = order.len() as u32;
+    let nodes: Vec<(*const IR, Type)> = cx.nodes.iter().map(|(node, ty)| (*node, cx.unifier.zonk(ty))).collect();
+    for (_, ty) in &nodes {
+        collect(ty, &mut order);
+    }
+    let rename = |ty: &Type| renumber(ty, &order);
+    Some(Typing {
+        signature: Signature {
+            params: typed.iter().map(|(name, ty)| (name.clone(), rename(ty))).collect(),
+            result: rename(&rest),
+            generics,
+        },
+        nodes: nodes.iter().map(|(node, ty)| (*node, rename(ty))).collect(),
+    })
+}
+
+fn collect(ty: &Type, order: &mut Vec<u32>) {
+    match ty {
+        Type::Var(v) if !order.contains(v) => order.push(*v),
+        Type::List(t) => collect(t, order),
+        Type::Fn(a, r) => {
+            collect(a, order);
+            collect(r, order);
+        }
+        _ => {}
+    }
+}
+
+fn renumber(ty: &Type, order: &[u32]) -> Type {
+    match ty {
+        Type::Var(v) => Type::Var(order.iter().position(|o| o == v).unwrap() as u32),
+        Type::List(t) => Type::List(Box::new(renumber(t, order))),
+        Type::Fn(a, r) => Type::Fn(Box::new(renumber(a, order)), Box::new(renumber(r, order))),
+        ty => ty.clone(),
+    }
+}
+
+fn list(t: Type) -> Type {
+    Type::List(Box::new(t))
+}
+
+fn func(a: Type, r: Type) -> Type {
+    Type::Fn(Box::new(a), Box::new(r))
+}
+
+struct Infer {
+    unifier: Unifier,
+    /// Each node's type as inferred, before substitution
+    nodes: Vec<(*const IR, Type)>,
+}
+
+impl Infer {
+    fn fresh(&mut self) -> Type {
+        self.unifier.fresh()
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> Option<()> {
+        self.unifier.unify(a, b)
+    }
+
+    fn infer(&mut self, env: &mut Vec<(String, Type)>, ir: &IR) -> Option<Type> {
+        let ty = self.constrain(env, ir)?;
+        self.nodes.push((ir, ty.clone()));
+        Some(ty)
+    }
+
+    fn constrain(&mut self, env: &mut Vec<(String, Type)>, ir: &IR) -> Option<Type> {
+        Some(match ir {
+            IR::Var(x) => env.iter().rev().find(|(name, _)| name == x)?.1.clone(),
+            IR::Lam(x, body) => {
+                let param = self.fresh();
+                env.push((x.clone(), param.clone()));
+                let body = self.infer(env, body);
+                env.pop();
+                func(param, body?)
+            }
+            IR::App(f, x) => {
+                let (f, x, result) = (self.infer(env, f)?, self.infer(env, x)?, self.fresh());
+                self.unify(&f, &func(x, result.clone()))?;
+                result
+            }
+            IR::Map(xs, f) => {
+                let (a, b) = (self.fresh(), self.fresh());
+                self.expect(env, xs, list(a.clone()))?;
+                self.expect(env, f, func(a, b.clone()))?;
+                list(b)
+            }
+            IR::Filter(xs, p) => {
+                let a = self.fresh();
+                self.expect(env, xs, list(a.clone()))?;
+                self.expect(env, p, func(a.clone(), Type::Bool))?;
+                list(a)
+            }
+            IR::Reduce(xs, f, init) => {
+                let a = self.fresh();
+                let acc = self.infer(env, init)?;
+                self.expect(env, xs, list(a.clone()))?;
+                self.expect(env, f, func(acc.clone(), func(a, acc.clone())))?;
+                acc
+            }
+            IR::Focus(xs, w, inside, outside) => {
+                let (a, b) = (self.fresh(), self.fresh());
+                self.expect(env, xs, list(a.clone()))?;
+                self.expect(env, w, func(a.clone(), Type::Bool))?;
+                self.expect(env, inside, func(a.clone(), b.clone()))?;
+                if !matches!(**outside, IR::Drop) {
+                    self.expect(env, outside, func(a, b.clone()))?;
+                }
+                list(b)
+            }
+            IR::Nil => list(self.fresh()),
+            IR::Cons(h, t) => {
+                let h = self.infer(env, h)?;
+                self.expect(env, t, list(h))?
+            }
+            IR::Num(_) => Type::Num,
+            IR::Bool(_) => Type::Bool,
+            IR::Str(_) => Type::Str,
+            IR::If(c, t, e) => {
+                self.expect(env, c, Type::Bool)?;
+                let t = self.infer(env, t)?;
+                self.expect(env, e, t)?
+            }
+            IR::Add(a, b) | IR::Sub(a, b) | IR::Mul(a, b) | IR::Div(a, b) | IR::Mod(a, b) => {
+                self.expect(env, a, Type::Num)?;
+                self.expect(env, b, Type::Num)?
+            }
+            IR::Lt(a, b) | IR::Le(a, b) => {
+                self.expect(env, a, Type::Num)?;
+                self.expect(env, b, Type::Num)?;
+                Type::Bool
+            }
+            IR::Eq(a, b) => {
+                let a = self.infer(env, a)?;
+                self.expect(env, b, a)?;
+                Type::Bool
+            }
+            IR::Compose(f, g) | IR::Pipe(g, f) => {
+                let (a, b, c) = (self.fresh(), self.fresh(), self.fresh());
+                self.expect(env, g, func(a.clone(), b.clone()))?;
+                self.expect(env, f, func(b, c.clone()))?;
+                func(a, c)
+            }
+            IR::Id => {
+                let a = self.fresh();
+                func(a.clone(), a)
+            }
+            IR::Const(x) => {
+                let x = self.infer(env, x)?;
+                func(self.fresh(), x)
+            }
+            IR::Drop => self.fresh(),
+            IR::Opaque(_) => return None,
+        })
+    }
+
+    /// Type of `ir` once unified with `ty`
+    fn expect(&mut self, env: &mut Vec<(String, Type)>, ir: &IR, ty: Type) -> Option<Type> {
+        let actual = self.infer(env, ir)?;
+        self.unify(&actual, &ty)?;
+        Some(ty)
+    }
+}
+
+/// Substitution solving equations between types, its `Var`s the unknowns
+#[derive(Debug, Default)]
+pub struct Unifier {
+    /// What each type variable is bound to
+    vars: Vec<Option<Type>>,
+}
+
+impl Unifier {
+    pub fn fresh(&mut self) -> Type {
+        self.vars.push(None);
+        Type::Var(self.vars.len() as u32 - 1)
+    }
+
+    /// Follow bindings until an unbound variable or a constructor
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(v) => match &self.vars[*v as usize] {
+                Some(bound) => self.resolve(bound),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    /// `ty` with every bound variable substituted, all the way down
+    pub fn zonk(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::List(t) => list(self.zonk(&t)),
+            Type::Fn(a, r) => func(self.zonk(&a), self.zonk(&r)),
+            ty => ty,
+        }
+    }
+
+    fn occurs(&self, v: u32, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(w) => v == w,
+            Type::List(t) => self.occurs(v, &t),
+            Type::Fn(a, r) => self.occurs(v, &a) || self.occurs(v, &r),
+            _ => false,
+        }
+    }
+
+    pub fn unify(&mut self, a: &Type, b: &Type) -> Option<()> {
+        match (self.resolve(a), self.resolve(b)) {
+            (Type::Var(v), Type::Var(w)) if v == w => Some(()),
+            (Type::Var(v), ty) | (ty, Type::Var(v)) => {
+                if self.occurs(v, &ty) {
+                    return None;
+                }
+                self.vars[v as usize] = Some(ty);
+                Some(())
+            }
+            (Type::List(a), Type::List(b)) => self.unify(&a, &b),
+            (Type::Fn(a1, r1), Type::Fn(a2, r2)) => {
+                self.unify(&a1, &a2)?;
+                self.unify(&r1, &r2)
+            }
+            (a, b) => (a == b).then_some(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::js;
+
+    fn signature(source: &str) -> Option<Signature> {
+        let function = js::extract(source).unwrap().remove(0);
+        infer(&crate::extract::decode(&function.ir).unwrap())
+    }
+
+    #[test]
+    fn test_generics_by_first_appearance() {
+        let map = signature("export function map(xs, f) { return xs.map(f); }").unwrap();
+        let (a, b) = (Type::Var(0), Type::Var(1));
+        assert_eq!(map.params, vec![("xs".to_string(), list(a.clone())), ("f".to_string(), func(a, b.clone()))]);
+        assert_eq!(map.result, list(b));
+        assert_eq!(map.generic_names(), vec!["A", "B"]);
+
+        // Nodes share the signature's numbering, open ones come after it
+        let function = js::extract("export function keep(xs) { const e = []; return xs; }").unwrap().remove(0);
+        let ir = crate::extract::decode(&function.ir).unwrap();
+        let typing = typing(&ir).unwrap();
+        let IR::Lam(_, body) = &ir else { unreachable!() };
+        let IR::App(f, empty) = &**body else { unreachable!() };
+        let IR::Lam(_, xs) = &**f else { unreachable!() };
+        assert_eq!((typing.of(xs), typing.of(empty)), (&Type::Var(0), &list(Type::Var(1))));
+        assert_eq!(typing.signature.generics, 1);
+    }
+
+    #[test]
+    fn test_concrete_and_recursive() {
+        let sum = signature("export function sum(xs) { return xs.reduce((a, x) => a + x, 0); }").unwrap();
+        assert_eq!((sum.params[0].1.clone(), sum.result, sum.generics), (list(Type::Num), Type::Num, 0));
+
+        let fib = signature("export function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }").unwrap();
+        assert_eq!((fib.params[0].1.clone(), fib.result), (Type::Num, Type::Num));
+
+        let pair = signature("export function pair() { return [1, 2]; }").unwrap();
+        assert_eq!((pair.params, pair.result, pair.generics), (vec![], list(Type::Num), 0));
+
+        // A numeric weight isn't a predicate
+        assert_eq!(signature("export function odd(xs) { return xs.filter(x => x % 2); }"), None);
+    }
+}
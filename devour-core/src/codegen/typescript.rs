@@ -0,0 +1,366 @@
+// TypeScript backend - champion IR pretty-printed as typed TS
+//
+// Lambdas print as arrows and traversals as array methods; a binding the
+// extractor lowered to `(λx.rest) e` comes back as `const x = e`. A focus is a
+// single-pass loop when it's the whole body and a `flatMap` under a lambda.
+// Types come from the inferred signature, `any` where there is none. Output
+// depends on the IR alone, so identical IR prints identical bytes.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use super::signature::{self, generic, Signature, Type};
+use super::{names, params, rename, shared_param, Export};
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+// Binding strength, loosest first
+const ARROW: u8 = 1;
+const TERNARY: u8 = 2;
+const EQUALITY: u8 = 9;
+const RELATIONAL: u8 = 10;
+const ADDITIVE: u8 = 12;
+const MULTIPLICATIVE: u8 = 13;
+const UNARY: u8 = 15;
+const CALL: u8 = 18;
+const ATOM: u8 = 20;
+
+/// `<name>.ts` for one gene
+pub fn module(export: &Export) -> Result<String> {
+    let signature = signature::infer(&export.ir);
+    let (names, body) = params(&export.ir);
+    let mut printer = Printer::new(export, &names);
+
+    let mut out = format!("// Gene: {}\n// Soul: {}\n\n", export.name, export.soul);
+    out.push_str(&format!("export function {}{} {{\n", export.name, head(export, &signature)));
+    printer.statements(body, &names, &signature, &mut out)?;
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// One gene's line of `index.d.ts`
+pub fn declaration(export: &Export) -> String {
+    format!("export declare function {}{};\n", export.name, head(export, &signature::infer(&export.ir)))
+}
+
+/// Generics, parameters and return type
+fn head(export: &Export, signature: &Option<Signature>) -> String {
+    let (names, _) = params(&export.ir);
+    let generics = match signature {
+        Some(s) if s.generics > 0 => format!("<{}>", s.generic_names().join(", ")),
+        _ => String::new(),
+    };
+    let params = match signature {
+        _ if names == ["_"] => vec![],
+        Some(s) => s.params.iter().map(|(name, ty)| format!("{}: {}", ident(name), ts_type(ty, 0))).collect(),
+        None => names.iter().map(|name| format!("{}: any", ident(name))).collect(),
+    };
+    let result = signature.as_ref().map(|s| ts_type(&s.result, 0)).unwrap_or_else(|| "any".to_string());
+    format!("{}({}): {}", generics, params.join(", "), result)
+}
+
+fn ts_type(ty: &Type, depth: usize) -> String {
+    match ty {
+        Type::Num => "number".to_string(),
+        Type::Bool => "boolean".to_string(),
+        Type::Str => "string".to_string(),
+        Type::List(item) if matches!(**item, Type::Fn(..)) => format!("({})[]", ts_type(item, depth)),
+        Type::List(item) => format!("{}[]", ts_type(item, depth)),
+        Type::Fn(param, result) => {
+            let name = ["x", "y", "z", "w"][depth % 4];
+            format!("({}: {}) => {}", name, ts_type(param, depth + 1), ts_type(result, depth + 1))
+        }
+        Type::Var(v) => generic(*v),
+    }
+}
+
+/// Names JavaScript reserves get a trailing underscore
+fn ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do",
+        "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "import", "in",
+        "instanceof", "let", "new", "null", "return", "super", "switch", "this", "throw", "true", "try",
+        "typeof", "var", "void", "while", "with", "yield", "await",
+    ];
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Parenthesized when it binds looser than its position needs
+fn wrap(code: String, own: u8, prec: u8) -> String {
+    if own < prec {
+        format!("({})", code)
+    } else {
+        code
+    }
+}
+
+struct Printer {
+    name: String,
+    /// Parameters of the gene, 1 for a nullary gene taking `_`
+    arity: usize,
+    nullary: bool,
+    /// Every name in the gene - fresh ones avoid these
+    taken: HashSet<String>,
+}
+
+impl Printer {
+    fn new(export: &Export, params: &[&str]) -> Self {
+        let mut taken = names(&export.ir);
+        taken.insert(export.name.clone());
+        Printer { name: export.name.clone(), arity: params.len(), nullary: params == ["_"], taken }
+    }
+
+    fn fresh(&mut self, base: &str) -> String {
+        let name = (1..).map(|i| if i == 1 { base.to_string() } else { format!("{}{}", base, i) })
+            .find(|name| !self.taken.contains(name))
+            .unwrap();
+        self.taken.insert(name.clone());
+        name
+    }
+
+    /// Function body: leading bindings as `const`, then the return
+    fn statements(&mut self, mut body: &IR, params: &[&str], signature: &Option<Signature>, out: &mut String) -> Result<()> {
+        let mut declared: HashSet<&str> = params.iter().copied().collect();
+        while let IR::App(f, value) = body {
+            let IR::Lam(x, rest) = &**f else { break };
+            if x == "_" || !declared.insert(x) {
+                break;
+            }
+            out.push_str(&format!("  const {} = {};\n", ident(x), self.expr(value, ARROW)?));
+            body = rest;
+        }
+
+        let IR::Focus(xs, w, inside, outside) = body else {
+            out.push_str(&format!("  return {};\n", self.expr(body, ARROW)?));
+            return Ok(());
+        };
+        let result = match signature {
+            Some(s) => ts_type(&s.result, 0),
+            None => "any[]".to_string(),
+        };
+        let acc = self.fresh("out");
+        let x = self.element(xs, &[w, inside, outside]);
+        out.push_str(&format!("  const {}: {} = [];\n", acc, result));
+        out.push_str(&format!("  for (const {} of {}) {{\n", ident(&x), self.expr(xs, ARROW)?));
+        out.push_str(&format!("    if ({}) {{\n", self.applied(w, &x, ARROW)?));
+        out.push_str(&format!("      {}.push({});\n", acc, self.applied(inside, &x, ARROW)?));
+        if !matches!(**outside, IR::Drop) {
+            out.push_str("    } else {\n");
+            out.push_str(&format!("      {}.push({});\n", acc, self.applied(outside, &x, ARROW)?));
+        }
+        out.push_str("    }\n  }\n");
+        out.push_str(&format!("  return {};\n", acc));
+        Ok(())
+    }
+
+    /// Name for the element a focus visits - the lambdas' own when they agree
+    fn element(&mut self, xs: &IR, fs: &[&IR]) -> String {
+        match shared_param(xs, fs) {
+            Some(p) => p.to_string(),
+            None => self.fresh("x"),
+        }
+    }
+
+    /// `f` applied to the variable `x`, a lambda's body inlined
+    fn applied(&mut self, f: &IR, x: &str, prec: u8) -> Result<String> {
+        match f {
+            IR::Lam(p, body) if p == x => self.expr(body, prec),
+            IR::Lam(p, body) => self.expr(&rename(body, p, x), prec),
+            _ => {
+                let code = format!("{}({})", self.expr(f, CALL)?, ident(x));
+                Ok(wrap(code, CALL, prec))
+            }
+        }
+    }
+
+    /// `ir` as an expression binding at least as tight as `prec`
+    fn expr(&mut self, ir: &IR, prec: u8) -> Result<String> {
+        let (code, own) = self.print(ir)?;
+        Ok(wrap(code, own, prec))
+    }
+
+    fn print(&mut self, ir: &IR) -> Result<(String, u8)> {
+        Ok(match ir {
+            IR::Var(x) if x == SELF => {
+                if self.arity != 1 {
+                    bail!("`{}` used as a value, takes {} arguments", SELF, self.arity);
+                }
+                (self.name.clone(), ATOM)
+            }
+            IR::Var(x) => (ident(x), ATOM),
+            IR::Num(n) if *n < 0 => (n.to_string(), UNARY),
+            IR::Num(n) => (n.to_string(), ATOM),
+            IR::Bool(b) => (b.to_string(), ATOM),
+            IR::Str(s) => (serde_json::to_string(s)?, ATOM),
+            IR::Nil => ("[]".to_string(), ATOM),
+            IR::Cons(..) => {
+                let mut items = vec![];
+                let mut rest = ir;
+                while let IR::Cons(h, t) = rest {
+                    items.push(self.expr(h, ARROW)?);
+                    rest = t;
+                }
+                if !matches!(rest, IR::Nil) {
+                    items.push(format!("...{}", self.expr(rest, ARROW)?));
+                }
+                (format!("[{}]", items.join(", ")), ATOM)
+            }
+            IR::Lam(x, body) if x == "_" => (format!("() => {}", self.expr(body, ARROW)?), ARROW),
+            IR::Lam(x, body) => (format!("({}) => {}", ident(x), self.expr(body, ARROW)?), ARROW),
+            IR::App(..) => self.call(ir)?,
+            IR::If(c, t, e) => {
+                let code = format!("{} ? {} : {}", self.expr(c, TERNARY + 1)?, self.expr(t, ARROW)?, self.expr(e, ARROW)?);
+                (code, TERNARY)
+            }
+            IR::Add(a, b) => self.binary(a, "+", b, ADDITIVE)?,
+            IR::Sub(a, b) => self.binary(a, "-", b, ADDITIVE)?,
+            IR::Mul(a, b) => self.binary(a, "*", b, MULTIPLICATIVE)?,
+            IR::Div(a, b) => self.binary(a, "/", b, MULTIPLICATIVE)?,
+            IR::Mod(a, b) => self.binary(a, "%", b, MULTIPLICATIVE)?,
+            IR::Eq(a, b) => self.binary(a, "===", b, EQUALITY)?,
+            IR::Lt(a, b) => self.binary(a, "<", b, RELATIONAL)?,
+            IR::Le(a, b) => self.binary(a, "<=", b, RELATIONAL)?,
+            IR::Map(xs, f) => (format!("{}.map({})", self.expr(xs, CALL)?, self.expr(f, ARROW)?), CALL),
+            IR::Filter(xs, p) => (format!("{}.filter({})", self.expr(xs, CALL)?, self.expr(p, ARROW)?), CALL),
+            IR::Reduce(xs, f, init) => {
+                // The IR's step is curried, the callback takes both at once
+                let step = match f.as_ref() {
+                    IR::Lam(acc, inner) => match inner.as_ref() {
+                        IR::Lam(x, body) if acc != "_" && x != "_" => {
+                            format!("({}, {}) => {}", ident(acc), ident(x), self.expr(body, ARROW)?)
+                        }
+                        _ => self.curried(f)?,
+                    },
+                    _ => self.curried(f)?,
+                };
+                (format!("{}.reduce({}, {})", self.expr(xs, CALL)?, step, self.expr(init, ARROW)?), CALL)
+            }
+            IR::Focus(xs, w, inside, outside) => {
+                let x = self.element(xs, &[w, inside, outside]);
+                let kept = format!("[{}]", self.applied(inside, &x, ARROW)?);
+                let rest = match **outside {
+                    IR::Drop => "[]".to_string(),
+                    _ => format!("[{}]", self.applied(outside, &x, ARROW)?),
+                };
+                let test = self.applied(w, &x, TERNARY + 1)?;
+                let code = format!("{}.flatMap(({}) => {} ? {} : {})", self.expr(xs, CALL)?, ident(&x), test, kept, rest);
+                (code, CALL)
+            }
+            IR::Compose(f, g) | IR::Pipe(g, f) => {
+                let x = self.fresh("x");
+                let inner = self.applied(g, &x, ARROW)?;
+                (format!("({}) => {}({})", x, self.expr(f, CALL)?, inner), ARROW)
+            }
+            IR::Id => {
+                let x = self.fresh("x");
+                (format!("({}) => {}", x, x), ARROW)
+            }
+            IR::Const(c) => (format!("() => {}", self.expr(c, ARROW)?), ARROW),
+            IR::Drop => bail!("drop outside a focus"),
+            IR::Opaque(reason) => bail!("opaque construct: {}", reason),
+        })
+    }
+
+    fn binary(&mut self, a: &IR, op: &str, b: &IR, prec: u8) -> Result<(String, u8)> {
+        Ok((format!("{} {} {}", self.expr(a, prec)?, op, self.expr(b, prec + 1)?), prec))
+    }
+
+    /// A reduce callback for a step that isn't a two-lambda literal
+    fn curried(&mut self, f: &IR) -> Result<String> {
+        let (acc, x) = (self.fresh("acc"), self.fresh("x"));
+        Ok(format!("({}, {}) => {}({})({})", acc, x, self.expr(f, CALL)?, acc, x))
+    }
+
+    /// Curried application - a full call of `rec` calls the gene by name
+    fn call(&mut self, ir: &IR) -> Result<(String, u8)> {
+        let mut args = vec![];
+        let mut head = ir;
+        while let IR::App(f, x) = head {
+            args.push(&**x);
+            head = f;
+        }
+        args.reverse();
+
+        if matches!(head, IR::Var(x) if x == SELF) {
+            if args.len() != self.arity {
+                bail!("`{}` applied to {} arguments, takes {}", SELF, args.len(), self.arity);
+            }
+            if self.nullary {
+                return Ok((format!("{}()", self.name), CALL));
+            }
+            let args = args.into_iter().map(|a| self.expr(a, ARROW)).collect::<Result<Vec<_>>>()?;
+            return Ok((format!("{}({})", self.name, args.join(", ")), CALL));
+        }
+
+        let mut code = self.expr(head, CALL)?;
+        for arg in args {
+            code = format!("{}({})", code, self.expr(arg, ARROW)?);
+        }
+        Ok((code, CALL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::eval::{self, Value};
+    use crate::codegen::tests::{gene, map, round_trip, score};
+    use crate::extract::js;
+
+    #[test]
+    fn test_typed_module_and_declaration() {
+        let map = map();
+        assert_eq!(
+            module(&map).unwrap(),
+            "// Gene: map\n// Soul: λ0\n\n\
+             export function map<A, B>(xs: A[], f: (x: A) => B): B[] {\n  return xs.map(f);\n}\n"
+        );
+        assert_eq!(declaration(&map), "export declare function map<A, B>(xs: A[], f: (x: A) => B): B[];\n");
+
+        let untyped = gene(js::extract, "export function odd(xs) { return xs.filter(x => x % 2); }");
+        assert_eq!(declaration(&untyped), "export declare function odd(xs: any): any;\n");
+    }
+
+    #[test]
+    fn test_bindings_folds_and_recursion() {
+        let code = module(&score()).unwrap();
+        assert!(code.contains("const ys = xs.filter((x) => 0 < x).map((x) => x * k);\n  return ys.reduce((a, y) => a + y, 0);"), "{}", code);
+
+        let fib = gene(js::extract, "export function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }");
+        assert!(module(&fib).unwrap().contains("return n < 2 ? n : fib(n - 1) + fib(n - 2);"));
+        assert_eq!(eval::run(&round_trip(module, js::extract, &fib), vec![Value::Num(15)]).unwrap(), Value::Num(610));
+    }
+
+    #[test]
+    fn test_focus_is_one_loop() {
+        // λxs.(focus xs λx.(< x 3) λx.(* x 10) drop)
+        let ir = r#"{"Lam":["xs",{"Focus":[{"Var":"xs"},{"Lam":["x",{"Lt":[{"Var":"x"},{"Num":3}]}]},{"Lam":["x",{"Mul":[{"Var":"x"},{"Num":10}]}]},"Drop"]}]}"#;
+        let focus = Export { name: "small".to_string(), soul: "λ0".to_string(), ir: serde_json::from_str(ir).unwrap() };
+        let code = module(&focus).unwrap();
+        assert_eq!(
+            code,
+            "// Gene: small\n// Soul: λ0\n\n\
+             export function small(xs: number[]): number[] {\n\
+             \x20 const out: number[] = [];\n\
+             \x20 for (const x of xs) {\n\
+             \x20   if (x < 3) {\n\
+             \x20     out.push(x * 10);\n\
+             \x20   }\n\
+             \x20 }\n\
+             \x20 return out;\n\
+             }\n"
+        );
+        assert_eq!(module(&focus).unwrap(), code);
+
+        // Under a lambda it's a flatMap
+        let ir = IR::Lam("xss".into(), Box::new(IR::Map(Box::new(IR::Var("xss".into())), Box::new(focus.ir.clone()))));
+        let nested = Export { name: "nested".to_string(), soul: "λ0".to_string(), ir };
+        let code = module(&nested).unwrap();
+        assert!(code.contains("return xss.map((xs) => xs.flatMap((x) => x < 3 ? [x * 10] : []));"), "{}", code);
+    }
+}
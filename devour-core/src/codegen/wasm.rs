@@ -0,0 +1,902 @@
+// λ-IR to a core WebAssembly module
+//
+// Every value is one i64 word: numbers (f64 bits under `Numbers::F64`),
+// booleans as 0 and 1, and addresses in linear memory for everything else.
+// A list is 0 (nil) or a 16-byte cons cell [head, tail]. A closure is
+// [table index, captures...], called through the table with itself as its
+// environment. Strings are [length, bytes] in the data segment. Memory comes
+// from a bump allocator that never frees - genes are pure, so an instance is
+// cheap to throw away.
+//
+// Each gene is exported under its name, uncurried over its leading lambdas.
+// `memory`, `alloc` and `cons` are exported for hosts building arguments.
+
+use anyhow::{bail, Result};
+
+use super::{params, Export};
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+/// What `Num` and arithmetic mean in a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Numbers {
+    #[default]
+    I64,
+    F64,
+}
+
+// Functions every module starts with - the combinators are closure bodies,
+// and the table starts with them in this order
+const ALLOC: u32 = 0;
+const CONS: u32 = 1;
+const ID: u32 = 2;
+const CONST: u32 = 3;
+const COMPOSE: u32 = 4;
+const PIPE: u32 = 5;
+const RUNTIME: u32 = 6;
+
+/// Functions of n i64 words returning one have type index n - closure
+/// bodies take (environment, argument)
+const CLOSURE: u32 = 2;
+
+/// Where strings start - address 0 is nil
+const DATA: u32 = 8;
+
+/// Compile `genes` to a module exporting each under its name
+pub fn compile(genes: &[Export], numbers: Numbers) -> Result<Vec<u8>> {
+    let mut module = Module {
+        numbers,
+        funcs: runtime(),
+        lifted: vec![],
+        table: vec![ID, CONST, COMPOSE, PIPE],
+        data: vec![],
+        arities: genes.iter().map(|gene| params(&gene.ir).0.len() as u32).collect(),
+    };
+    for (i, gene) in genes.iter().enumerate() {
+        let (names, body) = params(&gene.ir);
+        let mut function = Body::new(names.len() as u32, (RUNTIME + i as u32, names.len() as u32));
+        for (i, name) in names.iter().enumerate() {
+            function.scope.push((name.to_string(), Slot::Local(i as u32)));
+        }
+        module.expr(&mut function, body).map_err(|e| e.context(format!("compiling {}", gene.name)))?;
+        module.funcs.push(function);
+    }
+    let lifted = std::mem::take(&mut module.lifted);
+    module.funcs.extend(lifted);
+    Ok(module.finish(genes))
+}
+
+struct Module {
+    numbers: Numbers,
+    /// Runtime, then one per gene
+    funcs: Vec<Body>,
+    /// Closure bodies, placed after the genes
+    lifted: Vec<Body>,
+    /// Function index per table slot
+    table: Vec<u32>,
+    data: Vec<u8>,
+    /// Parameters of each gene's function
+    arities: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Local(u32),
+    /// Word k of the closure's captures
+    Captured(u32),
+}
+
+/// How a traversal applies its function to each element
+enum Applier<'a> {
+    /// A literal lambda - its body runs in the loop
+    Inline(&'a str, &'a IR),
+    /// Any other function value, held in a local
+    Closure(u32),
+}
+
+impl Module {
+    fn expr(&mut self, b: &mut Body, ir: &IR) -> Result<()> {
+        match ir {
+            IR::Var(x) => b.var(x)?,
+            IR::Num(n) => {
+                b.i64_const(match self.numbers {
+                    Numbers::I64 => *n,
+                    Numbers::F64 => (*n as f64).to_bits() as i64,
+                });
+            }
+            IR::Bool(v) => {
+                b.i64_const(*v as i64);
+            }
+            IR::Nil => {
+                b.i64_const(0);
+            }
+            IR::Str(s) => {
+                let address = self.string(s);
+                b.i64_const(address);
+            }
+            IR::Cons(h, t) => {
+                self.expr(b, h)?;
+                self.expr(b, t)?;
+                b.call(CONS);
+            }
+            IR::If(c, t, e) => {
+                self.expr(b, c)?;
+                b.truthy();
+                b.op(op::IF).op(op::I64);
+                self.expr(b, t)?;
+                b.op(op::ELSE);
+                self.expr(b, e)?;
+                b.op(op::END);
+            }
+            IR::Add(x, y) | IR::Sub(x, y) | IR::Mul(x, y) | IR::Div(x, y) | IR::Mod(x, y)
+            | IR::Eq(x, y) | IR::Lt(x, y) | IR::Le(x, y) => self.arithmetic(b, ir, x, y)?,
+            IR::Lam(..) => self.closure(b, ir)?,
+            IR::App(..) => self.apply(b, ir)?,
+            IR::Map(xs, f) => self.traverse(b, xs, Some(f), None, None)?,
+            IR::Filter(xs, p) => self.traverse(b, xs, None, Some(p), None)?,
+            IR::Focus(xs, w, f, g) => {
+                let g = (!matches!(**g, IR::Drop)).then_some(&**g);
+                self.traverse(b, xs, Some(f), Some(w), Some(g))?
+            }
+            IR::Reduce(xs, f, init) => self.fold(b, xs, f, init)?,
+            IR::Id => self.combinator(b, ID, &[])?,
+            IR::Const(x) => self.combinator(b, CONST, &[x])?,
+            IR::Compose(f, g) => self.combinator(b, COMPOSE, &[f, g])?,
+            IR::Pipe(f, g) => self.combinator(b, PIPE, &[f, g])?,
+            IR::Drop => bail!("drop outside a focus"),
+            IR::Opaque(reason) => bail!("opaque construct: {}", reason),
+        }
+        Ok(())
+    }
+
+    fn arithmetic(&mut self, b: &mut Body, ir: &IR, x: &IR, y: &IR) -> Result<()> {
+        self.expr(b, x)?;
+        if self.numbers == Numbers::F64 && matches!(ir, IR::Mod(..)) {
+            // No f64 remainder instruction: x - y * trunc(x / y)
+            let (tx, ty) = (b.temp(), b.temp());
+            b.local_set(tx);
+            self.expr(b, y)?;
+            b.local_set(ty);
+            for local in [tx, tx, ty] {
+                b.local_get(local).op(op::F64_REINTERPRET_I64);
+            }
+            b.op(op::F64_DIV).op(op::F64_TRUNC);
+            b.local_get(ty).op(op::F64_REINTERPRET_I64);
+            b.op(op::F64_MUL).op(op::F64_SUB).op(op::I64_REINTERPRET_F64);
+            return Ok(());
+        }
+        let float = self.numbers == Numbers::F64;
+        if float {
+            b.op(op::F64_REINTERPRET_I64);
+        }
+        self.expr(b, y)?;
+        if float {
+            b.op(op::F64_REINTERPRET_I64);
+        }
+        let (instruction, compares) = match (ir, float) {
+            (IR::Add(..), false) => (op::I64_ADD, false),
+            (IR::Sub(..), false) => (op::I64_SUB, false),
+            (IR::Mul(..), false) => (op::I64_MUL, false),
+            (IR::Div(..), false) => (op::I64_DIV_S, false),
+            (IR::Mod(..), false) => (op::I64_REM_S, false),
+            (IR::Eq(..), false) => (op::I64_EQ, true),
+            (IR::Lt(..), false) => (op::I64_LT_S, true),
+            (IR::Le(..), false) => (op::I64_LE_S, true),
+            (IR::Add(..), true) => (op::F64_ADD, false),
+            (IR::Sub(..), true) => (op::F64_SUB, false),
+            (IR::Mul(..), true) => (op::F64_MUL, false),
+            (IR::Div(..), true) => (op::F64_DIV, false),
+            (IR::Eq(..), true) => (op::F64_EQ, true),
+            (IR::Lt(..), true) => (op::F64_LT, true),
+            _ => (op::F64_LE, true),
+        };
+        b.op(instruction);
+        if compares {
+            b.op(op::I64_EXTEND_I32_U);
+        } else if float {
+            b.op(op::I64_REINTERPRET_F64);
+        }
+        Ok(())
+    }
+
+    /// Curried application - a full call of `rec` calls the gene directly
+    fn apply(&mut self, b: &mut Body, ir: &IR) -> Result<()> {
+        let mut args = vec![];
+        let mut head = ir;
+        while let IR::App(f, x) = head {
+            args.push(&**x);
+            head = f;
+        }
+        args.reverse();
+
+        if matches!(head, IR::Var(x) if x == SELF) && b.lookup(SELF).is_none() {
+            let (function, arity) = b.gene;
+            if args.len() as u32 != arity {
+                bail!("`{}` applied to {} arguments, takes {}", SELF, args.len(), arity);
+            }
+            for arg in args {
+                self.expr(b, arg)?;
+            }
+            b.call(function);
+            return Ok(());
+        }
+
+        let (closure, arg) = (b.temp(), b.temp());
+        self.expr(b, head)?;
+        b.local_set(closure);
+        for (i, x) in args.iter().enumerate() {
+            self.expr(b, x)?;
+            b.local_set(arg);
+            b.call_closure(closure, arg);
+            if i + 1 < args.len() {
+                b.local_set(closure);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lift a lambda into the table and allocate its closure
+    fn closure(&mut self, b: &mut Body, ir: &IR) -> Result<()> {
+        let IR::Lam(x, body) = ir else { unreachable!() };
+        let captures = free(ir);
+        for name in &captures {
+            if b.lookup(name).is_none() {
+                bail!("free variable `{}`", name);
+            }
+        }
+
+        let mut lifted = Body::new(2, b.gene);
+        for (k, name) in captures.iter().enumerate() {
+            lifted.scope.push((name.clone(), Slot::Captured(k as u32)));
+        }
+        lifted.scope.push((x.clone(), Slot::Local(1)));
+        self.expr(&mut lifted, body)?;
+
+        let function = RUNTIME + self.arities.len() as u32 + self.lifted.len() as u32;
+        self.lifted.push(lifted);
+        let slot = self.table.len() as i64;
+        self.table.push(function);
+
+        let object = b.temp();
+        b.i64_const(8 * (1 + captures.len() as i64)).call(ALLOC).local_set(object);
+        b.local_get(object).op(op::I32_WRAP_I64).i64_const(slot).store(0);
+        for (k, name) in captures.iter().enumerate() {
+            b.local_get(object).op(op::I32_WRAP_I64);
+            b.var(name)?;
+            b.store(8 * (k as u32 + 1));
+        }
+        b.local_get(object);
+        Ok(())
+    }
+
+    /// Closure over a built-in body - parts are evaluated before allocating
+    fn combinator(&mut self, b: &mut Body, function: u32, parts: &[&IR]) -> Result<()> {
+        let mut values = vec![];
+        for part in parts {
+            self.expr(b, part)?;
+            let value = b.temp();
+            b.local_set(value);
+            values.push(value);
+        }
+        let object = b.temp();
+        b.i64_const(8 * (1 + parts.len() as i64)).call(ALLOC).local_set(object);
+        b.local_get(object).op(op::I32_WRAP_I64).i64_const((function - ID) as i64).store(0);
+        for (k, value) in values.into_iter().enumerate() {
+            b.local_get(object).op(op::I32_WRAP_I64).local_get(value).store(8 * (k as u32 + 1));
+        }
+        b.local_get(object);
+        Ok(())
+    }
+
+    fn applier<'a>(&mut self, b: &mut Body, f: &'a IR) -> Result<Applier<'a>> {
+        if let IR::Lam(x, body) = f {
+            return Ok(Applier::Inline(x, body));
+        }
+        self.expr(b, f)?;
+        let closure = b.temp();
+        b.local_set(closure);
+        Ok(Applier::Closure(closure))
+    }
+
+    fn call(&mut self, b: &mut Body, applier: &Applier, arg: u32) -> Result<()> {
+        match applier {
+            Applier::Inline(x, body) => {
+                b.scope.push((x.to_string(), Slot::Local(arg)));
+                self.expr(b, body)?;
+                b.scope.pop();
+            }
+            Applier::Closure(closure) => b.call_closure(*closure, arg),
+        }
+        Ok(())
+    }
+
+    /// One loop over a list, building the result in order: `f` maps, `gate`
+    /// keeps, both together focus - `outside` maps what the gate rejects,
+    /// `Some(None)` drops it
+    fn traverse(&mut self, b: &mut Body, xs: &IR, f: Option<&IR>, gate: Option<&IR>, outside: Option<Option<&IR>>) -> Result<()> {
+        let f = f.map(|f| self.applier(b, f)).transpose()?;
+        let gate = gate.map(|p| self.applier(b, p)).transpose()?;
+        let outside = outside.flatten().map(|g| self.applier(b, g)).transpose()?;
+
+        let (list, x, result, last, cell) = (b.temp(), b.temp(), b.temp(), b.temp(), b.temp());
+        self.expr(b, xs)?;
+        b.local_set(list);
+        b.i64_const(0).local_set(result);
+        b.i64_const(0).local_set(last);
+        b.op(op::BLOCK).op(op::EMPTY).op(op::LOOP).op(op::EMPTY);
+        b.local_get(list).op(op::I64_EQZ).op(op::BR_IF).u32(1);
+        b.local_get(list).op(op::I32_WRAP_I64).load(0).local_set(x);
+
+        let append = |b: &mut Body| {
+            b.i64_const(0).call(CONS).local_set(cell);
+            b.local_get(last).op(op::I64_EQZ).op(op::IF).op(op::EMPTY);
+            b.local_get(cell).local_set(result);
+            b.op(op::ELSE);
+            b.local_get(last).op(op::I32_WRAP_I64).local_get(cell).store(8);
+            b.op(op::END);
+            b.local_get(cell).local_set(last);
+        };
+        let element = |module: &mut Module, b: &mut Body, applier: &Option<Applier>| match applier {
+            Some(applier) => module.call(b, applier, x),
+            None => {
+                b.local_get(x);
+                Ok(())
+            }
+        };
+        match &gate {
+            None => {
+                element(self, b, &f)?;
+                append(b);
+            }
+            Some(gate) => {
+                self.call(b, gate, x)?;
+                b.truthy();
+                b.op(op::IF).op(op::EMPTY);
+                element(self, b, &f)?;
+                append(b);
+                if outside.is_some() {
+                    b.op(op::ELSE);
+                    element(self, b, &outside)?;
+                    append(b);
+                }
+                b.op(op::END);
+            }
+        }
+
+        b.local_get(list).op(op::I32_WRAP_I64).load(8).local_set(list);
+        b.op(op::BR).u32(0).op(op::END).op(op::END);
+        b.local_get(result);
+        Ok(())
+    }
+
+    /// Left fold - `f` takes the accumulator, then the element
+    fn fold(&mut self, b: &mut Body, xs: &IR, f: &IR, init: &IR) -> Result<()> {
+        let inline = match f {
+            IR::Lam(acc, inner) => match &**inner {
+                IR::Lam(x, body) => Some((acc.as_str(), x.as_str(), &**body)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let closure = match inline {
+            Some(_) => None,
+            None => Some(self.applier(b, f)?),
+        };
+
+        let (list, x, acc, partial) = (b.temp(), b.temp(), b.temp(), b.temp());
+        self.expr(b, xs)?;
+        b.local_set(list);
+        self.expr(b, init)?;
+        b.local_set(acc);
+        b.op(op::BLOCK).op(op::EMPTY).op(op::LOOP).op(op::EMPTY);
+        b.local_get(list).op(op::I64_EQZ).op(op::BR_IF).u32(1);
+        b.local_get(list).op(op::I32_WRAP_I64).load(0).local_set(x);
+        match (inline, &closure) {
+            (Some((acc_name, x_name, body)), _) => {
+                b.scope.push((acc_name.to_string(), Slot::Local(acc)));
+                b.scope.push((x_name.to_string(), Slot::Local(x)));
+                self.expr(b, body)?;
+                b.scope.truncate(b.scope.len() - 2);
+            }
+            (None, Some(applier)) => {
+                self.call(b, applier, acc)?;
+                b.local_set(partial);
+                b.call_closure(partial, x);
+            }
+            (None, None) => unreachable!(),
+        }
+        b.local_set(acc);
+        b.local_get(list).op(op::I32_WRAP_I64).load(8).local_set(list);
+        b.op(op::BR).u32(0).op(op::END).op(op::END);
+        b.local_get(acc);
+        Ok(())
+    }
+
+    /// Address of `s` in the data segment
+    fn string(&mut self, s: &str) -> i64 {
+        let address = DATA as i64 + self.data.len() as i64;
+        self.data.extend_from_slice(&(s.len() as i64).to_le_bytes());
+        self.data.extend_from_slice(s.as_bytes());
+        self.data.resize(self.data.len().next_multiple_of(8), 0);
+        address
+    }
+
+    fn finish(self, genes: &[Export]) -> Vec<u8> {
+        let arity = |f: &Body| f.params;
+        let max_arity = self.funcs.iter().map(arity).max().unwrap_or(0).max(CLOSURE);
+        let heap = DATA as u64 + self.data.len() as u64;
+        let pages = (heap + 1).div_ceil(65536).max(1);
+
+        let mut out = b"\0asm".to_vec();
+        out.extend_from_slice(&1u32.to_le_bytes());
+
+        section(&mut out, 1, (0..=max_arity).map(|n| {
+            let mut ty = vec![0x60];
+            vec_of(&mut ty, (0..n).map(|_| vec![op::I64]));
+            vec_of(&mut ty, [vec![op::I64]]);
+            ty
+        }));
+        section(&mut out, 3, self.funcs.iter().map(|f| uleb(arity(f) as u64)));
+        section(&mut out, 4, [[vec![0x70, 0x01], uleb(self.table.len() as u64), uleb(self.table.len() as u64)].concat()]);
+        section(&mut out, 5, [[vec![0x00], uleb(pages)].concat()]);
+        section(&mut out, 6, [[vec![op::I64, 0x01, op::I64_CONST], sleb(heap as i64), vec![op::END]].concat()]);
+
+        let mut exports = vec![
+            [name("memory"), vec![0x02], uleb(0)].concat(),
+            [name("alloc"), vec![0x00], uleb(ALLOC as u64)].concat(),
+            [name("cons"), vec![0x00], uleb(CONS as u64)].concat(),
+        ];
+        for (i, gene) in genes.iter().enumerate() {
+            exports.push([name(&gene.name), vec![0x00], uleb((RUNTIME + i as u32) as u64)].concat());
+        }
+        section(&mut out, 7, exports);
+
+        let mut element = vec![0x00, op::I32_CONST, 0x00, op::END];
+        vec_of(&mut element, self.table.iter().map(|f| uleb(*f as u64)));
+        section(&mut out, 9, [element]);
+
+        section(&mut out, 10, self.funcs.iter().map(|f| {
+            let mut entry = vec![];
+            let extra = f.locals - f.params;
+            if extra > 0 {
+                vec_of(&mut entry, [[uleb(extra as u64), vec![op::I64]].concat()]);
+            } else {
+                entry.push(0);
+            }
+            entry.extend_from_slice(&f.code);
+            entry.push(op::END);
+            [uleb(entry.len() as u64), entry].concat()
+        }));
+
+        let mut data = vec![0x00, op::I32_CONST];
+        data.extend(sleb(DATA as i64));
+        data.push(op::END);
+        data.extend(uleb(self.data.len() as u64));
+        data.extend_from_slice(&self.data);
+        section(&mut out, 11, [data]);
+        out
+    }
+}
+
+/// Names free in `ir`, first occurrence first - `rec` is called, not captured
+fn free(ir: &IR) -> Vec<String> {
+    fn walk(ir: &IR, bound: &mut Vec<String>, free: &mut Vec<String>) {
+        match ir {
+            IR::Var(x) if x != SELF && !bound.contains(x) && !free.contains(x) => free.push(x.clone()),
+            IR::Lam(x, body) => {
+                bound.push(x.clone());
+                walk(body, bound, free);
+                bound.pop();
+            }
+            _ => {
+                for child in ir.children() {
+                    walk(child, bound, free);
+                }
+            }
+        }
+    }
+    let mut free = vec![];
+    walk(ir, &mut vec![], &mut free);
+    free
+}
+
+/// Runtime functions, in index order
+fn runtime() -> Vec<Body> {
+    let none = (0, 0);
+
+    // alloc(size): bump the heap by size rounded up to a word, growing memory
+    let mut alloc = Body::new(1, none);
+    let ptr = alloc.temp();
+    alloc.op(op::GLOBAL_GET).u32(0).local_tee(ptr);
+    alloc.local_get(0).i64_const(7).op(op::I64_ADD).i64_const(-8).op(op::I64_AND).op(op::I64_ADD);
+    alloc.op(op::GLOBAL_SET).u32(0);
+    alloc.op(op::BLOCK).op(op::EMPTY);
+    alloc.op(op::GLOBAL_GET).u32(0).op(op::MEMORY_SIZE).u32(0).op(op::I64_EXTEND_I32_U).i64_const(16).op(op::I64_SHL);
+    alloc.op(op::I64_LE_U).op(op::BR_IF).u32(0);
+    alloc.op(op::GLOBAL_GET).u32(0).i64_const(65535).op(op::I64_ADD).i64_const(16).op(op::I64_SHR_U);
+    alloc.op(op::MEMORY_SIZE).u32(0).op(op::I64_EXTEND_I32_U).op(op::I64_SUB).op(op::I32_WRAP_I64);
+    alloc.op(op::MEMORY_GROW).u32(0).op(op::I32_CONST).sleb(-1).op(op::I32_EQ);
+    alloc.op(op::IF).op(op::EMPTY).op(op::UNREACHABLE).op(op::END);
+    alloc.op(op::END);
+    alloc.local_get(ptr);
+
+    // cons(head, tail)
+    let mut cons = Body::new(2, none);
+    let cell = cons.temp();
+    cons.i64_const(16).call(ALLOC).local_set(cell);
+    cons.local_get(cell).op(op::I32_WRAP_I64).local_get(0).store(0);
+    cons.local_get(cell).op(op::I32_WRAP_I64).local_get(1).store(8);
+    cons.local_get(cell);
+
+    let mut id = Body::new(2, none);
+    id.local_get(1);
+
+    let mut constant = Body::new(2, none);
+    constant.local_get(0).op(op::I32_WRAP_I64).load(8);
+
+    // (∘ f g) applies g first, (|> f g) applies f first
+    let pair = |first: u32, second: u32| {
+        let mut body = Body::new(2, none);
+        let (f, y) = (body.temp(), body.temp());
+        body.local_get(0).op(op::I32_WRAP_I64).load(first).local_set(f);
+        body.call_closure(f, 1);
+        body.local_set(y);
+        body.local_get(0).op(op::I32_WRAP_I64).load(second).local_set(f);
+        body.call_closure(f, y);
+        body
+    };
+
+    vec![alloc, cons, id, constant, pair(16, 8), pair(8, 16)]
+}
+
+/// A function body being emitted
+struct Body {
+    code: Vec<u8>,
+    params: u32,
+    /// Params first, then i64 temporaries
+    locals: u32,
+    /// Names in scope, innermost last
+    scope: Vec<(String, Slot)>,
+    /// Function index and arity of the gene this body belongs to
+    gene: (u32, u32),
+}
+
+impl Body {
+    fn new(params: u32, gene: (u32, u32)) -> Self {
+        Body { code: vec![], params, locals: params, scope: vec![], gene }
+    }
+
+    fn temp(&mut self) -> u32 {
+        self.locals += 1;
+        self.locals - 1
+    }
+
+    fn lookup(&self, name: &str) -> Option<Slot> {
+        self.scope.iter().rev().find(|(n, _)| n == name).map(|(_, slot)| *slot)
+    }
+
+    fn var(&mut self, name: &str) -> Result<()> {
+        match self.lookup(name) {
+            Some(Slot::Local(i)) => {
+                self.local_get(i);
+            }
+            Some(Slot::Captured(k)) => {
+                self.local_get(0).op(op::I32_WRAP_I64).load(8 * (k + 1));
+            }
+            None if name == SELF => bail!("`{}` only as a full call", SELF),
+            None => bail!("free variable `{}`", name),
+        }
+        Ok(())
+    }
+
+    fn op(&mut self, byte: u8) -> &mut Self {
+        self.code.push(byte);
+        self
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.code.extend(uleb(value as u64));
+        self
+    }
+
+    fn sleb(&mut self, value: i64) -> &mut Self {
+        self.code.extend(sleb(value));
+        self
+    }
+
+    fn i64_const(&mut self, value: i64) -> &mut Self {
+        self.op(op::I64_CONST).sleb(value)
+    }
+
+    fn local_get(&mut self, local: u32) -> &mut Self {
+        self.op(op::LOCAL_GET).u32(local)
+    }
+
+    fn local_set(&mut self, local: u32) -> &mut Self {
+        self.op(op::LOCAL_SET).u32(local)
+    }
+
+    fn local_tee(&mut self, local: u32) -> &mut Self {
+        self.op(op::LOCAL_TEE).u32(local)
+    }
+
+    fn call(&mut self, function: u32) -> &mut Self {
+        self.op(op::CALL).u32(function)
+    }
+
+    /// i64 at address + offset, address on the stack as i32
+    fn load(&mut self, offset: u32) -> &mut Self {
+        self.op(op::I64_LOAD).u32(3).u32(offset)
+    }
+
+    /// Store the word on the stack at address + offset
+    fn store(&mut self, offset: u32) -> &mut Self {
+        self.op(op::I64_STORE).u32(3).u32(offset)
+    }
+
+    /// Word on the stack to an i32 condition
+    fn truthy(&mut self) -> &mut Self {
+        self.i64_const(0).op(op::I64_NE)
+    }
+
+    /// Call the closure in `closure` with the word in `arg`
+    fn call_closure(&mut self, closure: u32, arg: u32) {
+        self.local_get(closure).local_get(arg);
+        self.local_get(closure).op(op::I32_WRAP_I64).load(0).op(op::I32_WRAP_I64);
+        self.op(op::CALL_INDIRECT).u32(CLOSURE).u32(0);
+    }
+}
+
+fn uleb(mut value: u64) -> Vec<u8> {
+    let mut out = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn sleb(mut value: i64) -> Vec<u8> {
+    let mut out = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn name(s: &str) -> Vec<u8> {
+    [uleb(s.len() as u64), s.as_bytes().to_vec()].concat()
+}
+
+fn vec_of(out: &mut Vec<u8>, items: impl IntoIterator<Item = Vec<u8>>) {
+    let items: Vec<_> = items.into_iter().collect();
+    out.extend(uleb(items.len() as u64));
+    for item in items {
+        out.extend(item);
+    }
+}
+
+fn section(out: &mut Vec<u8>, id: u8, items: impl IntoIterator<Item = Vec<u8>>) {
+    let mut content = vec![];
+    vec_of(&mut content, items);
+    out.push(id);
+    out.extend(uleb(content.len() as u64));
+    out.extend(content);
+}
+
+/// Opcodes and types used
+mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0b;
+    pub const BR: u8 = 0x0c;
+    pub const BR_IF: u8 = 0x0d;
+    pub const CALL: u8 = 0x10;
+    pub const CALL_INDIRECT: u8 = 0x11;
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const LOCAL_TEE: u8 = 0x22;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+    pub const I64_LOAD: u8 = 0x29;
+    pub const I64_STORE: u8 = 0x37;
+    pub const MEMORY_SIZE: u8 = 0x3f;
+    pub const MEMORY_GROW: u8 = 0x40;
+    pub const I32_CONST: u8 = 0x41;
+    pub const I64_CONST: u8 = 0x42;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I64_EQZ: u8 = 0x50;
+    pub const I64_EQ: u8 = 0x51;
+    pub const I64_NE: u8 = 0x52;
+    pub const I64_LT_S: u8 = 0x53;
+    pub const I64_LE_S: u8 = 0x57;
+    pub const I64_LE_U: u8 = 0x58;
+    pub const F64_EQ: u8 = 0x61;
+    pub const F64_LT: u8 = 0x63;
+    pub const F64_LE: u8 = 0x65;
+    pub const I64_ADD: u8 = 0x7c;
+    pub const I64_SUB: u8 = 0x7d;
+    pub const I64_MUL: u8 = 0x7e;
+    pub const I64_DIV_S: u8 = 0x7f;
+    pub const I64_REM_S: u8 = 0x81;
+    pub const I64_AND: u8 = 0x83;
+    pub const I64_SHL: u8 = 0x86;
+    pub const I64_SHR_U: u8 = 0x88;
+    pub const F64_TRUNC: u8 = 0x9d;
+    pub const F64_ADD: u8 = 0xa0;
+    pub const F64_SUB: u8 = 0xa1;
+    pub const F64_MUL: u8 = 0xa2;
+    pub const F64_DIV: u8 = 0xa3;
+    pub const I32_WRAP_I64: u8 = 0xa7;
+    pub const I64_EXTEND_I32_U: u8 = 0xad;
+    pub const I64_REINTERPRET_F64: u8 = 0xbd;
+    pub const F64_REINTERPRET_I64: u8 = 0xbf;
+
+    /// Value and block types
+    pub const I64: u8 = 0x7e;
+    pub const EMPTY: u8 = 0x40;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::tests::map_vectors;
+    use wasmtime::{Engine, Instance, Store};
+
+    struct Organism {
+        store: Store<()>,
+        instance: Instance,
+    }
+
+    impl Organism {
+        fn new(genes: &[Export], numbers: Numbers) -> Self {
+            let engine = Engine::default();
+            let module = wasmtime::Module::new(&engine, compile(genes, numbers).unwrap()).unwrap();
+            let mut store = Store::new(&engine, ());
+            let instance = Instance::new(&mut store, &module, &[]).unwrap();
+            Organism { store, instance }
+        }
+
+        fn call(&mut self, name: &str, args: &[i64]) -> i64 {
+            let func = self.instance.get_func(&mut self.store, name).unwrap();
+            let args: Vec<_> = args.iter().map(|a| wasmtime::Val::I64(*a)).collect();
+            let mut result = [wasmtime::Val::I64(0)];
+            func.call(&mut self.store, &args, &mut result).unwrap();
+            result[0].unwrap_i64()
+        }
+
+        fn list(&mut self, xs: &[i64]) -> i64 {
+            xs.iter().rev().fold(0, |tail, x| self.call("cons", &[*x, tail]))
+        }
+
+        fn read(&mut self, mut list: i64) -> Vec<i64> {
+            let memory = self.instance.get_memory(&mut self.store, "memory").unwrap();
+            let word = |address: i64| {
+                let mut bytes = [0; 8];
+                memory.read(&self.store, address as usize, &mut bytes).unwrap();
+                i64::from_le_bytes(bytes)
+            };
+            let mut out = vec![];
+            while list != 0 {
+                out.push(word(list));
+                list = word(list + 8);
+            }
+            out
+        }
+    }
+
+    fn js(source: &str) -> Vec<Export> {
+        let functions = crate::extract::js::extract(source).unwrap();
+        crate::codegen::exports(functions.iter().map(|f| (f.name.as_str(), "λ", f.ir.as_str())))
+    }
+
+    fn gene(name: &str, ir: &str) -> Export {
+        Export { name: name.to_string(), soul: String::new(), ir: serde_json::from_str(ir).unwrap() }
+    }
+
+    /// Numbers and booleans as the module holds them, `None` for anything else
+    fn words(value: &serde_json::Value) -> Option<Vec<i64>> {
+        value.as_array()?.iter().map(|v| v.as_i64().or(v.as_bool().map(i64::from))).collect()
+    }
+
+    #[test]
+    fn test_map_vectors() {
+        let mut ran = 0;
+        for vector in map_vectors()["vectors"].as_array().unwrap() {
+            let (Some(xs), Some(expected)) = (words(&vector["input"]["xs"]), words(&vector["output"])) else {
+                continue;
+            };
+            let f = vector["input"]["f"].as_str().unwrap();
+            let mut organism = Organism::new(
+                &js(&format!("export function map(xs) {{ return xs.map({}); }}", f)),
+                Numbers::I64,
+            );
+            let list = organism.list(&xs);
+            let result = organism.call("map", &[list]);
+            assert_eq!(organism.read(result), expected, "{}", vector["name"]);
+            ran += 1;
+        }
+        assert!(ran >= 3);
+    }
+
+    #[test]
+    fn test_recursion_and_folds() {
+        let mut organism = Organism::new(
+            &js("export function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }\n\
+                 export function scale(xs, k) { return xs.map(x => x * k).filter(x => x % 2 === 0).reduce((a, x) => a + x, 0); }"),
+            Numbers::I64,
+        );
+        assert_eq!(organism.call("fib", &[20]), 6765);
+        let xs = organism.list(&[1, 2, 3, 4]);
+        assert_eq!(organism.call("scale", &[xs, 3]), 18);
+    }
+
+    #[test]
+    fn test_closures_through_the_table() {
+        let mut organism = Organism::new(
+            &[
+                // λxs.λk.(map xs (∘ λx.(+ x k) λx.(* x 2)))
+                gene("shift", r#"{"Lam":["xs",{"Lam":["k",{"Map":[{"Var":"xs"},{"Compose":[{"Lam":["x",{"Add":[{"Var":"x"},{"Var":"k"}]}]},{"Lam":["x",{"Mul":[{"Var":"x"},{"Num":2}]}]}]}]}]}]}"#),
+                // λk.λx.((λf.(f (f x))) λy.(+ y k))
+                gene("twice", r#"{"Lam":["k",{"Lam":["x",{"App":[{"Lam":["f",{"App":[{"Var":"f"},{"App":[{"Var":"f"},{"Var":"x"}]}]}]},{"Lam":["y",{"Add":[{"Var":"y"},{"Var":"k"}]}]}]}]}]}"#),
+                // λxs.(reduce xs (const id) 0) - a fold through closures keeps the last
+                gene("last", r#"{"Lam":["xs",{"Reduce":[{"Var":"xs"},{"Const":"Id"},{"Num":0}]}]}"#),
+            ],
+            Numbers::I64,
+        );
+        let xs = organism.list(&[1, 2, 3]);
+        let shifted = organism.call("shift", &[xs, 10]);
+        assert_eq!(organism.read(shifted), vec![12, 14, 16]);
+        assert_eq!(organism.call("twice", &[3, 1]), 7);
+        let xs = organism.list(&[4, 8, 9]);
+        assert_eq!(organism.call("last", &[xs]), 9);
+    }
+
+    #[test]
+    fn test_focus_keeps_order() {
+        let inside = r#"{"Lam":["x",{"Lt":[{"Var":"x"},{"Num":3}]}]},{"Lam":["x",{"Mul":[{"Var":"x"},{"Num":10}]}]}"#;
+        let mut organism = Organism::new(
+            &[
+                gene("dropping", &format!(r#"{{"Lam":["xs",{{"Focus":[{{"Var":"xs"}},{},"Drop"]}}]}}"#, inside)),
+                gene("negating", &format!(r#"{{"Lam":["xs",{{"Focus":[{{"Var":"xs"}},{},{{"Lam":["x",{{"Sub":[{{"Num":0}},{{"Var":"x"}}]}}]}}]}}]}}"#, inside)),
+            ],
+            Numbers::I64,
+        );
+        let xs = organism.list(&[1, 5, 2, 7]);
+        let dropped = organism.call("dropping", &[xs]);
+        assert_eq!(organism.read(dropped), vec![10, 20]);
+        let negated = organism.call("negating", &[xs]);
+        assert_eq!(organism.read(negated), vec![10, -5, 20, -7]);
+    }
+
+    #[test]
+    fn test_f64_numbers() {
+        // λx.λy.(+ (/ x y) (% x 2))
+        let mut organism = Organism::new(
+            &[gene("run", r#"{"Lam":["x",{"Lam":["y",{"Add":[{"Div":[{"Var":"x"},{"Var":"y"}]},{"Mod":[{"Var":"x"},{"Num":2}]}]}]}]}"#)],
+            Numbers::F64,
+        );
+        let result = organism.call("run", &[5f64.to_bits() as i64, 2f64.to_bits() as i64]);
+        assert_eq!(f64::from_bits(result as u64), 3.5);
+    }
+
+    #[test]
+    fn test_rejects_what_it_cannot_lower() {
+        let opaque = gene("run", r#"{"Lam":["x",{"Opaque":"x.length"}]}"#);
+        assert!(compile(&[opaque], Numbers::I64).is_err());
+        let free = gene("run", r#"{"Lam":["x",{"Var":"y"}]}"#);
+        assert!(compile(&[free], Numbers::I64).is_err());
+    }
+}
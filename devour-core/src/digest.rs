@@ -1,23 +1,44 @@
 use anyhow::Result;
-use rayon::prelude::*;
+use futures::stream::{self, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use walkdir::WalkDir;
 use blake3::Hasher;
 
-use crate::storage::{Store, Gene, GeneMetrics};
-use crate::runtime::ExtractorSet;
+use crate::effects::Effects;
+use crate::ingest::{self, lineage, LocalSource, Snapshot};
+use crate::storage::{self, FileDigest, Store, Gene, GeneMetrics, Span};
+use crate::runtime::{ExtractorSet, SandboxLimits};
+use crate::surgeon::cost::{self, CostModel};
+use crate::surgeon::egraph::IR;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct DigestStats {
     pub total: usize,
     pub unique: usize,
-    pub failed: usize,
+    /// Files no genes could be extracted from, and why
+    pub failed: Vec<FileError>,
+    /// Genes of unchanged files, not re-extracted
+    pub reused: usize,
+    pub extracted: usize,
+    /// Genes tombstoned because no file yields them any more
+    pub removed: usize,
+}
+
+#[derive(Debug)]
+pub struct FileError {
+    pub path: String,
+    pub reason: String,
 }
 
 /// Digest source into genes
-pub async fn digest(store: &Store, source: Option<&str>, parallel: bool) -> Result<DigestStats> {
+///
+/// Incremental: a file is re-extracted only when its blake3 differs from
+/// the one recorded for the same source and path. Installed extractors run
+/// sandboxed within `limits`; a file that breaks them fails on its own.
+pub async fn digest(store: &Store, source: Option<&str>, parallel: bool, limits: &SandboxLimits) -> Result<DigestStats> {
     // Load extractors
-    let extractors = ExtractorSet::new().await?;
+    let extractors = ExtractorSet::new(limits.clone()).await?;
     
     // Get sources to digest
     let sources = if let Some(s) = source {
@@ -27,84 +48,140 @@ pub async fn digest(store: &Store, source: Option<&str>, parallel: bool) -> Resu
         get_undigested_sources(store).await?
     };
     
-    let mut total = 0;
-    let mut unique_souls = dashmap::DashSet::new();
-    let mut failed = 0;
+    let mut stats = DigestStats::default();
+    let unique_souls = dashmap::DashSet::new();
+    let mut tombstoned = HashSet::new();
     
     for source_path in sources {
         tracing::info!("Digesting {}", source_path);
         
         // Extract source from CAS
-        let source_data = extract_source(store, &source_path).await?;
+        let (source_data, provenance) = extract_source(store, &source_path).await?;
+        let lineage = lineage(&provenance);
         
-        // Find all code files
+        // Find all code files, and what they digested to last time
         let files = find_code_files(&source_data);
+        let previous = store.file_digests(lineage).await?;
+        let plan = plan(&previous, &files);
         
-        // Process files
-        let genes: Vec<Result<Gene>> = if parallel {
-            files.par_iter()
-                .map(|file| extract_gene(&extractors, file, &source_path))
-                .collect()
+        for digest in &plan.reused {
+            stats.reused += digest.souls.len();
+            for soul in &digest.souls {
+                unique_souls.insert(soul.clone());
+            }
+        }
+        
+        // Process changed and new files - in parallel, several extractions
+        // are in flight at once and finish in any order
+        let extractions = stream::iter(plan.changed.iter().copied()).map(|file| {
+            let genes = extract_genes(&extractors, file, &provenance);
+            async move { (file, genes.await) }
+        });
+        let genes: Vec<(&CodeFile, Result<Vec<Gene>>)> = if parallel {
+            let width = std::thread::available_parallelism().map_or(4, |n| n.get());
+            extractions.buffer_unordered(width).collect().await
         } else {
-            files.iter()
-                .map(|file| extract_gene(&extractors, file, &source_path))
-                .collect()
+            extractions.buffered(1).collect().await
         };
         
         // Store genes
-        for gene_result in genes {
-            match gene_result {
-                Ok(gene) => {
-                    unique_souls.insert(gene.soul.clone());
-                    store.put_gene(&gene).await?;
-                    total += 1;
+        for (file, genes_result) in genes {
+            match genes_result {
+                Ok(genes) => {
+                    for gene in &genes {
+                        unique_souls.insert(gene.soul.clone());
+                        store.put_gene(gene).await?;
+                    }
+                    stats.extracted += genes.len();
+                    tombstoned.extend(store.put_file_digest(&FileDigest {
+                        source: lineage.to_string(),
+                        path: file.relative.clone(),
+                        hash: file.hash.clone(),
+                        souls: genes.into_iter().map(|g| g.soul).collect(),
+                    }).await?);
                 }
                 Err(e) => {
-                    tracing::warn!("Failed to extract gene: {}", e);
-                    failed += 1;
+                    tracing::warn!("Failed to extract genes from {}: {:#}", file.relative, e);
+                    stats.failed.push(FileError { path: file.relative.clone(), reason: format!("{:#}", e) });
                 }
             }
         }
+        
+        // After storing, so a gene that only moved files is not tombstoned
+        for digest in &plan.removed {
+            tombstoned.extend(store.tombstone(lineage, &digest.path).await?);
+        }
+        
+        if source_path.starts_with("b3:") {
+            store.mark_digested(&source_path).await?;
+        }
     }
     
-    Ok(DigestStats {
-        total,
-        unique: unique_souls.len(),
-        failed,
-    })
+    stats.total = stats.reused + stats.extracted;
+    stats.unique = unique_souls.len();
+    // A gene released by one file and stored from another is still live
+    stats.removed = tombstoned.iter().filter(|soul| !unique_souls.contains(*soul)).count();
+    Ok(stats)
 }
 
-/// Extract gene from file
-fn extract_gene(extractors: &ExtractorSet, file: &CodeFile, source: &str) -> Result<Gene> {
+/// What an incremental digest of one source has to do
+struct Plan<'a> {
+    reused: Vec<&'a FileDigest>,
+    changed: Vec<&'a CodeFile>,
+    removed: Vec<&'a FileDigest>,
+}
+
+fn plan<'a>(previous: &'a [FileDigest], files: &'a [CodeFile]) -> Plan<'a> {
+    let recorded: HashMap<&str, &FileDigest> = previous.iter().map(|d| (d.path.as_str(), d)).collect();
+    let present: HashSet<&str> = files.iter().map(|f| f.relative.as_str()).collect();
+
+    let mut plan = Plan { reused: vec![], changed: vec![], removed: vec![] };
+    for file in files {
+        match recorded.get(file.relative.as_str()) {
+            Some(digest) if digest.hash == file.hash => plan.reused.push(digest),
+            _ => plan.changed.push(file),
+        }
+    }
+    plan.removed = previous.iter().filter(|d| !present.contains(d.path.as_str())).collect();
+    plan
+}
+
+/// Extract one gene per function of a file
+async fn extract_genes(extractors: &ExtractorSet, file: &CodeFile, source: &str) -> Result<Vec<Gene>> {
     // Read file content
-    let code = std::fs::read_to_string(&file.path)?;
-    
-    // Extract λ-IR
-    let ir = tokio::runtime::Handle::current()
-        .block_on(extractors.extract(&file.path, &code))?;
+    let code = tokio::fs::read_to_string(&file.path).await?;
     
-    // Compute soul (semantic hash of IR)
-    let soul = compute_soul(&ir);
+    // Extract λ-IR per function
+    let functions = extractors.extract(&file.path, &code).await?;
     
-    // Extract signatures
-    let signatures = extract_signatures(&ir);
-    
-    // Compute metrics
-    let metrics = compute_metrics(&code, &ir);
-    
-    Ok(Gene {
-        soul,
-        ir,
-        source: source.to_string(),
-        signatures,
-        metrics,
-    })
+    Ok(functions.into_iter().map(|function| {
+        let body = function.code(&code);
+        let effects = function.effects.clone().unwrap_or_else(|| effects_of_ir(&function.ir));
+        Gene {
+            // Compute soul (semantic hash of IR)
+            soul: compute_soul(&function.ir),
+            signatures: extract_signatures(&function.ir),
+            metrics: compute_metrics(&body, &function.ir, &effects),
+            effects,
+            span: Some(Span {
+                file: file.relative.clone(),
+                start_line: function.start_line,
+                end_line: function.end_line,
+            }),
+            name: function.name,
+            ir: function.ir,
+            source: source.to_string(),
+        }
+    }).collect())
 }
 
 /// Compute soul from λ-IR
-fn compute_soul(ir: &str) -> String {
-    // Normalize IR (remove whitespace, sort components)
-    let normalized = normalize_ir(ir);
+pub(crate) fn compute_soul(ir: &str) -> String {
+    // Structured IR is alpha-normalized, so bound names don't change the soul
+    let normalized = match crate::extract::decode(ir) {
+        Some(ir) => ir.alpha_normalize().to_canonical_string(),
+        None => normalize_ir(ir),
+    };
     
     // Hash with Blake3
     let mut hasher = Hasher::new();
@@ -138,57 +215,53 @@ fn extract_signatures(ir: &str) -> Vec<String> {
     signatures
 }
 
-/// Compute gene metrics
-fn compute_metrics(code: &str, ir: &str) -> GeneMetrics {
-    GeneMetrics {
-        size: code.len(),
-        complexity: compute_complexity(ir),
-        purity: compute_purity(code),
-    }
+/// Effects of a function whose extractor didn't analyze them - textual IR
+/// has no structure to read and counts as effect-free
+fn effects_of_ir(ir: &str) -> Effects {
+    crate::extract::decode(ir).map(|ir| Effects::of_ir(&ir)).unwrap_or_default()
 }
 
-/// Compute cyclomatic complexity from IR
-fn compute_complexity(ir: &str) -> f32 {
-    let branches = ir.matches("CASE").count();
-    let loops = ir.matches("REC").count();
-    
-    1.0 + branches as f32 + loops as f32
+/// Compute gene metrics - structure and cost from the IR's cost model,
+/// source length and effects from the function itself
+fn compute_metrics(code: &str, ir: &str, effects: &Effects) -> GeneMetrics {
+    let mut metrics = GeneMetrics {
+        size: code.len(),
+        complexity: 1.0,
+        purity: effects.purity(),
+        ..Default::default()
+    };
+    if let Some(ir) = crate::extract::decode(ir) {
+        let cost = CostModel::default().compute(&ir);
+        metrics.complexity = 1.0 + branches(&ir) as f32;
+        metrics.cycles = cost.cycles;
+        metrics.bytes = cost.bytes;
+        metrics.allocs = cost.allocs;
+        metrics.nodes = ir.size();
+        metrics.depth = ir.depth();
+        metrics.growth = cost::growth(&ir);
+    }
+    metrics
 }
 
-/// Compute purity score (0.0 to 1.0)
-fn compute_purity(code: &str) -> f32 {
-    let mut impurities = 0;
-    
-    // Check for side effects
-    if code.contains("console.") { impurities += 1; }
-    if code.contains("Math.random") { impurities += 1; }
-    if code.contains("Date.now") { impurities += 1; }
-    if code.contains("fs.") { impurities += 1; }
-    if code.contains("process.") { impurities += 1; }
-    if code.contains("window.") { impurities += 1; }
-    if code.contains("document.") { impurities += 1; }
-    
-    // Check for mutations
-    if code.contains("let ") { impurities += 1; }
-    if code.contains("var ") { impurities += 1; }
-    if code.contains(".push(") { impurities += 1; }
-    if code.contains(".pop(") { impurities += 1; }
-    
-    // Calculate purity
-    let max_impurities = 10.0;
-    1.0 - (impurities as f32 / max_impurities).min(1.0)
+/// Decision points - cyclomatic complexity less one
+fn branches(ir: &IR) -> usize {
+    usize::from(matches!(ir, IR::If(..))) + ir.children().into_iter().map(branches).sum::<usize>()
 }
 
 #[derive(Debug)]
 struct CodeFile {
     path: String,
+    /// Path within the source - the digest state key
+    relative: String,
+    /// blake3 of the file bytes
+    hash: String,
     language: String,
 }
 
 /// Find all code files in source
 fn find_code_files(source_path: &str) -> Vec<CodeFile> {
     let mut files = Vec::new();
-    let extensions = vec!["js", "ts", "jsx", "tsx", "py", "rs", "go", "java", "c", "cpp"];
+    let extensions = vec!["js", "ts", "jsx", "tsx", "py", "rs", "go", "java", "c", "cpp", "lambda"];
     
     for entry in WalkDir::new(source_path)
         .follow_links(true)
@@ -212,8 +285,12 @@ fn find_code_files(source_path: &str) -> Vec<CodeFile> {
         if let Some(ext) = path.extension() {
             let ext_str = ext.to_string_lossy().to_lowercase();
             if extensions.contains(&ext_str.as_str()) {
+                let Ok(bytes) = std::fs::read(path) else { continue };
+                let relative = path.strip_prefix(source_path).unwrap_or(path);
                 files.push(CodeFile {
                     path: path.to_string_lossy().to_string(),
+                    relative: relative.to_string_lossy().replace('\\', "/"),
+                    hash: blake3::hash(&bytes).to_hex().to_string(),
                     language: detect_language(&ext_str),
                 });
             }
@@ -233,18 +310,115 @@ fn detect_language(ext: &str) -> String {
         "java" => "java",
         "c" | "h" => "c",
         "cpp" | "cc" | "cxx" => "cpp",
+        "lambda" => "lambda",
         _ => "unknown",
     }.to_string()
 }
 
 async fn get_undigested_sources(store: &Store) -> Result<Vec<String>> {
-    // Query for sources without genes
-    // Simplified - would query the index
-    Ok(vec![])
+    store.undigested_sources().await
 }
 
-async fn extract_source(store: &Store, source: &str) -> Result<String> {
-    // Extract source archive to temp directory
-    // Simplified - would handle tar/zip extraction
-    Ok(source.to_string())
-}
\ No newline at end of file
+/// Unpack a stored source (by CID) or a local archive into a scratch workspace
+///
+/// Returns the workspace and the source's provenance, which every gene
+/// digested from it records.
+async fn extract_source(store: &Store, source: &str) -> Result<(String, String)> {
+    let (cid, snapshot) = if source.starts_with("b3:") {
+        let obj = store.get(source).await?
+            .ok_or_else(|| anyhow::anyhow!("source {} not in store", source))?;
+        let provenance = obj.metadata.source.unwrap_or_else(|| obj.cid.clone());
+        (obj.cid, Snapshot { data: obj.data, provenance })
+    } else {
+        match LocalSource::detect(source, None) {
+            // Plain directories are digested in place
+            Some(LocalSource::Dir(_)) | None => return Ok((source.to_string(), source.to_string())),
+            Some(local) => {
+                let snapshot = local.archive()?;
+                (storage::compute_cid(&snapshot.data), snapshot)
+            }
+        }
+    };
+
+    let workspace = ingest::workspace(&cid);
+    ingest::unpack(&snapshot.data, &workspace)?;
+    Ok((workspace.to_string_lossy().into_owned(), snapshot.provenance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::GeneStore;
+
+    fn file(relative: &str, hash: &str) -> CodeFile {
+        CodeFile {
+            path: format!("/work/{}", relative),
+            relative: relative.to_string(),
+            hash: hash.to_string(),
+            language: "javascript".to_string(),
+        }
+    }
+
+    fn recorded(path: &str, hash: &str) -> FileDigest {
+        FileDigest {
+            source: "git:/src/fp".to_string(),
+            path: path.to_string(),
+            hash: hash.to_string(),
+            souls: vec![format!("λ{}", hash)],
+        }
+    }
+
+    #[test]
+    fn test_plan_reuses_unchanged_files() {
+        let previous = vec![recorded("map.js", "1"), recorded("filter.js", "2"), recorded("old.js", "3")];
+        let files = vec![file("map.js", "1"), file("filter.js", "9"), file("fold.js", "4")];
+        let plan = plan(&previous, &files);
+
+        assert_eq!(plan.reused.iter().map(|d| d.path.as_str()).collect::<Vec<_>>(), vec!["map.js"]);
+        assert_eq!(plan.changed.iter().map(|f| f.relative.as_str()).collect::<Vec<_>>(), vec!["filter.js", "fold.js"]);
+        assert_eq!(plan.removed.iter().map(|d| d.path.as_str()).collect::<Vec<_>>(), vec!["old.js"]);
+    }
+
+    #[test]
+    fn test_metrics_from_cost_model() {
+        let code = "export const below = (xs, ys) => xs.map(x => x > 0 ? ys.filter(y => y < x) : []);";
+        let function = crate::extract::js::extract(code).unwrap().remove(0);
+        let metrics = compute_metrics(code, &function.ir, &Effects::default());
+
+        assert_eq!(metrics.complexity, 2.0);
+        assert_eq!(metrics.growth, Some(cost::Growth::Polynomial(2)));
+        assert!(metrics.cycles > 0 && metrics.allocs > 0);
+        assert!(metrics.depth > 1 && metrics.nodes > metrics.depth);
+
+        // Textual IR has nothing to measure
+        let textual = compute_metrics("f", "LAM x\nAPP f x", &Effects::default());
+        assert_eq!((textual.complexity, textual.cycles, textual.growth), (1.0, 0, None));
+    }
+
+    #[tokio::test]
+    async fn test_digest_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = "export const inc = xs => xs.map(x => x + 1);\nexport const evens = xs => xs.filter(x => x % 2 === 0);\n";
+        std::fs::write(dir.path().join("list.js"), list).unwrap();
+        std::fs::write(dir.path().join("num.js"), "export const double = x => x * 2;\n").unwrap();
+        let source = dir.path().to_str().unwrap();
+        let store = crate::storage::MemoryStore::new();
+        let limits = SandboxLimits::default();
+
+        let stats = digest(&store, Some(source), true, &limits).await.unwrap();
+        assert!(stats.failed.is_empty(), "{:?}", stats.failed);
+        assert_eq!((stats.extracted, stats.reused, stats.unique), (3, 0, 3));
+        let mut names = Vec::new();
+        for (soul, _) in store.gene_cids().await.unwrap() {
+            names.push(store.get_gene(&soul).await.unwrap().unwrap().name);
+        }
+        names.sort();
+        assert_eq!(names, vec!["double", "evens", "inc"]);
+
+        // Only the edited file is extracted again
+        std::fs::write(dir.path().join("num.js"), "export const triple = x => x * 3;\n").unwrap();
+        let stats = digest(&store, Some(source), false, &limits).await.unwrap();
+        assert_eq!((stats.extracted, stats.reused, stats.removed), (1, 2, 1));
+        assert_eq!(store.status().await.unwrap().total_genes, 3);
+    }
+}
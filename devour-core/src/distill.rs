@@ -2,6 +2,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use crate::storage::Store;
+use crate::surgeon::cost::Growth;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Objectives {
@@ -65,16 +66,25 @@ pub async fn distill(store: &Store, objectives_path: Option<&str>) -> Result<usi
 fn score_gene(gene: &crate::storage::Gene, objectives: &Objectives) -> f32 {
     let mut score = 0.0;
     
-    // Speed (inverse of complexity)
-    let speed_score = 1.0 / (1.0 + gene.metrics.complexity);
+    // Speed - asymptotic class first, then the cost model's cycles; growth
+    // that can't be told counts as linear
+    let order = match gene.metrics.growth {
+        Some(Growth::Polynomial(k)) => k as f32,
+        Some(Growth::Exponential) => 4.0,
+        None => 1.0,
+    };
+    let speed_score = 1.0 / (1.0 + order + gene.metrics.cycles as f32 / 1000.0);
     score += speed_score * objectives.speed;
     
-    // Memory (inverse of size)
-    let memory_score = 1.0 / (1.0 + (gene.metrics.size as f32 / 1000.0));
+    // Memory (inverse of estimated bytes and allocations)
+    let memory_score = 1.0 / (1.0 + gene.metrics.bytes as f32 / 1000.0 + gene.metrics.allocs as f32 / 100.0);
     score += memory_score * objectives.memory;
     
-    // Size
-    let size_score = 1.0 / (1.0 + (gene.metrics.size as f32 / 100.0));
+    // Size (IR nodes, source bytes when the IR is textual)
+    let size_score = match gene.metrics.nodes {
+        0 => 1.0 / (1.0 + (gene.metrics.size as f32 / 100.0)),
+        nodes => 1.0 / (1.0 + (nodes as f32 / 20.0)),
+    };
     score += size_score * objectives.size;
     
     // Purity
@@ -93,10 +103,10 @@ async fn get_equivalence_classes(store: &Store) -> Result<Vec<Vec<String>>> {
     Ok(vec![])
 }
 
+/// The class is named by its smallest soul, so re-distilling replaces its champion
 async fn store_champion(store: &Store, soul: &str, score: f32, class: &[String]) -> Result<()> {
-    // Store champion in database
-    // Simplified - would insert into champions table
-    Ok(())
+    let canonical = class.iter().min().map_or(soul, String::as_str);
+    store.add_champion(canonical, soul, score).await
 }
 
 fn load_objectives(path: &str) -> Result<Objectives> {
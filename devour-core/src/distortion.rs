@@ -0,0 +1,251 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+use lambda_core::distortion::{AddressLedger, Component, Distortion, DistortionMeter, DistortionWeights};
+
+use crate::align::AlignmentRules;
+use crate::digest::compute_soul;
+
+/// Before/after distortion of a gene tree
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub generated: String,
+    pub genes: usize,
+    pub before: Snapshot,
+    pub after: Snapshot,
+    pub improvement: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub total: f32,
+    pub unpredictability: f32,
+    pub instability: f32,
+    pub nonlocality: f32,
+    pub redundancy: f32,
+    pub addresses: usize,
+    pub souls: usize,
+    pub contributions: Vec<Item>,
+}
+
+/// One address pair behind a component
+#[derive(Debug, Serialize)]
+pub struct Item {
+    pub component: String,
+    pub address: String,
+    pub target: String,
+    pub share: f32,
+}
+
+/// Fill a ledger from `genes/<name>/gene.yaml` and `λ/canonical.ir`
+///
+/// Gene names, `resonance.similar` names and alignment mappings all become
+/// addresses of the gene's soul. Returns the ledger and the number of genes.
+pub fn scan_genes(dir: &Path, rules: &AlignmentRules) -> Result<(AddressLedger, usize)> {
+    let mut ledger = AddressLedger::new();
+    let mut genes = 0;
+
+    let mut gene_dirs: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("gene.yaml").exists())
+        .collect();
+    gene_dirs.sort();
+
+    for gene_dir in gene_dirs {
+        let yaml_path = gene_dir.join("gene.yaml");
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(&yaml_path)?)
+            .with_context(|| format!("parsing {}", yaml_path.display()))?;
+
+        let name = match yaml["gene"].as_str() {
+            Some(name) => name.to_string(),
+            None => gene_dir.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+        };
+
+        let ir_path = gene_dir.join("λ").join("canonical.ir");
+        let ir = std::fs::read_to_string(&ir_path)
+            .with_context(|| format!("reading {}", ir_path.display()))?;
+        let soul = compute_soul(&ir);
+
+        ledger.record_soul(name.clone(), soul.clone());
+        genes += 1;
+
+        // Names other libraries use for the same gene
+        let similar = yaml["resonance"]["similar"].as_sequence().cloned().unwrap_or_default();
+        for alias in similar.iter().filter_map(|v| v.as_str()) {
+            ledger.record_soul(alias.to_string(), soul.clone());
+        }
+        if let Some(class) = rules.mappings.get(&name) {
+            let mapped = class.lodash.iter()
+                .chain(&class.ramda)
+                .chain(&class.underscore)
+                .chain(class.others.values().flatten());
+            for alias in mapped {
+                ledger.record_soul(alias.clone(), soul.clone());
+            }
+        }
+    }
+
+    Ok((ledger, genes))
+}
+
+/// Reduce a copy of the ledger and compare
+pub fn report(before: &AddressLedger, genes: usize) -> Report {
+    let mut after = before.clone();
+    after.reduce_distortion();
+
+    let meter = DistortionMeter::measure(before, &after);
+    Report {
+        generated: chrono::Utc::now().to_rfc3339(),
+        genes,
+        before: snapshot(before, &meter.before, &meter.weights),
+        after: snapshot(&after, &meter.after, &meter.weights),
+        improvement: meter.before.improvement(&meter.after),
+    }
+}
+
+fn snapshot(ledger: &AddressLedger, distortion: &Distortion, weights: &DistortionWeights) -> Snapshot {
+    let entries = ledger.entries();
+    Snapshot {
+        total: distortion.total(weights),
+        unpredictability: distortion.unpredictability,
+        instability: distortion.instability,
+        nonlocality: distortion.nonlocality,
+        redundancy: distortion.redundancy,
+        addresses: entries.iter().map(|e| 1 + e.aliases.len()).sum(),
+        souls: entries.len(),
+        contributions: ledger.contributions().into_iter().map(|c| Item {
+            component: component_name(c.component).to_string(),
+            address: c.address,
+            target: c.target,
+            share: c.share,
+        }).collect(),
+    }
+}
+
+fn component_name(component: Component) -> &'static str {
+    match component {
+        Component::Unpredictability => "unpredictability",
+        Component::Instability => "instability",
+        Component::Nonlocality => "nonlocality",
+        Component::Redundancy => "redundancy",
+    }
+}
+
+impl Report {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let weights = DistortionWeights::default();
+        let mut md = String::new();
+
+        md.push_str("# Address→Value Distortion Report\n\n");
+        md.push_str(&format!("Date: {}\n\n", self.generated));
+        md.push_str("## Summary\n\n");
+        md.push_str(&format!("**Total Distortion: {:.1}% → {:.1}%** ({:.1}% improvement, {} genes)\n\n",
+            self.before.total * 100.0, self.after.total * 100.0, self.improvement * 100.0, self.genes));
+
+        md.push_str("## Metrics\n\n");
+        md.push_str("| Metric | Before | After | Weight |\n");
+        md.push_str("|--------|--------|-------|--------|\n");
+        let rows = [
+            ("Unpredictability", self.before.unpredictability, self.after.unpredictability, weights.unpredictability),
+            ("Instability", self.before.instability, self.after.instability, weights.instability),
+            ("Nonlocality", self.before.nonlocality, self.after.nonlocality, weights.nonlocality),
+            ("Redundancy", self.before.redundancy, self.after.redundancy, weights.redundancy),
+        ];
+        for (name, before, after, weight) in rows {
+            md.push_str(&format!("| {} | {:.1}% | {:.1}% | {} |\n", name, before * 100.0, after * 100.0, weight));
+        }
+
+        md.push_str("\n## Statistics\n\n");
+        md.push_str(&format!("- Addresses holding values: {} → {}\n", self.before.addresses, self.after.addresses));
+        md.push_str(&format!("- Unique souls: {} → {}\n", self.before.souls, self.after.souls));
+
+        for (title, snapshot) in [("Before", &self.before), ("After", &self.after)] {
+            md.push_str(&format!("\n## Contributions ({})\n\n", title));
+            if snapshot.contributions.is_empty() {
+                md.push_str("None\n");
+                continue;
+            }
+            md.push_str("| Component | Address | Target | Share |\n");
+            md.push_str("|-----------|---------|--------|-------|\n");
+            for item in &snapshot.contributions {
+                md.push_str(&format!("| {} | `{}` | `{}` | {:.1}% |\n",
+                    item.component, item.address, item.target, item.share * 100.0));
+            }
+        }
+
+        md.push_str("\n---\n*Generated by devour distortion*\n");
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::EquivalenceClass;
+    use std::collections::HashMap;
+
+    /// genes/{filter,map} and a directory that holds no gene
+    fn fixture(dir: &Path) {
+        let gene = |name: &str, yaml: &str, ir: &str| {
+            let gene_dir = dir.join(name);
+            std::fs::create_dir_all(gene_dir.join("λ")).unwrap();
+            std::fs::write(gene_dir.join("gene.yaml"), yaml).unwrap();
+            std::fs::write(gene_dir.join("λ").join("canonical.ir"), ir).unwrap();
+        };
+        gene("filter", "version: 1\n", "LAM xs\nAPP filter xs p");
+        gene("map", "gene: map\nresonance:\n  similar: [collect]\n", "LAM xs\nAPP map xs f");
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+    }
+
+    fn rules() -> AlignmentRules {
+        let class = EquivalenceClass {
+            canonical: "map".to_string(),
+            lodash: vec!["mapValues".to_string()],
+            ramda: vec![],
+            underscore: vec![],
+            others: HashMap::new(),
+        };
+        AlignmentRules { mappings: HashMap::from([("map".to_string(), class)]), signatures: HashMap::new() }
+    }
+
+    #[test]
+    fn test_scan_and_itemize() {
+        let dir = tempfile::tempdir().unwrap();
+        fixture(dir.path());
+        let (ledger, genes) = scan_genes(dir.path(), &rules()).unwrap();
+        assert_eq!(genes, 2);
+
+        // map, collect and mapValues hold one soul; filter is named after its directory
+        let report = report(&ledger, genes);
+        assert_eq!((report.before.addresses, report.before.souls), (4, 2));
+        assert_eq!(report.before.redundancy, 0.5);
+        let redundant: Vec<(&str, &str, f32)> = report.before.contributions.iter()
+            .filter(|item| item.component == "redundancy")
+            .map(|item| (item.address.as_str(), item.target.as_str(), item.share))
+            .collect();
+        assert_eq!(redundant, vec![("collect", "map", 0.25), ("mapValues", "map", 0.25)]);
+        assert!(report.before.contributions.iter().any(|item| item.address == "filter"));
+        assert_eq!(report.after.redundancy, 0.0);
+        assert!(report.improvement > 0.0);
+
+        let md = report.to_markdown();
+        assert!(md.contains("% improvement, 2 genes)"), "{}", md);
+        assert!(md.contains("| Redundancy | 50.0% | 0.0% | 0.1 |\n"), "{}", md);
+        assert!(md.contains("| redundancy | `collect` | `map` | 25.0% |\n"), "{}", md);
+        assert!(md.contains("- Unique souls: 2 → 2\n"), "{}", md);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["genes"], 2);
+        assert_eq!(json["before"]["redundancy"], 0.5);
+        assert_eq!(json["before"]["contributions"].as_array().unwrap().len(), report.before.contributions.len());
+        assert_eq!(json["after"]["souls"], 2);
+    }
+}
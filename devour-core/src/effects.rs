@@ -0,0 +1,296 @@
+// Effect analysis - what running a gene can do besides returning a value
+//
+// Built-in extractors fill the set from the whole function body while they
+// parse it. IR that arrives without one (installed extractors, rule bindings)
+// is analyzed structurally by `Effects::of_ir`. Purity, the surgeon's
+// `Guard::Pure` and the intent's `Capabilities` all read the same set - a
+// call to a stored gene carries that gene's set, not what its IR shows.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use lambda_core::intent::Capabilities;
+use serde::{Deserialize, Serialize};
+
+use crate::extract::SELF;
+use crate::surgeon::egraph::IR;
+
+/// Who can see a mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// State the function created itself - invisible to callers
+    Local,
+    /// Parameters, globals or anything reachable through them
+    Escaping,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    Io,
+    Mutation(Scope),
+    Randomness,
+    Time,
+    Exception,
+    /// Unbounded loops - recursion is taken as structural
+    Nontermination,
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Effect::Io => write!(f, "io"),
+            Effect::Mutation(Scope::Local) => write!(f, "local mutation"),
+            Effect::Mutation(Scope::Escaping) => write!(f, "escaping mutation"),
+            Effect::Randomness => write!(f, "randomness"),
+            Effect::Time => write!(f, "time"),
+            Effect::Exception => write!(f, "exception"),
+            Effect::Nontermination => write!(f, "nontermination"),
+        }
+    }
+}
+
+/// Stored effect sets of genes, by name
+pub type GeneEffects = HashMap<String, Effects>;
+
+/// Effect set of one gene
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Effects(BTreeSet<Effect>);
+
+impl Effects {
+    pub fn insert(&mut self, effect: Effect) {
+        self.0.insert(effect);
+    }
+
+    pub fn contains(&self, effect: Effect) -> bool {
+        self.0.contains(&effect)
+    }
+
+    pub fn extend(&mut self, other: &Effects) {
+        self.0.extend(other.iter());
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Effect> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Nothing a caller can observe - local mutation stays inside
+    pub fn is_pure(&self) -> bool {
+        self.iter().all(|effect| effect == Effect::Mutation(Scope::Local))
+    }
+
+    /// 1.0 when pure, less for every observable effect
+    pub fn purity(&self) -> f32 {
+        let impurity: f32 = self.iter().map(|effect| match effect {
+            Effect::Io => 0.5,
+            Effect::Mutation(Scope::Escaping) => 0.4,
+            Effect::Randomness | Effect::Time => 0.3,
+            Effect::Exception | Effect::Nontermination => 0.1,
+            Effect::Mutation(Scope::Local) => 0.0,
+        }).sum();
+        1.0 - impurity.min(1.0)
+    }
+
+    /// Effects `caps` doesn't grant - escaping mutation is state outside the
+    /// gene and needs `io` like any other
+    pub fn denied(&self, caps: &Capabilities) -> Vec<Effect> {
+        self.iter().filter(|effect| match effect {
+            Effect::Io | Effect::Mutation(Scope::Escaping) => !caps.io,
+            Effect::Time => !caps.clock,
+            Effect::Randomness => !caps.entropy,
+            Effect::Mutation(Scope::Local) | Effect::Exception | Effect::Nontermination => false,
+        }).collect()
+    }
+
+    pub fn permitted(&self, caps: &Capabilities) -> bool {
+        self.denied(caps).is_empty()
+    }
+
+    /// Effects visible in IR alone: free names of known APIs and the reasons
+    /// constructs were left opaque. A mutation's scope is lost there, so it escapes.
+    pub fn of_ir(ir: &IR) -> Self {
+        Self::of_ir_in(ir, &GeneEffects::new())
+    }
+
+    /// Effects of IR where free names of `genes` stand for calls to them
+    pub fn of_ir_in(ir: &IR, genes: &GeneEffects) -> Self {
+        let mut effects = Effects::default();
+        collect(ir, genes, &mut Vec::new(), &mut effects);
+        effects
+    }
+}
+
+impl FromIterator<Effect> for Effects {
+    fn from_iter<I: IntoIterator<Item = Effect>>(iter: I) -> Self {
+        Effects(iter.into_iter().collect())
+    }
+}
+
+fn collect(ir: &IR, genes: &GeneEffects, bound: &mut Vec<String>, effects: &mut Effects) {
+    match ir {
+        IR::Var(x) if x != SELF && !bound.contains(x) => match genes.get(x) {
+            Some(gene) => effects.extend(gene),
+            None => effects.0.extend(of_call(x)),
+        },
+        IR::Lam(x, body) => {
+            bound.push(x.clone());
+            collect(body, genes, bound, effects);
+            bound.pop();
+        }
+        IR::Opaque(reason) => effects.0.extend(of_reason(reason)),
+        _ => {
+            for child in ir.children() {
+                collect(child, genes, bound, effects);
+            }
+        }
+    }
+}
+
+fn of_reason(reason: &str) -> Option<Effect> {
+    if let Some(name) = reason.strip_prefix("macro ").and_then(|m| m.strip_suffix('!')) {
+        return of_macro(name);
+    }
+    if let Some(path) = reason.strip_prefix("path ") {
+        return of_call(path);
+    }
+    if reason.starts_with("mutable binding") {
+        return Some(Effect::Mutation(Scope::Local));
+    }
+    match reason {
+        "while statement" | "do statement" | "while loop" | "loop" => Some(Effect::Nontermination),
+        "throw statement" | "raise statement" | "assert statement" => Some(Effect::Exception),
+        "assignment" | "assignment expression" | "mutation" | "mutable borrow" | "unsafe block"
+        | "global statement" | "nonlocal statement" | "del statement" => Some(Effect::Mutation(Scope::Escaping)),
+        _ => None,
+    }
+}
+
+/// Known effectful APIs by dotted path prefix - randomness and time come
+/// first so `os.urandom` and `time.sleep` don't read as plain io
+const APIS: &[(&str, Effect)] = &[
+    ("Math.random", Effect::Randomness),
+    ("crypto.getRandomValues", Effect::Randomness),
+    ("crypto.randomUUID", Effect::Randomness),
+    ("os.urandom", Effect::Randomness),
+    ("random", Effect::Randomness),
+    ("secrets", Effect::Randomness),
+    ("uuid", Effect::Randomness),
+    ("rand", Effect::Randomness),
+    ("thread_rng", Effect::Randomness),
+    ("Date", Effect::Time),
+    ("performance.now", Effect::Time),
+    ("setTimeout", Effect::Time),
+    ("setInterval", Effect::Time),
+    ("time", Effect::Time),
+    ("datetime", Effect::Time),
+    ("Instant", Effect::Time),
+    ("SystemTime", Effect::Time),
+    ("thread.sleep", Effect::Time),
+    ("console", Effect::Io),
+    ("process", Effect::Io),
+    ("fs", Effect::Io),
+    ("document", Effect::Io),
+    ("window", Effect::Io),
+    ("localStorage", Effect::Io),
+    ("XMLHttpRequest", Effect::Io),
+    ("fetch", Effect::Io),
+    ("alert", Effect::Io),
+    ("require", Effect::Io),
+    ("print", Effect::Io),
+    ("input", Effect::Io),
+    ("open", Effect::Io),
+    ("os", Effect::Io),
+    ("sys", Effect::Io),
+    ("subprocess", Effect::Io),
+    ("socket", Effect::Io),
+    ("requests", Effect::Io),
+    ("shutil", Effect::Io),
+    ("logging", Effect::Io),
+    ("io", Effect::Io),
+    ("env", Effect::Io),
+    ("net", Effect::Io),
+    ("File", Effect::Io),
+    ("Command", Effect::Io),
+    ("TcpStream", Effect::Io),
+    ("stdin", Effect::Io),
+    ("stdout", Effect::Io),
+    ("stderr", Effect::Io),
+];
+
+/// Effect of using the global `path` (`a.b.c` or `a::b::c`), if it's a known API
+pub fn of_call(path: &str) -> Option<Effect> {
+    let path = path.replace("::", ".");
+    let path = path.strip_prefix("std.").or_else(|| path.strip_prefix("core.")).unwrap_or(&path);
+    APIS.iter()
+        .find(|(api, _)| path == *api || path.strip_prefix(api).is_some_and(|rest| rest.starts_with('.')))
+        .map(|(_, effect)| *effect)
+}
+
+/// Effect of a Rust macro invocation, by macro name
+pub fn of_macro(name: &str) -> Option<Effect> {
+    match name.rsplit("::").next().unwrap_or(name) {
+        "println" | "print" | "eprintln" | "eprint" | "dbg" => Some(Effect::Io),
+        "panic" | "unreachable" | "todo" | "unimplemented" | "assert" | "assert_eq" | "assert_ne" => Some(Effect::Exception),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_apis() {
+        assert_eq!(of_call("console.log"), Some(Effect::Io));
+        assert_eq!(of_call("Math.random"), Some(Effect::Randomness));
+        assert_eq!(of_call("Math.max"), None);
+        assert_eq!(of_call("std::time::Instant::now"), Some(Effect::Time));
+        assert_eq!(of_call("os.urandom"), Some(Effect::Randomness));
+        assert_eq!(of_call("os.path.join"), Some(Effect::Io));
+        assert_eq!(of_call("printer"), None);
+        assert_eq!(of_macro("println"), Some(Effect::Io));
+        assert_eq!(of_macro("vec"), None);
+    }
+
+    #[test]
+    fn test_purity_and_capabilities() {
+        let local: Effects = [Effect::Mutation(Scope::Local)].into_iter().collect();
+        assert!(local.is_pure());
+        assert_eq!(local.purity(), 1.0);
+        assert!(local.permitted(&Capabilities::pure()));
+
+        let clock: Effects = [Effect::Time, Effect::Exception].into_iter().collect();
+        assert!(!clock.is_pure());
+        assert!(clock.purity() < 1.0);
+        assert_eq!(clock.denied(&Capabilities::pure()), vec![Effect::Time]);
+        assert!(clock.permitted(&Capabilities::full()));
+    }
+
+    #[test]
+    fn test_effects_of_ir() {
+        let var = |x: &str| Box::new(IR::Var(x.to_string()));
+        // λx. print x - free `print` is io
+        let io = IR::Lam("x".to_string(), Box::new(IR::App(var("print"), var("x"))));
+        assert_eq!(Effects::of_ir(&io).iter().collect::<Vec<_>>(), vec![Effect::Io]);
+
+        // λprint. print x - a bound name is just a parameter
+        let bound = IR::Lam("print".to_string(), Box::new(IR::App(var("print"), var("x"))));
+        assert!(Effects::of_ir(&bound).is_empty());
+
+        let opaque = IR::Map(var("xs"), Box::new(IR::Opaque("assignment".to_string())));
+        assert!(Effects::of_ir(&opaque).contains(Effect::Mutation(Scope::Escaping)));
+        assert!(Effects::of_ir(&IR::Opaque("macro println!".to_string())).contains(Effect::Io));
+
+        // `logged` prints in a statement its IR keeps opaque - its stored set says so
+        let genes: GeneEffects = [("logged".to_string(), [Effect::Io].into_iter().collect())].into_iter().collect();
+        let calls = IR::Map(var("xs"), var("logged"));
+        assert!(Effects::of_ir(&calls).is_empty());
+        assert_eq!(Effects::of_ir_in(&calls, &genes).iter().collect::<Vec<_>>(), vec![Effect::Io]);
+    }
+}
@@ -0,0 +1,1303 @@
+// JavaScript/TypeScript pure subset
+//
+// Top-level `function` declarations and `const` bindings of arrows or
+// function expressions (exported or not, or assigned to `exports.name`).
+// Bodies: `const` bindings, `if`/`else`, `return`, ternaries, arithmetic,
+// comparisons, `&&`/`||`/`!` as boolean connectives, calls, recursion,
+// `.map/.filter/.reduce`, number/string/boolean and array literals with a
+// trailing `...spread`. TypeScript annotations and generics are skipped.
+// Anything else lowers to `IR::Opaque` with the reason. Effects are scanned
+// from every token of the function, past where the subset stops.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use super::{apply, combine, encode, lambda, lower, opaque, simplify, Stmt, SELF};
+use crate::effects::{self, Effect, Effects, Scope};
+use crate::runtime::ExtractedFunction;
+use crate::surgeon::egraph::IR;
+
+/// Functions of a JavaScript or TypeScript file
+pub fn extract(code: &str) -> Result<Vec<ExtractedFunction>> {
+    let toks = lex(code)?;
+    let mut parser = Parser { toks: &toks, pos: 0, name: None, arity: 0, seen_params: Vec::new() };
+    let mut functions = Vec::new();
+    let mut depth = 0usize;
+
+    while parser.pos < toks.len() {
+        let start = parser.pos;
+        if depth == 0 {
+            if let Some((name, ir, literal)) = parser.declaration() {
+                functions.push(ExtractedFunction {
+                    name,
+                    ir: encode(&simplify(&ir))?,
+                    start_line: toks[start].line,
+                    end_line: toks[parser.pos - 1].line,
+                    effects: Some(scan_effects(&toks[literal..parser.pos], &parser.seen_params)),
+                });
+                continue;
+            }
+        }
+        match &toks[parser.pos].tok {
+            Tok::Punct(p) if matches!(p.as_str(), "(" | "[" | "{") => depth += 1,
+            Tok::Punct(p) if matches!(p.as_str(), ")" | "]" | "}") => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        parser.pos += 1;
+    }
+    Ok(functions)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Num(String),
+    Str(String),
+    Template,
+    Regex,
+    Punct(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    line: usize,
+}
+
+/// Multi-character punctuators, longest first
+const PUNCTS: [&str; 31] = [
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "=>", "==", "!=", "<=", ">=", "&&", "||",
+    "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "**", "<<", ">>", "::",
+];
+
+/// Keywords that start a new top-level statement - where error recovery stops
+const STATEMENT_STARTS: [&str; 12] = [
+    "export", "function", "const", "let", "var", "module", "exports", "import", "class", "interface", "type", "declare",
+];
+
+fn lex(code: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+            continue;
+        }
+
+        let start_line = line;
+        let tok = if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                i += 1;
+            }
+            Tok::Ident(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() {
+                let d = chars[i];
+                let exponent_sign = matches!(d, '+' | '-')
+                    && matches!(chars[i - 1], 'e' | 'E')
+                    && !chars[start..i].iter().any(|c| matches!(c, 'x' | 'X'));
+                if d.is_ascii_alphanumeric() || d == '_' || d == '.' || exponent_sign {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            Tok::Num(chars[start..i].iter().collect())
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            let mut s = String::new();
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                    match chars[i] {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        '\n' => line += 1,
+                        other => s.push(other),
+                    }
+                } else if chars[i] == '\n' {
+                    bail!("unterminated string on line {}", start_line);
+                } else {
+                    s.push(chars[i]);
+                }
+                i += 1;
+            }
+            i += 1;
+            Tok::Str(s)
+        } else if c == '`' {
+            i = skip_template(&chars, i + 1, &mut line)?;
+            Tok::Template
+        } else if c == '/' && regex_allowed(tokens.last().map(|t| &t.tok)) {
+            i = skip_regex(&chars, i + 1, start_line)?;
+            Tok::Regex
+        } else {
+            let rest: String = chars[i..(i + 4).min(chars.len())].iter().collect();
+            let punct = PUNCTS.iter()
+                .find(|p| rest.starts_with(**p))
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| c.to_string());
+            i += punct.chars().count();
+            Tok::Punct(punct)
+        };
+        tokens.push(Token { tok, line: start_line });
+    }
+    Ok(tokens)
+}
+
+/// Index after the closing backtick - `${...}` may nest templates
+fn skip_template(chars: &[char], mut i: usize, line: &mut usize) -> Result<usize> {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '`' => return Ok(i + 1),
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                let mut depth = 1;
+                i += 2;
+                while i < chars.len() && depth > 0 {
+                    match chars[i] {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        '`' => {
+                            i = skip_template(chars, i + 1, line)?;
+                            continue;
+                        }
+                        '\n' => *line += 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            '\n' => {
+                *line += 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    bail!("unterminated template literal")
+}
+
+/// A `/` after these starts a regular expression rather than a division
+fn regex_allowed(prev: Option<&Tok>) -> bool {
+    match prev {
+        None => true,
+        Some(Tok::Ident(word)) => matches!(
+            word.as_str(),
+            "return" | "typeof" | "case" | "do" | "else" | "in" | "of" | "new" | "delete" | "void" | "throw" | "yield" | "await"
+        ),
+        Some(Tok::Punct(p)) => !matches!(p.as_str(), ")" | "]" | "}"),
+        Some(_) => false,
+    }
+}
+
+/// Index after the closing slash and flags
+fn skip_regex(chars: &[char], mut i: usize, line: usize) -> Result<usize> {
+    let mut in_class = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => {
+                i += 1;
+                while i < chars.len() && chars[i].is_alphanumeric() {
+                    i += 1;
+                }
+                return Ok(i);
+            }
+            '\n' => break,
+            _ => {}
+        }
+        i += 1;
+    }
+    bail!("unterminated regular expression on line {}", line)
+}
+
+/// Lowered IR, or why the construct could not even be skipped over
+type Lowered = std::result::Result<IR, String>;
+
+/// Call argument and, for a function literal, its parameter count
+type Arg = (IR, Option<usize>);
+
+struct Parser<'a> {
+    toks: &'a [Token],
+    pos: usize,
+    /// Function being extracted - references to it are recursion
+    name: Option<String>,
+    /// Parameter count of the last function literal parsed
+    arity: usize,
+    /// Parameters of every function literal in the current declaration
+    seen_params: Vec<String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Tok> {
+        self.toks.get(self.pos + offset).map(|t| &t.tok)
+    }
+
+    fn is_punct(&self, p: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Punct(q)) if q == p)
+    }
+
+    fn is_word(&self, w: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(q)) if q == w)
+    }
+
+    fn eat_punct(&mut self, p: &str) -> bool {
+        let hit = self.is_punct(p);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn eat_word(&mut self, w: &str) -> bool {
+        let hit = self.is_word(w);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect(&mut self, p: &str) -> std::result::Result<(), String> {
+        if self.eat_punct(p) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' on line {}", p, self.line()))
+        }
+    }
+
+    fn ident(&mut self) -> std::result::Result<String, String> {
+        match self.peek() {
+            Some(Tok::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(format!("expected a name on line {}", self.line())),
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.toks.get(self.pos).or(self.toks.last()).map_or(0, |t| t.line)
+    }
+
+    /// Index of the bracket closing the one at `open`
+    fn matching(&self, open: usize) -> Option<usize> {
+        let mut depth = 0usize;
+        for (i, token) in self.toks.iter().enumerate().skip(open) {
+            match &token.tok {
+                Tok::Punct(p) if matches!(p.as_str(), "(" | "[" | "{") => depth += 1,
+                Tok::Punct(p) if matches!(p.as_str(), ")" | "]" | "}") => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Skip a type annotation up to one of `stops` outside any brackets
+    fn skip_type(&mut self, stops: &[&str]) {
+        let mut depth = 0i32;
+        while let Some(tok) = self.peek() {
+            if let Tok::Punct(p) = tok {
+                if depth == 0 && stops.contains(&p.as_str()) {
+                    return;
+                }
+                match p.as_str() {
+                    "(" | "[" | "{" | "<" => depth += 1,
+                    ")" | "]" | "}" | ">" => depth -= 1,
+                    ">>" => depth -= 2,
+                    ">>>" => depth -= 3,
+                    _ => {}
+                }
+                if depth < 0 {
+                    return;
+                }
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// `<T, U extends V>` if present
+    fn skip_generics(&mut self) {
+        if !self.eat_punct("<") {
+            return;
+        }
+        self.skip_type(&[">", ">>", ">>>"]);
+        self.eat_punct(">");
+    }
+
+    /// Type after `as` or `satisfies`
+    fn skip_type_expr(&mut self) {
+        loop {
+            match self.peek() {
+                Some(Tok::Punct(p)) if matches!(p.as_str(), "(" | "[" | "{") => {
+                    self.pos = self.matching(self.pos).map_or(self.toks.len(), |m| m + 1);
+                }
+                Some(Tok::Ident(_) | Tok::Str(_) | Tok::Num(_)) => self.pos += 1,
+                _ => return,
+            }
+            loop {
+                if self.is_punct("<") {
+                    self.skip_generics();
+                } else if self.is_punct("[") && matches!(self.peek_at(1), Some(Tok::Punct(p)) if p == "]") {
+                    self.pos += 2;
+                } else {
+                    break;
+                }
+            }
+            if !(self.eat_punct("|") || self.eat_punct("&") || self.eat_punct(".")) {
+                return;
+            }
+        }
+    }
+
+    /// Where a statement that failed to lower at `from` ends
+    fn statement_end(&self, from: usize) -> usize {
+        let mut depth = 0i32;
+        for i in from..self.toks.len() {
+            let token = &self.toks[i];
+            match &token.tok {
+                Tok::Punct(p) if matches!(p.as_str(), "(" | "[" | "{") => depth += 1,
+                Tok::Punct(p) if matches!(p.as_str(), ")" | "]" | "}") => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return i;
+                    }
+                }
+                Tok::Punct(p) if p == ";" && depth == 0 => return i + 1,
+                Tok::Ident(word) if depth == 0 && i > from
+                    && token.line > self.toks[i - 1].line
+                    && STATEMENT_STARTS.contains(&word.as_str()) => return i,
+                _ => {}
+            }
+        }
+        self.toks.len()
+    }
+
+    /// Top-level function starting here: its name, IR and where its literal starts
+    fn declaration(&mut self) -> Option<(String, IR, usize)> {
+        let start = self.pos;
+        let found = self.declaration_head();
+        let Some(name) = found else {
+            self.pos = start;
+            return None;
+        };
+
+        self.name = Some(name.clone());
+        self.seen_params.clear();
+        let literal = self.pos;
+        let ir = match self.function_literal() {
+            Ok(ir) => ir,
+            Err(reason) => {
+                self.pos = self.statement_end(literal);
+                opaque(reason)
+            }
+        };
+        self.eat_punct(";");
+        self.name = None;
+        Some((name, ir, literal))
+    }
+
+    /// Name of the function declared here, leaving `pos` at its literal
+    fn declaration_head(&mut self) -> Option<String> {
+        self.eat_word("export");
+        let default = self.eat_word("default");
+
+        if self.is_word("async") && matches!(self.peek_at(1), Some(Tok::Ident(w)) if w == "function") {
+            self.pos += 1;
+        }
+        if self.is_word("function") {
+            if matches!(self.peek_at(1), Some(Tok::Punct(p)) if p == "*") {
+                return None;
+            }
+            return match self.peek_at(1) {
+                Some(Tok::Ident(name)) => Some(name.clone()),
+                _ if default => Some("default".to_string()),
+                _ => None,
+            };
+        }
+        if default {
+            return self.is_function_literal().then(|| "default".to_string());
+        }
+
+        if self.eat_word("const") || self.eat_word("let") || self.eat_word("var") {
+            let name = self.ident().ok()?;
+            if self.eat_punct(":") {
+                self.skip_type(&["="]);
+            }
+            return (self.eat_punct("=") && self.is_function_literal()).then_some(name);
+        }
+
+        // exports.name = ... / module.exports.name = ...
+        if self.eat_word("module") && !self.eat_punct(".") {
+            return None;
+        }
+        if self.eat_word("exports") && self.eat_punct(".") {
+            let name = self.ident().ok()?;
+            return (self.eat_punct("=") && self.is_function_literal()).then_some(name);
+        }
+        None
+    }
+
+    /// An arrow function or `function` expression starts here
+    fn is_function_literal(&self) -> bool {
+        let mut i = self.pos;
+        if matches!(self.toks.get(i).map(|t| &t.tok), Some(Tok::Ident(w)) if w == "async")
+            && !matches!(self.toks.get(i + 1).map(|t| &t.tok), Some(Tok::Punct(p)) if p == "=>")
+        {
+            i += 1;
+        }
+        let tok = |i: usize| self.toks.get(i).map(|t| &t.tok);
+        let punct = |i: usize, p: &str| matches!(tok(i), Some(Tok::Punct(q)) if q == p);
+        match tok(i) {
+            Some(Tok::Ident(w)) if w == "function" => true,
+            Some(Tok::Ident(_)) => punct(i + 1, "=>"),
+            Some(Tok::Punct(p)) if p == "(" || p == "<" => {
+                let mut open = i;
+                if p == "<" {
+                    // Generic arrow: `<T,>(x: T) => x`
+                    let mut depth = 0i32;
+                    while let Some(t) = tok(open) {
+                        match t {
+                            Tok::Punct(q) if q == "<" => depth += 1,
+                            Tok::Punct(q) if q == ">" => depth -= 1,
+                            Tok::Punct(q) if q == ">>" => depth -= 2,
+                            _ => {}
+                        }
+                        open += 1;
+                        if depth <= 0 {
+                            break;
+                        }
+                    }
+                    if !punct(open, "(") {
+                        return false;
+                    }
+                }
+                let Some(close) = self.matching(open) else {
+                    return false;
+                };
+                if punct(close + 1, "=>") {
+                    return true;
+                }
+                if !punct(close + 1, ":") {
+                    return false;
+                }
+                // Return type annotation, then the arrow
+                let mut depth = 0i32;
+                for j in close + 2..self.toks.len() {
+                    match tok(j) {
+                        Some(Tok::Punct(q)) if q == "=>" && depth == 0 => return true,
+                        Some(Tok::Punct(q)) if matches!(q.as_str(), "(" | "[" | "{" | "<") => depth += 1,
+                        Some(Tok::Punct(q)) if matches!(q.as_str(), ")" | "]" | "}" | ">") => {
+                            depth -= 1;
+                            if depth < 0 {
+                                return false;
+                            }
+                        }
+                        Some(Tok::Punct(q)) if depth == 0 && matches!(q.as_str(), "," | ";" | "?" | ":") => return false,
+                        None => return false,
+                        _ => {}
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// `(a, b): T => body`, `a => body` or `function name?(a, b): T { body }`
+    fn function_literal(&mut self) -> Lowered {
+        if self.is_word("async") && !matches!(self.peek_at(1), Some(Tok::Punct(p)) if p == "=>") {
+            self.pos += 1;
+        }
+        if self.eat_word("function") {
+            if self.eat_punct("*") {
+                return Err("generator function".to_string());
+            }
+            if matches!(self.peek(), Some(Tok::Ident(_))) {
+                self.pos += 1;
+            }
+            return self.function_rest(false);
+        }
+        self.function_rest(true)
+    }
+
+    /// Generics, parameters, return type and body
+    fn function_rest(&mut self, arrow: bool) -> Lowered {
+        self.skip_generics();
+        let params = if arrow && matches!(self.peek(), Some(Tok::Ident(_))) {
+            vec![self.ident()?]
+        } else {
+            self.params()?
+        };
+        self.seen_params.extend(params.iter().cloned());
+        if self.eat_punct(":") {
+            self.skip_type(if arrow { &["=>"] } else { &["{"] });
+        }
+        if arrow {
+            self.expect("=>")?;
+        }
+        let body = if self.is_punct("{") {
+            self.block()?
+        } else if arrow {
+            self.expr()?
+        } else {
+            return Err(format!("missing function body on line {}", self.line()));
+        };
+        self.arity = params.len();
+        Ok(lambda(params, body))
+    }
+
+    fn params(&mut self) -> std::result::Result<Vec<String>, String> {
+        self.expect("(")?;
+        let mut params = Vec::new();
+        while !self.eat_punct(")") {
+            if self.is_punct("...") {
+                return Err("rest parameter".to_string());
+            }
+            if self.is_punct("[") || self.is_punct("{") {
+                return Err("destructuring parameter".to_string());
+            }
+            params.push(self.ident()?);
+            self.eat_punct("?");
+            if self.eat_punct(":") {
+                self.skip_type(&[",", ")", "="]);
+            }
+            if self.is_punct("=") {
+                return Err("default parameter".to_string());
+            }
+            if !self.eat_punct(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        Ok(params)
+    }
+
+    /// `{ statements }` lowered to one expression
+    fn block(&mut self) -> Lowered {
+        Ok(lower(&self.statements()?))
+    }
+
+    /// Statements of a block - the first unsupported one ends it
+    fn statements(&mut self) -> std::result::Result<Vec<Stmt>, String> {
+        let open = self.pos;
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        loop {
+            if self.eat_punct("}") {
+                break;
+            }
+            if self.peek().is_none() {
+                return Err("unterminated block".to_string());
+            }
+            match self.statement() {
+                Ok(Some(stmt)) => stmts.push(stmt),
+                Ok(None) => {}
+                Err(reason) => {
+                    stmts.push(Stmt::Unsupported(reason));
+                    let close = self.matching(open).ok_or("unterminated block")?;
+                    self.pos = close + 1;
+                    break;
+                }
+            }
+        }
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> std::result::Result<Option<Stmt>, String> {
+        if self.eat_punct(";") {
+            return Ok(None);
+        }
+        if self.is_punct("{") {
+            return Ok(Some(Stmt::Block(self.statements()?)));
+        }
+        let Some(Tok::Ident(word)) = self.peek() else {
+            return Err("expression statement".to_string());
+        };
+        match word.as_str() {
+            "const" => {
+                self.pos += 1;
+                if self.is_punct("[") || self.is_punct("{") {
+                    return Err("destructuring binding".to_string());
+                }
+                let name = self.ident()?;
+                if self.eat_punct(":") {
+                    self.skip_type(&["="]);
+                }
+                self.expect("=")?;
+                let value = self.expr()?;
+                if self.is_punct(",") {
+                    return Err("multiple declarators".to_string());
+                }
+                self.eat_punct(";");
+                Ok(Some(Stmt::Const(name, value)))
+            }
+            "let" | "var" => Err(format!("mutable binding {}", word)),
+            "return" => {
+                let line = self.toks[self.pos].line;
+                self.pos += 1;
+                let bare = self.is_punct(";") || self.is_punct("}") || self.line() > line || self.peek().is_none();
+                if bare {
+                    self.eat_punct(";");
+                    return Ok(Some(Stmt::Return(opaque("return without value"))));
+                }
+                let value = self.expr()?;
+                self.eat_punct(";");
+                Ok(Some(Stmt::Return(value)))
+            }
+            "if" => {
+                self.pos += 1;
+                self.expect("(")?;
+                let cond = self.expr()?;
+                self.expect(")")?;
+                let then = self.branch()?;
+                let otherwise = if self.eat_word("else") { Some(self.branch()?) } else { None };
+                Ok(Some(Stmt::If(cond, then, otherwise)))
+            }
+            "function" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let value = self.function_rest(false)?;
+                Ok(Some(Stmt::Const(name, value)))
+            }
+            "for" | "while" | "do" | "switch" | "throw" | "try" | "class" | "break" | "continue" => {
+                Err(format!("{} statement", word))
+            }
+            _ => Err("expression statement".to_string()),
+        }
+    }
+
+    /// Body of an `if` or `else`: a block or one statement
+    fn branch(&mut self) -> std::result::Result<Vec<Stmt>, String> {
+        if self.is_punct("{") {
+            return self.statements();
+        }
+        Ok(self.statement()?.into_iter().collect())
+    }
+
+    fn expr(&mut self) -> Lowered {
+        if self.is_function_literal() {
+            return self.function_literal();
+        }
+        let value = self.conditional()?;
+        if let Some(Tok::Punct(p)) = self.peek() {
+            if p == "=" || (p.ends_with('=') && !matches!(p.as_str(), "==" | "===" | "!=" | "!==" | "<=" | ">=")) {
+                self.pos += 1;
+                self.expr()?;
+                return Ok(opaque("assignment"));
+            }
+        }
+        Ok(value)
+    }
+
+    fn conditional(&mut self) -> Lowered {
+        let cond = self.binary(1)?;
+        if !self.eat_punct("?") {
+            return Ok(cond);
+        }
+        let then = self.expr()?;
+        self.expect(":")?;
+        let otherwise = self.expr()?;
+        Ok(IR::If(Box::new(cond), Box::new(then), Box::new(otherwise)))
+    }
+
+    /// Operator here and its precedence
+    fn binary_op(&self) -> Option<(String, u8)> {
+        let op = match self.peek()? {
+            Tok::Punct(p) => p.as_str(),
+            Tok::Ident(w) if w == "instanceof" || w == "in" => w.as_str(),
+            _ => return None,
+        };
+        let prec = match op {
+            "||" | "??" => 1,
+            "&&" => 2,
+            "|" => 3,
+            "^" => 4,
+            "&" => 5,
+            "==" | "!=" | "===" | "!==" => 6,
+            "<" | ">" | "<=" | ">=" | "instanceof" | "in" => 7,
+            "<<" | ">>" | ">>>" => 8,
+            "+" | "-" => 9,
+            "*" | "/" | "%" => 10,
+            "**" => 11,
+            _ => return None,
+        };
+        Some((op.to_string(), prec))
+    }
+
+    /// Precedence climbing - `**` is the only right-associative operator
+    fn binary(&mut self, min: u8) -> Lowered {
+        let mut lhs = self.unary()?;
+        while let Some((op, prec)) = self.binary_op() {
+            if prec < min {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.binary(if op == "**" { prec } else { prec + 1 })?;
+            lhs = combine(&op, lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Lowered {
+        let op = match self.peek() {
+            Some(Tok::Punct(p)) if matches!(p.as_str(), "!" | "-" | "+" | "~" | "++" | "--") => p.clone(),
+            Some(Tok::Ident(w)) if matches!(w.as_str(), "typeof" | "void" | "delete" | "await") => w.clone(),
+            Some(Tok::Ident(w)) if w == "new" => {
+                self.pos += 1;
+                self.postfix()?;
+                return Ok(opaque("new expression"));
+            }
+            _ => return self.postfix(),
+        };
+        self.pos += 1;
+        let operand = self.unary()?;
+        Ok(match op.as_str() {
+            "!" => IR::If(Box::new(operand), Box::new(IR::Bool(false)), Box::new(IR::Bool(true))),
+            "-" => match operand {
+                IR::Num(n) => IR::Num(-n),
+                operand => IR::Sub(Box::new(IR::Num(0)), Box::new(operand)),
+            },
+            "+" => operand,
+            "++" | "--" => opaque("mutation"),
+            _ => opaque(format!("{} operator", op)),
+        })
+    }
+
+    fn postfix(&mut self) -> Lowered {
+        let mut value = self.primary()?;
+        loop {
+            if self.eat_punct(".") {
+                let name = self.ident()?;
+                value = if self.is_punct("(") { self.method_call(value, &name)? } else { opaque(format!("property .{}", name)) };
+            } else if self.is_punct("(") {
+                let (args, spread) = self.arguments()?;
+                value = if spread { opaque("spread arguments") } else { apply(value, args.into_iter().map(|(arg, _)| arg).collect()) };
+            } else if self.eat_punct("[") {
+                self.expr()?;
+                self.expect("]")?;
+                value = opaque("index access");
+            } else if self.eat_punct("?.") {
+                if self.is_punct("(") {
+                    self.arguments()?;
+                } else if self.eat_punct("[") {
+                    self.expr()?;
+                    self.expect("]")?;
+                } else {
+                    self.ident()?;
+                }
+                value = opaque("optional chaining");
+            } else if self.eat_word("as") || self.eat_word("satisfies") {
+                self.skip_type_expr();
+            } else if self.eat_punct("!") {
+                // TypeScript non-null assertion
+            } else if (self.is_punct("++") || self.is_punct("--")) && self.line() == self.toks[self.pos - 1].line {
+                self.pos += 1;
+                value = opaque("mutation");
+            } else if matches!(self.peek(), Some(Tok::Template)) {
+                self.pos += 1;
+                value = opaque("tagged template");
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Arguments with the parameter count of those that are function literals
+    fn arguments(&mut self) -> std::result::Result<(Vec<Arg>, bool), String> {
+        self.expect("(")?;
+        let mut args = Vec::new();
+        let mut spread = false;
+        while !self.eat_punct(")") {
+            spread |= self.eat_punct("...");
+            let literal = self.is_function_literal();
+            let arg = self.expr()?;
+            args.push((arg, literal.then_some(self.arity)));
+            if !self.eat_punct(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        Ok((args, spread))
+    }
+
+    /// `.map(f)`, `.filter(p)` and `.reduce(f, init)` lower; callbacks that take the index do not
+    fn method_call(&mut self, target: IR, name: &str) -> Lowered {
+        let (mut args, spread) = self.arguments()?;
+        if spread {
+            return Ok(opaque("spread arguments"));
+        }
+        let takes = |args: &[Arg], max: usize| args[0].1.is_none_or(|arity| arity <= max);
+        let target = Box::new(target);
+        Ok(match (name, args.len()) {
+            ("map" | "filter", 1) if !takes(&args, 1) => opaque("index-aware callback"),
+            ("map", 1) => IR::Map(target, Box::new(args.remove(0).0)),
+            ("filter", 1) => IR::Filter(target, Box::new(args.remove(0).0)),
+            ("reduce", 2) if !takes(&args, 2) => opaque("index-aware callback"),
+            ("reduce", 2) => {
+                let init = args.pop().unwrap().0;
+                IR::Reduce(target, Box::new(args.remove(0).0), Box::new(init))
+            }
+            ("reduce", 1) => opaque("reduce without initial value"),
+            _ => opaque(format!("method .{}", name)),
+        })
+    }
+
+    fn primary(&mut self) -> Lowered {
+        let Some(tok) = self.peek().cloned() else {
+            return Err("unexpected end of input".to_string());
+        };
+        self.pos += 1;
+        Ok(match tok {
+            Tok::Num(text) => parse_int(&text).map_or_else(|| opaque(format!("number {}", text)), IR::Num),
+            Tok::Str(s) => IR::Str(s),
+            Tok::Template => opaque("template literal"),
+            Tok::Regex => opaque("regular expression"),
+            Tok::Ident(word) => match word.as_str() {
+                "true" => IR::Bool(true),
+                "false" => IR::Bool(false),
+                "null" | "undefined" | "this" | "super" => opaque(word),
+                "function" | "async" => {
+                    self.pos -= 1;
+                    self.function_literal()?
+                }
+                "class" | "return" | "const" | "let" | "var" | "if" | "for" | "while" | "yield" => {
+                    return Err(format!("unexpected '{}' on line {}", word, self.line()));
+                }
+                _ if self.name.as_deref() == Some(word.as_str()) => IR::Var(SELF.to_string()),
+                _ => IR::Var(word),
+            },
+            Tok::Punct(p) => match p.as_str() {
+                "(" => {
+                    let value = self.expr()?;
+                    if self.is_punct(",") {
+                        let close = self.matching(self.pos - 1).ok_or("unterminated parenthesis")?;
+                        self.pos = close + 1;
+                        return Ok(opaque("comma expression"));
+                    }
+                    self.expect(")")?;
+                    value
+                }
+                "[" => self.array()?,
+                "{" => {
+                    let close = self.matching(self.pos - 1).ok_or("unterminated object literal")?;
+                    self.pos = close + 1;
+                    opaque("object literal")
+                }
+                _ => return Err(format!("unexpected '{}' on line {}", p, self.line())),
+            },
+        })
+    }
+
+    /// Cons chain - a trailing `...xs` is the tail
+    fn array(&mut self) -> Lowered {
+        let mut items = Vec::new();
+        let mut spread: Option<IR> = None;
+        let mut misplaced = false;
+        loop {
+            if self.eat_punct("]") {
+                break;
+            }
+            misplaced |= spread.is_some();
+            if self.eat_punct("...") {
+                spread = Some(self.expr()?);
+            } else if self.is_punct(",") {
+                items.push(opaque("array hole"));
+            } else {
+                items.push(self.expr()?);
+            }
+            if !self.eat_punct(",") {
+                self.expect("]")?;
+                break;
+            }
+        }
+        if misplaced {
+            return Ok(opaque("spread before the last element"));
+        }
+        let tail = spread.unwrap_or(IR::Nil);
+        Ok(items.into_iter().rev().fold(tail, |tail, head| IR::Cons(Box::new(head), Box::new(tail))))
+    }
+}
+
+/// Integer literal - floats and bigints are outside the subset
+fn parse_int(text: &str) -> Option<i64> {
+    let text = text.replace('_', "");
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else if let Some(oct) = lower.strip_prefix("0o") {
+        i64::from_str_radix(oct, 8).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+/// Methods that mutate their array, map or set
+const MUTATORS: [&str; 13] = [
+    "push", "pop", "shift", "unshift", "splice", "sort", "reverse", "fill", "copyWithin", "set", "delete", "add", "clear",
+];
+
+/// Words that end no assignable expression - `return [..]` is a literal, not an index
+const KEYWORDS: [&str; 12] = ["return", "typeof", "case", "in", "of", "new", "delete", "void", "throw", "yield", "await", "else"];
+
+/// What an assignment or mutating call writes to
+enum Target {
+    /// Rebinding a name
+    Name(String),
+    /// Something inside the value a name holds
+    Inside(String),
+    /// A literal made on the spot
+    Fresh,
+    Unknown,
+}
+
+/// Effects of the tokens of one function literal. Names it declares (and
+/// its parameters) are local; writes inside a value are only local when the
+/// name was bound to a new array or object, which nothing else can alias.
+fn scan_effects(toks: &[Token], params: &[String]) -> Effects {
+    let word = |i: usize| match toks.get(i).map(|t| &t.tok) {
+        Some(Tok::Ident(w)) => Some(w.as_str()),
+        _ => None,
+    };
+    let punct = |i: usize| match toks.get(i).map(|t| &t.tok) {
+        Some(Tok::Punct(p)) => Some(p.as_str()),
+        _ => None,
+    };
+
+    let mut declared: HashSet<&str> = params.iter().map(String::as_str).collect();
+    let mut fresh = HashSet::new();
+    for i in 0..toks.len() {
+        if !matches!(word(i), Some("const" | "let" | "var" | "function")) {
+            continue;
+        }
+        let Some(name) = word(i + 1) else { continue };
+        declared.insert(name);
+        let mut j = i + 2;
+        if punct(j) == Some(":") {
+            while j < toks.len() && !matches!(punct(j), Some("=" | ";")) {
+                j += 1;
+            }
+        }
+        if punct(j) == Some("=") && (matches!(punct(j + 1), Some("[" | "{")) || word(j + 1) == Some("new")) {
+            fresh.insert(name);
+        }
+    }
+
+    let scope = |target: Target| match target {
+        Target::Name(name) if declared.contains(name.as_str()) => Scope::Local,
+        Target::Inside(name) if fresh.contains(name.as_str()) => Scope::Local,
+        Target::Fresh => Scope::Local,
+        _ => Scope::Escaping,
+    };
+    let inside = |target: Target| match target {
+        Target::Name(name) => Target::Inside(name),
+        target => target,
+    };
+
+    let mut effects = Effects::default();
+    for (i, token) in toks.iter().enumerate() {
+        match &token.tok {
+            Tok::Ident(w) => match w.as_str() {
+                "throw" => effects.insert(Effect::Exception),
+                "while" | "do" => effects.insert(Effect::Nontermination),
+                "for" if punct(i + 1) == Some("(") && punct(i + 2) == Some(";") && punct(i + 3) == Some(";") => {
+                    effects.insert(Effect::Nontermination)
+                }
+                "delete" => {
+                    if let Some(name) = word(i + 1) {
+                        effects.insert(Effect::Mutation(scope(Target::Inside(name.to_string()))));
+                    }
+                }
+                name => {
+                    // Not a member, an object key or a bare type annotation
+                    let annotation = punct(i.wrapping_sub(1)) == Some(":") && !matches!(punct(i + 1), Some("." | "("));
+                    let head = !matches!(punct(i.wrapping_sub(1)), Some("." | "?.")) && punct(i + 1) != Some(":") && !annotation;
+                    if head && !declared.contains(name) {
+                        let mut path = name.to_string();
+                        let mut j = i + 1;
+                        while let (Some("."), Some(member)) = (punct(j), word(j + 1)) {
+                            path = format!("{}.{}", path, member);
+                            j += 2;
+                        }
+                        if let Some(effect) = effects::of_call(&path) {
+                            effects.insert(effect);
+                        }
+                    }
+                }
+            },
+            Tok::Punct(p) if p == "=" || (p.ends_with('=') && !matches!(p.as_str(), "==" | "===" | "!=" | "!==" | "<=" | ">=")) => {
+                if let Some(target) = target(toks, i) {
+                    effects.insert(Effect::Mutation(scope(target)));
+                }
+            }
+            Tok::Punct(p) if p == "++" || p == "--" => {
+                let postfix = i > 0 && ends_value(&toks[i - 1].tok);
+                let target = if postfix {
+                    target(toks, i)
+                } else {
+                    word(i + 1).map(|name| match punct(i + 2) {
+                        Some("." | "?." | "[") => Target::Inside(name.to_string()),
+                        _ => Target::Name(name.to_string()),
+                    })
+                };
+                if let Some(target) = target {
+                    effects.insert(Effect::Mutation(scope(target)));
+                }
+            }
+            Tok::Punct(p) if p == "." && word(i + 1).is_some_and(|m| MUTATORS.contains(&m)) && punct(i + 2) == Some("(") => {
+                if let Some(target) = target(toks, i) {
+                    effects.insert(Effect::Mutation(scope(inside(target))));
+                }
+            }
+            _ => {}
+        }
+    }
+    effects
+}
+
+/// The token can end an expression that is indexed or called
+fn ends_value(tok: &Tok) -> bool {
+    match tok {
+        Tok::Ident(w) => !KEYWORDS.contains(&w.as_str()),
+        Tok::Punct(p) => p == ")" || p == "]",
+        _ => false,
+    }
+}
+
+/// What the expression ending just before `end` writes to; `None` when it
+/// isn't written at all - a declaration, or a type annotation before `=`
+fn target(toks: &[Token], end: usize) -> Option<Target> {
+    let mut i = end;
+    let mut inside = false;
+    loop {
+        let j = i.checked_sub(1)?;
+        match &toks[j].tok {
+            Tok::Punct(p) if p == "]" => {
+                let open = opening(toks, j)?;
+                if open == 0 || !ends_value(&toks[open - 1].tok) {
+                    return Some(Target::Fresh);
+                }
+                inside = true;
+                i = open;
+            }
+            Tok::Ident(name) => {
+                let before = j.checked_sub(1).map(|k| &toks[k].tok);
+                match before {
+                    Some(Tok::Punct(p)) if p == "." || p == "?." => {
+                        inside = true;
+                        i = j - 1;
+                    }
+                    Some(Tok::Punct(p)) if p == ":" => return None,
+                    Some(Tok::Ident(w)) if matches!(w.as_str(), "const" | "let" | "var") => return None,
+                    _ => {
+                        let name = name.clone();
+                        return Some(if inside { Target::Inside(name) } else { Target::Name(name) });
+                    }
+                }
+            }
+            Tok::Punct(p) if p == ")" => return Some(Target::Unknown),
+            _ => return None,
+        }
+    }
+}
+
+/// Index of the bracket opening the one closing at `close`
+fn opening(toks: &[Token], close: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for i in (0..=close).rev() {
+        match &toks[i].tok {
+            Tok::Punct(p) if matches!(p.as_str(), ")" | "]" | "}") => depth += 1,
+            Tok::Punct(p) if matches!(p.as_str(), "(" | "[" | "{") => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::decode;
+
+    fn var(x: &str) -> Box<IR> {
+        Box::new(IR::Var(x.to_string()))
+    }
+
+    fn lam(x: &str, body: IR) -> IR {
+        IR::Lam(x.to_string(), Box::new(body))
+    }
+
+    fn functions(code: &str) -> Vec<(String, IR)> {
+        extract(code).unwrap()
+            .into_iter()
+            .map(|f| (f.name, decode(&f.ir).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_typed_arrow_with_map() {
+        let code = "import { x } from './x';\n\nexport const double = (xs: number[]): number[] =>\n  xs.map((x: number) => x * 2);\n";
+        let extracted = extract(code).unwrap();
+        assert_eq!(extracted.len(), 1);
+        assert_eq!((extracted[0].start_line, extracted[0].end_line), (3, 4));
+        assert_eq!(
+            decode(&extracted[0].ir).unwrap(),
+            lam("xs", IR::Map(var("xs"), Box::new(lam("x", IR::Mul(var("x"), Box::new(IR::Num(2)))))))
+        );
+    }
+
+    #[test]
+    fn test_statements_and_recursion() {
+        let code = "\
+function fact<T>(n: number): number {
+  if (n <= 1) {
+    return 1;
+  }
+  const m = n - 1;
+  return n * fact(m);
+}
+";
+        let expected = lam("n", IR::If(
+            Box::new(IR::Le(var("n"), Box::new(IR::Num(1)))),
+            Box::new(IR::Num(1)),
+            Box::new(IR::App(
+                Box::new(lam("m", IR::Mul(var("n"), Box::new(IR::App(var(SELF), var("m")))))),
+                Box::new(IR::Sub(var("n"), Box::new(IR::Num(1)))),
+            )),
+        ));
+        assert_eq!(functions(code), vec![("fact".to_string(), expected)]);
+    }
+
+    #[test]
+    fn test_filter_reduce_and_spread() {
+        let code = "exports.evens = xs => xs.filter(x => x % 2 === 0).reduce((acc, x) => [x, ...acc], []);";
+        let evens = IR::Filter(var("xs"), Box::new(lam("x", IR::Eq(
+            Box::new(IR::Mod(var("x"), Box::new(IR::Num(2)))),
+            Box::new(IR::Num(0)),
+        ))));
+        let expected = lam("xs", IR::Reduce(
+            Box::new(evens),
+            Box::new(lam("acc", lam("x", IR::Cons(var("x"), var("acc"))))),
+            Box::new(IR::Nil),
+        ));
+        assert_eq!(functions(code), vec![("evens".to_string(), expected)]);
+    }
+
+    #[test]
+    fn test_unsupported_constructs_are_opaque() {
+        let code = "\
+function total(xs) {
+  let t = 0;
+  for (const x of xs) { t += x; }
+  return t;
+}
+const keys = (o) => Object.keys(o);
+const indexed = (xs) => xs.map((x, i) => x + i);
+const after = (s) => `${s}!`;
+";
+        assert_eq!(functions(code), vec![
+            ("total".to_string(), lam("xs", opaque("mutable binding let"))),
+            ("keys".to_string(), lam("o", opaque("method .keys"))),
+            ("indexed".to_string(), lam("xs", opaque("index-aware callback"))),
+            ("after".to_string(), lam("s", opaque("template literal"))),
+        ]);
+    }
+
+    #[test]
+    fn test_alpha_equivalent_functions_share_a_soul() {
+        let arrow = functions("const map = (xs, f) => xs.map(f);");
+        let declared = functions("function mapExplicit(ys, g) {\n  return ys.map(g);\n}");
+        assert_ne!(arrow[0].1, declared[0].1);
+        assert_eq!(
+            arrow[0].1.alpha_normalize().to_canonical_string(),
+            declared[0].1.alpha_normalize().to_canonical_string()
+        );
+    }
+
+    #[test]
+    fn test_effects_scanned_past_the_subset() {
+        use Effect::*;
+        let code = "\
+function total(xs) {
+  let t = 0;
+  for (const x of xs) { t += x; }
+  return t;
+}
+function doubled(xs: number[]): number[] {
+  const out: number[] = [];
+  for (const x of xs) out.push(x * 2);
+  return out;
+}
+function append(xs, x) { xs.push(x); return xs; }
+const logged = (x) => { console.log(x); return x; };
+const roll = () => Math.floor(Math.random() * 6);
+function stamp(o) { o.at = Date.now(); return o; }
+function spin() { while (true) {} }
+const fail = (msg: string) => { throw new Error(msg); };
+";
+        let effects: Vec<(String, Vec<Effect>)> = extract(code).unwrap()
+            .into_iter()
+            .map(|f| (f.name, f.effects.unwrap().iter().collect()))
+            .collect();
+        let expected = vec![
+            ("total", vec![Mutation(Scope::Local)]),
+            ("doubled", vec![Mutation(Scope::Local)]),
+            ("append", vec![Mutation(Scope::Escaping)]),
+            ("logged", vec![Io]),
+            ("roll", vec![Randomness]),
+            ("stamp", vec![Mutation(Scope::Escaping), Time]),
+            ("spin", vec![Nontermination]),
+            ("fail", vec![Exception]),
+        ];
+        assert_eq!(effects, expected.into_iter().map(|(name, e)| (name.to_string(), e)).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,130 @@
+// Built-in extractors: pure language subsets lowered to surgeon IR
+// Used when no extractors/<lang>.wasm is installed
+
+use anyhow::Result;
+
+use crate::runtime::ExtractedFunction;
+use crate::surgeon::egraph::IR;
+
+pub mod js;
+pub mod python;
+pub mod rust;
+
+/// Name a function's references to itself lower to - souls don't depend on its name
+pub const SELF: &str = "rec";
+
+/// Functions of `code`, if a built-in extractor covers `lang`
+pub fn native(lang: &str, code: &str) -> Option<Result<Vec<ExtractedFunction>>> {
+    match lang {
+        "javascript" | "typescript" => Some(js::extract(code)),
+        "python" => Some(python::extract(code)),
+        "rust" => Some(rust::extract(code)),
+        _ => None,
+    }
+}
+
+/// IR as stored on genes
+pub fn encode(ir: &IR) -> Result<String> {
+    Ok(serde_json::to_string(ir)?)
+}
+
+/// Structured IR back from a gene, `None` for textual IR
+pub fn decode(ir: &str) -> Option<IR> {
+    serde_json::from_str(ir).ok()
+}
+
+/// Curried abstraction - no parameters binds `_`
+pub fn lambda(params: Vec<String>, body: IR) -> IR {
+    if params.is_empty() {
+        return IR::Lam("_".to_string(), Box::new(body));
+    }
+    params.into_iter().rev().fold(body, |body, x| IR::Lam(x, Box::new(body)))
+}
+
+/// Curried application - no arguments passes `Nil`
+pub fn apply(f: IR, args: Vec<IR>) -> IR {
+    if args.is_empty() {
+        return IR::App(Box::new(f), Box::new(IR::Nil));
+    }
+    args.into_iter().fold(f, |f, x| IR::App(Box::new(f), Box::new(x)))
+}
+
+/// Construct outside the subset
+pub fn opaque(reason: impl Into<String>) -> IR {
+    IR::Opaque(reason.into())
+}
+
+/// Binary operator by its JavaScript spelling - `a > b` is `b < a`, `!==`
+/// negates `===`, loose and strict equality coincide
+pub fn combine(op: &str, a: IR, b: IR) -> IR {
+    let (a, b) = (Box::new(a), Box::new(b));
+    match op {
+        "+" => IR::Add(a, b),
+        "-" => IR::Sub(a, b),
+        "*" => IR::Mul(a, b),
+        "/" => IR::Div(a, b),
+        "%" => IR::Mod(a, b),
+        "==" | "===" => IR::Eq(a, b),
+        "!=" | "!==" => IR::If(Box::new(IR::Eq(a, b)), Box::new(IR::Bool(false)), Box::new(IR::Bool(true))),
+        "<" => IR::Lt(a, b),
+        ">" => IR::Lt(b, a),
+        "<=" => IR::Le(a, b),
+        ">=" => IR::Le(b, a),
+        "&&" => IR::If(a, b, Box::new(IR::Bool(false))),
+        "||" => IR::If(a, Box::new(IR::Bool(true)), b),
+        _ => opaque(format!("operator {}", op)),
+    }
+}
+
+/// Body statement, lowered once the whole block is known
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Const(String, IR),
+    Return(IR),
+    If(IR, Vec<Stmt>, Option<Vec<Stmt>>),
+    Block(Vec<Stmt>),
+    Unsupported(String),
+}
+
+/// `const x = e; rest` is `(λx.rest) e`; an `if` without a return falls through to the rest
+pub fn lower(stmts: &[Stmt]) -> IR {
+    let Some((first, rest)) = stmts.split_first() else {
+        return opaque("missing return");
+    };
+    match first {
+        Stmt::Const(x, value) => IR::App(Box::new(IR::Lam(x.clone(), Box::new(lower(rest)))), Box::new(value.clone())),
+        Stmt::Return(value) => value.clone(),
+        Stmt::If(cond, then, otherwise) => {
+            let then: Vec<Stmt> = then.iter().chain(rest).cloned().collect();
+            let otherwise: Vec<Stmt> = otherwise.iter().flatten().chain(rest).cloned().collect();
+            IR::If(Box::new(cond.clone()), Box::new(lower(&then)), Box::new(lower(&otherwise)))
+        }
+        Stmt::Block(inner) => lower(&inner.iter().chain(rest).cloned().collect::<Vec<_>>()),
+        Stmt::Unsupported(reason) => opaque(reason.clone()),
+    }
+}
+
+/// `λx.f x` is `f` and `(λx.x) e` is `e` - a closure that only forwards, or a
+/// binding that is only returned, doesn't change the soul
+pub fn simplify(ir: &IR) -> IR {
+    let ir = ir.map_children(simplify);
+    match &ir {
+        IR::Lam(x, body) => match &**body {
+            IR::App(f, arg) if matches!(&**arg, IR::Var(y) if y == x) && !free_in(x, f) => (**f).clone(),
+            _ => ir,
+        },
+        IR::App(f, arg) => match &**f {
+            IR::Lam(x, body) if matches!(&**body, IR::Var(y) if y == x) => (**arg).clone(),
+            _ => ir,
+        },
+        _ => ir,
+    }
+}
+
+fn free_in(x: &str, ir: &IR) -> bool {
+    match ir {
+        IR::Var(y) => y == x,
+        IR::Lam(y, body) => y != x && free_in(x, body),
+        _ => ir.children().into_iter().any(|child| free_in(x, child)),
+    }
+}
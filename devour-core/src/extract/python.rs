@@ -0,0 +1,1522 @@
+// Python pure subset
+//
+// Top-level `def`s and `name = lambda ...` bindings. Bodies: assignments
+// (sequential rebinding is a new binding), `if`/`elif`/`else`, `return`,
+// conditional expressions, arithmetic, comparisons (chained too), `and`/`or`/
+// `not`, calls, recursion, `lambda`, list literals with a trailing `*xs`,
+// `map`/`filter`/`functools.reduce`/`sum`, and single-`for` comprehensions:
+// `[e for x in xs]` is a `Map`, with `if` clauses a hard `Focus` (a `Filter`
+// when the element is `x` itself). `list(..)` is transparent and annotations
+// are skipped. Anything else lowers to `IR::Opaque` with the reason. Effects
+// are scanned from every token of the definition, past where the subset stops.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use super::{apply, combine, encode, lambda, lower, opaque, simplify, Stmt, SELF};
+use crate::effects::{self, Effect, Effects, Scope};
+use crate::runtime::ExtractedFunction;
+use crate::surgeon::egraph::IR;
+
+/// Functions of a Python file
+pub fn extract(code: &str) -> Result<Vec<ExtractedFunction>> {
+    let toks = lex(code)?;
+    let mut parser = Parser { toks: &toks, pos: 0, name: None };
+    let mut functions = Vec::new();
+
+    while parser.pos < toks.len() {
+        let start = parser.pos;
+        match parser.definition() {
+            Some((name, ir)) => functions.push(ExtractedFunction {
+                name,
+                ir: encode(&simplify(&ir))?,
+                start_line: toks[start].line,
+                end_line: parser.last_line(),
+                effects: Some(scan_effects(&toks[start..parser.pos])),
+            }),
+            None => {
+                parser.pos = start;
+                parser.skip_statement();
+            }
+        }
+    }
+    Ok(functions)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Name(String),
+    Num(String),
+    Str(String),
+    FStr,
+    Op(String),
+    Newline,
+    Indent,
+    Dedent,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    line: usize,
+}
+
+/// Multi-character operators, longest first
+const OPS: [&str; 24] = [
+    "**=", "//=", ">>=", "<<=", "**", "//", "==", "!=", "<=", ">=", "->", ":=", "+=", "-=", "*=", "/=", "%=", "&=",
+    "|=", "^=", "@=", "<<", ">>", "...",
+];
+
+/// Tokens with `Newline`/`Indent`/`Dedent` for the layout; no newlines inside brackets
+fn lex(code: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut indents = vec![0usize];
+    let mut depth = 0usize;
+    let mut line = 1;
+    let mut i = 0;
+    let mut line_start = true;
+
+    while i < chars.len() {
+        if line_start && depth == 0 {
+            let mut width = 0;
+            while i < chars.len() && (chars[i] == ' ' || chars[i] == '\t') {
+                width = if chars[i] == '\t' { (width / 8 + 1) * 8 } else { width + 1 };
+                i += 1;
+            }
+            // Blank and comment-only lines don't affect the layout
+            match chars.get(i) {
+                None => break,
+                Some('\n') => {
+                    line += 1;
+                    i += 1;
+                    continue;
+                }
+                Some('\r') | Some('#') => {
+                    while i < chars.len() && chars[i] != '\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            line_start = false;
+            if width > *indents.last().unwrap() {
+                indents.push(width);
+                tokens.push(Token { tok: Tok::Indent, line });
+            }
+            while width < *indents.last().unwrap() {
+                indents.pop();
+                tokens.push(Token { tok: Tok::Dedent, line });
+            }
+            if width != *indents.last().unwrap() {
+                bail!("inconsistent indentation on line {}", line);
+            }
+        }
+
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            if depth == 0 && !matches!(tokens.last().map(|t| &t.tok), None | Some(Tok::Newline)) {
+                tokens.push(Token { tok: Tok::Newline, line });
+            }
+            line += 1;
+            i += 1;
+            line_start = true;
+            continue;
+        }
+        if c == '\\' && (next == Some('\n') || next == Some('\r')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            line += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let start_line = line;
+        let tok = if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let prefix = word.len() <= 2 && word.chars().all(|c| "rRbBuUfF".contains(c));
+            if prefix && matches!(chars.get(i), Some('"') | Some('\'')) {
+                let (end, text) = string(&chars, i, !word.to_lowercase().contains('r'), &mut line)?;
+                i = end;
+                if word.to_lowercase().contains('f') { Tok::FStr } else { Tok::Str(text) }
+            } else {
+                Tok::Name(word)
+            }
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() {
+                let d = chars[i];
+                let exponent_sign = matches!(d, '+' | '-')
+                    && matches!(chars[i - 1], 'e' | 'E')
+                    && !chars[start..i].iter().any(|c| matches!(c, 'x' | 'X'));
+                if d.is_ascii_alphanumeric() || d == '_' || d == '.' || exponent_sign {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            Tok::Num(chars[start..i].iter().collect())
+        } else if c == '"' || c == '\'' {
+            let (end, text) = string(&chars, i, true, &mut line)?;
+            i = end;
+            Tok::Str(text)
+        } else {
+            let rest: String = chars[i..(i + 3).min(chars.len())].iter().collect();
+            let op = OPS.iter()
+                .find(|op| rest.starts_with(**op))
+                .map(|op| op.to_string())
+                .unwrap_or_else(|| c.to_string());
+            match op.as_str() {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            i += op.chars().count();
+            Tok::Op(op)
+        };
+        tokens.push(Token { tok, line: start_line });
+    }
+
+    if !matches!(tokens.last().map(|t| &t.tok), None | Some(Tok::Newline)) {
+        tokens.push(Token { tok: Tok::Newline, line });
+    }
+    for _ in 1..indents.len() {
+        tokens.push(Token { tok: Tok::Dedent, line });
+    }
+    Ok(tokens)
+}
+
+/// String literal at `start` (a quote): index after it and its text
+fn string(chars: &[char], start: usize, escapes: bool, line: &mut usize) -> Result<(usize, String)> {
+    let quote = chars[start];
+    let triple = chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let mut i = start + if triple { 3 } else { 1 };
+    let mut text = String::new();
+    while i < chars.len() {
+        let c = chars[i];
+        if c == quote && (!triple || (chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote))) {
+            return Ok((i + if triple { 3 } else { 1 }, text));
+        }
+        if c == '\n' {
+            if !triple {
+                bail!("unterminated string on line {}", line);
+            }
+            *line += 1;
+        }
+        if c == '\\' && i + 1 < chars.len() {
+            i += 1;
+            match chars[i] {
+                '\n' => *line += 1,
+                'n' if escapes => text.push('\n'),
+                't' if escapes => text.push('\t'),
+                'r' if escapes => text.push('\r'),
+                other if escapes => text.push(other),
+                other => {
+                    text.push('\\');
+                    text.push(other);
+                }
+            }
+        } else {
+            text.push(c);
+        }
+        i += 1;
+    }
+    bail!("unterminated string on line {}", line)
+}
+
+/// Lowered IR, or why the construct could not be lowered
+type Lowered = std::result::Result<IR, String>;
+
+struct Parser<'a> {
+    toks: &'a [Token],
+    pos: usize,
+    /// Function being extracted - references to it are recursion
+    name: Option<String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Tok> {
+        self.toks.get(self.pos + offset).map(|t| &t.tok)
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Op(o)) if o == op)
+    }
+
+    fn is_name(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Name(n)) if n == name)
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let hit = self.is_op(op);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        let hit = self.is_name(name);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn eat(&mut self, tok: Tok) -> bool {
+        let hit = self.peek() == Some(&tok);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    fn expect_op(&mut self, op: &str) -> std::result::Result<(), String> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' on line {}", op, self.line()))
+        }
+    }
+
+    fn expect_name(&mut self, name: &str) -> std::result::Result<(), String> {
+        if self.eat_name(name) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' on line {}", name, self.line()))
+        }
+    }
+
+    fn ident(&mut self) -> std::result::Result<String, String> {
+        match self.peek() {
+            Some(Tok::Name(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(format!("expected a name on line {}", self.line())),
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.toks.get(self.pos).or(self.toks.last()).map_or(0, |t| t.line)
+    }
+
+    /// Line of the last token consumed that isn't layout
+    fn last_line(&self) -> usize {
+        self.toks[..self.pos].iter()
+            .rev()
+            .find(|t| !matches!(t.tok, Tok::Newline | Tok::Indent | Tok::Dedent))
+            .map_or(0, |t| t.line)
+    }
+
+    /// Past the statement here, with its indented block if it has one
+    fn skip_statement(&mut self) {
+        let start = self.pos;
+        while !matches!(self.peek(), None | Some(Tok::Newline)) {
+            self.pos += 1;
+        }
+        self.eat(Tok::Newline);
+        if self.eat(Tok::Indent) {
+            self.skip_block_rest();
+        }
+        if self.pos == start {
+            self.pos += 1;
+        }
+    }
+
+    /// Past the `Dedent` closing the current block
+    fn skip_block_rest(&mut self) {
+        let mut depth = 0usize;
+        while let Some(tok) = self.peek() {
+            match tok {
+                Tok::Indent => depth += 1,
+                Tok::Dedent if depth == 0 => {
+                    self.pos += 1;
+                    return;
+                }
+                Tok::Dedent => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skip an annotation up to one of `stops` outside any brackets
+    fn skip_type(&mut self, stops: &[&str]) {
+        let mut depth = 0i32;
+        while let Some(tok) = self.peek() {
+            match tok {
+                Tok::Op(o) if depth == 0 && stops.contains(&o.as_str()) => return,
+                Tok::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => depth += 1,
+                Tok::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return;
+                    }
+                }
+                Tok::Newline => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Past the bracket closing the one just consumed
+    fn skip_brackets(&mut self) {
+        let mut depth = 1;
+        while let Some(tok) = self.peek() {
+            match tok {
+                Tok::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => depth += 1,
+                Tok::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    /// Top-level function here: `def name(...)` or `name = lambda ...`
+    fn definition(&mut self) -> Option<(String, IR)> {
+        let start = self.pos;
+        if self.eat_name("def") {
+            let name = self.ident().ok()?;
+            self.name = Some(name.clone());
+            let ir = self.function_rest().unwrap_or_else(|reason| {
+                self.pos = start;
+                self.skip_statement();
+                opaque(reason)
+            });
+            self.name = None;
+            return Some((name, ir));
+        }
+
+        let name = match (self.peek(), self.peek_at(1), self.peek_at(2)) {
+            (Some(Tok::Name(name)), Some(Tok::Op(eq)), Some(Tok::Name(kw))) if eq == "=" && kw == "lambda" => name.clone(),
+            _ => return None,
+        };
+        self.pos += 2;
+        self.name = Some(name.clone());
+        let ir = match self.expr() {
+            Ok(ir) if matches!(self.peek(), None | Some(Tok::Newline)) => {
+                self.eat(Tok::Newline);
+                ir
+            }
+            Ok(_) => {
+                self.pos = start;
+                self.skip_statement();
+                opaque("expression after lambda")
+            }
+            Err(reason) => {
+                self.pos = start;
+                self.skip_statement();
+                opaque(reason)
+            }
+        };
+        self.name = None;
+        Some((name, ir))
+    }
+
+    /// Parameters, return annotation and body - from after the name
+    fn function_rest(&mut self) -> Lowered {
+        self.expect_op("(")?;
+        let mut params = Vec::new();
+        while !self.eat_op(")") {
+            if self.is_op("*") || self.is_op("**") || self.is_op("/") {
+                return Err("variadic or positional-only parameters".to_string());
+            }
+            params.push(self.ident()?);
+            if self.eat_op(":") {
+                self.skip_type(&[",", ")", "="]);
+            }
+            if self.is_op("=") {
+                return Err("default parameter".to_string());
+            }
+            if !self.eat_op(",") {
+                self.expect_op(")")?;
+                break;
+            }
+        }
+        if self.eat_op("->") {
+            self.skip_type(&[":"]);
+        }
+        let body = self.block()?;
+        Ok(lambda(params, lower(&body)))
+    }
+
+    /// `: simple; statements` on the header line, or an indented block
+    fn block(&mut self) -> std::result::Result<Vec<Stmt>, String> {
+        self.expect_op(":")?;
+        let mut stmts = Vec::new();
+        if !self.eat(Tok::Newline) {
+            if let Err(reason) = self.simple_statements(&mut stmts, false) {
+                stmts.push(Stmt::Unsupported(reason));
+                while !matches!(self.peek(), None | Some(Tok::Newline)) {
+                    self.pos += 1;
+                }
+                self.eat(Tok::Newline);
+            }
+            return Ok(stmts);
+        }
+        if !self.eat(Tok::Indent) {
+            return Err(format!("expected an indented block on line {}", self.line()));
+        }
+        let mut first = true;
+        while !self.eat(Tok::Dedent) {
+            if self.peek().is_none() {
+                break;
+            }
+            if let Err(reason) = self.statement(&mut stmts, first) {
+                stmts.push(Stmt::Unsupported(reason));
+                self.skip_block_rest();
+                break;
+            }
+            first = false;
+        }
+        Ok(stmts)
+    }
+
+    /// One statement of a block - unsupported ones are errors that end it
+    fn statement(&mut self, stmts: &mut Vec<Stmt>, first: bool) -> std::result::Result<(), String> {
+        let Some(Tok::Name(word)) = self.peek() else {
+            if self.eat(Tok::Newline) {
+                return Ok(());
+            }
+            return self.simple_statements(stmts, first);
+        };
+        match word.as_str() {
+            "if" => {
+                self.pos += 1;
+                let mut branches = vec![(self.expr()?, self.block()?)];
+                while self.eat_name("elif") {
+                    branches.push((self.expr()?, self.block()?));
+                }
+                let mut otherwise = if self.eat_name("else") { Some(self.block()?) } else { None };
+                for (cond, then) in branches.into_iter().rev() {
+                    otherwise = Some(vec![Stmt::If(cond, then, otherwise)]);
+                }
+                stmts.extend(otherwise.unwrap_or_default());
+                Ok(())
+            }
+            "def" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let value = self.function_rest()?;
+                stmts.push(Stmt::Const(name, value));
+                Ok(())
+            }
+            "for" | "while" | "try" | "with" | "class" | "async" | "match" => Err(format!("{} statement", word)),
+            _ => self.simple_statements(stmts, first),
+        }
+    }
+
+    /// `;`-separated simple statements up to the end of the line
+    fn simple_statements(&mut self, stmts: &mut Vec<Stmt>, first: bool) -> std::result::Result<(), String> {
+        loop {
+            self.simple_statement(stmts, first)?;
+            if self.eat(Tok::Newline) || self.peek().is_none() {
+                return Ok(());
+            }
+            self.expect_op(";")?;
+            if self.eat(Tok::Newline) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn simple_statement(&mut self, stmts: &mut Vec<Stmt>, first: bool) -> std::result::Result<(), String> {
+        let ends = |tok: Option<&Tok>| matches!(tok, None | Some(Tok::Newline)) || matches!(tok, Some(Tok::Op(o)) if o == ";");
+        match self.peek() {
+            // Docstring
+            Some(Tok::Str(_)) if first && ends(self.peek_at(1)) => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(Tok::Name(word)) => match word.as_str() {
+                "return" => {
+                    self.pos += 1;
+                    let value = if ends(self.peek()) { opaque("return without value") } else { self.expr()? };
+                    stmts.push(Stmt::Return(value));
+                    Ok(())
+                }
+                "pass" => {
+                    self.pos += 1;
+                    Ok(())
+                }
+                "raise" | "global" | "nonlocal" | "import" | "from" | "assert" | "del" | "yield" | "break"
+                | "continue" => Err(format!("{} statement", word)),
+                _ => {
+                    let assigns = matches!(self.peek_at(1), Some(Tok::Op(o)) if o == "=" || o == ":");
+                    if !assigns {
+                        return Err("expression statement".to_string());
+                    }
+                    let name = self.ident()?;
+                    if self.eat_op(":") {
+                        self.skip_type(&["="]);
+                    }
+                    self.expect_op("=")?;
+                    let value = self.expr()?;
+                    if self.is_op("=") {
+                        return Err("chained assignment".to_string());
+                    }
+                    stmts.push(Stmt::Const(name, value));
+                    Ok(())
+                }
+            },
+            _ => Err("expression statement".to_string()),
+        }
+    }
+
+    fn expr(&mut self) -> Lowered {
+        if self.eat_name("lambda") {
+            let mut params = Vec::new();
+            while !self.eat_op(":") {
+                if self.is_op("*") || self.is_op("**") {
+                    return Err("variadic parameters".to_string());
+                }
+                params.push(self.ident()?);
+                if self.is_op("=") {
+                    return Err("default parameter".to_string());
+                }
+                if !self.eat_op(",") {
+                    self.expect_op(":")?;
+                    break;
+                }
+            }
+            return Ok(lambda(params, self.expr()?));
+        }
+        let value = self.or_expr()?;
+        if self.eat_name("if") {
+            let cond = self.or_expr()?;
+            self.expect_name("else")?;
+            let otherwise = self.expr()?;
+            return Ok(IR::If(Box::new(cond), Box::new(value), Box::new(otherwise)));
+        }
+        if self.eat_op(":=") {
+            self.expr()?;
+            return Ok(opaque("assignment expression"));
+        }
+        Ok(value)
+    }
+
+    fn or_expr(&mut self) -> Lowered {
+        let mut value = self.and_expr()?;
+        while self.eat_name("or") {
+            value = combine("||", value, self.and_expr()?);
+        }
+        Ok(value)
+    }
+
+    fn and_expr(&mut self) -> Lowered {
+        let mut value = self.not_expr()?;
+        while self.eat_name("and") {
+            value = combine("&&", value, self.not_expr()?);
+        }
+        Ok(value)
+    }
+
+    fn not_expr(&mut self) -> Lowered {
+        if self.eat_name("not") {
+            let operand = self.not_expr()?;
+            return Ok(IR::If(Box::new(operand), Box::new(IR::Bool(false)), Box::new(IR::Bool(true))));
+        }
+        self.comparison()
+    }
+
+    /// `a < b < c` is `a < b and b < c`
+    fn comparison(&mut self) -> Lowered {
+        let mut left = self.bitwise()?;
+        let mut result: Option<IR> = None;
+        loop {
+            let op = match self.peek() {
+                Some(Tok::Op(o)) if matches!(o.as_str(), "<" | ">" | "<=" | ">=" | "==" | "!=") => o.clone(),
+                Some(Tok::Name(n)) if n == "in" || n == "is" => n.clone(),
+                Some(Tok::Name(n)) if n == "not" && matches!(self.peek_at(1), Some(Tok::Name(m)) if m == "in") => {
+                    self.pos += 1;
+                    "in".to_string()
+                }
+                _ => return Ok(result.unwrap_or(left)),
+            };
+            self.pos += 1;
+            if op == "is" {
+                self.eat_name("not");
+            }
+            let right = self.bitwise()?;
+            let test = match op.as_str() {
+                "==" => combine("===", left, right.clone()),
+                "!=" => combine("!==", left, right.clone()),
+                "in" | "is" => opaque(format!("operator {}", op)),
+                _ => combine(&op, left, right.clone()),
+            };
+            result = Some(match result {
+                Some(previous) => combine("&&", previous, test),
+                None => test,
+            });
+            left = right;
+        }
+    }
+
+    fn bitwise(&mut self) -> Lowered {
+        let value = self.arith()?;
+        match self.peek() {
+            Some(Tok::Op(op)) if matches!(op.as_str(), "|" | "^" | "&" | "<<" | ">>") => {
+                let reason = format!("operator {}", op);
+                self.pos += 1;
+                self.bitwise()?;
+                Ok(opaque(reason))
+            }
+            _ => Ok(value),
+        }
+    }
+
+    fn arith(&mut self) -> Lowered {
+        let mut value = self.term()?;
+        while let Some(Tok::Op(o)) = self.peek() {
+            let op = match o.as_str() {
+                "+" | "-" => o.clone(),
+                _ => break,
+            };
+            self.pos += 1;
+            value = combine(&op, value, self.term()?);
+        }
+        Ok(value)
+    }
+
+    /// `/` and `//` both lower to `Div`
+    fn term(&mut self) -> Lowered {
+        let mut value = self.factor()?;
+        while let Some(Tok::Op(o)) = self.peek() {
+            let op = match o.as_str() {
+                "*" | "%" => o.clone(),
+                "/" | "//" => "/".to_string(),
+                "@" => "@".to_string(),
+                _ => break,
+            };
+            self.pos += 1;
+            value = combine(&op, value, self.factor()?);
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Lowered {
+        if self.eat_op("-") {
+            return Ok(match self.factor()? {
+                IR::Num(n) => IR::Num(-n),
+                operand => IR::Sub(Box::new(IR::Num(0)), Box::new(operand)),
+            });
+        }
+        if self.eat_op("+") {
+            return self.factor();
+        }
+        if self.eat_op("~") {
+            self.factor()?;
+            return Ok(opaque("operator ~"));
+        }
+        if self.eat_name("await") {
+            self.factor()?;
+            return Ok(opaque("await"));
+        }
+        let base = self.primary()?;
+        if self.eat_op("**") {
+            self.factor()?;
+            return Ok(opaque("operator **"));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Lowered {
+        let mut value = self.atom()?;
+        loop {
+            if self.eat_op(".") {
+                let attr = self.ident()?;
+                if !self.is_op("(") {
+                    value = opaque(format!("attribute .{}", attr));
+                    continue;
+                }
+                let args = self.arguments()?;
+                value = match (&value, attr.as_str()) {
+                    (IR::Var(module), "reduce") if module == "functools" => builtin("reduce", args),
+                    _ => opaque(format!("method .{}", attr)),
+                };
+            } else if self.is_op("(") {
+                let args = self.arguments()?;
+                value = match &value {
+                    IR::Var(name) if matches!(name.as_str(), "map" | "filter" | "reduce" | "list" | "sum") => {
+                        builtin(name, args)
+                    }
+                    _ => match args {
+                        Ok(args) => apply(value, args),
+                        Err(reason) => opaque(reason),
+                    },
+                };
+            } else if self.eat_op("[") {
+                self.skip_brackets();
+                value = opaque("subscript");
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Positional arguments, or why they are outside the subset
+    fn arguments(&mut self) -> std::result::Result<std::result::Result<Vec<IR>, String>, String> {
+        self.expect_op("(")?;
+        let mut args = Vec::new();
+        let mut unsupported = None;
+        while !self.eat_op(")") {
+            if self.is_op("*") || self.is_op("**") {
+                self.pos += 1;
+                unsupported = Some("unpacked arguments".to_string());
+            } else if matches!((self.peek(), self.peek_at(1)), (Some(Tok::Name(_)), Some(Tok::Op(o))) if o == "=") {
+                self.pos += 2;
+                unsupported = Some("keyword arguments".to_string());
+            }
+            let arg = self.expr()?;
+            args.push(if self.is_name("for") { self.comprehension(arg)? } else { arg });
+            if !self.eat_op(",") {
+                self.expect_op(")")?;
+                break;
+            }
+        }
+        Ok(match unsupported {
+            Some(reason) => Err(reason),
+            None => Ok(args),
+        })
+    }
+
+    fn atom(&mut self) -> Lowered {
+        let Some(tok) = self.peek().cloned() else {
+            return Err("unexpected end of input".to_string());
+        };
+        self.pos += 1;
+        Ok(match tok {
+            Tok::Num(text) => parse_int(&text).map_or_else(|| opaque(format!("number {}", text)), IR::Num),
+            Tok::Str(mut text) => {
+                let mut formatted = false;
+                loop {
+                    match self.peek() {
+                        Some(Tok::Str(more)) => text.push_str(more),
+                        Some(Tok::FStr) => formatted = true,
+                        _ => break,
+                    }
+                    self.pos += 1;
+                }
+                if formatted { opaque("f-string") } else { IR::Str(text) }
+            }
+            Tok::FStr => {
+                while matches!(self.peek(), Some(Tok::Str(_) | Tok::FStr)) {
+                    self.pos += 1;
+                }
+                opaque("f-string")
+            }
+            Tok::Name(name) => match name.as_str() {
+                "True" => IR::Bool(true),
+                "False" => IR::Bool(false),
+                "None" => opaque("None"),
+                "lambda" => {
+                    self.pos -= 1;
+                    self.expr()?
+                }
+                "yield" | "if" | "else" | "for" | "return" | "def" | "class" => {
+                    return Err(format!("unexpected '{}' on line {}", name, self.line()));
+                }
+                _ if self.name.as_deref() == Some(name.as_str()) => IR::Var(SELF.to_string()),
+                _ => IR::Var(name),
+            },
+            Tok::Op(op) => match op.as_str() {
+                "(" => {
+                    if self.eat_op(")") {
+                        return Ok(opaque("tuple"));
+                    }
+                    let value = self.expr()?;
+                    let value = if self.is_name("for") { self.comprehension(value)? } else { value };
+                    if self.is_op(",") {
+                        self.skip_brackets();
+                        return Ok(opaque("tuple"));
+                    }
+                    self.expect_op(")")?;
+                    value
+                }
+                "[" => self.list()?,
+                "{" => {
+                    self.skip_brackets();
+                    opaque("dict or set")
+                }
+                "..." => opaque("Ellipsis"),
+                _ => return Err(format!("unexpected '{}' on line {}", op, self.line())),
+            },
+            Tok::Newline | Tok::Indent | Tok::Dedent => {
+                return Err(format!("unexpected end of line {}", self.line()));
+            }
+        })
+    }
+
+    /// List literal, or a comprehension
+    fn list(&mut self) -> Lowered {
+        let mut items = Vec::new();
+        let mut star: Option<IR> = None;
+        let mut misplaced = false;
+        while !self.eat_op("]") {
+            misplaced |= star.is_some();
+            if self.eat_op("*") {
+                star = Some(self.or_expr()?);
+            } else {
+                let item = self.expr()?;
+                if items.is_empty() && star.is_none() && self.is_name("for") {
+                    let comprehension = self.comprehension(item)?;
+                    self.expect_op("]")?;
+                    return Ok(comprehension);
+                }
+                items.push(item);
+            }
+            if !self.eat_op(",") {
+                self.expect_op("]")?;
+                break;
+            }
+        }
+        if misplaced {
+            return Ok(opaque("unpacking before the last element"));
+        }
+        let tail = star.unwrap_or(IR::Nil);
+        Ok(items.into_iter().rev().fold(tail, |tail, head| IR::Cons(Box::new(head), Box::new(tail))))
+    }
+
+    /// `for x in xs [if c]*` after the element
+    fn comprehension(&mut self, element: IR) -> Lowered {
+        self.expect_name("for")?;
+        let Ok(x) = self.ident() else {
+            return Err("destructuring comprehension target".to_string());
+        };
+        if self.is_op(",") {
+            return Err("destructuring comprehension target".to_string());
+        }
+        self.expect_name("in")?;
+        let xs = Box::new(self.or_expr()?);
+        let mut gate: Option<IR> = None;
+        while self.eat_name("if") {
+            let cond = self.or_expr()?;
+            gate = Some(match gate {
+                Some(previous) => combine("&&", previous, cond),
+                None => cond,
+            });
+        }
+        if self.is_name("for") || self.is_name("async") {
+            return Err("nested comprehension".to_string());
+        }
+        let lam = |body: IR| Box::new(IR::Lam(x.clone(), Box::new(body)));
+        Ok(match gate {
+            None => IR::Map(xs, lam(element)),
+            Some(cond) if element == IR::Var(x.clone()) => IR::Filter(xs, lam(cond)),
+            Some(cond) => IR::Focus(xs, lam(cond), lam(element), Box::new(IR::Drop)),
+        })
+    }
+}
+
+/// `map(f, xs)`, `filter(p, xs)`, `reduce(f, xs, init)`, `sum(xs)` and `list(xs)`
+fn builtin(name: &str, args: std::result::Result<Vec<IR>, String>) -> IR {
+    let mut args = match args {
+        Ok(args) => args,
+        Err(reason) => return opaque(reason),
+    };
+    match (name, args.len()) {
+        ("list", 1) => args.remove(0),
+        ("map", 2) => {
+            let xs = args.pop().unwrap();
+            IR::Map(Box::new(xs), Box::new(args.remove(0)))
+        }
+        ("filter", 2) if matches!(args[0], IR::Opaque(ref reason) if reason == "None") => opaque("filter(None, ..)"),
+        ("filter", 2) => {
+            let xs = args.pop().unwrap();
+            IR::Filter(Box::new(xs), Box::new(args.remove(0)))
+        }
+        ("reduce", 3) => {
+            let init = args.pop().unwrap();
+            let xs = args.pop().unwrap();
+            IR::Reduce(Box::new(xs), Box::new(args.remove(0)), Box::new(init))
+        }
+        ("reduce", 2) => opaque("reduce without initial value"),
+        ("sum", 1) => IR::Reduce(
+            Box::new(args.remove(0)),
+            Box::new(lambda(vec!["a".to_string(), "b".to_string()], combine("+", IR::Var("a".to_string()), IR::Var("b".to_string())))),
+            Box::new(IR::Num(0)),
+        ),
+        _ => apply(IR::Var(name.to_string()), args),
+    }
+}
+
+/// Integer literal - floats and complex numbers are outside the subset
+fn parse_int(text: &str) -> Option<i64> {
+    let text = text.replace('_', "").to_ascii_lowercase();
+    if let Some(hex) = text.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = text.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else if let Some(oct) = text.strip_prefix("0o") {
+        i64::from_str_radix(oct, 8).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+/// Methods that mutate their list, dict or set
+const MUTATORS: [&str; 13] = [
+    "append", "extend", "insert", "remove", "pop", "clear", "sort", "reverse", "update", "add", "discard", "setdefault",
+    "popitem",
+];
+
+/// Calls that build a new container nobody else holds
+const CONSTRUCTORS: [&str; 7] = ["list", "dict", "set", "bytearray", "deque", "defaultdict", "Counter"];
+
+/// Statements whose header ends at a `:`
+const COMPOUND: [&str; 11] = ["if", "elif", "else", "while", "for", "with", "try", "except", "finally", "def", "class"];
+
+/// Words that end no indexable expression - `return [..]` is a literal
+const KEYWORDS: [&str; 14] = [
+    "return", "in", "not", "and", "or", "if", "else", "yield", "await", "lambda", "is", "del", "assert", "raise",
+];
+
+/// What an assignment, `del` or mutating call writes to
+enum Target {
+    /// Rebinding a name
+    Name(String),
+    /// Something inside the value a name holds
+    Inside(String),
+    /// A literal made on the spot
+    Fresh,
+    Unknown,
+}
+
+/// Effects of the tokens of one definition. Rebinding a name is local unless
+/// it was declared `global`/`nonlocal`; writes inside a value are only local
+/// when the name was bound to a new container, which nothing else can alias.
+/// Augmented assignment to a parameter may mutate the caller's list in place.
+fn scan_effects(toks: &[Token]) -> Effects {
+    let name = |i: usize| match toks.get(i).map(|t| &t.tok) {
+        Some(Tok::Name(n)) => Some(n.as_str()),
+        _ => None,
+    };
+    let op = |i: usize| match toks.get(i).map(|t| &t.tok) {
+        Some(Tok::Op(o)) => Some(o.as_str()),
+        _ => None,
+    };
+    let statements = statements(toks);
+
+    // Parameters, bound names, names declared outside, and names holding new containers
+    let mut params: HashSet<String> = HashSet::new();
+    let mut bound: HashSet<String> = HashSet::new();
+    let mut outer: HashSet<String> = HashSet::new();
+    let mut fresh: HashSet<String> = HashSet::new();
+    for i in 0..toks.len() {
+        match name(i) {
+            Some("def") => {
+                bound.extend(name(i + 1).map(String::from));
+                if op(i + 2) == Some("(") {
+                    let close = closing(toks, i + 2).unwrap_or(toks.len());
+                    params.extend(binders(toks, i + 3, close));
+                }
+            }
+            Some("lambda") => {
+                let colon = (i + 1..toks.len()).find(|&j| op(j) == Some(":")).unwrap_or(toks.len());
+                params.extend(binders(toks, i + 1, colon));
+            }
+            Some("for") => {
+                let end = (i + 1..toks.len()).find(|&j| name(j) == Some("in")).unwrap_or(i + 1);
+                bound.extend((i + 1..end).filter_map(name).map(String::from));
+            }
+            Some("as") => bound.extend(name(i + 1).map(String::from)),
+            Some("global" | "nonlocal") => {
+                let end = statements.iter().find(|&&(s, e)| s <= i && i < e).map_or(i + 1, |&(_, e)| e);
+                outer.extend((i + 1..end).filter_map(name).map(String::from));
+            }
+            _ if op(i + 1) == Some(":=") => bound.extend(name(i).map(String::from)),
+            _ => {}
+        }
+    }
+    for &(start, end) in &statements {
+        let Some((segments, value)) = assignment(toks, start, end) else { continue };
+        for &(s, e) in &segments {
+            for target in targets(&toks[s..e]) {
+                if let Target::Name(n) = target {
+                    bound.insert(n.clone());
+                    let new = matches!(op(value), Some("[" | "{"))
+                        || (name(value).is_some_and(|c| CONSTRUCTORS.contains(&c)) && op(value + 1) == Some("("));
+                    if new && segments.len() == 1 {
+                        fresh.insert(n);
+                    }
+                }
+            }
+        }
+    }
+    bound.extend(params.iter().cloned());
+    let locals: HashSet<&str> = bound.iter().map(String::as_str).filter(|n| !outer.contains(*n)).collect();
+
+    let inside = |target: Target| match target {
+        Target::Inside(n) | Target::Name(n) if fresh.contains(&n) && !outer.contains(&n) => Scope::Local,
+        Target::Fresh => Scope::Local,
+        _ => Scope::Escaping,
+    };
+
+    let mut effects = Effects::default();
+    let mut assigned: HashSet<String> = params.iter().cloned().collect();
+    for &(start, end) in &statements {
+        match name(start) {
+            Some("del") => {
+                for target in targets(&toks[start + 1..end]) {
+                    let scope = match target {
+                        Target::Name(n) if locals.contains(n.as_str()) => Scope::Local,
+                        target => inside(target),
+                    };
+                    effects.insert(Effect::Mutation(scope));
+                }
+            }
+            _ => {
+                let Some((segments, value)) = assignment(toks, start, end) else { continue };
+                let augmented = op(value - 1) != Some("=");
+                for &(s, e) in &segments {
+                    for target in targets(&toks[s..e]) {
+                        let scope = match target {
+                            Target::Name(n) if outer.contains(&n) => Some(Scope::Escaping),
+                            Target::Name(n) if augmented && params.contains(&n) && !fresh.contains(&n) => Some(Scope::Escaping),
+                            Target::Name(_) if augmented => Some(Scope::Local),
+                            // The first binding of a name isn't a mutation
+                            Target::Name(n) => (!assigned.insert(n)).then_some(Scope::Local),
+                            target => Some(inside(target)),
+                        };
+                        if let Some(scope) = scope {
+                            effects.insert(Effect::Mutation(scope));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for (i, token) in toks.iter().enumerate() {
+        match &token.tok {
+            Tok::Name(n) => match n.as_str() {
+                "raise" | "assert" => effects.insert(Effect::Exception),
+                "while" => effects.insert(Effect::Nontermination),
+                "import" | "from" => {}
+                n => {
+                    // Not an attribute, a local or a bare annotation
+                    let annotation = matches!(op(i.wrapping_sub(1)), Some(":" | "->")) && !matches!(op(i + 1), Some("." | "("));
+                    if op(i.wrapping_sub(1)) == Some(".") || annotation || locals.contains(n) {
+                        continue;
+                    }
+                    let mut path = n.to_string();
+                    let mut j = i + 1;
+                    while let (Some("."), Some(member)) = (op(j), name(j + 1)) {
+                        path = format!("{}.{}", path, member);
+                        j += 2;
+                    }
+                    if let Some(effect) = effects::of_call(&path) {
+                        effects.insert(effect);
+                    }
+                }
+            },
+            Tok::Op(o) if o == "." && name(i + 1).is_some_and(|m| MUTATORS.contains(&m)) && op(i + 2) == Some("(") => {
+                effects.insert(Effect::Mutation(inside(receiver(toks, i))));
+            }
+            _ => {}
+        }
+    }
+    effects
+}
+
+/// Simple statements as `[start, end)` - a compound header ends at its `:`
+fn statements(toks: &[Token]) -> Vec<(usize, usize)> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (i, token) in toks.iter().enumerate() {
+        let ends = match &token.tok {
+            Tok::Newline | Tok::Indent | Tok::Dedent => true,
+            Tok::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => {
+                depth += 1;
+                false
+            }
+            Tok::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            Tok::Op(o) if depth == 0 && o == ";" => true,
+            Tok::Op(o) if depth == 0 && o == ":" => {
+                matches!(toks.get(start).map(|t| &t.tok), Some(Tok::Name(w)) if COMPOUND.contains(&w.as_str()))
+            }
+            _ => false,
+        };
+        if ends {
+            if start < i {
+                statements.push((start, i));
+            }
+            start = i + 1;
+        }
+    }
+    if start < toks.len() {
+        statements.push((start, toks.len()));
+    }
+    statements
+}
+
+/// Target segments of an assignment statement and where its value starts
+fn assignment(toks: &[Token], start: usize, end: usize) -> Option<(Vec<(usize, usize)>, usize)> {
+    let mut segments = Vec::new();
+    let mut from = start;
+    let mut depth = 0usize;
+    for (i, token) in toks.iter().enumerate().take(end).skip(start) {
+        let Tok::Op(o) = &token.tok else { continue };
+        match o.as_str() {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            "=" if depth == 0 => {
+                segments.push((from, i));
+                from = i + 1;
+            }
+            o if depth == 0 && segments.is_empty() && o.ends_with('=') && !matches!(o, "==" | "!=" | "<=" | ">=") => {
+                return Some((vec![(start, i)], i + 1));
+            }
+            _ => {}
+        }
+    }
+    (!segments.is_empty()).then_some((segments, from))
+}
+
+/// Names and places one assignment target (or `del` list) writes to
+fn targets(toks: &[Token]) -> Vec<Target> {
+    // `x: int = ..` - the annotation isn't written
+    let toks = match top_level(toks, ":").first() {
+        Some(&colon) => &toks[..colon],
+        None => toks,
+    };
+    let wrapped = matches!((toks.first().map(|t| &t.tok), toks.last().map(|t| &t.tok)),
+        (Some(Tok::Op(o)), Some(Tok::Op(c))) if (o == "(" && c == ")") || (o == "[" && c == "]"));
+    let toks = if wrapped && closing(toks, 0) == Some(toks.len() - 1) { &toks[1..toks.len() - 1] } else { toks };
+
+    let mut parts = Vec::new();
+    let mut from = 0;
+    for comma in top_level(toks, ",").into_iter().chain([toks.len()]) {
+        let mut part = &toks[from..comma];
+        from = comma + 1;
+        if matches!(part.first().map(|t| &t.tok), Some(Tok::Op(o)) if o == "*") {
+            part = &part[1..];
+        }
+        parts.push(match part {
+            [] => continue,
+            [Token { tok: Tok::Name(n), .. }] => Target::Name(n.clone()),
+            [Token { tok: Tok::Name(n), .. }, Token { tok: Tok::Op(o), .. }, ..] if o == "." || o == "[" => {
+                Target::Inside(n.clone())
+            }
+            _ => Target::Unknown,
+        });
+    }
+    parts
+}
+
+/// Positions of `op` outside any brackets
+fn top_level(toks: &[Token], op: &str) -> Vec<usize> {
+    let mut depth = 0usize;
+    let mut found = Vec::new();
+    for (i, token) in toks.iter().enumerate() {
+        let Tok::Op(o) = &token.tok else { continue };
+        match o.as_str() {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            o if depth == 0 && o == op => found.push(i),
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Parameter names between `from` and `to`: those after `(`, `,`, `*` or `**` at the top
+fn binders(toks: &[Token], from: usize, to: usize) -> Vec<String> {
+    let mut depth = 0usize;
+    let mut names = Vec::new();
+    for i in from..to.min(toks.len()) {
+        match &toks[i].tok {
+            Tok::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => depth += 1,
+            Tok::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => depth = depth.saturating_sub(1),
+            Tok::Name(n) if depth == 0 => {
+                let leads = i == from || matches!(&toks[i - 1].tok, Tok::Op(o) if matches!(o.as_str(), "," | "*" | "**"));
+                if leads {
+                    names.push(n.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// What the receiver ending just before the `.` at `dot` names
+fn receiver(toks: &[Token], dot: usize) -> Target {
+    let mut i = dot;
+    loop {
+        let Some(j) = i.checked_sub(1) else { return Target::Unknown };
+        match &toks[j].tok {
+            Tok::Name(n) => {
+                if j > 0 && matches!(&toks[j - 1].tok, Tok::Op(o) if o == ".") {
+                    i = j - 1;
+                } else {
+                    return Target::Inside(n.clone());
+                }
+            }
+            Tok::Op(o) if o == "]" => {
+                let Some(open) = opening(toks, j) else { return Target::Unknown };
+                let indexed = open > 0 && match &toks[open - 1].tok {
+                    Tok::Name(w) => !KEYWORDS.contains(&w.as_str()),
+                    Tok::Op(o) => o == ")" || o == "]",
+                    _ => false,
+                };
+                if !indexed {
+                    return Target::Fresh;
+                }
+                i = open;
+            }
+            _ => return Target::Unknown,
+        }
+    }
+}
+
+/// Index of the bracket closing the one at `open`
+fn closing(toks: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in toks.iter().enumerate().skip(open) {
+        match &token.tok {
+            Tok::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => depth += 1,
+            Tok::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the bracket opening the one closing at `close`
+fn opening(toks: &[Token], close: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for i in (0..=close).rev() {
+        match &toks[i].tok {
+            Tok::Op(o) if matches!(o.as_str(), ")" | "]" | "}") => depth += 1,
+            Tok::Op(o) if matches!(o.as_str(), "(" | "[" | "{") => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::compute_soul;
+    use crate::extract::{decode, js, rust};
+
+    fn var(x: &str) -> Box<IR> {
+        Box::new(IR::Var(x.to_string()))
+    }
+
+    fn lam(x: &str, body: IR) -> IR {
+        IR::Lam(x.to_string(), Box::new(body))
+    }
+
+    fn functions(code: &str) -> Vec<(String, IR)> {
+        extract(code).unwrap()
+            .into_iter()
+            .map(|f| (f.name, decode(&f.ir).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_map_gene_soul_across_languages() {
+        let py = extract(include_str!("../../../genes/map/manifestations/py/map.py")).unwrap();
+        assert_eq!(py.len(), 1);
+        assert_eq!((py[0].name.as_str(), py[0].start_line, py[0].end_line), ("map_gene", 6, 10));
+        assert_eq!(decode(&py[0].ir).unwrap(), lam("xs", lam("f", IR::Map(var("xs"), var("f")))));
+
+        let rs = rust::extract(include_str!("../../../genes/map/manifestations/rs/lib.rs")).unwrap();
+        let js = js::extract("export const map = (list, fn) => list.map(x => fn(x));").unwrap();
+        let soul = compute_soul(&py[0].ir);
+        assert_eq!(compute_soul(&rs[0].ir), soul);
+        assert_eq!(compute_soul(&rs[1].ir), soul);
+        assert_eq!(compute_soul(&js[0].ir), soul);
+    }
+
+    #[test]
+    fn test_pure_subset() {
+        let code = r#"
+import functools
+
+def positives_doubled(xs: list[int]) -> list[int]:
+    return [x * 2 for x in xs if x > 0]
+
+def evens(xs):
+    return list(filter(lambda x: x % 2 == 0, xs))
+
+total = lambda xs: functools.reduce(lambda acc, x: acc + x, xs, 0)
+
+def fact(n):
+    """Factorial"""
+    if n <= 1:
+        return 1
+    elif n == 2: return 2
+    m = n - 1
+    return n * fact(m) if m > 0 else 0
+"#;
+        let gate = |body: IR| Box::new(lam("x", body));
+        let fact = lam("n", IR::If(
+            Box::new(IR::Le(var("n"), Box::new(IR::Num(1)))),
+            Box::new(IR::Num(1)),
+            Box::new(IR::If(
+                Box::new(IR::Eq(var("n"), Box::new(IR::Num(2)))),
+                Box::new(IR::Num(2)),
+                Box::new(IR::App(
+                    Box::new(lam("m", IR::If(
+                        Box::new(IR::Lt(Box::new(IR::Num(0)), var("m"))),
+                        Box::new(IR::Mul(var("n"), Box::new(IR::App(var(SELF), var("m"))))),
+                        Box::new(IR::Num(0)),
+                    ))),
+                    Box::new(IR::Sub(var("n"), Box::new(IR::Num(1)))),
+                )),
+            )),
+        ));
+        assert_eq!(functions(code), vec![
+            ("positives_doubled".to_string(), lam("xs", IR::Focus(
+                var("xs"),
+                gate(IR::Lt(Box::new(IR::Num(0)), var("x"))),
+                gate(IR::Mul(var("x"), Box::new(IR::Num(2)))),
+                Box::new(IR::Drop),
+            ))),
+            ("evens".to_string(), lam("xs", IR::Filter(
+                var("xs"),
+                gate(IR::Eq(Box::new(IR::Mod(var("x"), Box::new(IR::Num(2)))), Box::new(IR::Num(0)))),
+            ))),
+            ("total".to_string(), lam("xs", IR::Reduce(
+                var("xs"),
+                Box::new(lam("acc", lam("x", IR::Add(var("acc"), var("x"))))),
+                Box::new(IR::Num(0)),
+            ))),
+            ("fact".to_string(), fact),
+        ]);
+    }
+
+    #[test]
+    fn test_unsupported_constructs_are_opaque() {
+        let code = "
+def total(xs):
+    t = 0
+    for x in xs:
+        t += x
+    return t
+
+def greet(name):
+    return f'hello {name}'
+
+class Shape:
+    def area(self):
+        return 0
+
+def first(xs): return xs[0]
+";
+        assert_eq!(functions(code), vec![
+            ("total".to_string(), lam("xs", IR::App(Box::new(lam("t", opaque("for statement"))), Box::new(IR::Num(0))))),
+            ("greet".to_string(), lam("name", opaque("f-string"))),
+            ("first".to_string(), lam("xs", opaque("subscript"))),
+        ]);
+    }
+
+    #[test]
+    fn test_effects_scanned_past_the_subset() {
+        use Effect::*;
+        let code = "\
+def total(xs):
+    t = 0
+    for x in xs:
+        t += x
+    return t
+
+def doubled(xs):
+    out = []
+    for x in xs:
+        out.append(x * 2)
+    return out
+
+def append(xs, x):
+    xs.append(x)
+    return xs
+
+def logged(x):
+    print(x)
+    return x
+
+def roll():
+    return random.randint(1, 6)
+
+counter = 0
+
+def bump():
+    global counter
+    counter += 1
+    return counter
+
+def stamp(d: dict):
+    d['at'] = time.time()
+    return d
+
+def fail(msg):
+    raise ValueError(msg)
+
+def spin():
+    while True:
+        pass
+";
+        let effects: Vec<(String, Vec<Effect>)> = extract(code).unwrap()
+            .into_iter()
+            .map(|f| (f.name, f.effects.unwrap().iter().collect()))
+            .collect();
+        let expected = vec![
+            ("total", vec![Mutation(Scope::Local)]),
+            ("doubled", vec![Mutation(Scope::Local)]),
+            ("append", vec![Mutation(Scope::Escaping)]),
+            ("logged", vec![Io]),
+            ("roll", vec![Randomness]),
+            ("bump", vec![Mutation(Scope::Escaping)]),
+            ("stamp", vec![Mutation(Scope::Escaping), Time]),
+            ("fail", vec![Exception]),
+            ("spin", vec![Nontermination]),
+        ];
+        assert_eq!(effects, expected.into_iter().map(|(name, e)| (name.to_string(), e)).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,721 @@
+// Rust pure subset, via syn
+//
+// Free functions (also inside inline modules, tests excluded). Bodies:
+// immutable `let`, `if`/`else`, `return`, arithmetic, comparisons, calls,
+// recursion, closures, `vec![..]` and array literals. Iterator chains lower
+// to `Map`/`Filter`/`Reduce` with `iter`/`into_iter`/`collect` transparent,
+// `filter_map` gating on `if c { Some(e) } else { None }` is a hard `Focus`,
+// and `let mut v = Vec::new(); for x in xs { v.push(e); }` is a `Map` (a
+// `Filter` or hard `Focus` when the push is under an `if`). Borrows, derefs,
+// clones and casts are transparent. Anything else lowers to `IR::Opaque`. Effects
+// come from a walk over the whole body.
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, FnArg, Item, Pat, UnOp};
+
+use super::{apply, combine, encode, lambda, lower, opaque, simplify, Stmt, SELF};
+use crate::effects::{self, Effect, Effects, Scope};
+use crate::runtime::ExtractedFunction;
+use crate::surgeon::egraph::IR;
+
+/// Functions of a Rust source file
+pub fn extract(code: &str) -> Result<Vec<ExtractedFunction>> {
+    let file = syn::parse_file(code).context("not valid Rust")?;
+    let mut functions = Vec::new();
+    collect(&file.items, &mut functions)?;
+    Ok(functions)
+}
+
+fn collect(items: &[Item], functions: &mut Vec<ExtractedFunction>) -> Result<()> {
+    for item in items {
+        match item {
+            Item::Fn(f) if !is_test(&f.attrs) => functions.push(function(f)?),
+            Item::Mod(m) if !is_test(&m.attrs) => {
+                if let Some((_, items)) = &m.content {
+                    collect(items, functions)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// `#[test]` or `#[cfg(test)]`
+fn is_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| match &attr.meta {
+        syn::Meta::Path(path) => path.is_ident("test"),
+        syn::Meta::List(list) => list.path.is_ident("cfg") && list.tokens.to_string() == "test",
+        _ => false,
+    })
+}
+
+fn function(f: &syn::ItemFn) -> Result<ExtractedFunction> {
+    let lowerer = Lowerer { name: f.sig.ident.to_string() };
+    let params: Option<Vec<String>> = f.sig.inputs.iter()
+        .map(|input| match input {
+            FnArg::Receiver(_) => Some("self".to_string()),
+            FnArg::Typed(typed) => binding(&typed.pat),
+        })
+        .collect();
+    let ir = match params {
+        Some(params) => lambda(params, lowerer.block(&f.block)),
+        None => opaque("destructuring parameter"),
+    };
+    Ok(ExtractedFunction {
+        name: lowerer.name,
+        ir: encode(&simplify(&ir))?,
+        start_line: f.sig.fn_token.span.start().line,
+        end_line: f.block.brace_token.span.close().end().line,
+        effects: Some(EffectScan::function(f)),
+    })
+}
+
+/// Name a pattern binds, if it is a plain (possibly typed or borrowed) identifier
+fn binding(pat: &Pat) -> Option<String> {
+    match pat {
+        Pat::Ident(ident) if ident.subpat.is_none() => Some(ident.ident.to_string()),
+        Pat::Type(typed) => binding(&typed.pat),
+        Pat::Reference(reference) => binding(&reference.pat),
+        Pat::Wild(_) => Some("_".to_string()),
+        _ => None,
+    }
+}
+
+/// Single identifier a path expression names
+fn ident(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) if path.qself.is_none() => path.path.get_ident().map(|i| i.to_string()),
+        _ => None,
+    }
+}
+
+/// `e` out of `{ e }` and `(e)`
+fn tail(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(paren) => tail(&paren.expr),
+        Expr::Block(block) if block.label.is_none() => match block.block.stmts.as_slice() {
+            [syn::Stmt::Expr(inner, None)] => tail(inner),
+            _ => expr,
+        },
+        _ => expr,
+    }
+}
+
+/// Value of a block that is a single tail expression
+fn block_tail(block: &syn::Block) -> Option<&Expr> {
+    match block.stmts.as_slice() {
+        [syn::Stmt::Expr(expr, None)] => Some(tail(expr)),
+        _ => None,
+    }
+}
+
+/// `Some(e)` → `e`
+fn some(expr: &Expr) -> Option<&Expr> {
+    match tail(expr) {
+        Expr::Call(call) if ident(&call.func).as_deref() == Some("Some") && call.args.len() == 1 => call.args.first(),
+        _ => None,
+    }
+}
+
+fn is_none(expr: &Expr) -> bool {
+    ident(tail(expr)).as_deref() == Some("None")
+}
+
+/// `Vec::new()`, `Vec::with_capacity(n)` or `vec![]`
+fn is_empty_vec(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(call) => match &*call.func {
+            Expr::Path(path) => {
+                let segments: Vec<String> = path.path.segments.iter().map(|s| s.ident.to_string()).collect();
+                matches!(segments.as_slice(), [vec, ctor] if vec == "Vec" && (ctor == "new" || ctor == "with_capacity"))
+            }
+            _ => false,
+        },
+        Expr::Macro(mac) => mac.mac.path.is_ident("vec") && mac.mac.tokens.is_empty(),
+        _ => false,
+    }
+}
+
+/// Argument of `target.push(arg)`
+fn push_into<'a>(stmt: &'a syn::Stmt, target: &str) -> Option<&'a Expr> {
+    let syn::Stmt::Expr(Expr::MethodCall(call), _) = stmt else {
+        return None;
+    };
+    (call.method == "push" && ident(&call.receiver).as_deref() == Some(target) && call.args.len() == 1)
+        .then(|| call.args.first())
+        .flatten()
+}
+
+struct Lowerer {
+    /// Function being extracted - references to it are recursion
+    name: String,
+}
+
+impl Lowerer {
+    fn block(&self, block: &syn::Block) -> IR {
+        lower(&self.stmts(&block.stmts))
+    }
+
+    /// Statements of a block - the first unsupported one ends it
+    fn stmts(&self, stmts: &[syn::Stmt]) -> Vec<Stmt> {
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < stmts.len() {
+            if let Some((name, value)) = self.push_loop(&stmts[i..]) {
+                out.push(Stmt::Const(name, value));
+                i += 2;
+                continue;
+            }
+            let stmt = self.stmt(&stmts[i], i + 1 == stmts.len());
+            let unsupported = matches!(stmt, Stmt::Unsupported(_));
+            out.push(stmt);
+            if unsupported {
+                break;
+            }
+            i += 1;
+        }
+        out
+    }
+
+    fn stmt(&self, stmt: &syn::Stmt, last: bool) -> Stmt {
+        match stmt {
+            syn::Stmt::Local(local) => {
+                let Some(init) = local.init.as_ref().filter(|init| init.diverge.is_none()) else {
+                    return Stmt::Unsupported("let without a value".to_string());
+                };
+                let mutable = matches!(&local.pat, Pat::Ident(i) if i.mutability.is_some())
+                    || matches!(&local.pat, Pat::Type(t) if matches!(&*t.pat, Pat::Ident(i) if i.mutability.is_some()));
+                match binding(&local.pat) {
+                    _ if mutable => Stmt::Unsupported("mutable binding".to_string()),
+                    Some(name) => Stmt::Const(name, self.expr(&init.expr)),
+                    None => Stmt::Unsupported("destructuring binding".to_string()),
+                }
+            }
+            syn::Stmt::Expr(Expr::If(branch), semi) if !(last && semi.is_none()) => {
+                let then = self.stmts(&branch.then_branch.stmts);
+                let otherwise = branch.else_branch.as_ref().map(|(_, otherwise)| match &**otherwise {
+                    Expr::Block(block) => self.stmts(&block.block.stmts),
+                    other => vec![self.stmt(&syn::Stmt::Expr(other.clone(), None), false)],
+                });
+                Stmt::If(self.expr(&branch.cond), then, otherwise)
+            }
+            syn::Stmt::Expr(Expr::Return(ret), _) => Stmt::Return(match &ret.expr {
+                Some(value) => self.expr(value),
+                None => opaque("return without value"),
+            }),
+            syn::Stmt::Expr(expr, None) if last => Stmt::Return(self.expr(expr)),
+            syn::Stmt::Expr(Expr::ForLoop(_), _) => Stmt::Unsupported("for loop".to_string()),
+            syn::Stmt::Expr(Expr::While(_), _) => Stmt::Unsupported("while loop".to_string()),
+            syn::Stmt::Expr(Expr::Loop(_), _) => Stmt::Unsupported("loop".to_string()),
+            syn::Stmt::Expr(_, _) => Stmt::Unsupported("expression statement".to_string()),
+            syn::Stmt::Macro(mac) => Stmt::Unsupported(format!("macro {}!", path_name(&mac.mac.path))),
+            syn::Stmt::Item(_) => Stmt::Unsupported("nested item".to_string()),
+        }
+    }
+
+    /// `let mut v = Vec::new(); for x in xs { v.push(e); }` as `v = map xs λx.e`
+    fn push_loop(&self, stmts: &[syn::Stmt]) -> Option<(String, IR)> {
+        let [syn::Stmt::Local(local), syn::Stmt::Expr(Expr::ForLoop(for_loop), _), ..] = stmts else {
+            return None;
+        };
+        let Pat::Ident(target) = &local.pat else {
+            return None;
+        };
+        let init = local.init.as_ref()?;
+        if target.mutability.is_none() || !is_empty_vec(&init.expr) {
+            return None;
+        }
+        let target = target.ident.to_string();
+        let x = binding(&for_loop.pat)?;
+        let [body] = for_loop.body.stmts.as_slice() else {
+            return None;
+        };
+        let xs = Box::new(self.expr(&for_loop.expr));
+
+        if let Some(pushed) = push_into(body, &target) {
+            return Some((target, IR::Map(xs, Box::new(IR::Lam(x, Box::new(self.expr(pushed)))))));
+        }
+        let syn::Stmt::Expr(Expr::If(gate), _) = body else {
+            return None;
+        };
+        let [pushed] = gate.then_branch.stmts.as_slice() else {
+            return None;
+        };
+        let pushed = push_into(pushed, &target).filter(|_| gate.else_branch.is_none())?;
+        let weight = Box::new(IR::Lam(x.clone(), Box::new(self.expr(&gate.cond))));
+        let value = if ident(tail(pushed)).as_deref() == Some(x.as_str()) {
+            IR::Filter(xs, weight)
+        } else {
+            IR::Focus(xs, weight, Box::new(IR::Lam(x, Box::new(self.expr(pushed)))), Box::new(IR::Drop))
+        };
+        Some((target, value))
+    }
+
+    fn expr(&self, expr: &Expr) -> IR {
+        match expr {
+            Expr::Lit(lit) => match &lit.lit {
+                syn::Lit::Int(int) => int.base10_parse().map_or_else(|_| opaque(format!("number {}", int)), IR::Num),
+                syn::Lit::Bool(b) => IR::Bool(b.value),
+                syn::Lit::Str(s) => IR::Str(s.value()),
+                _ => opaque("non-integer literal"),
+            },
+            Expr::Path(path) => match ident(expr) {
+                Some(name) if name == self.name => IR::Var(SELF.to_string()),
+                Some(name) => IR::Var(name),
+                None => opaque(format!("path {}", path_name(&path.path))),
+            },
+            Expr::Paren(inner) => self.expr(&inner.expr),
+            Expr::Group(inner) => self.expr(&inner.expr),
+            Expr::Cast(cast) => self.expr(&cast.expr),
+            Expr::Reference(reference) if reference.mutability.is_none() => self.expr(&reference.expr),
+            Expr::Reference(_) => opaque("mutable borrow"),
+            Expr::Unary(unary) => {
+                let operand = self.expr(&unary.expr);
+                match unary.op {
+                    UnOp::Deref(_) => operand,
+                    UnOp::Not(_) => IR::If(Box::new(operand), Box::new(IR::Bool(false)), Box::new(IR::Bool(true))),
+                    UnOp::Neg(_) => match operand {
+                        IR::Num(n) => IR::Num(-n),
+                        operand => IR::Sub(Box::new(IR::Num(0)), Box::new(operand)),
+                    },
+                    _ => opaque("unary operator"),
+                }
+            }
+            Expr::Binary(binary) => match operator(&binary.op) {
+                Some(op) => combine(op, self.expr(&binary.left), self.expr(&binary.right)),
+                None => opaque("operator"),
+            },
+            Expr::If(branch) => match &branch.else_branch {
+                Some((_, otherwise)) => IR::If(
+                    Box::new(self.expr(&branch.cond)),
+                    Box::new(self.block(&branch.then_branch)),
+                    Box::new(self.expr(otherwise)),
+                ),
+                None => opaque("if without else"),
+            },
+            Expr::Block(block) if block.label.is_none() => self.block(&block.block),
+            Expr::Closure(closure) => {
+                let params: Option<Vec<String>> = closure.inputs.iter().map(binding).collect();
+                match params {
+                    Some(params) => lambda(params, self.expr(&closure.body)),
+                    None => opaque("destructuring closure parameter"),
+                }
+            }
+            Expr::Call(call) => {
+                let f = self.expr(&call.func);
+                apply(f, call.args.iter().map(|arg| self.expr(arg)).collect())
+            }
+            Expr::MethodCall(call) => self.method(call),
+            Expr::Array(array) => self.list(array.elems.iter()),
+            Expr::Macro(mac) if mac.mac.path.is_ident("vec") => {
+                use syn::punctuated::Punctuated;
+                match mac.mac.parse_body_with(Punctuated::<Expr, syn::Token![,]>::parse_terminated) {
+                    Ok(items) => self.list(items.iter()),
+                    Err(_) => opaque("vec! with a repeat count"),
+                }
+            }
+            Expr::Macro(mac) => opaque(format!("macro {}!", path_name(&mac.mac.path))),
+            Expr::Match(_) => opaque("match expression"),
+            Expr::ForLoop(_) | Expr::While(_) | Expr::Loop(_) => opaque("loop"),
+            Expr::Field(field) => opaque(format!("field access {}", quote_member(&field.member))),
+            Expr::Index(_) => opaque("index access"),
+            Expr::Try(_) => opaque("? operator"),
+            Expr::Assign(_) => opaque("assignment"),
+            Expr::Unsafe(_) => opaque("unsafe block"),
+            Expr::Async(_) | Expr::Await(_) => opaque("async"),
+            _ => opaque("expression"),
+        }
+    }
+
+    /// Iterator adaptors and consumers; other methods are opaque
+    fn method(&self, call: &syn::ExprMethodCall) -> IR {
+        let target = Box::new(self.expr(&call.receiver));
+        let args: Vec<&Expr> = call.args.iter().collect();
+        let method = call.method.to_string();
+        match (method.as_str(), args.as_slice()) {
+            ("iter" | "into_iter" | "copied" | "cloned" | "clone" | "collect" | "to_vec" | "to_owned", []) => *target,
+            ("map", [f]) => IR::Map(target, Box::new(self.expr(f))),
+            ("filter", [p]) => IR::Filter(target, Box::new(self.expr(p))),
+            ("fold", [init, f]) => IR::Reduce(target, Box::new(self.expr(f)), Box::new(self.expr(init))),
+            ("sum", []) => IR::Reduce(
+                target,
+                Box::new(lambda(vec!["a".to_string(), "b".to_string()], combine("+", IR::Var("a".to_string()), IR::Var("b".to_string())))),
+                Box::new(IR::Num(0)),
+            ),
+            ("filter_map", [f]) => self.hard_focus(target, f),
+            _ => opaque(format!("method .{}", method)),
+        }
+    }
+
+    /// `filter_map(|x| if c { Some(e) } else { None })`, or `c.then(|| e)` / `c.then_some(e)`
+    fn hard_focus(&self, xs: Box<IR>, f: &Expr) -> IR {
+        let Expr::Closure(closure) = tail(f) else {
+            return opaque("filter_map without a closure");
+        };
+        let (Some(x), 1) = (closure.inputs.first().and_then(binding), closure.inputs.len()) else {
+            return opaque("destructuring closure parameter");
+        };
+        let gate = match tail(&closure.body) {
+            Expr::If(branch) => match (&branch.else_branch, block_tail(&branch.then_branch).and_then(some)) {
+                (Some((_, otherwise)), Some(value)) if is_none(otherwise) => Some((&*branch.cond, value)),
+                _ => None,
+            },
+            Expr::MethodCall(call) if call.method == "then_some" && call.args.len() == 1 => {
+                Some((&*call.receiver, &call.args[0]))
+            }
+            Expr::MethodCall(call) if call.method == "then" && call.args.len() == 1 => match tail(&call.args[0]) {
+                Expr::Closure(inner) if inner.inputs.is_empty() => Some((&*call.receiver, &*inner.body)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some((cond, value)) = gate else {
+            return opaque("filter_map without a gate");
+        };
+        IR::Focus(
+            xs,
+            Box::new(IR::Lam(x.clone(), Box::new(self.expr(cond)))),
+            Box::new(IR::Lam(x, Box::new(self.expr(value)))),
+            Box::new(IR::Drop),
+        )
+    }
+
+    fn list<'a>(&self, items: impl DoubleEndedIterator<Item = &'a Expr>) -> IR {
+        items.rev().fold(IR::Nil, |tail, head| IR::Cons(Box::new(self.expr(head)), Box::new(tail)))
+    }
+}
+
+/// Operator by its JavaScript spelling, for the shared `combine`
+fn operator(op: &BinOp) -> Option<&'static str> {
+    Some(match op {
+        BinOp::Add(_) => "+",
+        BinOp::Sub(_) => "-",
+        BinOp::Mul(_) => "*",
+        BinOp::Div(_) => "/",
+        BinOp::Rem(_) => "%",
+        BinOp::Eq(_) => "===",
+        BinOp::Ne(_) => "!==",
+        BinOp::Lt(_) => "<",
+        BinOp::Le(_) => "<=",
+        BinOp::Gt(_) => ">",
+        BinOp::Ge(_) => ">=",
+        BinOp::And(_) => "&&",
+        BinOp::Or(_) => "||",
+        _ => return None,
+    })
+}
+
+fn path_name(path: &syn::Path) -> String {
+    path.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>().join("::")
+}
+
+fn quote_member(member: &syn::Member) -> String {
+    match member {
+        syn::Member::Named(name) => format!(".{}", name),
+        syn::Member::Unnamed(index) => format!(".{}", index.index),
+    }
+}
+
+/// Methods that mutate their receiver
+const MUTATORS: [&str; 25] = [
+    "push", "pop", "insert", "remove", "clear", "extend", "truncate", "retain", "drain", "append", "sort", "sort_by",
+    "sort_by_key", "sort_unstable", "dedup", "reverse", "swap", "push_str", "push_back", "push_front", "pop_back",
+    "pop_front", "resize", "fill", "iter_mut",
+];
+
+/// Effects of a function body. Bindings the function owns - `let`s, `for`
+/// patterns and by-value parameters - are local; writes through a reference,
+/// a deref, a static or shared interior mutability escape.
+struct EffectScan {
+    owned: HashSet<String>,
+    effects: Effects,
+}
+
+impl EffectScan {
+    fn function(f: &syn::ItemFn) -> Effects {
+        let mut scan = EffectScan { owned: HashSet::new(), effects: Effects::default() };
+        for input in &f.sig.inputs {
+            match input {
+                FnArg::Receiver(receiver) if receiver.reference.is_none() => {
+                    scan.owned.insert("self".to_string());
+                }
+                FnArg::Typed(typed) if !matches!(&*typed.ty, syn::Type::Reference(_)) => scan.own(&typed.pat),
+                _ => {}
+            }
+        }
+        scan.visit_block(&f.block);
+        scan.effects
+    }
+
+    fn own(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(ident) => {
+                self.owned.insert(ident.ident.to_string());
+            }
+            Pat::Type(typed) => self.own(&typed.pat),
+            Pat::Tuple(tuple) => tuple.elems.iter().for_each(|p| self.own(p)),
+            Pat::TupleStruct(tuple) => tuple.elems.iter().for_each(|p| self.own(p)),
+            Pat::Struct(s) => s.fields.iter().for_each(|field| self.own(&field.pat)),
+            Pat::Slice(slice) => slice.elems.iter().for_each(|p| self.own(p)),
+            _ => {}
+        }
+    }
+
+    /// Who sees a write to the place `expr`
+    fn scope(&self, expr: &Expr) -> Scope {
+        match expr {
+            Expr::Path(path) if path.qself.is_none() => match path.path.get_ident() {
+                Some(name) if self.owned.contains(&name.to_string()) => Scope::Local,
+                _ => Scope::Escaping,
+            },
+            Expr::Field(field) => self.scope(&field.base),
+            Expr::Index(index) => self.scope(&index.expr),
+            Expr::Paren(paren) => self.scope(&paren.expr),
+            _ => Scope::Escaping,
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for EffectScan {
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        // `let r = &mut x` aliases, it doesn't own
+        let borrowed = matches!(local.init.as_ref().map(|init| &*init.expr), Some(Expr::Reference(r)) if r.mutability.is_some());
+        if !borrowed {
+            self.own(&local.pat);
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_for_loop(&mut self, for_loop: &'ast syn::ExprForLoop) {
+        self.own(&for_loop.pat);
+        visit::visit_expr_for_loop(self, for_loop);
+    }
+
+    fn visit_expr_assign(&mut self, assign: &'ast syn::ExprAssign) {
+        self.effects.insert(Effect::Mutation(self.scope(&assign.left)));
+        visit::visit_expr_assign(self, assign);
+    }
+
+    fn visit_expr_binary(&mut self, binary: &'ast syn::ExprBinary) {
+        let compound = matches!(binary.op,
+            BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_) | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_) | BinOp::BitAndAssign(_) | BinOp::BitOrAssign(_) | BinOp::ShlAssign(_) | BinOp::ShrAssign(_));
+        if compound {
+            self.effects.insert(Effect::Mutation(self.scope(&binary.left)));
+        }
+        visit::visit_expr_binary(self, binary);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let method = call.method.to_string();
+        if MUTATORS.contains(&method.as_str()) {
+            self.effects.insert(Effect::Mutation(self.scope(&call.receiver)));
+        }
+        match method.as_str() {
+            "unwrap" | "expect" => self.effects.insert(Effect::Exception),
+            "borrow_mut" => self.effects.insert(Effect::Mutation(Scope::Escaping)),
+            _ => {}
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        // A single name is as likely to be one of the crate's own functions
+        if let Expr::Path(path) = &*call.func {
+            if path.path.segments.len() > 1 {
+                if let Some(effect) = effects::of_call(&path_name(&path.path)) {
+                    self.effects.insert(effect);
+                }
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Some(effect) = effects::of_macro(&path_name(&mac.path)) {
+            self.effects.insert(effect);
+        }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.effects.insert(Effect::Nontermination);
+        visit::visit_expr_while(self, expr);
+    }
+
+    fn visit_expr_loop(&mut self, expr: &'ast syn::ExprLoop) {
+        self.effects.insert(Effect::Nontermination);
+        visit::visit_expr_loop(self, expr);
+    }
+
+    fn visit_expr_unsafe(&mut self, expr: &'ast syn::ExprUnsafe) {
+        self.effects.insert(Effect::Mutation(Scope::Escaping));
+        visit::visit_expr_unsafe(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::compute_soul;
+    use crate::extract::decode;
+
+    fn var(x: &str) -> Box<IR> {
+        Box::new(IR::Var(x.to_string()))
+    }
+
+    fn lam(x: &str, body: IR) -> IR {
+        IR::Lam(x.to_string(), Box::new(body))
+    }
+
+    fn functions(code: &str) -> Vec<(String, IR)> {
+        extract(code).unwrap()
+            .into_iter()
+            .map(|f| (f.name, decode(&f.ir).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_map_manifestations_share_a_soul() {
+        let code = include_str!("../../../genes/map/manifestations/rs/lib.rs");
+        let extracted = extract(code).unwrap();
+        let names: Vec<_> = extracted.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["map", "map_explicit"]);
+        assert_eq!(decode(&extracted[0].ir).unwrap(), lam("xs", lam("f", IR::Map(var("xs"), var("f")))));
+        assert_eq!(compute_soul(&extracted[0].ir), compute_soul(&extracted[1].ir));
+        assert_eq!((extracted[1].start_line, extracted[1].end_line), (15, 24));
+    }
+
+    #[test]
+    fn test_iterator_chain() {
+        let code = "fn score(xs: &[i64]) -> i64 {\n    xs.iter().filter(|&&x| x % 2 == 0).map(|x| x * 3).fold(0, |acc, x| acc + x)\n}";
+        let evens = IR::Filter(var("xs"), Box::new(lam("x", IR::Eq(
+            Box::new(IR::Mod(var("x"), Box::new(IR::Num(2)))),
+            Box::new(IR::Num(0)),
+        ))));
+        let tripled = IR::Map(Box::new(evens), Box::new(lam("x", IR::Mul(var("x"), Box::new(IR::Num(3))))));
+        let expected = lam("xs", IR::Reduce(
+            Box::new(tripled),
+            Box::new(lam("acc", lam("x", IR::Add(var("acc"), var("x"))))),
+            Box::new(IR::Num(0)),
+        ));
+        assert_eq!(functions(code), vec![("score".to_string(), expected)]);
+    }
+
+    #[test]
+    fn test_filter_map_is_hard_focus() {
+        let code = "
+pub fn doubled_positives(xs: Vec<i64>) -> Vec<i64> {
+    xs.into_iter().filter_map(|x| if x > 0 { Some(x * 2) } else { None }).collect()
+}
+
+pub fn doubled_positives_loop(items: Vec<i64>) -> Vec<i64> {
+    let mut out = Vec::new();
+    for item in items {
+        if item > 0 {
+            out.push(item * 2);
+        }
+    }
+    out
+}
+";
+        let extracted = functions(code);
+        let expected = lam("xs", IR::Focus(
+            var("xs"),
+            Box::new(lam("x", IR::Lt(Box::new(IR::Num(0)), var("x")))),
+            Box::new(lam("x", IR::Mul(var("x"), Box::new(IR::Num(2))))),
+            Box::new(IR::Drop),
+        ));
+        assert_eq!(extracted[0].1, expected);
+        assert_eq!(
+            extracted[0].1.alpha_normalize().to_canonical_string(),
+            extracted[1].1.alpha_normalize().to_canonical_string()
+        );
+    }
+
+    #[test]
+    fn test_recursion_and_opaque_constructs() {
+        let code = "
+fn fact(n: u64) -> u64 {
+    if n <= 1 { 1 } else { n * fact(n - 1) }
+}
+
+fn head(xs: &[i64]) -> i64 {
+    match xs { [x, ..] => *x, [] => 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn fact_of_three() {}
+}
+";
+        let fact = lam("n", IR::If(
+            Box::new(IR::Le(var("n"), Box::new(IR::Num(1)))),
+            Box::new(IR::Num(1)),
+            Box::new(IR::Mul(var("n"), Box::new(IR::App(var(SELF), Box::new(IR::Sub(var("n"), Box::new(IR::Num(1)))))))),
+        ));
+        assert_eq!(functions(code), vec![
+            ("fact".to_string(), fact),
+            ("head".to_string(), lam("xs", opaque("match expression"))),
+        ]);
+    }
+
+    #[test]
+    fn test_effects_of_the_whole_body() {
+        use Effect::*;
+        let code = "\
+fn total(xs: &[i64]) -> i64 {
+    let mut t = 0;
+    for x in xs {
+        t += x;
+    }
+    t
+}
+
+fn append(xs: &mut Vec<i64>, x: i64) {
+    xs.push(x);
+}
+
+fn sorted(mut xs: Vec<i64>) -> Vec<i64> {
+    xs.sort();
+    xs
+}
+
+fn logged(x: i64) -> i64 {
+    println!(\"{}\", x);
+    x
+}
+
+fn elapsed() -> u128 {
+    std::time::Instant::now().elapsed().as_nanos()
+}
+
+fn first(xs: &[i64]) -> i64 {
+    *xs.first().unwrap()
+}
+
+fn spin() {
+    loop {}
+}
+";
+        let effects: Vec<(String, Vec<Effect>)> = extract(code).unwrap()
+            .into_iter()
+            .map(|f| (f.name, f.effects.unwrap().iter().collect()))
+            .collect();
+        let expected = vec![
+            ("total", vec![Mutation(Scope::Local)]),
+            ("append", vec![Mutation(Scope::Escaping)]),
+            ("sorted", vec![Mutation(Scope::Local)]),
+            ("logged", vec![Io]),
+            ("elapsed", vec![Time]),
+            ("first", vec![Exception]),
+            ("spin", vec![Nontermination]),
+        ];
+        assert_eq!(effects, expected.into_iter().map(|(name, e)| (name.to_string(), e)).collect::<Vec<_>>());
+    }
+}
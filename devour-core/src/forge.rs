@@ -1,36 +1,60 @@
 use anyhow::Result;
 use blake3::Hasher;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use crate::codegen::{self, wasm, Export};
+use crate::manifest::{self, Budgets};
 use crate::storage::Store;
 
 /// Forge organism from champion genes
-pub async fn forge(store: &Store, organism: &str, targets: &[String], shims: &[String]) -> Result<String> {
+pub async fn forge(
+    store: &Store,
+    organism: &str,
+    targets: &[String],
+    shims: &[String],
+    budgets: &Budgets,
+) -> Result<String> {
     // Get champion genes
     let champions = get_champions(store).await?;
     
     tracing::info!("Forging {} with {} genes", organism, champions.len());
     
-    // Create organism directory
+    // Build out of place, under the organism's own name - a refused body
+    // leaves nothing behind and the last accepted one stays in place
     let org_dir = PathBuf::from("organisms").join(organism);
-    std::fs::create_dir_all(&org_dir)?;
+    let scratch = PathBuf::from("organisms").join(".forging").join(organism);
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
     
     // Generate for each target
+    let exports = codegen::exports(
+        champions.iter().map(|g| (g.name.as_str(), g.soul.as_str(), g.ir.as_str())),
+    );
     for target in targets {
         match target.as_str() {
-            "wasm" => forge_wasm(&org_dir, &champions).await?,
-            "typescript" | "ts" => forge_typescript(&org_dir, &champions).await?,
-            "python" | "py" => forge_python(&org_dir, &champions).await?,
-            "rust" | "rs" => forge_rust(&org_dir, &champions).await?,
+            "wasm" => forge_wasm(&scratch, &exports).await?,
+            "typescript" | "ts" => forge_typescript(&scratch, &exports).await?,
+            "python" | "py" => forge_python(&scratch, &exports).await?,
+            "rust" | "rs" => forge_rust(&scratch, &exports).await?,
             _ => tracing::warn!("Unknown target: {}", target),
         }
     }
     
     // Generate shims if requested
     for shim in shims {
-        generate_shim(&org_dir, shim, &champions).await?;
+        generate_shim(&scratch, shim, &champions).await?;
     }
     
+    // Refuse bodies that outgrow the manifest budgets
+    let breaches = budgets.check_forge(&scratch)?;
+    if let Err(refused) = manifest::enforce(organism, &breaches) {
+        std::fs::remove_dir_all(&scratch)?;
+        return Err(refused);
+    }
+    install(&scratch, &org_dir)?;
+    
     // Compute soulset
     let soulset = compute_soulset(&champions);
     
@@ -43,58 +67,82 @@ pub async fn forge(store: &Store, organism: &str, targets: &[String], shims: &[S
     Ok(soulset)
 }
 
+/// Move everything forged in `scratch` into `org_dir`, replacing what the
+/// last forge left under the same names
+fn install(scratch: &Path, org_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(org_dir)?;
+    for entry in std::fs::read_dir(scratch)? {
+        let entry = entry?;
+        let target = org_dir.join(entry.file_name());
+        if target.is_dir() {
+            std::fs::remove_dir_all(&target)?;
+        } else if target.exists() {
+            std::fs::remove_file(&target)?;
+        }
+        std::fs::rename(entry.path(), target)?;
+    }
+    std::fs::remove_dir(scratch)?;
+    Ok(())
+}
+
 /// Forge WASM module
-async fn forge_wasm(org_dir: &PathBuf, champions: &[ChampionGene]) -> Result<()> {
+async fn forge_wasm(org_dir: &PathBuf, exports: &[Export]) -> Result<()> {
     let wasm_dir = org_dir.join("dist").join("wasm");
     std::fs::create_dir_all(&wasm_dir)?;
     
-    // Generate main WAT module
-    let mut wat = String::from("(module\n");
-    wat.push_str("  (memory 1)\n");
-    wat.push_str("  (export \"memory\" (memory 0))\n\n");
-    
-    for gene in champions {
-        // Compile each gene to WASM
-        let wasm_body = crate::runtime::compile_to_wasm(&gene.ir).await?;
-        
-        // Add to module (simplified)
-        wat.push_str(&format!("  ;; Gene: {}\n", gene.name));
-        wat.push_str(&format!("  ;; Soul: {}\n", gene.soul));
-    }
-    
-    wat.push_str(")\n");
-    
-    std::fs::write(wasm_dir.join("organism.wat"), wat)?;
+    // Genes don't call each other, so one that fails to compile is left out
+    // on its own rather than taking the module with it
+    let compiled: Vec<Export> = exports.iter()
+        .filter(|gene| match wasm::compile(std::slice::from_ref(*gene), Default::default()) {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!("Skipping {} in WASM: {:#}", gene.name, e);
+                false
+            }
+        })
+        .cloned()
+        .collect();
+    let module = wasm::compile(&compiled, Default::default())?;
+    
+    std::fs::write(wasm_dir.join("organism.wasm"), module)?;
     
     Ok(())
 }
 
 /// Forge TypeScript module
-async fn forge_typescript(org_dir: &PathBuf, champions: &[ChampionGene]) -> Result<()> {
+async fn forge_typescript(org_dir: &PathBuf, exports: &[Export]) -> Result<()> {
     let ts_dir = org_dir.join("dist").join("ts");
     std::fs::create_dir_all(&ts_dir)?;
     
     let mut index = String::from("// Generated organism\n\n");
-    
-    for gene in champions {
-        // Generate TS from gene
-        let ts_code = generate_typescript(gene)?;
+    let mut declarations = String::from("// Generated organism\n\n");
+    
+    for gene in exports {
+        let ts_code = match codegen::typescript::module(gene) {
+            Ok(code) => code,
+            Err(e) => {
+                tracing::warn!("Skipping {} in TypeScript: {}", gene.name, e);
+                continue;
+            }
+        };
         
         // Write individual file
         std::fs::write(ts_dir.join(format!("{}.ts", gene.name)), &ts_code)?;
         
-        // Add to index
+        // Add to index and declarations
         index.push_str(&format!("export {{ {} }} from './{}';\n", gene.name, gene.name));
+        declarations.push_str(&codegen::typescript::declaration(gene));
     }
     
     std::fs::write(ts_dir.join("index.ts"), index)?;
+    std::fs::write(ts_dir.join("index.d.ts"), declarations)?;
     
     // Generate package.json
     let package = serde_json::json!({
         "name": format!("@pure-lambda/{}", org_dir.file_name().unwrap().to_str().unwrap()),
         "version": "0.1.0",
         "main": "index.js",
-        "types": "index.ts"
+        "types": "index.d.ts"
     });
     
     std::fs::write(ts_dir.join("package.json"), serde_json::to_string_pretty(&package)?)?;
@@ -103,46 +151,100 @@ async fn forge_typescript(org_dir: &PathBuf, champions: &[ChampionGene]) -> Resu
 }
 
 /// Forge Python module
-async fn forge_python(org_dir: &PathBuf, champions: &[ChampionGene]) -> Result<()> {
+async fn forge_python(org_dir: &PathBuf, exports: &[Export]) -> Result<()> {
+    let org = org_dir.file_name().unwrap().to_str().unwrap();
+    let package = org.replace(['-', '.'], "_");
     let py_dir = org_dir.join("dist").join("py");
-    std::fs::create_dir_all(&py_dir)?;
+    let pkg_dir = py_dir.join(&package);
+    std::fs::create_dir_all(&pkg_dir)?;
+    
+    let mut modules = vec![];
+    for gene in exports {
+        match codegen::python::module(gene) {
+            Ok(code) => {
+                let module = codegen::python::ident(&gene.name);
+                std::fs::write(pkg_dir.join(format!("{}.py", module)), code)?;
+                modules.push(module);
+            }
+            Err(e) => tracing::warn!("Skipping {} in Python: {}", gene.name, e),
+        }
+    }
     
     let mut init = String::from("# Generated organism\n\n");
-    
-    for gene in champions {
-        init.push_str(&format!("from .{} import {}\n", gene.name, gene.name));
+    for name in &modules {
+        init.push_str(&format!("from .{} import {}\n", name, name));
     }
-    
     init.push_str("\n__all__ = [\n");
-    for gene in champions {
-        init.push_str(&format!("    '{}',\n", gene.name));
+    for name in &modules {
+        init.push_str(&format!("    \"{}\",\n", name));
     }
     init.push_str("]\n");
     
-    std::fs::write(py_dir.join("__init__.py"), init)?;
+    std::fs::write(pkg_dir.join("__init__.py"), init)?;
+    
+    // Generate pyproject.toml
+    let pyproject = format!(r#"[build-system]
+requires = ["setuptools>=61"]
+build-backend = "setuptools.build_meta"
+
+[project]
+name = "pure-lambda-{}"
+version = "0.1.0"
+requires-python = ">=3.9"
+
+[tool.setuptools]
+packages = ["{}"]
+"#, org, package);
+    
+    std::fs::write(py_dir.join("pyproject.toml"), pyproject)?;
     
     Ok(())
 }
 
 /// Forge Rust module
-async fn forge_rust(org_dir: &PathBuf, champions: &[ChampionGene]) -> Result<()> {
+async fn forge_rust(org_dir: &PathBuf, exports: &[Export]) -> Result<()> {
     let rs_dir = org_dir.join("dist").join("rs");
-    std::fs::create_dir_all(&rs_dir)?;
-    
-    let mut lib = String::from("// Generated organism\n\n");
+    let src_dir = rs_dir.join("src");
+    std::fs::create_dir_all(&src_dir)?;
+    
+    let mut modules = vec![];
+    for gene in exports {
+        // The gene's own vectors become the module's tests
+        let vectors = std::fs::read_to_string(PathBuf::from("genes").join(&gene.name).join("tests").join("vectors.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok());
+        match codegen::rust::module(gene, vectors.as_ref()) {
+            Ok(code) => {
+                let module = codegen::rust::ident(&gene.name);
+                std::fs::write(src_dir.join(format!("{}.rs", module)), code)?;
+                modules.push(module);
+            }
+            Err(e) => tracing::warn!("Skipping {} in Rust: {}", gene.name, e),
+        }
+    }
     
-    for gene in champions {
-        lib.push_str(&format!("pub mod {};\n", gene.name));
+    // Genes keep the names they were extracted under
+    let mut lib = String::from("// Generated organism\n\n#![allow(non_snake_case)]\n\n");
+    for name in &modules {
+        lib.push_str(&format!("pub mod {};\n", name));
+    }
+    lib.push('\n');
+    for name in &modules {
+        lib.push_str(&format!("pub use {}::{};\n", name, name));
     }
     
-    std::fs::write(rs_dir.join("lib.rs"), lib)?;
+    std::fs::write(src_dir.join("lib.rs"), lib)?;
     
-    // Generate Cargo.toml
+    // Generate Cargo.toml - its own workspace, wherever the organism sits
+    let org = org_dir.file_name().unwrap().to_str().unwrap();
+    let package: String = org.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' }).collect();
     let cargo = format!(r#"[package]
 name = "pure-lambda-{}"
 version = "0.1.0"
 edition = "2021"
-"#, org_dir.file_name().unwrap().to_str().unwrap());
+
+[workspace]
+"#, package);
     
     std::fs::write(rs_dir.join("Cargo.toml"), cargo)?;
     
@@ -193,14 +295,6 @@ fn generate_underscore_shim(dir: &PathBuf, champions: &[ChampionGene]) -> Result
     Ok(())
 }
 
-/// Generate TypeScript from gene
-fn generate_typescript(gene: &ChampionGene) -> Result<String> {
-    // This would transform IR to TypeScript
-    // For now, return stub
-    Ok(format!("// Gene: {}\n// Soul: {}\nexport function {}() {{}}\n", 
-               gene.name, gene.soul, gene.name))
-}
-
 /// Compute merkle root of souls
 fn compute_soulset(champions: &[ChampionGene]) -> String {
     let mut hasher = Hasher::new();
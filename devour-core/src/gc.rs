@@ -0,0 +1,203 @@
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::ingest::lineage;
+use crate::storage::{ObjectType, Store};
+
+/// Outcome of one collection
+#[derive(Debug, Default)]
+pub struct GcReport {
+    pub roots: usize,
+    pub live: usize,
+    pub swept: Vec<String>,
+    pub bytes: u64,
+    pub dry_run: bool,
+}
+
+/// Mark everything reachable from the roots, sweep the rest
+///
+/// An object reaches every `b3:` CID and every gene soul (`λ` + 16 hex)
+/// that appears in its bytes. The latest source of each lineage is a root,
+/// and so are the genes it digests to. With `dry_run` nothing is deleted.
+pub async fn gc(store: &Store, dry_run: bool) -> Result<GcReport> {
+    let mut roots = store.roots().await?;
+    let objects: HashMap<String, usize> = store.objects().await?.into_iter().collect();
+    let genes: HashMap<String, String> = store.gene_cids().await?.into_iter().collect();
+    roots.extend(source_genes(store, &roots, &genes).await?);
+
+    // Mark
+    let mut live = BTreeSet::new();
+    let mut queue: Vec<String> = roots.clone();
+    while let Some(cid) = queue.pop() {
+        if !live.insert(cid.clone()) {
+            continue;
+        }
+        let Some(obj) = store.get(&cid).await? else { continue };
+        let text = String::from_utf8_lossy(&obj.data);
+        for reference in references(&text) {
+            let target = match reference {
+                Reference::Cid(cid) => Some(cid),
+                Reference::Soul(soul) => genes.get(&soul).cloned(),
+            };
+            if let Some(target) = target {
+                if !live.contains(&target) {
+                    queue.push(target);
+                }
+            }
+        }
+    }
+
+    // Sweep
+    let mut swept: Vec<String> = objects.keys().filter(|cid| !live.contains(*cid)).cloned().collect();
+    swept.sort();
+    let bytes = if dry_run {
+        swept.iter().map(|cid| objects[cid] as u64).sum()
+    } else {
+        store.remove(&swept).await?
+    };
+
+    Ok(GcReport {
+        roots: roots.len(),
+        live: live.iter().filter(|cid| objects.contains_key(*cid)).count(),
+        swept,
+        bytes,
+        dry_run,
+    })
+}
+
+/// Objects of the genes a root source's lineage digests to now - nothing
+/// past digest refers to them until a champion or organism does
+async fn source_genes(store: &Store, roots: &[String], genes: &HashMap<String, String>) -> Result<Vec<String>> {
+    let mut lineages = HashSet::new();
+    for cid in roots {
+        let Some(obj) = store.get(cid).await? else { continue };
+        if obj.metadata.typ == ObjectType::Source {
+            let provenance = obj.metadata.source.unwrap_or(obj.cid);
+            lineages.insert(lineage(&provenance).to_string());
+        }
+    }
+
+    let mut kept = Vec::new();
+    for lineage in lineages {
+        for digest in store.file_digests(&lineage).await? {
+            kept.extend(digest.souls.iter().filter_map(|soul| genes.get(soul).cloned()));
+        }
+    }
+    Ok(kept)
+}
+
+#[derive(Debug, PartialEq)]
+enum Reference {
+    Cid(String),
+    Soul(String),
+}
+
+/// CIDs and souls mentioned anywhere in an object
+fn references(text: &str) -> Vec<Reference> {
+    let mut found = Vec::new();
+    for (i, _) in text.match_indices("b3:") {
+        if let Some(hex) = hex_run(&text[i + 3..], 64) {
+            found.push(Reference::Cid(format!("b3:{}", hex)));
+        }
+    }
+    for (i, _) in text.match_indices('λ') {
+        if let Some(hex) = hex_run(&text[i + 'λ'.len_utf8()..], 16) {
+            found.push(Reference::Soul(format!("λ{}", hex)));
+        }
+    }
+    found
+}
+
+/// Exactly `len` lowercase hex digits at the start of `text`
+fn hex_run(text: &str, len: usize) -> Option<&str> {
+    let run = text.bytes().take_while(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(b)).count();
+    (run == len).then(|| &text[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{test_gene as gene, GeneStore, MemoryStore, ObjectMeta, ObjectType};
+
+    fn meta(typ: ObjectType) -> ObjectMeta {
+        ObjectMeta { typ, source: None, timestamp: 0, size: 0 }
+    }
+
+    #[tokio::test]
+    async fn test_mark_and_sweep() {
+        let store = MemoryStore::new();
+        let kept = "λ0123456789abcdef";
+        let dropped = "λfedcba9876543210";
+        store.put_gene(&gene(kept)).await.unwrap();
+        store.put_gene(&gene(dropped)).await.unwrap();
+        let source = store.put(b"tarball", meta(ObjectType::Source)).await.unwrap();
+        let stale = store.put(b"old digest", meta(ObjectType::IR)).await.unwrap();
+
+        // Organism names one gene and the source it came from
+        let organism = format!("{{\"genes\": [\"{}\"], \"from\": \"{}\"}}", kept, source);
+        store.put(organism.as_bytes(), meta(ObjectType::Organism)).await.unwrap();
+
+        let report = gc(&store, true).await.unwrap();
+        assert_eq!(report.live, 3);
+        assert_eq!(report.swept.len(), 2);
+        assert!(report.swept.contains(&stale));
+        assert!(report.bytes > 0);
+        assert_eq!(store.objects().await.unwrap().len(), 5);  // Dry run deleted nothing
+
+        store.pin(&stale).await.unwrap();
+        let report = gc(&store, false).await.unwrap();
+        assert_eq!(report.swept.len(), 1);
+        assert!(store.get_gene(kept).await.unwrap().is_some());
+        assert!(store.get_gene(dropped).await.unwrap().is_none());
+        assert!(store.get(&stale).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_added_source_is_a_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("gc.js"), "export const halve = x => x / 2;\n").unwrap();
+        let store = MemoryStore::new();
+        let source = crate::storage::add(&store, dir.path().to_str().unwrap(), None, None).await.unwrap();
+
+        let report = gc(&store, false).await.unwrap();
+        assert!(report.swept.is_empty());
+        assert!(store.get(&source).await.unwrap().is_some());
+
+        // Its genes outlive the collection too
+        let limits = crate::runtime::SandboxLimits::default();
+        crate::digest::digest(&store, None, false, &limits).await.unwrap();
+        let report = gc(&store, false).await.unwrap();
+        assert!(report.swept.is_empty());
+        assert_eq!(store.status().await.unwrap().total_genes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_superseded_source_is_collected() {
+        let store = MemoryStore::new();
+        let version = |time| ObjectMeta { source: Some("lodash".to_string()), timestamp: time, ..meta(ObjectType::Source) };
+        let old = store.put(b"lodash 1", version(1)).await.unwrap();
+        store.put(b"lodash 2", version(2)).await.unwrap();
+        store.put(b"ramda", ObjectMeta { source: Some("ramda".to_string()), ..meta(ObjectType::Source) }).await.unwrap();
+
+        let report = gc(&store, true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.roots, 2);
+        assert_eq!(report.swept, vec![old.clone()]);
+
+        // Pinned, the old version stays
+        store.pin(&old).await.unwrap();
+        let report = gc(&store, false).await.unwrap();
+        assert!(!report.dry_run);
+        assert!(report.swept.is_empty());
+    }
+
+    #[test]
+    fn test_references() {
+        let cid = format!("b3:{}", "ab".repeat(32));
+        let text = format!("[\"{}\", \"λ0123456789abcdef\", \"λshort\", \"b3:abc\"]", cid);
+        assert_eq!(references(&text), vec![
+            Reference::Cid(cid),
+            Reference::Soul("λ0123456789abcdef".to_string()),
+        ]);
+    }
+}
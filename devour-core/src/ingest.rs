@@ -0,0 +1,309 @@
+use anyhow::{bail, Context, Result};
+use flate2::{read::GzDecoder, Compression, GzBuilder};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Source that needs no network
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalSource {
+    Dir(PathBuf),
+    Tar(PathBuf),
+    /// `.tar.gz`, or an npm `.tgz`
+    TarGz(PathBuf),
+    /// Repository (working tree or bare) at a branch, tag or commit
+    Git { repo: PathBuf, reference: String },
+}
+
+/// Normalized archive of a source, and where exactly it came from
+#[derive(Debug)]
+pub struct Snapshot {
+    pub data: Vec<u8>,
+    /// Recorded on every gene digested from it - `git:<repo>@<commit>` for git
+    pub provenance: String,
+}
+
+impl LocalSource {
+    /// Recognize an existing directory, repository or archive path
+    ///
+    /// A repository is read at `git_ref` (default `HEAD`), never from its
+    /// working tree, so the snapshot is exactly one commit.
+    pub fn detect(source: &str, git_ref: Option<&str>) -> Option<Self> {
+        let path = PathBuf::from(source);
+        if path.is_dir() {
+            if git2::Repository::open(&path).is_ok() {
+                let reference = git_ref.unwrap_or("HEAD").to_string();
+                return Some(LocalSource::Git { repo: path, reference });
+            }
+            return Some(LocalSource::Dir(path));
+        }
+        if !path.is_file() {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(LocalSource::TarGz(path))
+        } else if name.ends_with(".tar") {
+            Some(LocalSource::Tar(path))
+        } else {
+            None
+        }
+    }
+
+    /// Deterministic archive of the source - same files, same bytes
+    pub fn archive(&self) -> Result<Snapshot> {
+        let (files, provenance) = match self {
+            LocalSource::Dir(dir) => (read_dir(dir)?, dir.display().to_string()),
+            LocalSource::Tar(path) => (read_tar(std::fs::File::open(path)?)?, path.display().to_string()),
+            LocalSource::TarGz(path) => (read_tar(GzDecoder::new(std::fs::File::open(path)?))?, path.display().to_string()),
+            LocalSource::Git { repo, reference } => {
+                let (files, commit) = read_git(repo, reference)?;
+                let repo = std::fs::canonicalize(repo)?;
+                (files, format!("git:{}@{}", repo.display(), commit))
+            }
+        };
+        Ok(Snapshot { data: write_archive(&files)?, provenance })
+    }
+}
+
+/// Source identity across versions - `git:<repo>@<commit>` → `git:<repo>`
+pub fn lineage(provenance: &str) -> &str {
+    match provenance.rsplit_once('@') {
+        Some((repo, commit)) if provenance.starts_with("git:")
+            && commit.len() == 40
+            && commit.bytes().all(|b| b.is_ascii_hexdigit()) => repo,
+        _ => provenance,
+    }
+}
+
+/// Clone a remote repository and snapshot it at `reference`
+pub fn clone(url: &str, reference: &str) -> Result<Snapshot> {
+    let key = blake3::hash(url.as_bytes()).to_hex();
+    let dest = std::env::temp_dir().join("devour-work").join(format!("clone-{}", &key[..16]));
+    if dest.exists() {
+        std::fs::remove_dir_all(&dest)?;
+    }
+    let cloned = git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(url, &dest)
+        .with_context(|| format!("cloning {}", url))
+        .and_then(|_| read_git(&dest, reference));
+
+    // Gone whether or not the clone could be read
+    if dest.exists() {
+        std::fs::remove_dir_all(&dest)?;
+    }
+    let (files, commit) = cloned?;
+    Ok(Snapshot { data: write_archive(&files)?, provenance: format!("git:{}@{}", url, commit) })
+}
+
+/// Relative path → (executable, contents)
+type Files = BTreeMap<String, (bool, Vec<u8>)>;
+
+fn read_dir(dir: &Path) -> Result<Files> {
+    let mut files = Files::new();
+    for entry in WalkDir::new(dir).follow_links(false) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir)?;
+        if relative.components().any(|c| c.as_os_str() == ".git") {
+            continue;
+        }
+        files.insert(archive_path(relative)?, (is_executable(&entry.metadata()?), std::fs::read(entry.path())?));
+    }
+    Ok(files)
+}
+
+fn read_tar(reader: impl Read) -> Result<Files> {
+    let mut files = Files::new();
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = archive_path(&entry.path()?)?;
+        let executable = entry.header().mode()? & 0o111 != 0;
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        files.insert(path, (executable, data));
+    }
+    Ok(files)
+}
+
+/// Files of the commit `reference` resolves to, and that commit's hash
+fn read_git(path: &Path, reference: &str) -> Result<(Files, String)> {
+    let repo = git2::Repository::open(path)?;
+    let commit = repo.revparse_single(reference)
+        .with_context(|| format!("unknown ref {} in {}", reference, path.display()))?
+        .peel_to_commit()?;
+
+    // Regular and executable blobs only - symlinks and submodules are skipped
+    let mut blobs = Vec::new();
+    commit.tree()?.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        let mode = entry.filemode();
+        if let (Some(name), 0o100644 | 0o100755) = (entry.name(), mode) {
+            blobs.push((format!("{}{}", dir, name), mode == 0o100755, entry.id()));
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+
+    let mut files = Files::new();
+    for (path, executable, oid) in blobs {
+        let blob = repo.find_blob(oid)?;
+        files.insert(archive_path(Path::new(&path))?, (executable, blob.content().to_vec()));
+    }
+    Ok((files, commit.id().to_string()))
+}
+
+/// Sorted entries, zeroed times and owners, modes 644/755, gzip without a timestamp
+fn write_archive(files: &Files) -> Result<Vec<u8>> {
+    let gz = GzBuilder::new().mtime(0).write(Vec::new(), Compression::best());
+    let mut builder = tar::Builder::new(gz);
+    builder.mode(tar::HeaderMode::Deterministic);
+
+    for (path, (executable, data)) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(if *executable { 0o755 } else { 0o644 });
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder.append_data(&mut header, path, data.as_slice())?;
+    }
+
+    let mut gz = builder.into_inner()?;
+    gz.flush()?;
+    Ok(gz.finish()?)
+}
+
+/// Forward-slash relative path that cannot escape the workspace
+fn archive_path(path: &Path) -> Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => bail!("unsafe path in source: {}", path.display()),
+        }
+    }
+    if parts.is_empty() {
+        bail!("empty path in source");
+    }
+    Ok(parts.join("/"))
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Unpack an archive made by `LocalSource::archive` into a fresh directory
+pub fn unpack(data: &[u8], into: &Path) -> Result<()> {
+    if into.exists() {
+        std::fs::remove_dir_all(into)?;
+    }
+    std::fs::create_dir_all(into)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(data));
+    archive.set_preserve_mtime(false);
+    archive.unpack(into).context("source object is not a tar.gz archive")?;
+    Ok(())
+}
+
+/// Scratch workspace for one source, keyed by its CID
+pub fn workspace(cid: &str) -> PathBuf {
+    let key = cid.trim_start_matches("b3:");
+    std::env::temp_dir()
+        .join("devour-work")
+        .join(&key[..key.len().min(16)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archives_are_deterministic() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("pkg");
+        std::fs::create_dir_all(src.join("lib")).unwrap();
+        std::fs::write(src.join("index.js"), "module.exports = require('./lib/map')").unwrap();
+        std::fs::write(src.join("lib/map.js"), "exports.map = (xs, f) => xs.map(f)").unwrap();
+
+        let from_dir = LocalSource::Dir(src.clone()).archive().unwrap().data;
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(src.join("index.js"), "module.exports = require('./lib/map')").unwrap();
+        assert_eq!(LocalSource::Dir(src.clone()).archive().unwrap().data, from_dir);
+
+        // The same files as a .tgz archive to the same bytes
+        let tgz = dir.path().join("pkg.tgz");
+        std::fs::write(&tgz, &from_dir).unwrap();
+        assert_eq!(LocalSource::detect(tgz.to_str().unwrap(), None), Some(LocalSource::TarGz(tgz.clone())));
+        assert_eq!(LocalSource::TarGz(tgz).archive().unwrap().data, from_dir);
+
+        let out = dir.path().join("work");
+        unpack(&from_dir, &out).unwrap();
+        assert_eq!(std::fs::read_to_string(out.join("lib/map.js")).unwrap(), "exports.map = (xs, f) => xs.map(f)");
+    }
+
+    fn commit(repo: &git2::Repository, file: &str, content: &str) -> git2::Oid {
+        let root = repo.workdir().unwrap();
+        std::fs::write(root.join(file), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::new("devour", "devour@localhost", &git2::Time::new(0, 0)).unwrap();
+        let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, "snapshot", &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn test_lineage_drops_commit() {
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(lineage(&format!("git:/src/fp@{}", commit)), "git:/src/fp");
+        assert_eq!(lineage("git:git@github.com:org/fp"), "git:git@github.com:org/fp");
+        assert_eq!(lineage("lodash"), "lodash");
+    }
+
+    #[test]
+    fn test_git_snapshot_at_ref() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let first = commit(&repo, "map.js", "exports.map = (xs, f) => xs.map(f)");
+        repo.tag_lightweight("v1", &repo.find_object(first, None).unwrap(), false).unwrap();
+        let second = commit(&repo, "map.js", "exports.map = (f) => (xs) => xs.map(f)");
+
+        let path = dir.path().to_str().unwrap();
+        let head = LocalSource::detect(path, None).unwrap().archive().unwrap();
+        assert!(head.provenance.ends_with(&format!("@{}", second)));
+
+        // Tag, commit hash and an untracked file in the working tree
+        std::fs::write(dir.path().join("scratch.js"), "wip").unwrap();
+        let tagged = LocalSource::detect(path, Some("v1")).unwrap().archive().unwrap();
+        let pinned = LocalSource::detect(path, Some(&first.to_string())).unwrap().archive().unwrap();
+        assert!(tagged.provenance.ends_with(&format!("@{}", first)));
+        assert_eq!(tagged.data, pinned.data);
+        assert_ne!(tagged.data, head.data);
+        assert_eq!(LocalSource::detect(path, None).unwrap().archive().unwrap().data, head.data);
+
+        assert!(LocalSource::detect(path, Some("no-such-ref")).unwrap().archive().is_err());
+    }
+
+    #[test]
+    fn test_rejects_escaping_paths() {
+        assert!(archive_path(Path::new("../etc/passwd")).is_err());
+        assert!(archive_path(Path::new("/etc/passwd")).is_err());
+        assert_eq!(archive_path(Path::new("./package/index.js")).unwrap(), "package/index.js");
+    }
+}
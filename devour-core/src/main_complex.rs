@@ -1,6 +1,6 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use tracing::{info, Level};
+use tracing::{info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 mod storage;
@@ -12,8 +12,17 @@ mod attest;
 mod runtime;
 mod recipe;
 mod surgeon;
+mod manifest;
+mod distortion;
+mod gc;
+mod ingest;
+mod extract;
+mod effects;
+mod codegen;
 
-use crate::storage::Store;
+use crate::storage::Backend;
+use crate::manifest::Manifest;
+use crate::runtime::SandboxLimits;
 
 #[derive(Parser)]
 #[command(name = "devour")]
@@ -26,17 +35,28 @@ struct Cli {
     #[arg(long, default_value = ".store")]
     store: String,
     
+    /// Storage backend
+    #[arg(long, value_enum, default_value = "sqlite")]
+    backend: Backend,
+    
     #[arg(long)]
     verbose: bool,
+    
+    /// Intent and budgets that surgery and forge must respect
+    #[arg(long, default_value = manifest::DEFAULT_MANIFEST)]
+    manifest: String,
 }
 
 #[derive(Subcommand)]
 enum Command {
-    /// Consume external source (npm/github/crate)
+    /// Consume external source (npm/github/crate, local dir or tarball)
     Add {
         source: String,
         #[arg(long)]
         version: Option<String>,
+        /// Branch, tag or commit for git sources
+        #[arg(long = "ref")]
+        git_ref: Option<String>,
     },
     
     /// Extract genes from consumed sources
@@ -95,6 +115,40 @@ enum Command {
         #[arg(long)]
         self_play: bool,
     },
+    
+    /// Delete objects unreachable from organisms, champions, attestations and pins
+    Gc {
+        /// Report what would be swept without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Keep an object alive across gc
+    Pin {
+        cid: String,
+    },
+    
+    /// Release a pinned object
+    Unpin {
+        cid: String,
+    },
+    
+    /// Measure each rewrite rule's distortion impact over a gene corpus
+    Impact {
+        /// JSON object mapping gene address → surgeon IR
+        corpus: String,
+    },
+    
+    /// Measure address→value distortion of a gene tree
+    Distortion {
+        #[arg(long, default_value = "genes")]
+        genes: String,
+        #[arg(long)]
+        rules: Option<String>,
+        /// Writes <out>.md and <out>.json
+        #[arg(long, default_value = "distortion-report")]
+        out: String,
+    },
 }
 
 #[tokio::main]
@@ -109,48 +163,64 @@ async fn main() -> Result<()> {
     tracing::subscriber::set_global_default(subscriber)?;
     
     // Initialize store
-    let store = Store::open(&cli.store).await?;
+    let store = storage::open(&cli.store, cli.backend).await?;
+    
+    // Load intent and budgets
+    let manifest = Manifest::load_or_default(&cli.manifest)?;
+    for key in &manifest.unknown_keys {
+        warn!("Unknown key in {}: {}", cli.manifest, key);
+    }
+    for key in &manifest.unenforced {
+        warn!("{} in {} is declared but not enforced", key, cli.manifest);
+    }
     
     match cli.command {
-        Command::Add { source, version } => {
+        Command::Add { source, version, git_ref } => {
             info!("🦖 Consuming {}", source);
-            let cid = storage::add(&store, &source, version.as_deref()).await?;
+            let cid = storage::add(&*store, &source, version.as_deref(), git_ref.as_deref()).await?;
             info!("✓ Stored as {}", cid);
         }
         
         Command::Digest { source, parallel } => {
             info!("🧬 Digesting into genes");
-            let stats = digest::digest(&store, source.as_deref(), parallel).await?;
-            info!("✓ Extracted {} genes, {} unique souls", stats.total, stats.unique);
+            let stats = digest::digest(&*store, source.as_deref(), parallel, &SandboxLimits::default()).await?;
+            info!("✓ {} genes ({} extracted, {} reused, {} tombstoned), {} unique souls",
+                stats.total, stats.extracted, stats.reused, stats.removed, stats.unique);
+            if !stats.failed.is_empty() {
+                warn!("✗ {} files failed to extract", stats.failed.len());
+                for failure in &stats.failed {
+                    warn!("  {}: {}", failure.path, failure.reason);
+                }
+            }
         }
         
         Command::Align { rules } => {
             info!("🔄 Aligning equivalent genes");
-            let classes = align::align(&store, rules.as_deref()).await?;
+            let classes = align::align(&*store, rules.as_deref()).await?;
             info!("✓ Found {} equivalence classes", classes);
         }
         
         Command::Distill { objectives } => {
             info!("🏆 Distilling champion implementations");
-            let champions = distill::distill(&store, objectives.as_deref()).await?;
+            let champions = distill::distill(&*store, objectives.as_deref()).await?;
             info!("✓ Selected {} champions", champions);
         }
         
         Command::Forge { organism, targets, shims } => {
             info!("🔨 Forging organism: {}", organism);
-            let soulset = forge::forge(&store, &organism, &targets, &shims).await?;
+            let soulset = forge::forge(&*store, &organism, &targets, &shims, &manifest.budgets).await?;
             info!("✓ Forged with soulset: {}", soulset);
         }
         
         Command::Attest { organism, sign } => {
             info!("📜 Generating attestation for {}", organism);
-            let proof = attest::attest(&store, &organism, sign).await?;
+            let proof = attest::attest(&*store, &organism, sign).await?;
             info!("✓ Attestation: {}", proof);
         }
         
         Command::Run { recipe, watch } => {
             info!("🚀 Running recipe: {}", recipe);
-            recipe::run(&store, &recipe, watch).await?;
+            recipe::run(&*store, &recipe, watch, &manifest).await?;
         }
         
         Command::Status => {
@@ -167,7 +237,15 @@ async fn main() -> Result<()> {
         Command::Surgery { input, budget_ms, self_play } => {
             info!("🔬 Performing mathematical surgery");
             
-            let mut surgeon = surgeon::Surgeon::new();
+            let genes = storage::gene_effects(&*store).await?;
+            let mut surgeon = surgeon::Surgeon::new()
+                .with_capabilities(manifest.intent.caps.clone())
+                .with_gene_effects(genes.clone());
+            let reductions: Vec<(String, f64)> = store.rule_impacts().await?
+                .into_iter()
+                .map(|stats| (stats.rule, stats.reduction as f64))
+                .collect();
+            surgeon.prioritize(&reductions);
             let budget = std::time::Duration::from_millis(budget_ms);
             
             // Load IR (simplified - would load from store)
@@ -178,23 +256,83 @@ async fn main() -> Result<()> {
                 )),
                 Box::new(surgeon::egraph::IR::Var("g".to_string()))
             );
+            let effects = effects::Effects::of_ir_in(&ir, &genes);
             
             if self_play {
-                let results = surgeon.self_improve(ir, 10);
+                let results = surgeon.self_improve(ir, &effects, 10);
                 for (i, result) in results.iter().enumerate() {
+                    manifest::enforce(&format!("Round {}", i + 1), &manifest.budgets.check_surgery(result))?;
                     println!("Round {}: improvement = {:.2}%", 
                              i + 1, result.improvement_ratio() * 100.0);
                 }
             } else {
-                let result = surgeon.operate(&ir, budget);
+                let result = surgeon.operate(&ir, &effects, budget);
+                manifest::enforce("Surgery", &manifest.budgets.check_surgery(&result))?;
                 println!("Surgery complete:");
                 println!("  Initial cost: {:.0}", result.initial_cost.score());
                 println!("  Final cost: {:.0}", result.final_cost.score());
                 println!("  Improvement: {:.2}%", result.improvement_ratio() * 100.0);
+                println!("  Edit distance: {:.3}", result.edit_distance());
                 println!("  Rules applied: {:?}", result.rules_applied);
                 println!("  Verified: {}", result.verified);
             }
         }
+        
+        Command::Gc { dry_run } => {
+            info!("🧹 Collecting unreachable objects");
+            let report = gc::gc(&*store, dry_run).await?;
+            for cid in &report.swept {
+                println!("{} {}", if report.dry_run { "would sweep" } else { "swept" }, cid);
+            }
+            info!("✓ {} roots, {} live, {} swept, {} bytes {}",
+                  report.roots, report.live, report.swept.len(), report.bytes,
+                  if report.dry_run { "reclaimable" } else { "reclaimed" });
+        }
+        
+        Command::Pin { cid } => {
+            if store.get(&cid).await?.is_none() {
+                warn!("{} is not in the store (pinned anyway)", cid);
+            }
+            store.pin(&cid).await?;
+            info!("📌 Pinned {}", cid);
+        }
+        
+        Command::Unpin { cid } => {
+            store.unpin(&cid).await?;
+            info!("✓ Unpinned {}", cid);
+        }
+        
+        Command::Impact { corpus } => {
+            info!("📐 Measuring rule impact on {}", corpus);
+            let genes: std::collections::BTreeMap<String, surgeon::egraph::IR> =
+                serde_json::from_str(&std::fs::read_to_string(&corpus)?)?;
+            let genes: Vec<_> = genes.into_iter().collect();
+            
+            let effects = storage::gene_effects(&*store).await?;
+            let impacts = surgeon::impact::measure_all(&surgeon::rules::RuleSet::default(), &genes, &effects);
+            for impact in &impacts {
+                store.record_rule_impact(impact).await?;
+                println!("{:<20} rewrote {:>4}/{:<4} Δ {:+.4}  (δ₁ {:+.3} δ₂ {:+.3} δ₃ {:+.3} δ₄ {:+.3})",
+                         impact.rule, impact.rewritten, impact.genes, -impact.reduction(),
+                         impact.delta.unpredictability, impact.delta.instability,
+                         impact.delta.nonlocality, impact.delta.redundancy);
+            }
+            info!("✓ Recorded impact of {} rules", impacts.len());
+        }
+        
+        Command::Distortion { genes, rules, out } => {
+            info!("📐 Measuring distortion of {}", genes);
+            let rules = match rules {
+                Some(path) => align::load_rules(&path)?,
+                None => align::default_rules(),
+            };
+            let (ledger, count) = distortion::scan_genes(std::path::Path::new(&genes), &rules)?;
+            let report = distortion::report(&ledger, count);
+            std::fs::write(format!("{}.md", out), report.to_markdown())?;
+            std::fs::write(format!("{}.json", out), report.to_json()?)?;
+            info!("✓ Distortion {:.1}% → {:.1}%, report in {}.md/.json",
+                  report.before.total * 100.0, report.after.total * 100.0, out);
+        }
     }
     
     Ok(())
@@ -0,0 +1,350 @@
+/// MANIFEST.intent - the organism's time capsule, loaded as an Intent
+///
+/// invariants → laws, rule set, ε and capabilities
+/// budgets    → hard limits that surgery and forge refuse to exceed
+
+use anyhow::{bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+use std::path::Path;
+
+use lambda_core::intent::{Capabilities, CostWeights, Intent, Law, RuleSet};
+
+use crate::surgeon::OperationResult;
+
+pub const DEFAULT_MANIFEST: &str = "MANIFEST.intent";
+
+/// Top-level keys we understand (informational ones are accepted as-is)
+const KNOWN_KEYS: &[&str] = &[
+    "soulset", "intent", "philosophy", "invariants", "budgets",
+    "weights", "laws", "capabilities",
+    "checklist_next", "genesis", "milestones", "remember",
+];
+
+/// Parsed manifest
+#[derive(Debug)]
+pub struct Manifest {
+    pub intent: Intent,
+    pub budgets: Budgets,
+    /// Keys present in the file that map onto nothing (e.g. `budgets.wasm_max_gb`)
+    pub unknown_keys: Vec<String>,
+    /// Budgets declared but not enforced - nothing measures them yet
+    pub unenforced: Vec<String>,
+}
+
+/// Declared resource budgets - `None` means unbounded
+#[derive(Debug, Clone, Default)]
+pub struct Budgets {
+    pub wasm_max_mb: Option<f64>,
+    pub arena_nodes: Option<usize>,
+    pub proof_timeout_ms: Option<u64>,
+    /// Metrics that must not get worse (cycles, allocs, bytes, size)
+    pub no_regressions: Vec<String>,
+}
+
+/// A budget that an output went over
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breach {
+    pub budget: String,
+    pub limit: f64,
+    pub actual: f64,
+}
+
+impl fmt::Display for Breach {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} exceeds {}", self.budget, self.actual, self.limit)
+    }
+}
+
+impl Manifest {
+    /// Load manifest from file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    /// Load manifest, falling back to guardian intent with no budgets
+    pub fn load_or_default(path: impl AsRef<Path>) -> Result<Self> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Ok(Manifest {
+                intent: Intent::guardian(),
+                budgets: Budgets::default(),
+                unknown_keys: vec![],
+                unenforced: vec![],
+            })
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let root: Mapping = serde_yaml::from_str(content).context("MANIFEST.intent is not a mapping")?;
+
+        let mut manifest = Manifest {
+            intent: Intent::guardian(),
+            budgets: Budgets::default(),
+            unknown_keys: vec![],
+            unenforced: vec![],
+        };
+
+        for (key, value) in &root {
+            let key = key_str(key)?;
+            match key {
+                "invariants" => manifest.parse_invariants(value)?,
+                "budgets" => manifest.parse_budgets(value)?,
+                "weights" => manifest.parse_weights(value)?,
+                "laws" => manifest.parse_laws(value)?,
+                "capabilities" => manifest.parse_capabilities(value)?,
+                k if KNOWN_KEYS.contains(&k) => {}
+                k => manifest.unknown_keys.push(k.to_string()),
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Invariants are "name: spec" strings
+    fn parse_invariants(&mut self, value: &Value) -> Result<()> {
+        let items = value.as_sequence().context("invariants must be a list")?;
+        for item in items {
+            let line = item.as_str().context("invariant must be a string")?;
+            let (name, spec) = line.split_once(':').unwrap_or((line, ""));
+            let (name, spec) = (name.trim(), spec.trim());
+
+            match name {
+                "proofs" => self.intent.allowed_rules = RuleSet::Proven,
+                "no_regressions" => {
+                    // "cycles, allocs, size <= 0"
+                    let metrics = spec.split("<=").next().unwrap_or("");
+                    self.budgets.no_regressions = metrics
+                        .split(',')
+                        .map(|m| m.trim().to_string())
+                        .filter(|m| !m.is_empty())
+                        .collect();
+                }
+                "roi/focus" => {
+                    // "allowed; early_stop: allowed with ε<=1e-3"
+                    if let Some(bound) = spec.split("ε<=").nth(1) {
+                        let bound = bound.split(|c: char| c.is_whitespace() || c == ';').next().unwrap_or("");
+                        self.intent.epsilon = bound.parse()
+                            .with_context(|| format!("bad ε bound in invariant: {}", line))?;
+                    }
+                }
+                "soul preservation" => push_law(&mut self.intent.laws, Law::Identity),
+                "deterministic" => {
+                    self.intent.caps.clock = false;
+                    self.intent.caps.entropy = false;
+                }
+                _ => self.unknown_keys.push(format!("invariants.{}", name)),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_budgets(&mut self, value: &Value) -> Result<()> {
+        let map = value.as_mapping().context("budgets must be a mapping")?;
+        for (key, value) in map {
+            let key = key_str(key)?;
+            let number = value.as_f64()
+                .with_context(|| format!("budgets.{} must be a number", key))?;
+            match key {
+                "wasm_max_mb" => self.budgets.wasm_max_mb = Some(number),
+                // Nothing measures latency yet
+                "p95_latency_regress" => self.unenforced.push(format!("budgets.{}", key)),
+                "arena_nodes" => self.budgets.arena_nodes = Some(number as usize),
+                "proof_timeout_ms" => self.budgets.proof_timeout_ms = Some(number as u64),
+                k => self.unknown_keys.push(format!("budgets.{}", k)),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_weights(&mut self, value: &Value) -> Result<()> {
+        let map = value.as_mapping().context("weights must be a mapping")?;
+        let weights: &mut CostWeights = &mut self.intent.weights;
+        for (key, value) in map {
+            let key = key_str(key)?;
+            let number = match value.as_str() {
+                Some("inf") | Some("∞") => f32::INFINITY,
+                _ => value.as_f64()
+                    .with_context(|| format!("weights.{} must be a number", key))? as f32,
+            };
+            match key {
+                "cycles" => weights.cycles = number,
+                "bytes" => weights.bytes = number,
+                "allocs" => weights.allocs = number,
+                "io_risk" => weights.io_risk = number,
+                k => self.unknown_keys.push(format!("weights.{}", k)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Listing laws replaces the defaults; unknown names become `Law::Custom`
+    fn parse_laws(&mut self, value: &Value) -> Result<()> {
+        let items = value.as_sequence().context("laws must be a list")?;
+        self.intent.laws.clear();
+        for item in items {
+            let name = item.as_str().context("law must be a string")?;
+            let law = match name {
+                "identity" => Law::Identity,
+                "fusion" => Law::Fusion,
+                "length_preserved" => Law::LengthPreserved,
+                "equivalence" => Law::Equivalence,
+                custom => Law::Custom(custom.to_string()),
+            };
+            push_law(&mut self.intent.laws, law);
+        }
+        Ok(())
+    }
+
+    fn parse_capabilities(&mut self, value: &Value) -> Result<()> {
+        let map = value.as_mapping().context("capabilities must be a mapping")?;
+        let caps: &mut Capabilities = &mut self.intent.caps;
+        for (key, value) in map {
+            let key = key_str(key)?;
+            let allowed = value.as_bool()
+                .with_context(|| format!("capabilities.{} must be a bool", key))?;
+            match key {
+                "cpu" => caps.cpu = allowed,
+                "mem" => caps.mem = allowed,
+                "io" => caps.io = allowed,
+                "clock" => caps.clock = allowed,
+                "entropy" => caps.entropy = allowed,
+                k => self.unknown_keys.push(format!("capabilities.{}", k)),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Budgets {
+    /// Check a surgery result against no_regressions, arena and proof budgets
+    pub fn check_surgery(&self, result: &OperationResult) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+        let before = &result.initial_cost;
+        let after = &result.final_cost;
+
+        for metric in &self.no_regressions {
+            let (limit, actual) = match metric.as_str() {
+                "cycles" => (before.cycles as f64, after.cycles as f64),
+                "bytes" => (before.bytes as f64, after.bytes as f64),
+                "allocs" => (before.allocs as f64, after.allocs as f64),
+                "size" => (result.original.size() as f64, result.transformed.size() as f64),
+                _ => continue,
+            };
+            if actual > limit {
+                breaches.push(Breach { budget: format!("no_regressions.{}", metric), limit, actual });
+            }
+        }
+
+        if let Some(nodes) = self.arena_nodes {
+            let size = result.transformed.size();
+            if size > nodes {
+                breaches.push(Breach { budget: "arena_nodes".into(), limit: nodes as f64, actual: size as f64 });
+            }
+        }
+
+        if let Some(timeout) = self.proof_timeout_ms {
+            let elapsed = result.duration.as_millis() as u64;
+            if elapsed > timeout {
+                breaches.push(Breach { budget: "proof_timeout_ms".into(), limit: timeout as f64, actual: elapsed as f64 });
+            }
+        }
+
+        breaches
+    }
+
+    /// Check a forged organism's WASM output against wasm_max_mb
+    pub fn check_forge(&self, org_dir: &Path) -> Result<Vec<Breach>> {
+        let mut breaches = Vec::new();
+
+        if let Some(max_mb) = self.wasm_max_mb {
+            let wasm_dir = org_dir.join("dist").join("wasm");
+            let mut bytes = 0u64;
+            if wasm_dir.exists() {
+                for entry in walkdir::WalkDir::new(&wasm_dir) {
+                    let entry = entry?;
+                    if entry.file_type().is_file() {
+                        bytes += entry.metadata()?.len();
+                    }
+                }
+            }
+            let mb = bytes as f64 / (1024.0 * 1024.0);
+            if mb > max_mb {
+                breaches.push(Breach { budget: "wasm_max_mb".into(), limit: max_mb, actual: mb });
+            }
+        }
+
+        Ok(breaches)
+    }
+}
+
+/// Refuse an output that went over budget
+pub fn enforce(what: &str, breaches: &[Breach]) -> Result<()> {
+    if breaches.is_empty() {
+        return Ok(());
+    }
+    let list: Vec<String> = breaches.iter().map(|b| b.to_string()).collect();
+    bail!("{} refused, over budget: {}", what, list.join("; "))
+}
+
+fn push_law(laws: &mut Vec<Law>, law: Law) {
+    if !laws.contains(&law) {
+        laws.push(law);
+    }
+}
+
+fn key_str(key: &Value) -> Result<&str> {
+    key.as_str().context("manifest keys must be strings")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_manifest() {
+        let content = include_str!("../../MANIFEST.intent");
+        let manifest = Manifest::parse(content).unwrap();
+
+        assert!(manifest.unknown_keys.is_empty());
+        assert_eq!(manifest.intent.epsilon, 1e-3);
+        assert!(manifest.intent.laws.contains(&Law::Identity));
+        assert!(!manifest.intent.caps.entropy);
+        assert_eq!(manifest.budgets.arena_nodes, Some(256));
+        assert_eq!(manifest.budgets.proof_timeout_ms, Some(5000));
+        assert_eq!(manifest.budgets.wasm_max_mb, Some(1.2));
+        assert_eq!(manifest.budgets.no_regressions, vec!["cycles", "allocs", "size"]);
+        assert_eq!(manifest.unenforced, vec!["budgets.p95_latency_regress"]);
+    }
+
+    #[test]
+    fn test_unknown_keys_and_overrides() {
+        let content = r#"
+intent: "test"
+colour: blue
+budgets:
+  wasm_max_gb: 1
+weights:
+  cycles: 2.0
+  io_risk: inf
+laws: [length_preserved, "len(out) == len(in)"]
+capabilities:
+  io: true
+  network: true
+"#;
+        let manifest = Manifest::parse(content).unwrap();
+
+        assert_eq!(manifest.unknown_keys, vec!["colour", "budgets.wasm_max_gb", "capabilities.network"]);
+        assert_eq!(manifest.intent.weights.cycles, 2.0);
+        assert!(manifest.intent.weights.io_risk.is_infinite());
+        assert_eq!(manifest.intent.laws, vec![
+            Law::LengthPreserved,
+            Law::Custom("len(out) == len(in)".to_string()),
+        ]);
+        assert!(manifest.intent.caps.io);
+    }
+}
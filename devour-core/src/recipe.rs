@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use petgraph::graph::{DiGraph, NodeIndex};
 use std::collections::HashMap;
 
+use crate::manifest::Manifest;
+use crate::runtime::SandboxLimits;
 use crate::storage::Store;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,6 +32,9 @@ pub struct Source {
 pub struct NormalizeConfig {
     pub languages: Vec<String>,
     pub extractors: Vec<String>,
+    /// What an extractor may use per file
+    #[serde(default)]
+    pub limits: SandboxLimits,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,8 +61,8 @@ pub struct AttestConfig {
     pub publish: Vec<String>,
 }
 
-/// Run recipe pipeline
-pub async fn run(store: &Store, recipe_path: &str, watch: bool) -> Result<()> {
+/// Run recipe pipeline within the budgets of `manifest`
+pub async fn run(store: &Store, recipe_path: &str, watch: bool, manifest: &Manifest) -> Result<()> {
     // Load recipe
     let recipe = load_recipe(recipe_path)?;
     
@@ -67,7 +72,7 @@ pub async fn run(store: &Store, recipe_path: &str, watch: bool) -> Result<()> {
     tracing::info!("Running recipe with {} tasks", dag.node_count());
     
     // Execute DAG
-    execute_dag(store, dag, &recipe).await?;
+    execute_dag(store, dag, &recipe, manifest).await?;
     
     // Watch mode
     if watch {
@@ -137,7 +142,7 @@ fn build_dag(recipe: &Recipe) -> DiGraph<Task, ()> {
 }
 
 /// Execute task DAG
-async fn execute_dag(store: &Store, dag: DiGraph<Task, ()>, recipe: &Recipe) -> Result<()> {
+async fn execute_dag(store: &Store, dag: DiGraph<Task, ()>, recipe: &Recipe, manifest: &Manifest) -> Result<()> {
     // Topological sort
     let sorted = petgraph::algo::toposort(&dag, None)
         .map_err(|_| anyhow::anyhow!("Recipe contains cycles"))?;
@@ -145,14 +150,14 @@ async fn execute_dag(store: &Store, dag: DiGraph<Task, ()>, recipe: &Recipe) ->
     // Execute in order
     for node_idx in sorted {
         let task = &dag[node_idx];
-        execute_task(store, task, recipe).await?;
+        execute_task(store, task, recipe, manifest).await?;
     }
     
     Ok(())
 }
 
 /// Execute single task
-async fn execute_task(store: &Store, task: &Task, recipe: &Recipe) -> Result<()> {
+async fn execute_task(store: &Store, task: &Task, recipe: &Recipe, manifest: &Manifest) -> Result<()> {
     match task {
         Task::Add { source } => {
             tracing::info!("Adding source: {}", source);
@@ -166,12 +171,18 @@ async fn execute_task(store: &Store, task: &Task, recipe: &Recipe) -> Result<()>
                 source.clone()
             };
             
-            crate::storage::add(store, &source_str, src.version.as_deref()).await?;
+            crate::storage::add(store, &source_str, src.version.as_deref(), src.git_ref.as_deref()).await?;
         }
         
         Task::Digest { source } => {
             tracing::info!("Digesting: {}", source);
-            crate::digest::digest(store, Some(source), true).await?;
+            let stats = crate::digest::digest(store, Some(source), true, &recipe.normalize.limits).await?;
+            if !stats.failed.is_empty() {
+                tracing::warn!("{} files of {} failed to extract", stats.failed.len(), source);
+                for failure in &stats.failed {
+                    tracing::warn!("  {}: {}", failure.path, failure.reason);
+                }
+            }
         }
         
         Task::Align => {
@@ -191,7 +202,7 @@ async fn execute_task(store: &Store, task: &Task, recipe: &Recipe) -> Result<()>
             } else {
                 vec![]
             };
-            crate::forge::forge(store, organism, &recipe.forge.targets, &shims).await?;
+            crate::forge::forge(store, organism, &recipe.forge.targets, &shims, &manifest.budgets).await?;
         }
         
         Task::Attest { organism } => {
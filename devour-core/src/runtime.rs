@@ -1,131 +1,418 @@
 use anyhow::{Context, Result};
-use wasmtime::*;
-use wasmtime_wasi::{WasiCtx, WasiCtxBuilder};
+use serde::{Deserialize, Serialize};
+use wasmtime::component::{Component, Linker, ResourceTable};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
+use std::future::Future;
 use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::effects::{Effect, Effects, Scope};
+use crate::surgeon::egraph::IR;
+
+/// Host side of the `pure-lambda:extractor` world
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "../wasm/extractor.wit",
+        world: "extractor",
+        async: true,
+    });
+}
+
+use bindings::pure_lambda::extractor::types::{self as wit, Node, Severity};
+
+/// How often the engine's epoch advances - the resolution of `timeout_ms`
+const TICK: Duration = Duration::from_millis(10);
+
+/// What one extractor call may use before it's cut off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxLimits {
+    /// Wasm instructions, roughly
+    pub fuel: u64,
+    pub memory_mb: usize,
+    /// Wall clock per call
+    pub timeout_ms: u64,
+    /// Captured stdout and stderr, each
+    pub output_kb: usize,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        SandboxLimits {
+            fuel: 10_000_000_000,
+            memory_mb: 512,
+            timeout_ms: 10_000,
+            output_kb: 64,
+        }
+    }
+}
 
 /// WASM runtime for extractors and transformers
 pub struct Runtime {
     engine: Engine,
     linker: Linker<WasmState>,
+    limits: SandboxLimits,
+    _ticker: Ticker,
 }
 
+/// Store data of one sandboxed call
 struct WasmState {
     wasi: WasiCtx,
-    memory: Option<Memory>,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl WasiView for WasmState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+/// Advances the engine's epoch so deadlines measure wall-clock time -
+/// without it a guest that never yields can't be interrupted
+struct Ticker {
+    stop: Arc<AtomicBool>,
+}
+
+impl Ticker {
+    fn start(engine: &Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (engine, stopped) = (engine.clone(), stop.clone());
+        std::thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                std::thread::sleep(TICK);
+                engine.increment_epoch();
+            }
+        });
+        Ticker { stop }
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
 }
 
 impl Runtime {
-    pub fn new() -> Result<Self> {
+    pub fn new(limits: SandboxLimits) -> Result<Self> {
         let mut config = Config::new();
         config.wasm_component_model(true);
         config.async_support(true);
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
         
         let engine = Engine::new(&config)?;
         let mut linker = Linker::new(&engine);
         
         // Add WASI support
-        wasmtime_wasi::add_to_linker(&mut linker, |state: &mut WasmState| &mut state.wasi)?;
+        wasmtime_wasi::add_to_linker_async(&mut linker)?;
         
-        Ok(Runtime { engine, linker })
+        let ticker = Ticker::start(&engine);
+        Ok(Runtime { engine, linker, limits, _ticker: ticker })
     }
     
     /// Load extractor component
-    pub async fn load_extractor(&self, wasm_path: &Path) -> Result<Extractor> {
+    ///
+    /// Linked once, instantiated per call - a trapped instance can't be
+    /// reentered, and each call gets its own fuel, memory and output.
+    pub fn load_extractor(&self, wasm_path: &Path) -> Result<Extractor> {
         let component = Component::from_file(&self.engine, wasm_path)?;
+        let pre = bindings::ExtractorPre::new(self.linker.instantiate_pre(&component)?)
+            .with_context(|| format!("{} is not a pure-lambda:extractor@0.1.0 component", wasm_path.display()))?;
+        
+        Ok(Extractor { pre, name: wasm_path.display().to_string() })
+    }
+    
+    /// Fresh store for one call: fuel, memory cap, deadline and captured stdio
+    fn sandbox(&self) -> Result<(Store<WasmState>, Output)> {
+        let limits = &self.limits;
+        let output = Output {
+            stdout: MemoryOutputPipe::new(limits.output_kb * 1024),
+            stderr: MemoryOutputPipe::new(limits.output_kb * 1024),
+        };
         let mut store = Store::new(
             &self.engine,
             WasmState {
-                wasi: WasiCtxBuilder::new().inherit_stdio().build(),
-                memory: None,
+                wasi: WasiCtxBuilder::new()
+                    .stdout(output.stdout.clone())
+                    .stderr(output.stderr.clone())
+                    .build(),
+                table: ResourceTable::new(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(limits.memory_mb * 1024 * 1024)
+                    .trap_on_grow_failure(true)
+                    .build(),
             },
         );
-        
-        let instance = self.linker.instantiate_async(&mut store, &component).await?;
-        
-        Ok(Extractor { store, instance })
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(limits.fuel)?;
+        store.set_epoch_deadline(limits.timeout_ms.div_ceil(TICK.as_millis() as u64).max(1));
+        Ok((store, output))
     }
     
-    /// Execute extraction
-    pub async fn extract_ir(extractor: &mut Extractor, code: &str) -> Result<String> {
-        // Get extract_ir function
-        let extract_fn = extractor.instance
-            .get_typed_func::<(&str,), (String,)>(&mut extractor.store, "extract-ir")?;
-        
-        // Call function
-        let (ir,) = extract_fn.call_async(&mut extractor.store, (code,)).await?;
+    /// Call `export` on a fresh instance of `extractor`
+    ///
+    /// Failures carry the limit that was hit, or the trap, and whatever the
+    /// guest wrote to stderr.
+    async fn call<R>(
+        &self,
+        extractor: &Extractor,
+        export: &str,
+        call: impl for<'a> FnOnce(&'a bindings::Extractor, &'a mut Store<WasmState>) -> Call<'a, R>,
+    ) -> Result<R> {
+        let (mut store, output) = self.sandbox()?;
+        let result = async {
+            let instance = extractor.pre.instantiate_async(&mut store).await?;
+            call(&instance, &mut store).await
+        };
+        // The epoch deadline stops a spinning guest, this one a guest
+        // blocked in a host call
+        let timeout = Duration::from_millis(self.limits.timeout_ms) + TICK;
+        let result = match tokio::time::timeout(timeout, result).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::Error::new(Trap::Interrupt)),
+        };
         
-        Ok(ir)
+        output.log(&extractor.name);
+        result.map_err(|e| anyhow::anyhow!("{} {}: {}{}", extractor.name, export, self.reason(&e), output.stderr_tail()))
     }
     
-    /// Verify properties
-    pub async fn verify(extractor: &mut Extractor, ir: &str, props: &[String]) -> Result<String> {
-        let verify_fn = extractor.instance
-            .get_typed_func::<(&str, &[String]), (String,)>(&mut extractor.store, "verify")?;
-        
-        let (proof,) = verify_fn.call_async(&mut extractor.store, (ir, props)).await?;
+    /// What stopped a call, in terms of the limits
+    fn reason(&self, error: &anyhow::Error) -> String {
+        match error.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => format!("ran out of fuel ({} units)", self.limits.fuel),
+            Some(Trap::Interrupt) => format!("timed out after {}ms", self.limits.timeout_ms),
+            _ if format!("{:#}", error).contains("memory growth failure") => {
+                format!("exceeded {} MiB of memory", self.limits.memory_mb)
+            }
+            _ => format!("{:#}", error),
+        }
+    }
+    
+    /// Languages an extractor handles
+    pub async fn languages(&self, extractor: &Extractor) -> Result<Vec<String>> {
+        self.call(extractor, "languages", |instance, store| {
+            Box::pin(instance.pure_lambda_extractor_extract().call_languages(store))
+        }).await
+    }
+    
+    /// Execute function-level extraction
+    ///
+    /// An extractor that rejects the file fails it with its diagnostics;
+    /// diagnostics of a file it did extract are logged.
+    pub async fn extract_functions(&self, extractor: &Extractor, path: &str, code: &str) -> Result<Vec<ExtractedFunction>> {
+        let (path, code) = (path.to_string(), code.to_string());
+        let extraction = self.call(extractor, "extract", |instance, store| {
+            Box::pin(async move { instance.pure_lambda_extractor_extract().call_extract(store, &path, &code).await })
+        }).await?;
         
-        Ok(proof)
+        let extraction = match extraction {
+            Ok(extraction) => extraction,
+            Err(diagnostics) => anyhow::bail!("{} rejected the file: {}", extractor.name, describe(&diagnostics)),
+        };
+        for diagnostic in &extraction.diagnostics {
+            match diagnostic.severity {
+                Severity::Error | Severity::Warning => tracing::warn!("{}: {}", extractor.name, describe(std::slice::from_ref(diagnostic))),
+                Severity::Info => tracing::debug!("{}: {}", extractor.name, describe(std::slice::from_ref(diagnostic))),
+            }
+        }
+        extraction.functions.into_iter().map(ExtractedFunction::lift).collect()
+    }
+}
+
+/// A call into a fresh instance - boxed, since it borrows the instance and
+/// its store
+type Call<'a, R> = Pin<Box<dyn Future<Output = Result<R>> + Send + 'a>>;
+
+/// Diagnostics as one line, with lines where given
+fn describe(diagnostics: &[wit::Diagnostic]) -> String {
+    diagnostics.iter()
+        .map(|d| match &d.span {
+            Some(span) => format!("line {}: {}", span.start_line, d.message),
+            None => d.message.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Surgeon IR from the node arena - every child must come before its parent,
+/// so the tree is finite
+fn lift(ir: &wit::Ir) -> Result<IR> {
+    let mut lifted: Vec<IR> = Vec::with_capacity(ir.nodes.len());
+    for (i, node) in ir.nodes.iter().enumerate() {
+        let at = |id: u32| -> Result<Box<IR>> {
+            lifted.get(id as usize)
+                .map(|child| Box::new(child.clone()))
+                .with_context(|| format!("node {} refers to node {}, which doesn't precede it", i, id))
+        };
+        let node = match node {
+            Node::Var(x) => IR::Var(x.clone()),
+            Node::Lam((x, body)) => IR::Lam(x.clone(), at(*body)?),
+            Node::App((f, x)) => IR::App(at(*f)?, at(*x)?),
+            Node::Map((xs, f)) => IR::Map(at(*xs)?, at(*f)?),
+            Node::Filter((xs, p)) => IR::Filter(at(*xs)?, at(*p)?),
+            Node::Reduce((xs, f, init)) => IR::Reduce(at(*xs)?, at(*f)?, at(*init)?),
+            Node::Nil => IR::Nil,
+            Node::Cons((h, t)) => IR::Cons(at(*h)?, at(*t)?),
+            Node::Num(n) => IR::Num(*n),
+            Node::Boolean(b) => IR::Bool(*b),
+            Node::Text(s) => IR::Str(s.clone()),
+            Node::Cond((c, t, e)) => IR::If(at(*c)?, at(*t)?, at(*e)?),
+            Node::Add((a, b)) => IR::Add(at(*a)?, at(*b)?),
+            Node::Sub((a, b)) => IR::Sub(at(*a)?, at(*b)?),
+            Node::Mul((a, b)) => IR::Mul(at(*a)?, at(*b)?),
+            Node::Div((a, b)) => IR::Div(at(*a)?, at(*b)?),
+            Node::Modulo((a, b)) => IR::Mod(at(*a)?, at(*b)?),
+            Node::Eq((a, b)) => IR::Eq(at(*a)?, at(*b)?),
+            Node::Lt((a, b)) => IR::Lt(at(*a)?, at(*b)?),
+            Node::Le((a, b)) => IR::Le(at(*a)?, at(*b)?),
+            Node::Compose((f, g)) => IR::Compose(at(*f)?, at(*g)?),
+            Node::Pipe((f, g)) => IR::Pipe(at(*f)?, at(*g)?),
+            Node::Id => IR::Id,
+            Node::Constant(x) => IR::Const(at(*x)?),
+            Node::Focus((xs, w, f, g)) => IR::Focus(at(*xs)?, at(*w)?, at(*f)?, at(*g)?),
+            Node::Drop => IR::Drop,
+            Node::Opaque(reason) => IR::Opaque(reason.clone()),
+        };
+        lifted.push(node);
+    }
+    lifted.get(ir.root as usize).cloned().with_context(|| format!("root {} is not a node", ir.root))
+}
+
+fn effect(effect: wit::Effect) -> Effect {
+    match effect {
+        wit::Effect::Io => Effect::Io,
+        wit::Effect::LocalMutation => Effect::Mutation(Scope::Local),
+        wit::Effect::EscapingMutation => Effect::Mutation(Scope::Escaping),
+        wit::Effect::Randomness => Effect::Randomness,
+        wit::Effect::Time => Effect::Time,
+        wit::Effect::Exception => Effect::Exception,
+        wit::Effect::Nontermination => Effect::Nontermination,
+    }
+}
+
+/// Stdio of one call - what the guest prints never reaches ours
+struct Output {
+    stdout: MemoryOutputPipe,
+    stderr: MemoryOutputPipe,
+}
+
+impl Output {
+    fn log(&self, extractor: &str) {
+        for (stream, pipe) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            let contents = pipe.contents();
+            if !contents.is_empty() {
+                tracing::debug!("{} {}: {}", extractor, stream, String::from_utf8_lossy(&contents));
+            }
+        }
+    }
+
+    /// Last line the guest wrote to stderr, as a suffix for errors
+    fn stderr_tail(&self) -> String {
+        let contents = self.stderr.contents();
+        let stderr = String::from_utf8_lossy(&contents);
+        match stderr.lines().rev().find(|line| !line.trim().is_empty()) {
+            Some(line) => format!(" (stderr: {})", line.trim()),
+            None => String::new(),
+        }
+    }
+}
+
+/// One function found in a source file - each becomes its own gene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedFunction {
+    /// Exported name
+    pub name: String,
+    pub ir: String,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// `None` when the extractor doesn't analyze effects - digest falls back to the IR
+    #[serde(default)]
+    pub effects: Option<Effects>,
+}
+
+impl ExtractedFunction {
+    /// Function as an extractor component returned it
+    fn lift(function: wit::Function) -> Result<Self> {
+        let ir = lift(&function.ir).with_context(|| format!("malformed IR for {}", function.name))?;
+        Ok(ExtractedFunction {
+            name: function.name,
+            ir: crate::extract::encode(&ir)?,
+            start_line: function.span.start_line as usize,
+            end_line: function.span.end_line as usize,
+            effects: function.effects.map(|effects| effects.into_iter().map(effect).collect()),
+        })
+    }
+
+    /// Source text of the function
+    pub fn code(&self, file: &str) -> String {
+        let start = self.start_line.max(1);
+        file.lines()
+            .skip(start - 1)
+            .take((self.end_line + 1).saturating_sub(start))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 }
 
 pub struct Extractor {
-    store: Store<WasmState>,
-    instance: Instance,
+    pre: bindings::ExtractorPre<WasmState>,
+    /// Component path, for errors
+    name: String,
 }
 
 /// Load and execute λ-IR extractors for different languages
 pub struct ExtractorSet {
     runtime: Runtime,
-    extractors: dashmap::DashMap<String, Extractor>,
+    extractors: dashmap::DashMap<String, Arc<Extractor>>,
 }
 
 impl ExtractorSet {
-    pub async fn new() -> Result<Self> {
-        let runtime = Runtime::new()?;
+    pub async fn new(limits: SandboxLimits) -> Result<Self> {
+        let runtime = Runtime::new(limits)?;
         let extractors = dashmap::DashMap::new();
         
-        // Load default extractors
-        for (lang, path) in &[
-            ("typescript", "extractors/typescript.wasm"),
-            ("javascript", "extractors/javascript.wasm"),
-            ("python", "extractors/python.wasm"),
-            ("rust", "extractors/rust.wasm"),
-        ] {
-            if Path::new(path).exists() {
-                let extractor = runtime.load_extractor(Path::new(path)).await?;
-                extractors.insert(lang.to_string(), extractor);
+        // Installed extractors, for the languages they report
+        let installed = std::fs::read_dir("extractors").into_iter().flatten().filter_map(|e| e.ok());
+        for entry in installed {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("wasm") {
+                continue;
+            }
+            let extractor = Arc::new(runtime.load_extractor(&path)?);
+            match runtime.languages(&extractor).await {
+                Ok(langs) => for lang in langs {
+                    extractors.insert(lang, extractor.clone());
+                },
+                Err(e) => tracing::warn!("Skipping extractor: {:#}", e),
             }
         }
         
         Ok(ExtractorSet { runtime, extractors })
     }
     
-    /// Extract IR from source file
-    pub async fn extract(&self, file_path: &str, code: &str) -> Result<String> {
+    /// Extract the functions of a source file
+    pub async fn extract(&self, file_path: &str, code: &str) -> Result<Vec<ExtractedFunction>> {
         // Detect language from extension
         let lang = detect_language(file_path);
         
-        // Get appropriate extractor
-        let mut extractor = self.extractors.get_mut(&lang)
-            .context(format!("No extractor for language: {}", lang))?;
-        
-        // Extract IR
-        Runtime::extract_ir(&mut extractor, code).await
-    }
-    
-    /// Verify gene properties
-    pub async fn verify_gene(&self, ir: &str, props: Vec<String>) -> Result<String> {
-        // Use generic verifier
-        if let Some(mut verifier) = self.extractors.get_mut("verifier") {
-            Runtime::verify(&mut verifier, ir, &props).await
-        } else {
-            // Fallback to basic verification
-            Ok(serde_json::json!({
-                "verified": true,
-                "properties": props,
-                "timestamp": chrono::Utc::now().timestamp()
-            }).to_string())
+        // An installed extractor wins over the built-in subset
+        if let Some(extractor) = self.extractors.get(&lang) {
+            return self.runtime.extract_functions(&extractor, file_path, code).await;
         }
+        crate::extract::native(&lang, code)
+            .context(format!("No extractor for language: {}", lang))?
     }
 }
 
@@ -136,13 +423,79 @@ fn detect_language(path: &str) -> String {
         Some("py") => "python".to_string(),
         Some("rs") => "rust".to_string(),
         Some("go") => "go".to_string(),
+        Some("lambda") => "lambda".to_string(),
         _ => "unknown".to_string(),
     }
 }
 
-/// Compile λ-IR to WASM module
-pub async fn compile_to_wasm(ir: &str) -> Result<Vec<u8>> {
-    // This would call the lambda-wasm compiler
-    // For now, return empty module
-    Ok(vec![])
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Built by `cargo build --release --target wasm32-wasip2` in extractors/reference
+    const REFERENCE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../extractors/reference/target/wasm32-wasip2/release/reference_extractor.wasm"
+    );
+
+    #[test]
+    fn test_lift_node_arena() {
+        // λx. x + 1
+        let ir = wit::Ir {
+            nodes: vec![Node::Var("x".to_string()), Node::Num(1), Node::Add((0, 1)), Node::Lam(("x".to_string(), 2))],
+            root: 3,
+        };
+        let expected = IR::Lam("x".to_string(), Box::new(IR::Add(Box::new(IR::Var("x".to_string())), Box::new(IR::Num(1)))));
+        assert_eq!(lift(&ir).unwrap(), expected);
+
+        // A child after its parent could be a cycle
+        let cyclic = wit::Ir { nodes: vec![Node::Constant(1), Node::Nil], root: 0 };
+        assert!(lift(&cyclic).is_err());
+    }
+
+    /// The reference extractor, sandboxed within `limits`
+    fn reference(limits: SandboxLimits) -> (Runtime, Extractor) {
+        assert!(Path::new(REFERENCE).exists(), "{} not built", REFERENCE);
+        let runtime = Runtime::new(limits).unwrap();
+        let extractor = runtime.load_extractor(Path::new(REFERENCE)).unwrap();
+        (runtime, extractor)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs extractors/reference built for wasm32-wasip2"]
+    async fn test_reference_extractor() {
+        let (runtime, extractor) = reference(SandboxLimits::default());
+        assert_eq!(runtime.languages(&extractor).await.unwrap(), vec!["lambda".to_string()]);
+
+        let code = "; fixture\ninc = λx.(+ x 1)\nbroken = (map xs\nall = λxs.(map xs λy.(+ y 1))\n";
+        let functions = runtime.extract_functions(&extractor, "fixture.lambda", code).await.unwrap();
+        assert_eq!(functions.iter().map(|f| (f.name.as_str(), f.start_line)).collect::<Vec<_>>(), vec![("inc", 2), ("all", 4)]);
+
+        // Same soul as the JavaScript it was written from
+        let js = crate::extract::js::extract("export const inc = n => n + 1;").unwrap();
+        assert_eq!(crate::digest::compute_soul(&functions[0].ir), crate::digest::compute_soul(&js[0].ir));
+
+        // Nothing but errors fails the file, with the guest's reasons
+        let err = runtime.extract_functions(&extractor, "bad.lambda", "x = (").await.unwrap_err();
+        assert!(format!("{:#}", err).contains("line 1"));
+
+        let (starved, extractor) = reference(SandboxLimits { fuel: 1_000, ..Default::default() });
+        let err = starved.extract_functions(&extractor, "fixture.lambda", code).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("ran out of fuel"));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs extractors/reference built for wasm32-wasip2"]
+    async fn test_memory_and_time_limits() {
+        // Far more source than fits in 4 MiB, or parses within one tick
+        let code = "inc = λx.(+ x 1)\n".repeat(2_000_000);
+
+        let (runtime, extractor) = reference(SandboxLimits { memory_mb: 4, ..Default::default() });
+        let err = runtime.extract_functions(&extractor, "big.lambda", &code).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("exceeded 4 MiB of memory"), "{:#}", err);
+
+        let (runtime, extractor) = reference(SandboxLimits { timeout_ms: 1, ..Default::default() });
+        let err = runtime.extract_functions(&extractor, "big.lambda", &code).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("timed out after 1ms"), "{:#}", err);
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use super::{compute_cid, format_size, gene_object, typed_roots, sort_impacts, FileDigest, GeneStore, Gene, Object, ObjectMeta, ObjectType, RuleImpactStats, Status};
+use crate::surgeon::impact::RuleImpact;
+
+/// Embedded store - sled trees for objects and every index, no database setup
+pub struct SledStore {
+    db: sled::Db,
+    cas: sled::Tree,
+    genes: sled::Tree,         // soul → cid
+    equivalences: sled::Tree,  // soul1 \0 soul2 → confidence
+    champions: sled::Tree,     // canonical → (soul, score)
+    impacts: sled::Tree,       // rule → running sums
+    pins: sled::Tree,          // cid → ()
+    file_digests: sled::Tree,  // source \0 path → FileDigest
+    tombstones: sled::Tree,    // soul → ()
+    digested: sled::Tree,      // cid → ()
+}
+
+impl SledStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let root = PathBuf::from(path);
+        std::fs::create_dir_all(&root)?;
+        let db = sled::open(root.join("sled"))?;
+
+        Ok(SledStore {
+            cas: db.open_tree("cas")?,
+            genes: db.open_tree("genes")?,
+            equivalences: db.open_tree("equivalences")?,
+            champions: db.open_tree("champions")?,
+            impacts: db.open_tree("rule_impacts")?,
+            pins: db.open_tree("pins")?,
+            file_digests: db.open_tree("file_digests")?,
+            tombstones: db.open_tree("tombstones")?,
+            digested: db.open_tree("digested_sources")?,
+            db,
+        })
+    }
+
+    /// `a \0 b` - keeps "λa"/"λab" style prefixes apart in prefix scans
+    fn pair_key(a: &str, b: &str) -> Vec<u8> {
+        [a.as_bytes(), &[0], b.as_bytes()].concat()
+    }
+
+    fn all_file_digests(&self) -> Result<Vec<FileDigest>> {
+        let mut digests = Vec::new();
+        for item in self.file_digests.iter() {
+            let (_, data) = item?;
+            digests.push(bincode::deserialize(&data)?);
+        }
+        Ok(digests)
+    }
+
+    /// Tombstone the souls no recorded file yields any more
+    fn release(&self, souls: Vec<String>) -> Result<Vec<String>> {
+        let yielded: BTreeSet<String> = self.all_file_digests()?
+            .into_iter()
+            .flat_map(|d| d.souls)
+            .collect();
+
+        let mut tombstoned = Vec::new();
+        for soul in souls.into_iter().filter(|soul| !yielded.contains(soul)) {
+            self.genes.remove(soul.as_bytes())?;
+            for item in self.equivalences.iter() {
+                let (key, _) = item?;
+                if key.split(|&b| b == 0).any(|s| s == soul.as_bytes()) {
+                    self.equivalences.remove(key)?;
+                }
+            }
+            self.tombstones.insert(soul.as_bytes(), &[])?;
+            tombstoned.push(soul);
+        }
+        Ok(tombstoned)
+    }
+
+    fn champion_souls(&self) -> Result<Vec<String>> {
+        let mut souls = Vec::new();
+        for item in self.champions.iter() {
+            let (_, data) = item?;
+            let (soul, _): (String, f32) = bincode::deserialize(&data)?;
+            souls.push(soul);
+        }
+        Ok(souls)
+    }
+
+    fn objects_of(&self, typ: ObjectType) -> Result<usize> {
+        let mut count = 0;
+        for item in self.cas.iter() {
+            let (_, data) = item?;
+            let obj: Object = bincode::deserialize(&data)?;
+            if obj.metadata.typ == typ {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl GeneStore for SledStore {
+    async fn put(&self, data: &[u8], meta: ObjectMeta) -> Result<String> {
+        let cid = compute_cid(data);
+        if !self.cas.contains_key(cid.as_bytes())? {
+            let obj = Object {
+                cid: cid.clone(),
+                data: data.to_vec(),
+                metadata: meta,
+            };
+            self.cas.insert(cid.as_bytes(), bincode::serialize(&obj)?)?;
+        }
+        Ok(cid)
+    }
+
+    async fn get(&self, cid: &str) -> Result<Option<Object>> {
+        match self.cas.get(cid.as_bytes())? {
+            Some(data) => Ok(Some(bincode::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_meta(&self, cid: &str, meta: ObjectMeta) -> Result<()> {
+        if let Some(data) = self.cas.get(cid.as_bytes())? {
+            let mut obj: Object = bincode::deserialize(&data)?;
+            obj.metadata = meta;
+            self.cas.insert(cid.as_bytes(), bincode::serialize(&obj)?)?;
+        }
+        Ok(())
+    }
+
+    async fn put_gene(&self, gene: &Gene) -> Result<()> {
+        let (data, meta) = gene_object(gene)?;
+        let cid = self.put(&data, meta).await?;
+        self.tombstones.remove(gene.soul.as_bytes())?;
+        self.genes.insert(gene.soul.as_bytes(), cid.as_bytes())?;
+        Ok(())
+    }
+
+    async fn get_gene(&self, soul: &str) -> Result<Option<Gene>> {
+        let cid = match self.genes.get(soul.as_bytes())? {
+            Some(cid) => String::from_utf8(cid.to_vec())?,
+            None => return Ok(None),
+        };
+        match self.get(&cid).await? {
+            Some(obj) => Ok(Some(serde_json::from_slice(&obj.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_equivalents(&self, soul: &str) -> Result<Vec<String>> {
+        if !self.genes.contains_key(soul.as_bytes())? {
+            return Ok(vec![]);
+        }
+        let mut souls = BTreeSet::new();
+        for item in self.equivalences.scan_prefix(Self::pair_key(soul, "")) {
+            let (key, _) = item?;
+            let soul2 = &key[soul.len() + 1..];
+            if self.genes.contains_key(soul2)? {
+                souls.insert(String::from_utf8(soul2.to_vec())?);
+            }
+        }
+        Ok(souls.into_iter().collect())
+    }
+
+    async fn add_equivalence(&self, soul1: &str, soul2: &str, confidence: f32) -> Result<()> {
+        self.equivalences.insert(Self::pair_key(soul1, soul2), &confidence.to_le_bytes())?;
+        Ok(())
+    }
+
+    async fn add_champion(&self, canonical: &str, soul: &str, score: f32) -> Result<()> {
+        self.champions.insert(canonical.as_bytes(), bincode::serialize(&(soul, score))?)?;
+        Ok(())
+    }
+
+    async fn record_rule_impact(&self, impact: &RuleImpact) -> Result<()> {
+        let stats = match self.impacts.get(impact.rule.as_bytes())? {
+            Some(data) => {
+                let mut stats: RuleImpactStats = bincode::deserialize(&data)?;
+                stats.accumulate(impact);
+                stats
+            }
+            None => RuleImpactStats::sums(impact),
+        };
+        self.impacts.insert(impact.rule.as_bytes(), bincode::serialize(&stats)?)?;
+        Ok(())
+    }
+
+    async fn rule_impacts(&self) -> Result<Vec<RuleImpactStats>> {
+        let mut impacts = Vec::new();
+        for item in self.impacts.iter() {
+            let (_, data) = item?;
+            impacts.push(bincode::deserialize::<RuleImpactStats>(&data)?.mean());
+        }
+        sort_impacts(&mut impacts);
+        Ok(impacts)
+    }
+
+    async fn pin(&self, cid: &str) -> Result<()> {
+        self.pins.insert(cid.as_bytes(), &[])?;
+        Ok(())
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        self.pins.remove(cid.as_bytes())?;
+        Ok(())
+    }
+
+    async fn roots(&self) -> Result<Vec<String>> {
+        let mut objects = Vec::new();
+        for item in self.cas.iter() {
+            let (_, data) = item?;
+            let obj: Object = bincode::deserialize(&data)?;
+            objects.push((obj.cid, obj.metadata));
+        }
+        let mut roots = typed_roots(objects);
+        for soul in self.champion_souls()? {
+            if let Some(cid) = self.genes.get(soul.as_bytes())? {
+                roots.push(String::from_utf8(cid.to_vec())?);
+            }
+        }
+        for item in self.pins.iter() {
+            let (cid, _) = item?;
+            roots.push(String::from_utf8(cid.to_vec())?);
+        }
+        Ok(roots)
+    }
+
+    async fn objects(&self) -> Result<Vec<(String, usize)>> {
+        let mut objects = Vec::new();
+        for item in self.cas.iter() {
+            let (cid, data) = item?;
+            objects.push((String::from_utf8(cid.to_vec())?, data.len()));
+        }
+        Ok(objects)
+    }
+
+    async fn gene_cids(&self) -> Result<Vec<(String, String)>> {
+        let mut genes = Vec::new();
+        for item in self.genes.iter() {
+            let (soul, cid) = item?;
+            genes.push((String::from_utf8(soul.to_vec())?, String::from_utf8(cid.to_vec())?));
+        }
+        Ok(genes)
+    }
+
+    async fn file_digests(&self, source: &str) -> Result<Vec<FileDigest>> {
+        let mut digests = Vec::new();
+        for item in self.file_digests.scan_prefix(Self::pair_key(source, "")) {
+            let (_, data) = item?;
+            digests.push(bincode::deserialize(&data)?);
+        }
+        Ok(digests)
+    }
+
+    async fn put_file_digest(&self, digest: &FileDigest) -> Result<Vec<String>> {
+        let key = Self::pair_key(&digest.source, &digest.path);
+        let dropped = match self.file_digests.insert(key, bincode::serialize(digest)?)? {
+            Some(data) => bincode::deserialize::<FileDigest>(&data)?.souls
+                .into_iter()
+                .filter(|soul| !digest.souls.contains(soul))
+                .collect(),
+            None => vec![],
+        };
+        self.release(dropped)
+    }
+
+    async fn tombstone(&self, source: &str, path: &str) -> Result<Vec<String>> {
+        match self.file_digests.remove(Self::pair_key(source, path))? {
+            Some(data) => self.release(bincode::deserialize::<FileDigest>(&data)?.souls),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn undigested_sources(&self) -> Result<Vec<String>> {
+        let mut sources = Vec::new();
+        for item in self.cas.iter() {
+            let (cid, data) = item?;
+            let obj: Object = bincode::deserialize(&data)?;
+            if obj.metadata.typ == ObjectType::Source && !self.digested.contains_key(&cid)? {
+                sources.push(obj.cid);
+            }
+        }
+        Ok(sources)
+    }
+
+    async fn mark_digested(&self, cid: &str) -> Result<()> {
+        self.digested.insert(cid.as_bytes(), &[])?;
+        Ok(())
+    }
+
+    async fn remove(&self, cids: &[String]) -> Result<u64> {
+        let mut freed = 0;
+        for cid in cids {
+            if let Some(data) = self.cas.remove(cid.as_bytes())? {
+                freed += data.len() as u64;
+            }
+            self.digested.remove(cid.as_bytes())?;
+        }
+
+        // Drop index entries for genes whose object is gone
+        let mut swept = Vec::new();
+        for (soul, cid) in self.gene_cids().await? {
+            if !self.cas.contains_key(cid.as_bytes())? {
+                self.genes.remove(soul.as_bytes())?;
+                swept.push(soul);
+            }
+        }
+        for item in self.equivalences.iter() {
+            let (key, _) = item?;
+            let mut souls = key.split(|&b| b == 0);
+            let touches = souls.any(|soul| swept.iter().any(|s| s.as_bytes() == soul));
+            if touches {
+                self.equivalences.remove(key)?;
+            }
+        }
+        // Files whose gene is gone get extracted again next digest
+        for digest in self.all_file_digests()? {
+            if digest.souls.iter().any(|soul| swept.contains(soul)) {
+                self.file_digests.remove(Self::pair_key(&digest.source, &digest.path))?;
+            }
+        }
+
+        self.db.flush_async().await?;
+        Ok(freed)
+    }
+
+    async fn status(&self) -> Result<Status> {
+        let mut classes = BTreeSet::new();
+        for item in self.equivalences.iter() {
+            let (key, _) = item?;
+            let soul1 = key.split(|&b| b == 0).next().unwrap_or(&[]).to_vec();
+            classes.insert(soul1);
+        }
+        let mut merged = BTreeSet::new();
+        for (soul, _) in self.gene_cids().await? {
+            merged.extend(self.find_equivalents(&soul).await?);
+        }
+        let mut champions = 0;
+        for soul in self.champion_souls()? {
+            if self.genes.contains_key(soul.as_bytes())? {
+                champions += 1;
+            }
+        }
+
+        Ok(Status {
+            sources: self.objects_of(ObjectType::Source)?,
+            total_genes: self.genes.len(),
+            unique_souls: self.genes.len() - merged.len(),
+            equiv_classes: classes.len(),
+            champions,
+            organisms: self.objects_of(ObjectType::Organism)?,
+            storage_size: format_size(self.db.size_on_disk()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::GeneMetrics;
+
+    #[tokio::test]
+    async fn test_reopen_keeps_genes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let gene = Gene {
+            soul: "λa".to_string(),
+            name: "filter".to_string(),
+            ir: "(filter xs p)".to_string(),
+            source: "npm:ramda".to_string(),
+            span: None,
+            signatures: vec![],
+            metrics: GeneMetrics::default(),
+            effects: Default::default(),
+        };
+
+        {
+            let store = SledStore::open(path).unwrap();
+            store.put_gene(&gene).await.unwrap();
+            store.put_gene(&Gene { soul: "λab".to_string(), ..gene.clone() }).await.unwrap();
+            store.add_equivalence("λa", "λab", 1.0).await.unwrap();
+        }
+
+        // sled's flusher thread lets go of the file lock a moment after drop
+        let store = (0..100)
+            .find_map(|_| SledStore::open(path).ok().or_else(|| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                None
+            }))
+            .unwrap();
+        assert_eq!(store.get_gene("λa").await.unwrap().unwrap().ir, "(filter xs p)");
+        // "λa" is a byte prefix of "λab" - the separator keeps their keys apart
+        assert_eq!(store.find_equivalents("λa").await.unwrap(), vec!["λab"]);
+        assert!(store.find_equivalents("λab").await.unwrap().is_empty());
+        assert_eq!(store.status().await.unwrap().total_genes, 2);
+    }
+}
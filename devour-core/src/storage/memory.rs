@@ -0,0 +1,318 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use super::{compute_cid, format_size, gene_object, typed_roots, sort_impacts, FileDigest, GeneStore, Gene, Object, ObjectMeta, ObjectType, RuleImpactStats, Status};
+use crate::surgeon::impact::RuleImpact;
+
+/// Everything in process memory - for tests and dry runs
+#[derive(Default)]
+pub struct MemoryStore {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    objects: HashMap<String, Object>,
+    genes: HashMap<String, String>,                  // soul → cid
+    equivalences: BTreeMap<(String, String), f32>,
+    champions: BTreeMap<String, (String, f32)>,      // canonical → (soul, score)
+    impacts: BTreeMap<String, RuleImpactStats>,      // Running sums
+    pins: BTreeSet<String>,
+    file_digests: BTreeMap<(String, String), FileDigest>,  // (source, path)
+    tombstones: BTreeSet<String>,
+    digested: BTreeSet<String>,
+}
+
+impl Inner {
+    /// Stored souls recorded as equivalent to the stored `soul`
+    fn equivalents(&self, soul: &str) -> BTreeSet<&String> {
+        if !self.genes.contains_key(soul) {
+            return BTreeSet::new();
+        }
+        self.equivalences.keys()
+            .filter(|(soul1, soul2)| soul1 == soul && self.genes.contains_key(soul2))
+            .map(|(_, soul2)| soul2)
+            .collect()
+    }
+
+    /// Tombstone the souls no recorded file yields any more
+    fn release(&mut self, souls: Vec<String>) -> Vec<String> {
+        let mut tombstoned = Vec::new();
+        for soul in souls {
+            if self.file_digests.values().any(|d| d.souls.contains(&soul)) {
+                continue;
+            }
+            self.genes.remove(&soul);
+            self.equivalences.retain(|(soul1, soul2), _| *soul1 != soul && *soul2 != soul);
+            self.tombstones.insert(soul.clone());
+            tombstoned.push(soul);
+        }
+        tombstoned
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl GeneStore for MemoryStore {
+    async fn put(&self, data: &[u8], meta: ObjectMeta) -> Result<String> {
+        let cid = compute_cid(data);
+        let mut inner = self.inner.lock().unwrap();
+        inner.objects.entry(cid.clone()).or_insert_with(|| Object {
+            cid: cid.clone(),
+            data: data.to_vec(),
+            metadata: meta,
+        });
+        Ok(cid)
+    }
+
+    async fn get(&self, cid: &str) -> Result<Option<Object>> {
+        Ok(self.inner.lock().unwrap().objects.get(cid).cloned())
+    }
+
+    async fn set_meta(&self, cid: &str, meta: ObjectMeta) -> Result<()> {
+        if let Some(obj) = self.inner.lock().unwrap().objects.get_mut(cid) {
+            obj.metadata = meta;
+        }
+        Ok(())
+    }
+
+    async fn put_gene(&self, gene: &Gene) -> Result<()> {
+        let (data, meta) = gene_object(gene)?;
+        let cid = self.put(&data, meta).await?;
+        let mut inner = self.inner.lock().unwrap();
+        inner.tombstones.remove(&gene.soul);
+        inner.genes.insert(gene.soul.clone(), cid);
+        Ok(())
+    }
+
+    async fn get_gene(&self, soul: &str) -> Result<Option<Gene>> {
+        let inner = self.inner.lock().unwrap();
+        match inner.genes.get(soul).and_then(|cid| inner.objects.get(cid)) {
+            Some(obj) => Ok(Some(serde_json::from_slice(&obj.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_equivalents(&self, soul: &str) -> Result<Vec<String>> {
+        Ok(self.inner.lock().unwrap().equivalents(soul).into_iter().cloned().collect())
+    }
+
+    async fn add_equivalence(&self, soul1: &str, soul2: &str, confidence: f32) -> Result<()> {
+        self.inner.lock().unwrap()
+            .equivalences
+            .insert((soul1.to_string(), soul2.to_string()), confidence);
+        Ok(())
+    }
+
+    async fn add_champion(&self, canonical: &str, soul: &str, score: f32) -> Result<()> {
+        self.inner.lock().unwrap()
+            .champions
+            .insert(canonical.to_string(), (soul.to_string(), score));
+        Ok(())
+    }
+
+    async fn record_rule_impact(&self, impact: &RuleImpact) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.impacts.get_mut(&impact.rule) {
+            Some(stats) => stats.accumulate(impact),
+            None => {
+                inner.impacts.insert(impact.rule.clone(), RuleImpactStats::sums(impact));
+            }
+        }
+        Ok(())
+    }
+
+    async fn rule_impacts(&self) -> Result<Vec<RuleImpactStats>> {
+        let mut impacts: Vec<RuleImpactStats> = self.inner.lock().unwrap()
+            .impacts.values()
+            .cloned()
+            .map(RuleImpactStats::mean)
+            .collect();
+        sort_impacts(&mut impacts);
+        Ok(impacts)
+    }
+
+    async fn pin(&self, cid: &str) -> Result<()> {
+        self.inner.lock().unwrap().pins.insert(cid.to_string());
+        Ok(())
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        self.inner.lock().unwrap().pins.remove(cid);
+        Ok(())
+    }
+
+    async fn roots(&self) -> Result<Vec<String>> {
+        let inner = self.inner.lock().unwrap();
+        let typed = typed_roots(inner.objects.values().map(|o| (o.cid.clone(), o.metadata.clone())));
+        let champions = inner.champions.values().filter_map(|(soul, _)| inner.genes.get(soul)).cloned();
+        Ok(typed.into_iter().chain(champions).chain(inner.pins.iter().cloned()).collect())
+    }
+
+    async fn objects(&self) -> Result<Vec<(String, usize)>> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.objects.values().map(|o| (o.cid.clone(), o.data.len())).collect())
+    }
+
+    async fn gene_cids(&self) -> Result<Vec<(String, String)>> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.genes.iter().map(|(soul, cid)| (soul.clone(), cid.clone())).collect())
+    }
+
+    async fn file_digests(&self, source: &str) -> Result<Vec<FileDigest>> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.file_digests.values().filter(|d| d.source == source).cloned().collect())
+    }
+
+    async fn put_file_digest(&self, digest: &FileDigest) -> Result<Vec<String>> {
+        let mut inner = self.inner.lock().unwrap();
+        let key = (digest.source.clone(), digest.path.clone());
+        let old = inner.file_digests.insert(key, digest.clone());
+        let dropped: Vec<String> = old.into_iter()
+            .flat_map(|d| d.souls)
+            .filter(|soul| !digest.souls.contains(soul))
+            .collect();
+        Ok(inner.release(dropped))
+    }
+
+    async fn tombstone(&self, source: &str, path: &str) -> Result<Vec<String>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.file_digests.remove(&(source.to_string(), path.to_string())) {
+            Some(gone) => Ok(inner.release(gone.souls)),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn undigested_sources(&self) -> Result<Vec<String>> {
+        let inner = self.inner.lock().unwrap();
+        let mut sources: Vec<String> = inner.objects.values()
+            .filter(|o| o.metadata.typ == ObjectType::Source && !inner.digested.contains(&o.cid))
+            .map(|o| o.cid.clone())
+            .collect();
+        sources.sort();
+        Ok(sources)
+    }
+
+    async fn mark_digested(&self, cid: &str) -> Result<()> {
+        self.inner.lock().unwrap().digested.insert(cid.to_string());
+        Ok(())
+    }
+
+    async fn remove(&self, cids: &[String]) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut freed = 0;
+        for cid in cids {
+            if let Some(obj) = inner.objects.remove(cid) {
+                freed += obj.data.len() as u64;
+            }
+        }
+        let Inner { objects, genes, equivalences, file_digests, digested, .. } = &mut *inner;
+        let swept: BTreeSet<String> = genes.iter()
+            .filter(|(_, cid)| !objects.contains_key(*cid))
+            .map(|(soul, _)| soul.clone())
+            .collect();
+        genes.retain(|soul, _| !swept.contains(soul));
+        equivalences.retain(|(soul1, soul2), _| !swept.contains(soul1) && !swept.contains(soul2));
+        // Files whose gene is gone get extracted again next digest
+        file_digests.retain(|_, d| !d.souls.iter().any(|soul| swept.contains(soul)));
+        digested.retain(|cid| objects.contains_key(cid));
+        Ok(freed)
+    }
+
+    async fn status(&self) -> Result<Status> {
+        let inner = self.inner.lock().unwrap();
+        let count = |typ| inner.objects.values().filter(|o| o.metadata.typ == typ).count();
+        let size: usize = inner.objects.values().map(|o| o.data.len()).sum();
+        let merged: BTreeSet<&String> = inner.genes.keys().flat_map(|soul| inner.equivalents(soul)).collect();
+
+        Ok(Status {
+            sources: count(ObjectType::Source),
+            total_genes: inner.genes.len(),
+            unique_souls: inner.genes.len() - merged.len(),
+            equiv_classes: inner.equivalences.keys().map(|(soul1, _)| soul1).collect::<BTreeSet<_>>().len(),
+            champions: inner.champions.values().filter(|(soul, _)| inner.genes.contains_key(soul)).count(),
+            organisms: count(ObjectType::Organism),
+            storage_size: format_size(size as u64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_gene as gene;
+    use lambda_core::distortion::Distortion;
+
+    #[tokio::test]
+    async fn test_genes_and_equivalences() {
+        let store = MemoryStore::new();
+        store.put_gene(&gene("λa")).await.unwrap();
+        store.put_gene(&gene("λb")).await.unwrap();
+        store.add_equivalence("λa", "λb", 0.9).await.unwrap();
+        store.add_equivalence("λa", "λmissing", 0.9).await.unwrap();
+
+        assert_eq!(store.get_gene("λa").await.unwrap().unwrap().soul, "λa");
+        assert!(store.get_gene("λc").await.unwrap().is_none());
+        assert_eq!(store.find_equivalents("λa").await.unwrap(), vec!["λb"]);
+
+        let status = store.status().await.unwrap();
+        assert_eq!(status.total_genes, 2);
+        assert_eq!(status.equiv_classes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_tombstones() {
+        let store = MemoryStore::new();
+        let file = |path: &str, souls: &[&str]| FileDigest {
+            source: "git:/src/fp".to_string(),
+            path: path.to_string(),
+            hash: "h".to_string(),
+            souls: souls.iter().map(|s| s.to_string()).collect(),
+        };
+        for soul in ["λa", "λb", "λc"] {
+            store.put_gene(&gene(soul)).await.unwrap();
+        }
+        store.put_file_digest(&file("map.js", &["λa"])).await.unwrap();
+        store.put_file_digest(&file("lib/map.js", &["λa", "λc"])).await.unwrap();
+        store.put_file_digest(&file("filter.js", &["λb"])).await.unwrap();
+        assert_eq!(store.file_digests("git:/src/fp").await.unwrap().len(), 3);
+
+        // λa is still yielded by lib/map.js
+        assert!(store.tombstone("git:/src/fp", "map.js").await.unwrap().is_empty());
+        assert!(store.get_gene("λa").await.unwrap().is_some());
+
+        assert_eq!(store.tombstone("git:/src/fp", "filter.js").await.unwrap(), vec!["λb"]);
+        assert!(store.get_gene("λb").await.unwrap().is_none());
+
+        // A function deleted from a file that is still there
+        assert_eq!(store.put_file_digest(&file("lib/map.js", &["λa"])).await.unwrap(), vec!["λc"]);
+        assert!(store.get_gene("λc").await.unwrap().is_none());
+        assert_eq!(store.file_digests("git:/src/fp").await.unwrap(), vec![file("lib/map.js", &["λa"])]);
+    }
+
+    #[tokio::test]
+    async fn test_rule_impact_means() {
+        let store = MemoryStore::new();
+        let impact = |redundancy| RuleImpact {
+            rule: "map_fusion".to_string(),
+            genes: 4,
+            rewritten: 1,
+            delta: Distortion { redundancy, ..Distortion::zero() },
+        };
+        store.record_rule_impact(&impact(0.2)).await.unwrap();
+        store.record_rule_impact(&impact(0.4)).await.unwrap();
+
+        let stats = store.rule_impacts().await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].runs, 2);
+        assert_eq!(stats[0].genes, 8);
+        assert!((stats[0].redundancy - 0.3).abs() < 1e-6);
+    }
+}
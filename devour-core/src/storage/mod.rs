@@ -0,0 +1,497 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use async_trait::async_trait;
+use blake3::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::effects::{Effects, GeneEffects};
+use crate::ingest::{self, lineage, LocalSource};
+use crate::surgeon::cost::Growth;
+use crate::surgeon::impact::RuleImpact;
+
+pub mod memory;
+pub mod embedded;
+pub mod sqlite;
+
+pub use memory::MemoryStore;
+pub use embedded::SledStore;
+pub use sqlite::SqliteStore;
+
+/// Content-addressable gene storage, whatever the backend
+#[async_trait]
+pub trait GeneStore: Send + Sync {
+    /// Store object and return CID
+    async fn put(&self, data: &[u8], meta: ObjectMeta) -> Result<String>;
+
+    /// Get object by CID
+    async fn get(&self, cid: &str) -> Result<Option<Object>>;
+
+    /// Replace a stored object's metadata - its bytes seen again from somewhere new
+    async fn set_meta(&self, cid: &str, meta: ObjectMeta) -> Result<()>;
+
+    /// Store gene
+    async fn put_gene(&self, gene: &Gene) -> Result<()>;
+
+    /// Get gene by soul
+    async fn get_gene(&self, soul: &str) -> Result<Option<Gene>>;
+
+    /// Souls recorded as equivalent to `soul` (both must be stored genes)
+    async fn find_equivalents(&self, soul: &str) -> Result<Vec<String>>;
+
+    /// Add equivalence
+    async fn add_equivalence(&self, soul1: &str, soul2: &str, confidence: f32) -> Result<()>;
+
+    /// Record `soul` as the champion of the class `canonical` names
+    async fn add_champion(&self, canonical: &str, soul: &str, score: f32) -> Result<()>;
+
+    /// Accumulate one measured rule impact
+    async fn record_rule_impact(&self, impact: &RuleImpact) -> Result<()>;
+
+    /// Per-rule impact means, most distortion removed first
+    async fn rule_impacts(&self) -> Result<Vec<RuleImpactStats>>;
+
+    /// Keep `cid` alive across gc
+    async fn pin(&self, cid: &str) -> Result<()>;
+
+    async fn unpin(&self, cid: &str) -> Result<()>;
+
+    /// GC roots: latest sources, organisms, champions' genes, attestations and pins
+    async fn roots(&self) -> Result<Vec<String>>;
+
+    /// Every stored object as (cid, size)
+    async fn objects(&self) -> Result<Vec<(String, usize)>>;
+
+    /// Gene index as (soul, cid)
+    async fn gene_cids(&self) -> Result<Vec<(String, String)>>;
+
+    /// Per-file digest state of one source
+    async fn file_digests(&self, source: &str) -> Result<Vec<FileDigest>>;
+
+    /// Record (or replace) what one file digested to. Genes the file no
+    /// longer yields are released as in `tombstone`.
+    async fn put_file_digest(&self, digest: &FileDigest) -> Result<Vec<String>>;
+
+    /// Forget a file that disappeared; its genes are tombstoned unless another
+    /// file still yields the same soul. Returns the tombstoned souls.
+    async fn tombstone(&self, source: &str, path: &str) -> Result<Vec<String>>;
+
+    /// Source objects never digested
+    async fn undigested_sources(&self) -> Result<Vec<String>>;
+
+    async fn mark_digested(&self, cid: &str) -> Result<()>;
+
+    /// Delete objects and every index row that points at them; returns bytes freed
+    async fn remove(&self, cids: &[String]) -> Result<u64>;
+
+    /// Get status
+    async fn status(&self) -> Result<Status>;
+}
+
+/// Any backend - pipeline stages take `&Store`
+pub type Store = dyn GeneStore;
+
+/// Which backend `open` builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// sled CAS + SQLite index
+    Sqlite,
+    /// sled only, no database setup
+    Sled,
+    /// Nothing on disk
+    Memory,
+}
+
+/// Open a store rooted at `path`
+pub async fn open(path: &str, backend: Backend) -> Result<Box<Store>> {
+    Ok(match backend {
+        Backend::Sqlite => Box::new(SqliteStore::open(path).await?),
+        Backend::Sled => Box::new(SledStore::open(path)?),
+        Backend::Memory => Box::new(MemoryStore::new()),
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Object {
+    pub cid: String,
+    pub data: Vec<u8>,
+    pub metadata: ObjectMeta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectMeta {
+    pub typ: ObjectType,
+    pub source: Option<String>,
+    pub timestamp: i64,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectType {
+    Source,
+    Gene,
+    IR,
+    Proof,
+    Organism,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gene {
+    pub soul: String,
+    /// Exported name of the function
+    #[serde(default)]
+    pub name: String,
+    pub ir: String,
+    pub source: String,
+    #[serde(default)]
+    pub span: Option<Span>,
+    pub signatures: Vec<String>,
+    pub metrics: GeneMetrics,
+    /// What running the function can do - `metrics.purity` is derived from it
+    #[serde(default)]
+    pub effects: Effects,
+}
+
+/// Where a gene's function sits in its source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Span {
+    pub file: String,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeneMetrics {
+    /// Source bytes of the function
+    pub size: usize,
+    /// 1 + branches in the IR
+    pub complexity: f32,
+    pub purity: f32,
+    /// Cost model estimate of one call - zero for textual IR
+    #[serde(default)]
+    pub cycles: u64,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub allocs: u64,
+    /// IR nodes
+    #[serde(default)]
+    pub nodes: usize,
+    #[serde(default)]
+    pub depth: usize,
+    /// Asymptotic class, where the IR says enough to tell
+    #[serde(default)]
+    pub growth: Option<Growth>,
+}
+
+impl GeneMetrics {
+    /// Rows for the `metrics` table - `growth` is the polynomial degree,
+    /// infinite when exponential
+    pub fn rows(&self) -> Vec<(&'static str, f64)> {
+        let mut rows = vec![
+            ("size", self.size as f64),
+            ("complexity", self.complexity as f64),
+            ("purity", self.purity as f64),
+            ("cycles", self.cycles as f64),
+            ("bytes", self.bytes as f64),
+            ("allocs", self.allocs as f64),
+            ("nodes", self.nodes as f64),
+            ("depth", self.depth as f64),
+        ];
+        match self.growth {
+            Some(Growth::Polynomial(k)) => rows.push(("growth", k as f64)),
+            Some(Growth::Exponential) => rows.push(("growth", f64::INFINITY)),
+            None => {}
+        }
+        rows
+    }
+}
+
+/// What one file of a source digested to last time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDigest {
+    /// Source identity across versions - provenance without the commit
+    pub source: String,
+    pub path: String,
+    /// blake3 of the file bytes
+    pub hash: String,
+    /// One per function found in the file
+    pub souls: Vec<String>,
+}
+
+/// Distortion impact of a rule, averaged over every measured run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleImpactStats {
+    pub rule: String,
+    pub runs: usize,
+    pub genes: usize,
+    pub rewritten: usize,
+    pub unpredictability: f32,
+    pub instability: f32,
+    pub nonlocality: f32,
+    pub redundancy: f32,
+    pub reduction: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Status {
+    pub sources: usize,
+    pub total_genes: usize,
+    pub unique_souls: usize,
+    pub equiv_classes: usize,
+    pub champions: usize,
+    pub organisms: usize,
+    pub storage_size: String,
+}
+
+impl RuleImpactStats {
+    /// Running sums for backends without SQL aggregation - see `mean`
+    fn sums(impact: &RuleImpact) -> Self {
+        RuleImpactStats {
+            rule: impact.rule.clone(),
+            runs: 1,
+            genes: impact.genes,
+            rewritten: impact.rewritten,
+            unpredictability: impact.delta.unpredictability,
+            instability: impact.delta.instability,
+            nonlocality: impact.delta.nonlocality,
+            redundancy: impact.delta.redundancy,
+            reduction: impact.reduction(),
+        }
+    }
+
+    fn accumulate(&mut self, impact: &RuleImpact) {
+        let run = Self::sums(impact);
+        self.runs += 1;
+        self.genes += run.genes;
+        self.rewritten += run.rewritten;
+        self.unpredictability += run.unpredictability;
+        self.instability += run.instability;
+        self.nonlocality += run.nonlocality;
+        self.redundancy += run.redundancy;
+        self.reduction += run.reduction;
+    }
+
+    /// Turn running sums into per-run means
+    fn mean(mut self) -> Self {
+        let runs = self.runs.max(1) as f32;
+        self.unpredictability /= runs;
+        self.instability /= runs;
+        self.nonlocality /= runs;
+        self.redundancy /= runs;
+        self.reduction /= runs;
+        self
+    }
+}
+
+/// Most distortion removed first, ties by rule
+fn sort_impacts(impacts: &mut [RuleImpactStats]) {
+    impacts.sort_by(|a, b| b.reduction.total_cmp(&a.reduction).then_with(|| a.rule.cmp(&b.rule)));
+}
+
+/// Roots by type: organisms, attestation proofs and the latest source of
+/// each lineage - an older version goes once a newer one is added, unless pinned
+fn typed_roots(objects: impl IntoIterator<Item = (String, ObjectMeta)>) -> Vec<String> {
+    let mut roots = Vec::new();
+    let mut latest: HashMap<String, (i64, Vec<String>)> = HashMap::new();
+    for (cid, meta) in objects {
+        match meta.typ {
+            ObjectType::Organism | ObjectType::Proof => roots.push(cid),
+            ObjectType::Source => {
+                let key = meta.source.as_deref().map_or_else(|| cid.clone(), |p| lineage(p).to_string());
+                let (time, cids) = latest.entry(key).or_insert((meta.timestamp, vec![]));
+                if meta.timestamp > *time {
+                    *time = meta.timestamp;
+                    cids.clear();
+                }
+                if meta.timestamp == *time {
+                    cids.push(cid);
+                }
+            }
+            _ => {}
+        }
+    }
+    roots.extend(latest.into_values().flat_map(|(_, cids)| cids));
+    roots
+}
+
+/// Blake3 content address
+pub(crate) fn compute_cid(data: &[u8]) -> String {
+    let mut hasher = Hasher::new();
+    hasher.update(data);
+    format!("b3:{}", hex::encode(hasher.finalize().as_bytes()))
+}
+
+/// Gene serialized as a CAS object
+fn gene_object(gene: &Gene) -> Result<(Vec<u8>, ObjectMeta)> {
+    let data = serde_json::to_vec(gene)?;
+    let meta = ObjectMeta {
+        typ: ObjectType::Gene,
+        source: Some(gene.source.clone()),
+        timestamp: chrono::Utc::now().timestamp(),
+        size: data.len(),
+    };
+    Ok((data, meta))
+}
+
+/// Stored effects of every named gene
+pub async fn gene_effects(store: &Store) -> Result<GeneEffects> {
+    let mut effects = GeneEffects::new();
+    for (soul, _) in store.gene_cids().await? {
+        if let Some(gene) = store.get_gene(&soul).await? {
+            if !gene.name.is_empty() {
+                effects.entry(gene.name).or_default().extend(&gene.effects);
+            }
+        }
+    }
+    Ok(effects)
+}
+
+fn format_size(size: u64) -> String {
+    if size > 1_000_000_000 {
+        format!("{:.1} GB", size as f64 / 1_000_000_000.0)
+    } else if size > 1_000_000 {
+        format!("{:.1} MB", size as f64 / 1_000_000.0)
+    } else {
+        format!("{} KB", size / 1000)
+    }
+}
+
+/// Add source to store
+///
+/// `git_ref` pins a branch, tag or commit for git sources. Re-adding an
+/// unchanged source stores no new bytes, only where they came from now.
+pub async fn add(store: &Store, source: &str, version: Option<&str>, git_ref: Option<&str>) -> Result<String> {
+    // Fetch source (simplified - would handle npm/github/crates)
+    let (data, provenance) = if let Some(local) = LocalSource::detect(source, git_ref) {
+        // Offline - normalized so the CID depends only on file contents
+        let snapshot = local.archive()?;
+        (snapshot.data, snapshot.provenance)
+    } else if source.contains("github.com") {
+        let snapshot = ingest::clone(source, git_ref.unwrap_or("HEAD"))?;
+        (snapshot.data, snapshot.provenance)
+    } else if source.starts_with("http") {
+        (reqwest::get(source).await?.bytes().await?.to_vec(), source.to_string())
+    } else {
+        // NPM package
+        (fetch_npm(source, version).await?, source.to_string())
+    };
+
+    let cid = compute_cid(&data);
+    let unchanged = store.get(&cid).await?.is_some();
+    if unchanged {
+        tracing::info!("{} unchanged ({})", provenance, cid);
+    }
+
+    let meta = ObjectMeta {
+        typ: ObjectType::Source,
+        source: Some(provenance),
+        timestamp: chrono::Utc::now().timestamp(),
+        size: data.len(),
+    };
+
+    // A new commit with an identical tree is still a new version
+    if unchanged {
+        store.set_meta(&cid, meta).await?;
+        return Ok(cid);
+    }
+    store.put(&data, meta).await
+}
+
+async fn fetch_npm(package: &str, version: Option<&str>) -> Result<Vec<u8>> {
+    // Fetch from NPM registry
+    let url = if let Some(v) = version {
+        format!("https://registry.npmjs.org/{}/{}", package, v)
+    } else {
+        format!("https://registry.npmjs.org/{}/latest", package)
+    };
+
+    let resp = reqwest::get(&url).await?;
+    Ok(resp.bytes().await?.to_vec())
+}
+
+/// Gene fixture shared by the storage and gc tests
+#[cfg(test)]
+pub(crate) fn test_gene(soul: &str) -> Gene {
+    Gene {
+        soul: soul.to_string(),
+        name: "map".to_string(),
+        ir: "(map xs f)".to_string(),
+        source: "npm:lodash".to_string(),
+        span: None,
+        signatures: vec![],
+        metrics: GeneMetrics::default(),
+        effects: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_backends_agree() {
+        let dir = tempfile::tempdir().unwrap();
+        for backend in [Backend::Memory, Backend::Sled, Backend::Sqlite] {
+            let path = dir.path().join(format!("{:?}", backend));
+            let store = open(path.to_str().unwrap(), backend).await.unwrap();
+
+            store.put_gene(&test_gene("λa")).await.unwrap();
+            store.put_gene(&test_gene("λb")).await.unwrap();
+            store.add_equivalence("λa", "λb", 0.9).await.unwrap();
+            assert_eq!(store.find_equivalents("λa").await.unwrap(), vec!["λb"], "{:?}", backend);
+
+            let cids: BTreeMap<String, String> = store.gene_cids().await.unwrap().into_iter().collect();
+            store.pin(&cids["λb"]).await.unwrap();
+            assert_eq!(store.roots().await.unwrap(), vec![cids["λb"].clone()], "{:?}", backend);
+
+            assert!(store.remove(&[cids["λa"].clone()]).await.unwrap() > 0, "{:?}", backend);
+            assert!(store.get_gene("λa").await.unwrap().is_none(), "{:?}", backend);
+            assert!(store.find_equivalents("λb").await.unwrap().is_empty(), "{:?}", backend);
+            assert_eq!(store.status().await.unwrap().total_genes, 1, "{:?}", backend);
+
+            store.unpin(&cids["λb"]).await.unwrap();
+            assert!(store.roots().await.unwrap().is_empty(), "{:?}", backend);
+
+            // λd stands for its class, and as its champion keeps it alive
+            store.put_gene(&test_gene("λc")).await.unwrap();
+            store.put_gene(&test_gene("λd")).await.unwrap();
+            store.add_equivalence("λc", "λd", 0.9).await.unwrap();
+            store.add_champion("λc", "λd", 1.0).await.unwrap();
+            let status = store.status().await.unwrap();
+            assert_eq!((status.total_genes, status.unique_souls, status.champions), (3, 2, 1), "{:?}", backend);
+            let cids: BTreeMap<String, String> = store.gene_cids().await.unwrap().into_iter().collect();
+            assert_eq!(store.roots().await.unwrap(), vec![cids["λd"].clone()], "{:?}", backend);
+
+            let meta = |source: &str| ObjectMeta { typ: ObjectType::Source, source: Some(source.to_string()), timestamp: 0, size: 3 };
+            let cid = store.put(b"src", meta("v1")).await.unwrap();
+            store.set_meta(&cid, meta("v2")).await.unwrap();
+            let source = store.get(&cid).await.unwrap().unwrap().metadata.source;
+            assert_eq!(source.as_deref(), Some("v2"), "{:?}", backend);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_readd_refreshes_provenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("id.js"), "export const id = x => x;\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("id.js")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::new("devour", "devour@localhost", &git2::Time::new(0, 0)).unwrap();
+        let first = repo.commit(Some("HEAD"), &sig, &sig, "id", &tree, &[]).unwrap();
+
+        let store = MemoryStore::new();
+        let path = dir.path().to_str().unwrap();
+        let cid = add(&store, path, None, None).await.unwrap();
+
+        // Same tree, new commit
+        let parent = repo.find_commit(first).unwrap();
+        let second = repo.commit(Some("HEAD"), &sig, &sig, "again", &tree, &[&parent]).unwrap();
+        assert_eq!(add(&store, path, None, None).await.unwrap(), cid);
+        let provenance = store.get(&cid).await.unwrap().unwrap().metadata.source.unwrap();
+        assert!(provenance.ends_with(&format!("@{}", second)), "{}", provenance);
+    }
+}
@@ -0,0 +1,474 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{sqlite::SqlitePoolOptions, Row, Sqlite, SqlitePool, Transaction};
+use std::path::PathBuf;
+
+use super::{compute_cid, format_size, gene_object, typed_roots, FileDigest, GeneStore, Gene, Object, ObjectMeta, RuleImpactStats, Status};
+use crate::surgeon::impact::RuleImpact;
+
+/// sled CAS with a SQLite index - queries are checked at runtime, no DATABASE_URL needed to build
+pub struct SqliteStore {
+    cas: sled::Db,
+    index: SqlitePool,
+}
+
+impl SqliteStore {
+    pub async fn open(path: &str) -> Result<Self> {
+        let root = PathBuf::from(path);
+        std::fs::create_dir_all(&root)?;
+
+        // Content-addressable store (sled)
+        let cas = sled::open(root.join("cas"))?;
+
+        // Index database (SQLite)
+        let db_url = format!("sqlite:{}/index.db?mode=rwc", path);
+        let index = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect(&db_url)
+            .await?;
+
+        // Initialize schema
+        sqlx::migrate!("./migrations").run(&index).await?;
+
+        Ok(SqliteStore { cas, index })
+    }
+
+    /// Tombstone the souls no recorded file yields any more
+    async fn release(tx: &mut Transaction<'_, Sqlite>, souls: Vec<String>) -> Result<Vec<String>> {
+        let mut tombstoned = Vec::new();
+        for soul in souls {
+            let yielded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM file_genes WHERE soul = ?")
+                .bind(&soul)
+                .fetch_one(&mut **tx)
+                .await?;
+            if yielded > 0 {
+                continue;
+            }
+
+            sqlx::query("DELETE FROM equivalences WHERE soul1 = ? OR soul2 = ?")
+                .bind(&soul)
+                .bind(&soul)
+                .execute(&mut **tx)
+                .await?;
+            sqlx::query("DELETE FROM metrics WHERE soul = ?").bind(&soul).execute(&mut **tx).await?;
+            sqlx::query("DELETE FROM genes WHERE soul = ?").bind(&soul).execute(&mut **tx).await?;
+            sqlx::query("INSERT OR IGNORE INTO tombstones (soul) VALUES (?)").bind(&soul).execute(&mut **tx).await?;
+            tombstoned.push(soul);
+        }
+        Ok(tombstoned)
+    }
+
+    async fn count(&self, sql: &str) -> Result<usize> {
+        let count: i64 = sqlx::query_scalar(sql).fetch_one(&self.index).await?;
+        Ok(count as usize)
+    }
+}
+
+#[async_trait]
+impl GeneStore for SqliteStore {
+    async fn put(&self, data: &[u8], meta: ObjectMeta) -> Result<String> {
+        let cid = compute_cid(data);
+
+        // Store in CAS
+        let obj = Object {
+            cid: cid.clone(),
+            data: data.to_vec(),
+            metadata: meta,
+        };
+
+        let serialized = bincode::serialize(&obj)?;
+        self.cas.insert(cid.as_bytes(), serialized)?;
+
+        // Update index
+        sqlx::query("INSERT OR IGNORE INTO objects (cid, type, source, size) VALUES (?, ?, ?, ?)")
+            .bind(&cid)
+            .bind(format!("{:?}", obj.metadata.typ))
+            .bind(&obj.metadata.source)
+            .bind(obj.metadata.size as i64)
+            .execute(&self.index)
+            .await?;
+
+        Ok(cid)
+    }
+
+    async fn get(&self, cid: &str) -> Result<Option<Object>> {
+        match self.cas.get(cid.as_bytes())? {
+            Some(data) => Ok(Some(bincode::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_meta(&self, cid: &str, meta: ObjectMeta) -> Result<()> {
+        let Some(data) = self.cas.get(cid.as_bytes())? else { return Ok(()) };
+        let mut obj: Object = bincode::deserialize(&data)?;
+        obj.metadata = meta;
+        self.cas.insert(cid.as_bytes(), bincode::serialize(&obj)?)?;
+
+        sqlx::query("UPDATE objects SET type = ?, source = ?, size = ? WHERE cid = ?")
+            .bind(format!("{:?}", obj.metadata.typ))
+            .bind(&obj.metadata.source)
+            .bind(obj.metadata.size as i64)
+            .bind(cid)
+            .execute(&self.index)
+            .await?;
+        Ok(())
+    }
+
+    async fn put_gene(&self, gene: &Gene) -> Result<()> {
+        // Store gene object
+        let (data, meta) = gene_object(gene)?;
+        let cid = self.put(&data, meta).await?;
+
+        // Index gene
+        let span = gene.span.as_ref();
+        sqlx::query(
+            "INSERT OR REPLACE INTO genes (soul, cid, source, ir, complexity, purity, size, effects, name, file, start_line, end_line)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&gene.soul)
+        .bind(&cid)
+        .bind(&gene.source)
+        .bind(&gene.ir)
+        .bind(gene.metrics.complexity)
+        .bind(gene.metrics.purity)
+        .bind(gene.metrics.size as i64)
+        .bind(serde_json::to_string(&gene.effects)?)
+        .bind(&gene.name)
+        .bind(span.map(|s| &s.file))
+        .bind(span.map(|s| s.start_line as i64))
+        .bind(span.map(|s| s.end_line as i64))
+        .execute(&self.index)
+        .await?;
+        sqlx::query("DELETE FROM tombstones WHERE soul = ?")
+            .bind(&gene.soul)
+            .execute(&self.index)
+            .await?;
+
+        // One measurement per metric - re-digesting replaces the last one
+        let mut tx = self.index.begin().await?;
+        sqlx::query("DELETE FROM metrics WHERE soul = ?").bind(&gene.soul).execute(&mut *tx).await?;
+        for (metric, value) in gene.metrics.rows() {
+            sqlx::query("INSERT INTO metrics (soul, metric_type, value) VALUES (?, ?, ?)")
+                .bind(&gene.soul)
+                .bind(metric)
+                .bind(value)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_gene(&self, soul: &str) -> Result<Option<Gene>> {
+        let cid: Option<String> = sqlx::query_scalar("SELECT cid FROM genes WHERE soul = ?")
+            .bind(soul)
+            .fetch_optional(&self.index)
+            .await?;
+
+        match cid {
+            Some(cid) => match self.get(&cid).await? {
+                Some(obj) => Ok(Some(serde_json::from_slice(&obj.data)?)),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn find_equivalents(&self, soul: &str) -> Result<Vec<String>> {
+        let souls = sqlx::query_scalar(
+            "SELECT DISTINCT g2.soul
+             FROM genes g1
+             JOIN equivalences e ON g1.soul = e.soul1
+             JOIN genes g2 ON e.soul2 = g2.soul
+             WHERE g1.soul = ?",
+        )
+        .bind(soul)
+        .fetch_all(&self.index)
+        .await?;
+
+        Ok(souls)
+    }
+
+    async fn add_equivalence(&self, soul1: &str, soul2: &str, confidence: f32) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO equivalences (soul1, soul2, confidence) VALUES (?, ?, ?)")
+            .bind(soul1)
+            .bind(soul2)
+            .bind(confidence)
+            .execute(&self.index)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn add_champion(&self, canonical: &str, soul: &str, score: f32) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO champions (canonical, soul, score) VALUES (?, ?, ?)")
+            .bind(canonical)
+            .bind(soul)
+            .bind(score)
+            .execute(&self.index)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn record_rule_impact(&self, impact: &RuleImpact) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO rule_impacts
+                (rule, runs, genes, rewritten, unpredictability, instability, nonlocality, redundancy, reduction)
+             VALUES (?, 1, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(rule) DO UPDATE SET
+                runs = runs + 1,
+                genes = genes + excluded.genes,
+                rewritten = rewritten + excluded.rewritten,
+                unpredictability = unpredictability + excluded.unpredictability,
+                instability = instability + excluded.instability,
+                nonlocality = nonlocality + excluded.nonlocality,
+                redundancy = redundancy + excluded.redundancy,
+                reduction = reduction + excluded.reduction,
+                updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(&impact.rule)
+        .bind(impact.genes as i64)
+        .bind(impact.rewritten as i64)
+        .bind(impact.delta.unpredictability)
+        .bind(impact.delta.instability)
+        .bind(impact.delta.nonlocality)
+        .bind(impact.delta.redundancy)
+        .bind(impact.reduction())
+        .execute(&self.index)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn rule_impacts(&self) -> Result<Vec<RuleImpactStats>> {
+        let rows = sqlx::query(
+            "SELECT rule, runs, genes, rewritten, unpredictability, instability, nonlocality, redundancy, reduction
+             FROM rule_impacts
+             ORDER BY reduction / runs DESC, rule",
+        )
+        .fetch_all(&self.index)
+        .await?;
+
+        rows.into_iter().map(|r| {
+            Ok(RuleImpactStats {
+                rule: r.try_get("rule")?,
+                runs: r.try_get::<i64, _>("runs")? as usize,
+                genes: r.try_get::<i64, _>("genes")? as usize,
+                rewritten: r.try_get::<i64, _>("rewritten")? as usize,
+                unpredictability: r.try_get::<f64, _>("unpredictability")? as f32,
+                instability: r.try_get::<f64, _>("instability")? as f32,
+                nonlocality: r.try_get::<f64, _>("nonlocality")? as f32,
+                redundancy: r.try_get::<f64, _>("redundancy")? as f32,
+                reduction: r.try_get::<f64, _>("reduction")? as f32,
+            }.mean())
+        }).collect()
+    }
+
+    async fn pin(&self, cid: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO pins (cid) VALUES (?)")
+            .bind(cid)
+            .execute(&self.index)
+            .await?;
+        Ok(())
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        sqlx::query("DELETE FROM pins WHERE cid = ?")
+            .bind(cid)
+            .execute(&self.index)
+            .await?;
+        Ok(())
+    }
+
+    async fn roots(&self) -> Result<Vec<String>> {
+        let mut roots: Vec<String> = sqlx::query_scalar(
+            "SELECT cid FROM pins
+             UNION SELECT cid FROM attestations
+             UNION SELECT g.cid FROM champions c JOIN genes g ON c.soul = g.soul",
+        )
+        .fetch_all(&self.index)
+        .await?;
+
+        let mut objects = Vec::new();
+        for item in self.cas.iter() {
+            let (_, data) = item?;
+            let obj: Object = bincode::deserialize(&data)?;
+            objects.push((obj.cid, obj.metadata));
+        }
+        roots.extend(typed_roots(objects));
+        Ok(roots)
+    }
+
+    async fn objects(&self) -> Result<Vec<(String, usize)>> {
+        let mut objects = Vec::new();
+        for item in self.cas.iter() {
+            let (cid, data) = item?;
+            objects.push((String::from_utf8(cid.to_vec())?, data.len()));
+        }
+
+        // Index rows whose data is already gone are objects too, so gc sweeps them
+        let indexed: Vec<String> = sqlx::query_scalar("SELECT cid FROM objects")
+            .fetch_all(&self.index)
+            .await?;
+        for cid in indexed {
+            if !self.cas.contains_key(cid.as_bytes())? {
+                objects.push((cid, 0));
+            }
+        }
+        Ok(objects)
+    }
+
+    async fn gene_cids(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query("SELECT soul, cid FROM genes")
+            .fetch_all(&self.index)
+            .await?;
+        rows.into_iter()
+            .map(|r| Ok((r.try_get("soul")?, r.try_get("cid")?)))
+            .collect()
+    }
+
+    async fn file_digests(&self, source: &str) -> Result<Vec<FileDigest>> {
+        let rows = sqlx::query("SELECT path, hash FROM file_digests WHERE source = ? ORDER BY path")
+            .bind(source)
+            .fetch_all(&self.index)
+            .await?;
+        let mut digests = Vec::new();
+        for r in rows {
+            let path: String = r.try_get("path")?;
+            let souls = sqlx::query_scalar("SELECT soul FROM file_genes WHERE source = ? AND path = ? ORDER BY soul")
+                .bind(source)
+                .bind(&path)
+                .fetch_all(&self.index)
+                .await?;
+            digests.push(FileDigest { source: source.to_string(), path, hash: r.try_get("hash")?, souls });
+        }
+        Ok(digests)
+    }
+
+    async fn put_file_digest(&self, digest: &FileDigest) -> Result<Vec<String>> {
+        let mut tx = self.index.begin().await?;
+
+        let old: Vec<String> = sqlx::query_scalar("DELETE FROM file_genes WHERE source = ? AND path = ? RETURNING soul")
+            .bind(&digest.source)
+            .bind(&digest.path)
+            .fetch_all(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR REPLACE INTO file_digests (source, path, hash) VALUES (?, ?, ?)")
+            .bind(&digest.source)
+            .bind(&digest.path)
+            .bind(&digest.hash)
+            .execute(&mut *tx)
+            .await?;
+        for soul in &digest.souls {
+            sqlx::query("INSERT OR IGNORE INTO file_genes (source, path, soul) VALUES (?, ?, ?)")
+                .bind(&digest.source)
+                .bind(&digest.path)
+                .bind(soul)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let dropped = old.into_iter().filter(|soul| !digest.souls.contains(soul)).collect();
+        let tombstoned = Self::release(&mut tx, dropped).await?;
+        tx.commit().await?;
+        Ok(tombstoned)
+    }
+
+    async fn tombstone(&self, source: &str, path: &str) -> Result<Vec<String>> {
+        let mut tx = self.index.begin().await?;
+
+        sqlx::query("DELETE FROM file_digests WHERE source = ? AND path = ?")
+            .bind(source)
+            .bind(path)
+            .execute(&mut *tx)
+            .await?;
+        let souls = sqlx::query_scalar("DELETE FROM file_genes WHERE source = ? AND path = ? RETURNING soul")
+            .bind(source)
+            .bind(path)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let tombstoned = Self::release(&mut tx, souls).await?;
+        tx.commit().await?;
+        Ok(tombstoned)
+    }
+
+    async fn undigested_sources(&self) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar(
+            "SELECT cid FROM objects
+             WHERE type = 'Source' AND cid NOT IN (SELECT cid FROM digested_sources)
+             ORDER BY created_at, cid",
+        )
+        .fetch_all(&self.index)
+        .await?)
+    }
+
+    async fn mark_digested(&self, cid: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO digested_sources (cid) VALUES (?)")
+            .bind(cid)
+            .execute(&self.index)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, cids: &[String]) -> Result<u64> {
+        let mut freed = 0;
+        let mut tx = self.index.begin().await?;
+
+        for cid in cids {
+            if let Some(data) = self.cas.remove(cid.as_bytes())? {
+                freed += data.len() as u64;
+            }
+
+            // Genes stored in this object, and everything keyed by their soul
+            let souls: Vec<String> = sqlx::query_scalar("SELECT soul FROM genes WHERE cid = ?")
+                .bind(cid)
+                .fetch_all(&mut *tx)
+                .await?;
+            for soul in &souls {
+                sqlx::query("DELETE FROM equivalences WHERE soul1 = ? OR soul2 = ?")
+                    .bind(soul)
+                    .bind(soul)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("DELETE FROM metrics WHERE soul = ?").bind(soul).execute(&mut *tx).await?;
+
+                // Files that yielded it get extracted again next digest
+                sqlx::query(
+                    "DELETE FROM file_digests WHERE (source, path) IN
+                        (SELECT source, path FROM file_genes WHERE soul = ?)",
+                )
+                .bind(soul)
+                .execute(&mut *tx)
+                .await?;
+            }
+            sqlx::query("DELETE FROM file_genes WHERE (source, path) NOT IN (SELECT source, path FROM file_digests)")
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM genes WHERE cid = ?").bind(cid).execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM objects WHERE cid = ?").bind(cid).execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM digested_sources WHERE cid = ?").bind(cid).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+        self.cas.flush_async().await?;
+        Ok(freed)
+    }
+
+    async fn status(&self) -> Result<Status> {
+        Ok(Status {
+            sources: self.count("SELECT COUNT(*) FROM objects WHERE type = 'Source'").await?,
+            total_genes: self.count("SELECT COUNT(*) FROM genes").await?,
+            unique_souls: self.count(
+                "SELECT COUNT(*) FROM genes WHERE soul NOT IN
+                    (SELECT e.soul2 FROM equivalences e JOIN genes g ON e.soul1 = g.soul)",
+            ).await?,
+            equiv_classes: self.count("SELECT COUNT(DISTINCT soul1) FROM equivalences").await?,
+            champions: self.count("SELECT COUNT(*) FROM champions c JOIN genes g ON c.soul = g.soul").await?,
+            organisms: self.count("SELECT COUNT(*) FROM organisms").await?,
+            storage_size: format_size(self.cas.size_on_disk()?),
+        })
+    }
+}
@@ -1,8 +1,11 @@
 // Deterministic cost model - no heuristics, only measurements
 // C = α*cycles + β*bytes + γ*allocs + δ*io_risk
 
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use super::egraph::IR;
+use crate::extract::SELF;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cost {
@@ -105,6 +108,9 @@ impl CostModel {
             IR::Str(s) => Cost { cycles: 1, bytes: s.len() as u64, allocs: 1, io_risk: 0.0 },
             IR::Nil => Cost { cycles: 1, bytes: 8, allocs: 0, io_risk: 0.0 },
             IR::Id => Cost { cycles: 1, bytes: 8, allocs: 0, io_risk: 0.0 },
+            IR::Drop => Cost { cycles: 0, bytes: 0, allocs: 0, io_risk: 0.0 },
+            // Unknown code - assume a call's worth of work and some risk
+            IR::Opaque(_) => Cost { cycles: self.op_costs.app_overhead, bytes: 8, allocs: 1, io_risk: 0.5 },
             
             // Lambda abstraction
             IR::Lam(_, body) => {
@@ -184,6 +190,23 @@ impl CostModel {
                 }
             }
             
+            // Focus: one pass, only elements inside the focus allocate
+            IR::Focus(xs, w, f, g) => {
+                let xs_cost = self.compute(xs);
+                let w_cost = self.compute(w);
+                let f_cost = self.compute(f);
+                let g_cost = self.compute(g);
+                let list_size = self.estimate_list_size(xs);
+                let inside = if matches!(**g, IR::Drop) { list_size / 2 } else { list_size };
+                
+                Cost {
+                    cycles: list_size * (5 + w_cost.cycles + f_cost.cycles.max(g_cost.cycles)) + xs_cost.cycles,
+                    bytes: inside * 16 + xs_cost.bytes + w_cost.bytes + f_cost.bytes + g_cost.bytes,
+                    allocs: inside + xs_cost.allocs + w_cost.allocs + f_cost.allocs + g_cost.allocs,
+                    io_risk: xs_cost.io_risk.max(w_cost.io_risk).max(f_cost.io_risk).max(g_cost.io_risk),
+                }
+            }
+            
             // Control flow
             IR::If(c, t, e) => {
                 let c_cost = self.compute(c);
@@ -199,7 +222,8 @@ impl CostModel {
             }
             
             // Arithmetic operations
-            IR::Add(a, b) | IR::Mul(a, b) | IR::Eq(a, b) => {
+            IR::Add(a, b) | IR::Sub(a, b) | IR::Mul(a, b) | IR::Div(a, b) | IR::Mod(a, b) |
+            IR::Eq(a, b) | IR::Lt(a, b) | IR::Le(a, b) => {
                 let a_cost = self.compute(a);
                 let b_cost = self.compute(b);
                 
@@ -254,6 +278,81 @@ impl CostModel {
     }
 }
 
+/// Asymptotic time in the length of the input lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Growth {
+    /// O(n^k) - degree 0 is constant time
+    Polynomial(u32),
+    Exponential,
+}
+
+impl fmt::Display for Growth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Growth::Polynomial(0) => write!(f, "O(1)"),
+            Growth::Polynomial(1) => write!(f, "O(n)"),
+            Growth::Polynomial(k) => write!(f, "O(n^{})", k),
+            Growth::Exponential => write!(f, "O(2^n)"),
+        }
+    }
+}
+
+/// Growth class of `ir`, when the IR says enough to tell
+///
+/// Traversals nest: a map whose function traverses again is one degree up.
+/// Recursion through `SELF` adds a degree when it recurses once per call and
+/// is exponential when it branches. Opaque constructs and recursion from
+/// inside a traversal's function are not detectable.
+pub fn growth(ir: &IR) -> Option<Growth> {
+    if has_opaque(ir) || recurses_in_traversal(ir, false) {
+        return None;
+    }
+    let degree = degree(ir);
+    Some(match self_calls(ir) {
+        0 => Growth::Polynomial(degree),
+        1 => Growth::Polynomial(degree + 1),
+        _ => Growth::Exponential,
+    })
+}
+
+/// Nesting depth of list traversals
+fn degree(ir: &IR) -> u32 {
+    match ir {
+        IR::Map(xs, f) | IR::Filter(xs, f) => degree(xs).max(1 + degree(f)),
+        IR::Reduce(xs, f, init) => degree(xs).max(degree(init)).max(1 + degree(f)),
+        IR::Focus(xs, w, f, g) => degree(xs).max(1 + degree(w).max(degree(f)).max(degree(g))),
+        _ => ir.children().into_iter().map(degree).max().unwrap_or(0),
+    }
+}
+
+/// Self-calls on the longest path through the body - one branch of an `if` runs
+fn self_calls(ir: &IR) -> u32 {
+    match ir {
+        IR::App(f, x) if matches!(&**f, IR::Var(name) if name == SELF) => 1 + self_calls(x),
+        IR::If(c, t, e) => self_calls(c) + self_calls(t).max(self_calls(e)),
+        _ => ir.children().into_iter().map(self_calls).sum(),
+    }
+}
+
+fn recurses_in_traversal(ir: &IR, inside: bool) -> bool {
+    match ir {
+        IR::Var(name) => inside && name == SELF,
+        IR::Map(xs, f) | IR::Filter(xs, f) => recurses_in_traversal(xs, inside) || recurses_in_traversal(f, true),
+        IR::Reduce(xs, f, init) => {
+            recurses_in_traversal(xs, inside) || recurses_in_traversal(init, inside) || recurses_in_traversal(f, true)
+        }
+        IR::Focus(xs, w, f, g) => {
+            recurses_in_traversal(xs, inside) || [w, f, g].iter().any(|part| recurses_in_traversal(part, true))
+        }
+        _ => ir.children().into_iter().any(|child| recurses_in_traversal(child, inside)),
+    }
+}
+
+fn has_opaque(ir: &IR) -> bool {
+    matches!(ir, IR::Opaque(_)) || ir.children().into_iter().any(has_opaque)
+}
+
 /// Operation costs database
 #[derive(Debug, Clone)]
 struct OpCosts {
@@ -274,4 +373,64 @@ impl Default for OpCosts {
             alloc_cost: 100,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(x: &str) -> Box<IR> {
+        Box::new(IR::Var(x.to_string()))
+    }
+
+    fn lam(x: &str, body: IR) -> Box<IR> {
+        Box::new(IR::Lam(x.to_string(), Box::new(body)))
+    }
+
+    #[test]
+    fn test_growth_of_traversals() {
+        assert_eq!(growth(&IR::Add(var("x"), var("y"))), Some(Growth::Polynomial(0)));
+
+        // map(xs, x => x + 1)
+        let map = IR::Map(var("xs"), lam("x", IR::Add(var("x"), Box::new(IR::Num(1)))));
+        assert_eq!(growth(&map), Some(Growth::Polynomial(1)));
+
+        // map(xs, x => filter(ys, y => y < x)) - a traversal per element
+        let nested = IR::Map(var("xs"), lam("x", IR::Filter(var("ys"), lam("y", IR::Lt(var("y"), var("x"))))));
+        assert_eq!(growth(&nested), Some(Growth::Polynomial(2)));
+        assert_eq!(growth(&nested).unwrap().to_string(), "O(n^2)");
+
+        // Chained traversals stay linear
+        let chained = IR::Filter(Box::new(map), lam("x", IR::Lt(var("x"), Box::new(IR::Num(3)))));
+        assert_eq!(growth(&chained), Some(Growth::Polynomial(1)));
+    }
+
+    #[test]
+    fn test_growth_of_recursion() {
+        let n = || var("n");
+        let one = || Box::new(IR::Num(1));
+        let call = |arg: IR| IR::App(var(SELF), Box::new(arg));
+
+        // n < 1 ? 0 : n + rec(n - 1)
+        let sum = IR::If(
+            Box::new(IR::Lt(n(), one())),
+            Box::new(IR::Num(0)),
+            Box::new(IR::Add(n(), Box::new(call(IR::Sub(n(), one()))))),
+        );
+        assert_eq!(growth(&sum), Some(Growth::Polynomial(1)));
+
+        // n < 2 ? n : rec(n - 1) + rec(n - 2)
+        let fib = IR::If(
+            Box::new(IR::Lt(n(), Box::new(IR::Num(2)))),
+            n(),
+            Box::new(IR::Add(
+                Box::new(call(IR::Sub(n(), one()))),
+                Box::new(call(IR::Sub(n(), Box::new(IR::Num(2))))),
+            )),
+        );
+        assert_eq!(growth(&fib), Some(Growth::Exponential));
+
+        assert_eq!(growth(&IR::Map(var("xs"), var(SELF))), None);
+        assert_eq!(growth(&IR::Opaque("while statement".to_string())), None);
+    }
+}
@@ -4,12 +4,31 @@
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use serde::{Deserialize, Serialize};
+use lambda_core::distance::Tree;
 
-use lambda_core::{IR, Symbol};
 use super::cost::CostModel;
 use super::rules::Rule;
 
 pub type EClassId = usize;
+pub type Symbol = String;
+
+/// Owned λ-IR the surgeon operates on
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IR {
+    // Lambda calculus
+    Var(Symbol),
+    Lam(Symbol, Box<IR>),
+    App(Box<IR>, Box<IR>),
+    
+    // Collections
+    Map(Box<IR>, Box<IR>),
+    Filter(Box<IR>, Box<IR>),
+    Reduce(Box<IR>, Box<IR>, Box<IR>),
+    Nil,
+    Cons(Box<IR>, Box<IR>),
+    
+    // Literals
+    Num(i64),
     Bool(bool),
     Str(String),
     
@@ -18,8 +37,13 @@ pub type EClassId = usize;
     
     // Operators
     Add(Box<IR>, Box<IR>),
+    Sub(Box<IR>, Box<IR>),
     Mul(Box<IR>, Box<IR>),
+    Div(Box<IR>, Box<IR>),
+    Mod(Box<IR>, Box<IR>),
     Eq(Box<IR>, Box<IR>),
+    Lt(Box<IR>, Box<IR>),
+    Le(Box<IR>, Box<IR>),
     
     // Composition
     Compose(Box<IR>, Box<IR>),
@@ -28,9 +52,101 @@ pub type EClassId = usize;
     // Special
     Id,  // Identity function
     Const(Box<IR>), // Constant function
+    Focus(Box<IR>, Box<IR>, Box<IR>, Box<IR>), // xs, weight, inside, outside
+    Drop,  // Outside of a hard focus: element is discarded
+    Opaque(String), // Source construct an extractor could not lower - the reason
 }
 
 impl IR {
+    /// Number of nodes in the tree
+    pub fn size(&self) -> usize {
+        1 + self.children().iter().map(|c| c.size()).sum::<usize>()
+    }
+    
+    /// Nodes on the longest root-to-leaf path
+    pub fn depth(&self) -> usize {
+        1 + self.children().iter().map(|c| c.depth()).max().unwrap_or(0)
+    }
+    
+    /// Direct children, left to right
+    pub fn children(&self) -> Vec<&IR> {
+        match self {
+            IR::Var(_) | IR::Nil | IR::Num(_) | IR::Bool(_) | IR::Str(_) | IR::Id | IR::Drop | IR::Opaque(_) => vec![],
+            IR::Lam(_, body) | IR::Const(body) => vec![&**body],
+            IR::App(a, b) | IR::Map(a, b) | IR::Filter(a, b) | IR::Cons(a, b) |
+            IR::Add(a, b) | IR::Sub(a, b) | IR::Mul(a, b) | IR::Div(a, b) | IR::Mod(a, b) |
+            IR::Eq(a, b) | IR::Lt(a, b) | IR::Le(a, b) |
+            IR::Compose(a, b) | IR::Pipe(a, b) => vec![&**a, &**b],
+            IR::Reduce(a, b, c) | IR::If(a, b, c) => vec![&**a, &**b, &**c],
+            IR::Focus(a, b, c, d) => vec![&**a, &**b, &**c, &**d],
+        }
+    }
+    
+    /// Same node with every direct child replaced by `f(child)`
+    pub fn map_children(&self, mut f: impl FnMut(&IR) -> IR) -> IR {
+        let mut b = |ir: &IR| Box::new(f(ir));
+        match self {
+            IR::Var(_) | IR::Nil | IR::Num(_) | IR::Bool(_) | IR::Str(_) | IR::Id | IR::Drop | IR::Opaque(_) => self.clone(),
+            IR::Lam(x, body) => IR::Lam(x.clone(), b(body)),
+            IR::Const(x) => IR::Const(b(x)),
+            IR::App(x, y) => IR::App(b(x), b(y)),
+            IR::Map(x, y) => IR::Map(b(x), b(y)),
+            IR::Filter(x, y) => IR::Filter(b(x), b(y)),
+            IR::Cons(x, y) => IR::Cons(b(x), b(y)),
+            IR::Add(x, y) => IR::Add(b(x), b(y)),
+            IR::Sub(x, y) => IR::Sub(b(x), b(y)),
+            IR::Mul(x, y) => IR::Mul(b(x), b(y)),
+            IR::Div(x, y) => IR::Div(b(x), b(y)),
+            IR::Mod(x, y) => IR::Mod(b(x), b(y)),
+            IR::Eq(x, y) => IR::Eq(b(x), b(y)),
+            IR::Lt(x, y) => IR::Lt(b(x), b(y)),
+            IR::Le(x, y) => IR::Le(b(x), b(y)),
+            IR::Compose(x, y) => IR::Compose(b(x), b(y)),
+            IR::Pipe(x, y) => IR::Pipe(b(x), b(y)),
+            IR::Reduce(x, y, z) => IR::Reduce(b(x), b(y), b(z)),
+            IR::If(x, y, z) => IR::If(b(x), b(y), b(z)),
+            IR::Focus(w, x, y, z) => IR::Focus(b(w), b(x), b(y), b(z)),
+        }
+    }
+    
+    /// Operator of this node, children excluded
+    pub fn head(&self) -> String {
+        match self {
+            IR::Var(s) => s.clone(),
+            IR::Lam(x, _) => format!("λ{}", x),
+            IR::App(_, _) => "app".to_string(),
+            IR::Map(_, _) => "map".to_string(),
+            IR::Filter(_, _) => "filter".to_string(),
+            IR::Reduce(_, _, _) => "reduce".to_string(),
+            IR::Nil => "nil".to_string(),
+            IR::Cons(_, _) => "cons".to_string(),
+            IR::Num(n) => n.to_string(),
+            IR::Bool(b) => b.to_string(),
+            IR::Str(s) => format!("\"{}\"", s),
+            IR::If(_, _, _) => "if".to_string(),
+            IR::Add(_, _) => "+".to_string(),
+            IR::Sub(_, _) => "-".to_string(),
+            IR::Mul(_, _) => "*".to_string(),
+            IR::Div(_, _) => "/".to_string(),
+            IR::Mod(_, _) => "%".to_string(),
+            IR::Eq(_, _) => "=".to_string(),
+            IR::Lt(_, _) => "<".to_string(),
+            IR::Le(_, _) => "<=".to_string(),
+            IR::Compose(_, _) => "∘".to_string(),
+            IR::Pipe(_, _) => "|>".to_string(),
+            IR::Id => "id".to_string(),
+            IR::Const(_) => "const".to_string(),
+            IR::Focus(_, _, _, _) => "focus".to_string(),
+            IR::Drop => "drop".to_string(),
+            IR::Opaque(_) => "opaque".to_string(),
+        }
+    }
+    
+    /// Labelled tree for edit-distance diffs
+    pub fn to_tree(&self) -> Tree<String> {
+        Tree::node(self.head(), self.children().into_iter().map(IR::to_tree).collect())
+    }
+    
     /// Alpha-normalize: rename all variables consistently
     pub fn alpha_normalize(&self) -> IR {
         let mut renamer = AlphaRenamer::new();
@@ -55,12 +171,21 @@ impl IR {
             IR::If(c, t, e) => format!("(if {} {} {})", 
                 c.to_canonical_string(), t.to_canonical_string(), e.to_canonical_string()),
             IR::Add(a, b) => format!("(+ {} {})", a.to_canonical_string(), b.to_canonical_string()),
+            IR::Sub(a, b) => format!("(- {} {})", a.to_canonical_string(), b.to_canonical_string()),
             IR::Mul(a, b) => format!("(* {} {})", a.to_canonical_string(), b.to_canonical_string()),
+            IR::Div(a, b) => format!("(/ {} {})", a.to_canonical_string(), b.to_canonical_string()),
+            IR::Mod(a, b) => format!("(% {} {})", a.to_canonical_string(), b.to_canonical_string()),
             IR::Eq(a, b) => format!("(= {} {})", a.to_canonical_string(), b.to_canonical_string()),
+            IR::Lt(a, b) => format!("(< {} {})", a.to_canonical_string(), b.to_canonical_string()),
+            IR::Le(a, b) => format!("(<= {} {})", a.to_canonical_string(), b.to_canonical_string()),
             IR::Compose(f, g) => format!("(∘ {} {})", f.to_canonical_string(), g.to_canonical_string()),
             IR::Pipe(f, g) => format!("(|> {} {})", f.to_canonical_string(), g.to_canonical_string()),
             IR::Id => "id".to_string(),
             IR::Const(x) => format!("(const {})", x.to_canonical_string()),
+            IR::Focus(xs, w, f, g) => format!("(focus {} {} {} {})",
+                xs.to_canonical_string(), w.to_canonical_string(), f.to_canonical_string(), g.to_canonical_string()),
+            IR::Drop => "drop".to_string(),
+            IR::Opaque(reason) => format!("(opaque {:?})", reason),
         }
     }
     
@@ -109,12 +234,20 @@ pub enum ENode {
     Str(String),
     If(EClassId, EClassId, EClassId),
     Add(EClassId, EClassId),
+    Sub(EClassId, EClassId),
     Mul(EClassId, EClassId),
+    Div(EClassId, EClassId),
+    Mod(EClassId, EClassId),
     Eq(EClassId, EClassId),
+    Lt(EClassId, EClassId),
+    Le(EClassId, EClassId),
     Compose(EClassId, EClassId),
     Pipe(EClassId, EClassId),
     Id,
     Const(EClassId),
+    Focus(EClassId, EClassId, EClassId, EClassId),
+    Drop,
+    Opaque(String),
 }
 
 impl EGraph {
@@ -185,16 +318,41 @@ impl EGraph {
                 let b_id = self.add(*b);
                 self.add_node(ENode::Add(a_id, b_id))
             }
+            IR::Sub(a, b) => {
+                let a_id = self.add(*a);
+                let b_id = self.add(*b);
+                self.add_node(ENode::Sub(a_id, b_id))
+            }
             IR::Mul(a, b) => {
                 let a_id = self.add(*a);
                 let b_id = self.add(*b);
                 self.add_node(ENode::Mul(a_id, b_id))
             }
+            IR::Div(a, b) => {
+                let a_id = self.add(*a);
+                let b_id = self.add(*b);
+                self.add_node(ENode::Div(a_id, b_id))
+            }
+            IR::Mod(a, b) => {
+                let a_id = self.add(*a);
+                let b_id = self.add(*b);
+                self.add_node(ENode::Mod(a_id, b_id))
+            }
             IR::Eq(a, b) => {
                 let a_id = self.add(*a);
                 let b_id = self.add(*b);
                 self.add_node(ENode::Eq(a_id, b_id))
             }
+            IR::Lt(a, b) => {
+                let a_id = self.add(*a);
+                let b_id = self.add(*b);
+                self.add_node(ENode::Lt(a_id, b_id))
+            }
+            IR::Le(a, b) => {
+                let a_id = self.add(*a);
+                let b_id = self.add(*b);
+                self.add_node(ENode::Le(a_id, b_id))
+            }
             IR::Compose(f, g) => {
                 let f_id = self.add(*f);
                 let g_id = self.add(*g);
@@ -210,6 +368,15 @@ impl EGraph {
                 let x_id = self.add(*x);
                 self.add_node(ENode::Const(x_id))
             }
+            IR::Focus(xs, w, f, g) => {
+                let xs_id = self.add(*xs);
+                let w_id = self.add(*w);
+                let f_id = self.add(*f);
+                let g_id = self.add(*g);
+                self.add_node(ENode::Focus(xs_id, w_id, f_id, g_id))
+            }
+            IR::Drop => self.add_node(ENode::Drop),
+            IR::Opaque(reason) => self.add_node(ENode::Opaque(reason)),
         }
     }
     
@@ -346,12 +513,7 @@ impl AlphaRenamer {
                 }
                 IR::Lam(new_x, Box::new(new_body))
             }
-            IR::App(f, x) => IR::App(
-                Box::new(self.normalize(f)),
-                Box::new(self.normalize(x))
-            ),
-            // ... similar for other constructors
-            _ => ir.clone(),
+            _ => ir.map_children(|child| self.normalize(child)),
         }
     }
 }
@@ -0,0 +1,107 @@
+// Distortion impact - measured, not guessed
+// Apply one rule across a gene corpus and diff the address ledger
+
+use lambda_core::distortion::{distortion_impact, AddressLedger, Distortion, DistortionWeights};
+
+use crate::effects::GeneEffects;
+
+use super::egraph::IR;
+use super::hash_ir;
+use super::rules::{Rule, RuleSet};
+
+/// What one rule did to the distortion of a corpus
+#[derive(Debug, Clone)]
+pub struct RuleImpact {
+    pub rule: String,
+    pub genes: usize,
+    pub rewritten: usize,
+    /// After minus before - negative components are improvements
+    pub delta: Distortion,
+}
+
+impl RuleImpact {
+    /// Weighted distortion removed by the rule (negative if it added some)
+    pub fn reduction(&self) -> f32 {
+        -self.delta.total(&DistortionWeights::default())
+    }
+}
+
+/// Soul of a surgeon IR, in the same `λ` + 16 hex form digest uses
+pub fn soul_of(ir: &IR) -> String {
+    format!("λ{}", &hash_ir(ir)[..16])
+}
+
+/// Ledger with one address per gene
+pub fn ledger_of(corpus: &[(String, IR)]) -> AddressLedger {
+    let mut ledger = AddressLedger::new();
+    for (address, ir) in corpus {
+        ledger.record_soul(address.clone(), soul_of(ir));
+    }
+    ledger
+}
+
+/// Apply `rule` to every gene and measure how the ledger's distortion moves;
+/// `genes` holds the stored effects of the genes the corpus calls
+pub fn measure(rule: &Rule, corpus: &[(String, IR)], genes: &GeneEffects) -> RuleImpact {
+    let ledger = ledger_of(corpus);
+    let rewritten: Vec<(String, String)> = corpus.iter()
+        .filter_map(|(address, ir)| rule.rewrite(ir, genes).map(|after| (address.clone(), soul_of(&after))))
+        .collect();
+
+    RuleImpact {
+        rule: rule.id.clone(),
+        genes: corpus.len(),
+        rewritten: rewritten.len(),
+        delta: distortion_impact(&ledger, &rewritten),
+    }
+}
+
+/// Measure every rule, most distortion removed first
+pub fn measure_all(rules: &RuleSet, corpus: &[(String, IR)], genes: &GeneEffects) -> Vec<RuleImpact> {
+    let mut impacts: Vec<RuleImpact> = rules.iter().map(|rule| measure(rule, corpus, genes)).collect();
+    impacts.sort_by(|a, b| b.reduction().total_cmp(&a.reduction()).then_with(|| a.rule.cmp(&b.rule)));
+    impacts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Box<IR> {
+        Box::new(IR::Var(name.to_string()))
+    }
+
+    #[test]
+    fn test_measured_rule_impact() {
+        // The same pipeline written three times - twice unfused
+        let unfused = IR::Map(Box::new(IR::Map(var("xs"), var("f"))), var("g"));
+        let corpus = vec![
+            ("pipeline".to_string(), unfused.clone()),
+            ("pipeline_copy".to_string(), unfused),
+            ("fused".to_string(), IR::Map(var("xs"), Box::new(IR::Compose(var("g"), var("f"))))),
+            ("copy".to_string(), IR::Map(var("ys"), Box::new(IR::Id))),
+        ];
+        let rules = RuleSet::default();
+        let fusion = rules.iter().find(|r| r.id == "map_fusion").unwrap();
+        let map_id = rules.iter().find(|r| r.id == "map_id").unwrap();
+        let genes = GeneEffects::new();
+
+        // Fusion makes both copies aliases of the fused gene - no address is
+        // dropped and no version written, so only redundancy moves
+        let impact = measure(fusion, &corpus, &genes);
+        assert_eq!(impact.rewritten, 2);
+        assert!((impact.delta.redundancy - 0.25).abs() < 1e-6);
+        assert_eq!(impact.delta.instability, 0.0);
+        assert_eq!(impact.delta.nonlocality, 0.0);
+
+        // A rewrite onto a new soul moves nothing
+        let impact = measure(map_id, &corpus, &genes);
+        assert_eq!(impact.rewritten, 1);
+        assert_eq!(impact.delta.redundancy, 0.0);
+        assert_eq!(impact.delta.instability, 0.0);
+
+        let all = measure_all(&rules, &corpus, &genes);
+        assert_eq!(all.len(), rules.len());
+        assert!(all.windows(2).all(|w| w[0].reduction() >= w[1].reduction()));
+    }
+}
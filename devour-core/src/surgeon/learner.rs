@@ -254,12 +254,12 @@ impl SelfPlay {
         }
     }
     
-    pub fn improve(&self, surgeon: &mut super::Surgeon, ir: super::egraph::IR) -> Vec<super::OperationResult> {
+    pub fn improve(&self, surgeon: &mut super::Surgeon, ir: super::egraph::IR, effects: &crate::effects::Effects) -> Vec<super::OperationResult> {
         let mut results = Vec::new();
         let mut current = ir;
         
         for round in 0..self.rounds {
-            let result = surgeon.operate(&current, std::time::Duration::from_millis(100));
+            let result = surgeon.operate(&current, effects, std::time::Duration::from_millis(100));
             
             if result.improvement() < self.improvement_threshold {
                 // No significant improvement - stop
@@ -14,13 +14,16 @@ pub mod patches;
 pub mod leverage;
 pub mod proof_cache;
 pub mod hebbian;
+pub mod impact;
 
-use egraph::{EGraph, EClassId};
-use lambda_core::IR;
+use egraph::{EGraph, EClassId, IR};
+use lambda_core::distance::{normalized_distance, EditCosts};
+use lambda_core::intent::Capabilities;
 use rules::{Rule, RuleSet};
 use cost::{Cost, CostModel};
 use verifier::Verifier;
 use learner::RuleLearner;
+use crate::effects::{Effects, GeneEffects};
 
 /// The mathematical surgeon - operates on code through pure transformations
 pub struct Surgeon {
@@ -30,6 +33,10 @@ pub struct Surgeon {
     verifier: Verifier,
     learner: RuleLearner,
     experience: ExperienceDB,
+    /// What operated code may do - nothing outside it goes in or comes out
+    caps: Capabilities,
+    /// Stored effects of the genes operated code may call
+    genes: GeneEffects,
 }
 
 impl Surgeon {
@@ -41,14 +48,38 @@ impl Surgeon {
             verifier: Verifier::new(),
             learner: RuleLearner::new(),
             experience: ExperienceDB::new(),
+            caps: Capabilities::full(),
+            genes: GeneEffects::new(),
         }
     }
     
-    /// Perform surgery on IR - pure transformation
-    pub fn operate(&mut self, ir: &IR, budget: Duration) -> OperationResult {
+    /// Only operate on code whose effects `caps` grants
+    pub fn with_capabilities(mut self, caps: Capabilities) -> Self {
+        self.caps = caps;
+        self
+    }
+    
+    /// Read calls to these genes as doing what their stored effects say
+    pub fn with_gene_effects(mut self, genes: GeneEffects) -> Self {
+        self.genes = genes;
+        self
+    }
+    
+    /// Whether a gene with these effects may be operated on
+    pub fn permits(&self, effects: &Effects) -> bool {
+        effects.permitted(&self.caps)
+    }
+    
+    /// Perform surgery on the IR of a gene with stored `effects` - pure transformation
+    pub fn operate(&mut self, ir: &IR, effects: &Effects, budget: Duration) -> OperationResult {
         let start = Instant::now();
         let initial_cost = self.cost_model.compute(ir);
         
+        // Effects the intent doesn't grant are not ours to touch
+        if !self.permits(effects) || !self.permits(&Effects::of_ir_in(ir, &self.genes)) {
+            return OperationResult::unchanged(ir, initial_cost, start.elapsed());
+        }
+        
         // Add to e-graph
         self.egraph.clear();
         let root = self.egraph.add(ir.clone());
@@ -71,8 +102,11 @@ impl Surgeon {
             if iterations > 1000 { break; } // Safety bound
         }
         
-        // Extract minimal cost candidates
-        let candidates = self.egraph.extract_min_k(&self.cost_model, 3);
+        // Extract minimal cost candidates - none may bring in effects the intent denies
+        let candidates: Vec<IR> = self.egraph.extract_min_k(&self.cost_model, 3)
+            .into_iter()
+            .filter(|candidate| self.permits(&Effects::of_ir_in(candidate, &self.genes)))
+            .collect();
         
         // Verify and select best
         for candidate in candidates {
@@ -97,22 +131,14 @@ impl Surgeon {
         }
         
         // No improvement found - return original
-        OperationResult {
-            original: ir.clone(),
-            transformed: ir.clone(),
-            initial_cost,
-            final_cost: initial_cost.clone(),
-            rules_applied: vec![],
-            verified: true,
-            duration: start.elapsed(),
-        }
+        OperationResult::unchanged(ir, initial_cost, start.elapsed())
     }
     
     /// Batch operate on multiple IRs
-    pub fn operate_batch(&mut self, irs: Vec<IR>, budget: Duration) -> Vec<OperationResult> {
+    pub fn operate_batch(&mut self, irs: Vec<(IR, Effects)>, budget: Duration) -> Vec<OperationResult> {
         let budget_per_ir = budget / irs.len() as u32;
         irs.into_iter()
-            .map(|ir| self.operate(&ir, budget_per_ir))
+            .map(|(ir, effects)| self.operate(&ir, &effects, budget_per_ir))
             .collect()
     }
     
@@ -121,13 +147,20 @@ impl Surgeon {
         self.learner.discover_pattern(traces)
     }
     
+    /// Reward rules by the distortion they measurably removed
+    pub fn prioritize(&mut self, reductions: &[(String, f64)]) {
+        for (rule_id, reduction) in reductions {
+            self.learner.update(rule_id.clone(), *reduction);
+        }
+    }
+    
     /// Self-play: operate on own outputs repeatedly
-    pub fn self_improve(&mut self, ir: IR, rounds: usize) -> Vec<OperationResult> {
+    pub fn self_improve(&mut self, ir: IR, effects: &Effects, rounds: usize) -> Vec<OperationResult> {
         let mut current = ir;
         let mut results = Vec::new();
         
         for _ in 0..rounds {
-            let result = self.operate(&current, Duration::from_millis(100));
+            let result = self.operate(&current, effects, Duration::from_millis(100));
             if result.final_cost.score() >= result.initial_cost.score() {
                 break; // No more improvements
             }
@@ -151,10 +184,28 @@ pub struct OperationResult {
 }
 
 impl OperationResult {
+    /// Original handed back as is
+    fn unchanged(ir: &IR, cost: Cost, duration: Duration) -> Self {
+        OperationResult {
+            original: ir.clone(),
+            transformed: ir.clone(),
+            final_cost: cost.clone(),
+            initial_cost: cost,
+            rules_applied: vec![],
+            verified: true,
+            duration,
+        }
+    }
+    
     pub fn improvement(&self) -> f64 {
         self.initial_cost.score() - self.final_cost.score()
     }
     
+    /// How far the transformed form moved from the original (normalized tree edit distance)
+    pub fn edit_distance(&self) -> f32 {
+        normalized_distance(&self.original.to_tree(), &self.transformed.to_tree(), &EditCosts::unit())
+    }
+    
     pub fn improvement_ratio(&self) -> f64 {
         if self.initial_cost.score() == 0.0 {
             0.0
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use super::egraph::{IR, EGraph, Match};
+use crate::effects::{Effect, Effects, GeneEffects, Scope};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
@@ -291,6 +292,44 @@ impl RuleSet {
     }
 }
 
+impl Rule {
+    /// Rewrite every match, innermost first; None if the rule never fires.
+    /// Calls to `genes` carry their stored effects into the guards.
+    pub fn rewrite(&self, ir: &IR, genes: &GeneEffects) -> Option<IR> {
+        let mut fired = false;
+        let result = self.rewrite_at(ir, genes, &mut fired);
+        if fired { Some(result) } else { None }
+    }
+    
+    fn rewrite_at(&self, ir: &IR, genes: &GeneEffects, fired: &mut bool) -> IR {
+        let ir = ir.map_children(|child| self.rewrite_at(child, genes, fired));
+        let mut bindings = HashMap::new();
+        if self.pattern.matches(&ir, &mut bindings) && self.guards.iter().all(|guard| guard.holds(&bindings, genes)) {
+            *fired = true;
+            self.rewrite.instantiate(&bindings)
+        } else {
+            ir
+        }
+    }
+}
+
+impl Guard {
+    /// Whether the guard holds for a match - effects are read off the bound
+    /// IR and the stored sets of the genes it calls; type and algebraic
+    /// guards aren't decided here and hold
+    pub fn holds(&self, bindings: &HashMap<String, IR>, genes: &GeneEffects) -> bool {
+        let effects = |var: &str| bindings.get(var).map(|ir| Effects::of_ir_in(ir, genes)).unwrap_or_default();
+        match self {
+            Guard::Pure(var) => effects(var).is_pure(),
+            Guard::NoSideEffects(var) => {
+                let effects = effects(var);
+                !effects.contains(Effect::Io) && !effects.contains(Effect::Mutation(Scope::Escaping))
+            }
+            Guard::TypeEq(_, _) | Guard::Associative(_) | Guard::Commutative(_) => true,
+        }
+    }
+}
+
 impl Pattern {
     /// Get the root type for indexing
     fn root_type(&self) -> String {
@@ -359,4 +398,33 @@ impl Pattern {
             _ => IR::Nil, // Placeholder
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Box<IR> {
+        Box::new(IR::Var(name.to_string()))
+    }
+
+    #[test]
+    fn test_pure_guard_reads_effects() {
+        let rules = RuleSet::default();
+        let fusion = rules.iter().find(|r| r.id == "map_fusion").unwrap();
+
+        let none = GeneEffects::new();
+
+        let pure = IR::Map(Box::new(IR::Map(var("xs"), var("f"))), var("g"));
+        assert_eq!(fusion.rewrite(&pure, &none), Some(IR::Map(var("xs"), Box::new(IR::Compose(var("g"), var("f"))))));
+
+        // Fusing would interleave the prints with f
+        let printing = IR::Map(Box::new(IR::Map(var("xs"), var("f"))), var("print"));
+        assert_eq!(fusion.rewrite(&printing, &none), None);
+
+        // `logged` looks pure in IR, but its stored effects say it prints
+        let logging = IR::Map(Box::new(IR::Map(var("xs"), var("f"))), var("logged"));
+        assert!(fusion.rewrite(&logging, &none).is_some());
+        let genes: GeneEffects = [("logged".to_string(), [Effect::Io].into_iter().collect())].into_iter().collect();
+        assert_eq!(fusion.rewrite(&logging, &genes), None);
+    }
+}
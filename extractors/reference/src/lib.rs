@@ -0,0 +1,56 @@
+// Reference extractor - canonical λ-IR text through the extractor world
+//
+// One function per line, `name = term`, with the term in the syntax
+// `IR::to_canonical_string` prints. Blank lines and `;` comments are skipped.
+// devour-core's runtime tests load it as their fixture:
+//
+//   cargo build --release --target wasm32-wasip2
+
+wit_bindgen::generate!({
+    path: "../../wasm/extractor.wit",
+    world: "extractor",
+});
+
+mod parse;
+
+use exports::pure_lambda::extractor::extract::Guest;
+use pure_lambda::extractor::types::{Diagnostic, Extraction, Function, Severity, Span};
+
+struct Reference;
+
+impl Guest for Reference {
+    fn languages() -> Vec<String> {
+        vec!["lambda".to_string()]
+    }
+
+    fn extract(_path: String, code: String) -> Result<Extraction, Vec<Diagnostic>> {
+        let mut extraction = Extraction { functions: vec![], diagnostics: vec![] };
+        for (i, line) in code.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let span = Span { start_line: i as u32 + 1, end_line: i as u32 + 1 };
+            let parsed = line.split_once(" = ")
+                .ok_or_else(|| "expected `name = term`".to_string())
+                .and_then(|(name, term)| Ok((name.trim().to_string(), parse::ir(term)?)));
+            match parsed {
+                // Text carries no effect analysis - the host reads the IR
+                Ok((name, ir)) => extraction.functions.push(Function { name, ir, span, effects: None }),
+                Err(message) => extraction.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message,
+                    span: Some(span),
+                }),
+            }
+        }
+
+        // Nothing but errors - the file as a whole failed
+        if extraction.functions.is_empty() && !extraction.diagnostics.is_empty() {
+            return Err(extraction.diagnostics);
+        }
+        Ok(extraction)
+    }
+}
+
+export!(Reference);
@@ -0,0 +1,186 @@
+// Canonical λ-IR text to the node arena
+
+use crate::pure_lambda::extractor::types::{Ir, Node, NodeId};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    /// `λx.` - the body follows
+    Binder(String),
+    Text(String),
+    Atom(String),
+}
+
+/// Parse one term - children land in the arena before their parents
+pub fn ir(source: &str) -> Result<Ir, String> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0, nodes: vec![] };
+    let root = parser.term()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("unexpected {:?} after the term", token));
+    }
+    Ok(Ir { nodes: parser.nodes, root })
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            'λ' => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('.') => break,
+                        Some(c) if !c.is_whitespace() && c != '(' && c != ')' => name.push(c),
+                        _ => return Err(format!("unterminated binder λ{}", name)),
+                    }
+                }
+                tokens.push(Token::Binder(name));
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Text(string(&mut chars)?));
+            }
+            _ => {
+                let mut atom = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    atom.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Atom(atom));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Rest of a string literal as `{:?}` prints it, past the opening quote
+fn string(chars: &mut impl Iterator<Item = char>) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next().ok_or("unterminated string")? {
+            '"' => return Ok(text),
+            '\\' => match chars.next().ok_or("unterminated string")? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                'r' => text.push('\r'),
+                '0' => text.push('\0'),
+                'u' => {
+                    let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').collect();
+                    let code = u32::from_str_radix(&hex, 16).map_err(|_| format!("bad escape \\u{{{}}}", hex))?;
+                    text.push(char::from_u32(code).ok_or(format!("bad escape \\u{{{}}}", hex))?);
+                }
+                c => text.push(c),
+            },
+            c => text.push(c),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    nodes: Vec<Node>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("unexpected end of term")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn push(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        (self.nodes.len() - 1) as NodeId
+    }
+
+    fn term(&mut self) -> Result<NodeId, String> {
+        let node = match self.next()? {
+            Token::Binder(x) => Node::Lam((x, self.term()?)),
+            Token::Text(s) => Node::Text(s),
+            Token::Atom(atom) => atom_node(atom),
+            Token::Close => return Err("unexpected `)`".to_string()),
+            Token::Open => {
+                let node = self.form()?;
+                match self.next()? {
+                    Token::Close => {}
+                    token => return Err(format!("expected `)`, found {:?}", token)),
+                }
+                node
+            }
+        };
+        Ok(self.push(node))
+    }
+
+    /// Inside parentheses - an operator and its operands, or an application
+    fn form(&mut self) -> Result<Node, String> {
+        let head = match self.tokens.get(self.pos) {
+            Some(Token::Atom(head)) => head.clone(),
+            _ => return Ok(Node::App((self.term()?, self.term()?))),
+        };
+        let arity = match head.as_str() {
+            "const" => 1,
+            "map" | "filter" | "cons" | "+" | "-" | "*" | "/" | "%" | "=" | "<" | "<=" | "∘" | "|>" => 2,
+            "reduce" | "if" => 3,
+            "focus" => 4,
+            "opaque" => {
+                self.pos += 1;
+                return match self.next()? {
+                    Token::Text(reason) => Ok(Node::Opaque(reason)),
+                    token => Err(format!("expected an opaque reason, found {:?}", token)),
+                };
+            }
+            _ => return Ok(Node::App((self.term()?, self.term()?))),
+        };
+        self.pos += 1;
+        let args = (0..arity).map(|_| self.term()).collect::<Result<Vec<_>, _>>()?;
+        Ok(match (head.as_str(), args.as_slice()) {
+            ("const", &[a]) => Node::Constant(a),
+            ("map", &[a, b]) => Node::Map((a, b)),
+            ("filter", &[a, b]) => Node::Filter((a, b)),
+            ("cons", &[a, b]) => Node::Cons((a, b)),
+            ("+", &[a, b]) => Node::Add((a, b)),
+            ("-", &[a, b]) => Node::Sub((a, b)),
+            ("*", &[a, b]) => Node::Mul((a, b)),
+            ("/", &[a, b]) => Node::Div((a, b)),
+            ("%", &[a, b]) => Node::Modulo((a, b)),
+            ("=", &[a, b]) => Node::Eq((a, b)),
+            ("<", &[a, b]) => Node::Lt((a, b)),
+            ("<=", &[a, b]) => Node::Le((a, b)),
+            ("∘", &[a, b]) => Node::Compose((a, b)),
+            ("|>", &[a, b]) => Node::Pipe((a, b)),
+            ("reduce", &[a, b, c]) => Node::Reduce((a, b, c)),
+            ("if", &[a, b, c]) => Node::Cond((a, b, c)),
+            ("focus", &[a, b, c, d]) => Node::Focus((a, b, c, d)),
+            _ => unreachable!("operands parsed by arity"),
+        })
+    }
+}
+
+fn atom_node(atom: String) -> Node {
+    match atom.as_str() {
+        "nil" => Node::Nil,
+        "id" => Node::Id,
+        "drop" => Node::Drop,
+        "true" => Node::Boolean(true),
+        "false" => Node::Boolean(false),
+        _ => match atom.parse::<i64>() {
+            Ok(n) => Node::Num(n),
+            Err(_) => Node::Var(atom),
+        },
+    }
+}
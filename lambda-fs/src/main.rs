@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::io;
 use sha3::{Sha3_256, Digest};
+use lambda_core::protein::{self, IrGraph, OpClass, ProteinVector};
 
 mod lens;
 use lens::{Lens, ROI, Observation};
@@ -169,12 +170,21 @@ impl LambdaFS {
         // For now, return mock data based on derivation type
         match lens.derivation.as_str() {
             "wasm://proteomics#graph_spectrum" => {
-                // Mock protein vector (32 floats)
-                let mut vec = Vec::new();
-                for i in 0..32 {
-                    vec.extend_from_slice(&((i as f32 / 32.0).to_le_bytes()));
+                // Protein vector (k f32, L2-normalized)
+                let k = lens.inputs.params.get("k")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(protein::K as u64);
+                if k as usize != protein::K {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                        format!("graph_spectrum computes k={}, lens asks for k={}", protein::K, k)));
                 }
-                Ok(vec)
+                
+                let cid = inputs.get("canon")
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "lens has no canon input"))?;
+                let canon = self.store.get(cid)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, cid.clone()))?;
+                
+                Ok(ProteinVector::from_graph(&canon_graph(&canon)).to_le_bytes())
             }
             "wasm://wave#observe" => {
                 // Mock wave observation
@@ -191,6 +201,65 @@ impl LambdaFS {
     }
 }
 
+/// Graph of a gene canon: JSON tree, or an s-expression as fallback
+fn canon_graph(canon: &[u8]) -> IrGraph {
+    match serde_json::from_slice::<serde_json::Value>(canon) {
+        Ok(json) => {
+            let mut graph = IrGraph::new();
+            add_json_node(&mut graph, &json, None);
+            graph
+        }
+        Err(_) => IrGraph::from_sexpr(&String::from_utf8_lossy(canon)),
+    }
+}
+
+/// Objects are labelled by their "op"/"type"/"kind"; arrays with a string
+/// head read like s-expressions, other arrays hang off the parent
+fn add_json_node(graph: &mut IrGraph, value: &serde_json::Value, parent: Option<usize>) {
+    use serde_json::Value;
+    
+    let link = |graph: &mut IrGraph, label: OpClass| {
+        let node = graph.add_node(label);
+        if let (Some(p), Some(n)) = (parent, node) {
+            graph.add_edge(p, n);
+        }
+        node
+    };
+    
+    match value {
+        Value::Object(fields) => {
+            let head = ["op", "type", "kind"].iter()
+                .find_map(|key| fields.get(*key).and_then(|v| v.as_str()).map(|v| (*key, v)));
+            let label = head.map(|(_, name)| OpClass::from_name(name)).unwrap_or(OpClass::Other);
+            let node = link(graph, label);
+            for (key, child) in fields {
+                if head.map_or(true, |(k, _)| k != key) {
+                    add_json_node(graph, child, node.or(parent));
+                }
+            }
+        }
+        Value::Array(items) => match items.split_first() {
+            Some((Value::String(head), rest)) => {
+                let node = link(graph, OpClass::from_name(head));
+                for child in rest {
+                    add_json_node(graph, child, node.or(parent));
+                }
+            }
+            _ => {
+                for child in items {
+                    add_json_node(graph, child, parent);
+                }
+            }
+        },
+        Value::String(name) => {
+            link(graph, OpClass::from_name(name));
+        }
+        Value::Number(_) | Value::Bool(_) | Value::Null => {
+            link(graph, OpClass::Literal);
+        }
+    }
+}
+
 /// CLI for testing λFS
 fn main() {
     use clap::{Command, Arg};
@@ -304,4 +373,26 @@ fn main() {
             println!("Use 'lambda-fs help' for usage information");
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canon_graph() {
+        // Fields other than the head hang off it, in key order
+        let graph = canon_graph(br#"{"op": "map", "f": ["+", "x", 1], "xs": "xs"}"#);
+        assert_eq!(graph.labels, vec![OpClass::Map, OpClass::Arith, OpClass::Var, OpClass::Literal, OpClass::Var]);
+        assert_eq!(graph.edges, vec![(0, 1), (1, 2), (1, 3), (0, 4)]);
+
+        // An array without a string head is just a list of children
+        let graph = canon_graph(br#"{"type": "call", "args": [1, {"kind": "lambda"}]}"#);
+        assert_eq!(graph.labels, vec![OpClass::App, OpClass::Literal, OpClass::Lam]);
+        assert_eq!(graph.edges, vec![(0, 1), (0, 2)]);
+
+        // Anything else is read as an s-expression
+        let graph = canon_graph(b"(map xs (+ x 1))");
+        assert_eq!(graph.labels[0], OpClass::Map);
+        assert_eq!(graph.labels.len(), 5);
+    }
+}
@@ -0,0 +1,253 @@
+/// Distance - Zhang–Shasha tree edit distance between terms
+/// How far did a transformation move the code?
+///
+/// d(a, b) = min Σ cost(insert | delete | relabel) turning a into b
+
+use crate::ir::{IR, Arena};
+use crate::eval::children;
+use alloc::vec::Vec;
+use alloc::vec;
+
+/// Operator + payload of one node, children excluded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeLabel {
+    pub op: u8,
+    pub data: i64,
+}
+
+impl NodeLabel {
+    pub fn of(ir: &IR) -> Self {
+        let (op, data) = match ir {
+            IR::Var(s) => (0, s.0 as i64),
+            IR::Lam(s, _) => (1, s.0 as i64),
+            IR::App(_, _) => (2, 0),
+            IR::Num(n) => (3, *n),
+            IR::Bool(b) => (4, *b as i64),
+            IR::Nil => (5, 0),
+            IR::Add(_, _) => (6, 0),
+            IR::Sub(_, _) => (7, 0),
+            IR::Mul(_, _) => (8, 0),
+            IR::Div(_, _) => (9, 0),
+            IR::Eq(_, _) => (10, 0),
+            IR::Lt(_, _) => (11, 0),
+            IR::Gt(_, _) => (12, 0),
+            IR::And(_, _) => (13, 0),
+            IR::Or(_, _) => (14, 0),
+            IR::Not(_) => (15, 0),
+            IR::If(_, _, _) => (16, 0),
+            IR::Let(s, _, _) => (17, s.0 as i64),
+            IR::Ref(_) => (18, 0),
+            IR::Focus(focus) => (19, focus.mode as i64),
+            IR::Map(_, _) => (20, 0),
+            IR::Filter(_, _) => (21, 0),
+            IR::Compose(_, _) => (22, 0),
+            IR::Drop => (23, 0),
+            IR::Identity => (24, 0),
+            IR::Observe(observe) => (25, ((observe.theta as i64) << 32) | observe.phase as i64),
+        };
+        NodeLabel { op, data }
+    }
+}
+
+/// Edit operation costs
+pub struct EditCosts<L> {
+    pub insert: f32,
+    pub delete: f32,
+    pub relabel: fn(&L, &L) -> f32,
+}
+
+impl<L: PartialEq> EditCosts<L> {
+    /// Every edit costs 1, relabelling to an equal label is free
+    pub fn unit() -> Self {
+        EditCosts {
+            insert: 1.0,
+            delete: 1.0,
+            relabel: |a, b| if a == b { 0.0 } else { 1.0 },
+        }
+    }
+}
+
+impl EditCosts<NodeLabel> {
+    /// Only the shape and operators matter - names and literals are free to change
+    pub fn structural() -> Self {
+        EditCosts {
+            insert: 1.0,
+            delete: 1.0,
+            relabel: |a, b| if a.op == b.op { 0.0 } else { 1.0 },
+        }
+    }
+}
+
+/// Labelled ordered tree in post-order, with leftmost-leaf descendants
+#[derive(Debug, Clone)]
+pub struct Tree<L> {
+    labels: Vec<L>,
+    lmd: Vec<usize>,
+}
+
+impl<L> Tree<L> {
+    pub fn leaf(label: L) -> Self {
+        Tree { labels: vec![label], lmd: vec![0] }
+    }
+
+    /// Build a node from its children, left to right
+    pub fn node(label: L, children: Vec<Tree<L>>) -> Self {
+        let mut labels = Vec::new();
+        let mut lmd = Vec::new();
+        for child in children {
+            let base = labels.len();
+            labels.extend(child.labels);
+            lmd.extend(child.lmd.into_iter().map(|l| l + base));
+        }
+        // Post-order starts at the leftmost leaf, so the root's is always 0
+        lmd.push(0);
+        labels.push(label);
+        Tree { labels, lmd }
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Nodes that are the highest with their leftmost leaf, ascending
+    fn keyroots(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&i| !(i + 1..self.len()).any(|j| self.lmd[j] == self.lmd[i]))
+            .collect()
+    }
+}
+
+impl Tree<NodeLabel> {
+    /// Tree of an arena term (shared sub-terms are unfolded)
+    pub fn from_ir(ir: &IR, arena: &Arena) -> Self {
+        let kids = children(ir)
+            .into_iter()
+            .map(|child| Tree::from_ir(&arena.get(child), arena))
+            .collect();
+        Tree::node(NodeLabel::of(ir), kids)
+    }
+}
+
+/// Zhang–Shasha: O(|a|·|b|·depth²) time, O(|a|·|b|) space
+pub fn edit_distance<L>(a: &Tree<L>, b: &Tree<L>, costs: &EditCosts<L>) -> f32 {
+    let (n, m) = (a.len(), b.len());
+    if n == 0 || m == 0 {
+        return n as f32 * costs.delete + m as f32 * costs.insert;
+    }
+
+    let mut tree_dist = vec![vec![0.0f32; m]; n];
+
+    for &i in &a.keyroots() {
+        for &j in &b.keyroots() {
+            let (li, lj) = (a.lmd[i], b.lmd[j]);
+            let rows = i - li + 2;
+            let cols = j - lj + 2;
+            let mut forest = vec![vec![0.0f32; cols]; rows];
+
+            for x in 1..rows {
+                forest[x][0] = forest[x - 1][0] + costs.delete;
+            }
+            for y in 1..cols {
+                forest[0][y] = forest[0][y - 1] + costs.insert;
+            }
+
+            for x in 1..rows {
+                for y in 1..cols {
+                    let (i1, j1) = (li + x - 1, lj + y - 1);
+                    let delete = forest[x - 1][y] + costs.delete;
+                    let insert = forest[x][y - 1] + costs.insert;
+
+                    if a.lmd[i1] == li && b.lmd[j1] == lj {
+                        // Both prefixes are whole trees
+                        let relabel = forest[x - 1][y - 1] + (costs.relabel)(&a.labels[i1], &b.labels[j1]);
+                        forest[x][y] = delete.min(insert).min(relabel);
+                        tree_dist[i1][j1] = forest[x][y];
+                    } else {
+                        let (p, q) = (a.lmd[i1] - li, b.lmd[j1] - lj);
+                        let subtree = forest[p][q] + tree_dist[i1][j1];
+                        forest[x][y] = delete.min(insert).min(subtree);
+                    }
+                }
+            }
+        }
+    }
+
+    tree_dist[n - 1][m - 1]
+}
+
+/// Edit distance scaled by the larger tree, in [0, 1] for unit costs
+pub fn normalized_distance<L>(a: &Tree<L>, b: &Tree<L>, costs: &EditCosts<L>) -> f32 {
+    let size = a.len().max(b.len());
+    if size == 0 {
+        return 0.0;
+    }
+    let unit = costs.insert.max(costs.delete);
+    (edit_distance(a, b, costs) / (size as f32 * unit)).min(1.0)
+}
+
+/// Normalized unit-cost distance between two terms of one arena
+pub fn ir_distance(a: &IR, b: &IR, arena: &Arena) -> f32 {
+    normalized_distance(&Tree::from_ir(a, arena), &Tree::from_ir(b, arena), &EditCosts::unit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Symbol;
+
+    fn t(label: char, children: Vec<Tree<char>>) -> Tree<char> {
+        Tree::node(label, children)
+    }
+
+    #[test]
+    fn test_classic_example() {
+        // f(d(a, c(b)), e) → f(c(d(a, b)), e) has distance 2
+        let a = t('f', vec![
+            t('d', vec![Tree::leaf('a'), t('c', vec![Tree::leaf('b')])]),
+            Tree::leaf('e'),
+        ]);
+        let b = t('f', vec![
+            t('c', vec![t('d', vec![Tree::leaf('a'), Tree::leaf('b')])]),
+            Tree::leaf('e'),
+        ]);
+
+        assert_eq!(edit_distance(&a, &b, &EditCosts::unit()), 2.0);
+        assert_eq!(edit_distance(&a, &a, &EditCosts::unit()), 0.0);
+    }
+
+    #[test]
+    fn test_relabel_costs() {
+        let a = t('+', vec![Tree::leaf('x'), Tree::leaf('1')]);
+        let b = t('+', vec![Tree::leaf('x'), Tree::leaf('2')]);
+
+        let mut costs = EditCosts::unit();
+        assert_eq!(edit_distance(&a, &b, &costs), 1.0);
+        costs.relabel = |a, b| if a == b { 0.0 } else { 5.0 };  // Cheaper to delete + insert
+        assert_eq!(edit_distance(&a, &b, &costs), 2.0);
+        assert!((normalized_distance(&a, &b, &EditCosts::unit()) - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ir_distance() {
+        let mut arena = Arena::new();
+        let x = arena.alloc(IR::Var(Symbol(1)));
+        let y = arena.alloc(IR::Var(Symbol(2)));
+        let one = arena.alloc(IR::Num(1));
+        let a = IR::Add(x, one);
+        let b = IR::Add(y, one);
+
+        assert_eq!(ir_distance(&a, &a, &arena), 0.0);
+        assert!(ir_distance(&a, &b, &arena) > 0.0);
+
+        let structural = edit_distance(
+            &Tree::from_ir(&a, &arena),
+            &Tree::from_ir(&b, &arena),
+            &EditCosts::structural(),
+        );
+        assert_eq!(structural, 0.0);
+    }
+}
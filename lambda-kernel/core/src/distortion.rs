@@ -4,12 +4,13 @@
 /// δ = w₁·unpredictability + w₂·instability + w₃·nonlocality + w₄·redundancy
 
 use crate::soul::compute_soul;
-use crate::ir::IR;
+use crate::ir::{IR, Arena};
 use alloc::vec::Vec;
 use alloc::vec;
 use alloc::string::String;
 use alloc::format;
 use alloc::collections::BTreeMap as HashMap;
+use alloc::collections::BTreeSet;
 
 /// Components of address→value distortion
 #[derive(Debug, Clone)]
@@ -39,6 +40,16 @@ impl Distortion {
         }
     }
     
+    /// Componentwise change from self to after
+    pub fn delta(&self, after: &Distortion) -> Distortion {
+        Distortion {
+            unpredictability: after.unpredictability - self.unpredictability,
+            instability: after.instability - self.instability,
+            nonlocality: after.nonlocality - self.nonlocality,
+            redundancy: after.redundancy - self.redundancy,
+        }
+    }
+    
     /// Measure improvement
     pub fn improvement(&self, after: &Distortion) -> f32 {
         let before_sum = self.unpredictability + self.instability + 
@@ -74,8 +85,13 @@ impl DistortionWeights {
 }
 
 /// Address→Soul→Value ledger
+/// Every write is stamped with a logical time; redirects form an explicit graph
+#[derive(Debug, Clone)]
 pub struct AddressLedger {
-    entries: Vec<LedgerEntry>,
+    clock: u64,
+    entries: Vec<LedgerEntry>,                   // One per soul
+    history: HashMap<String, Vec<Version>>,      // Address → souls it held, oldest first
+    redirects: HashMap<String, Vec<String>>,     // Address → addresses it points at
 }
 
 #[derive(Debug, Clone)]
@@ -87,7 +103,7 @@ pub struct LedgerEntry {
     pub aliases: Vec<String>,  // Other addresses for same soul
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ValueType {
     Direct,           // Address maps directly to value
     Lens,            // Address is a lens/view
@@ -95,70 +111,223 @@ pub enum ValueType {
     Merged,          // Multiple addresses merged here
 }
 
+/// One address pair and its share of a distortion component
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+    pub component: Component,
+    pub address: String,
+    pub target: String,   // Soul, or the address the value resolves to
+    pub share: f32,       // Shares of a component sum to its value
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Component {
+    Unpredictability,
+    Instability,
+    Nonlocality,
+    Redundancy,
+}
+
+/// What an address held at a logical time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    pub time: u64,
+    pub soul: String,
+}
+
 impl AddressLedger {
     pub fn new() -> Self {
         AddressLedger {
+            clock: 0,
             entries: Vec::new(),
+            history: HashMap::new(),
+            redirects: HashMap::new(),
         }
     }
     
+    /// Current logical time
+    pub fn now(&self) -> u64 {
+        self.clock
+    }
+    
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+    
+    /// Versions written at an address, oldest first
+    pub fn history(&self, address: &str) -> &[Version] {
+        self.history.get(address).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+    
     /// Add entry to ledger
-    pub fn record(&mut self, address: String, ir: &IR) -> LedgerEntry {
-        let soul = compute_soul(ir);
-        
-        // Check if soul already exists
-        let existing = self.entries.iter()
-            .find(|e| e.soul == soul)
-            .cloned();
-        
-        let entry = if let Some(mut existing) = existing {
-            // Soul exists - this is an alias
-            existing.aliases.push(address.clone());
-            existing.value_type = ValueType::Merged;
-            existing
-        } else {
-            // New soul
-            LedgerEntry {
-                address: address.clone(),
-                soul: soul.clone(),
-                value_type: ValueType::Direct,
-                proof: None,
-                aliases: vec![],
+    pub fn record(&mut self, address: String, ir: &IR, arena: &mut Arena) -> LedgerEntry {
+        let soul = format!("{:016x}", compute_soul(ir, arena));
+        self.record_soul(address, soul)
+    }
+    
+    /// Add entry for an already computed soul
+    pub fn record_soul(&mut self, address: String, soul: String) -> LedgerEntry {
+        self.clock += 1;
+        self.history.entry(address.clone()).or_default()
+            .push(Version { time: self.clock, soul: soul.clone() });
+        
+        let index = self.place(address, soul);
+        self.entries[index].clone()
+    }
+    
+    /// The ledger as if each (address, soul) had been recorded in place of
+    /// what the address holds now - no new version is written. An address
+    /// rewritten onto a soul another address holds becomes its alias.
+    pub fn rewrite(&self, rewritten: &[(String, String)]) -> AddressLedger {
+        let mut after = self.clone();
+        for (address, soul) in rewritten {
+            if let Some(latest) = after.history.get_mut(address).and_then(|v| v.last_mut()) {
+                latest.soul = soul.clone();
+            }
+            after.place(address.clone(), soul.clone());
+        }
+        after
+    }
+    
+    /// Make `address` hold `soul`; returns the index of its entry
+    fn place(&mut self, address: String, soul: String) -> usize {
+        // Writing a value ends any redirect; rewriting moves the address
+        self.redirects.remove(&address);
+        if self.holder(&address).map(|i| &self.entries[i].soul) != Some(&soul) {
+            self.detach(&address);
+        }
+        
+        let index = match self.entries.iter().position(|e| e.soul == soul) {
+            Some(i) => {
+                // Soul exists - this is an alias, stored once
+                let existing = &mut self.entries[i];
+                if existing.address != address && !existing.aliases.contains(&address) {
+                    existing.aliases.push(address);
+                    existing.value_type = ValueType::Merged;
+                }
+                i
+            }
+            None => {
+                // New soul
+                self.entries.push(LedgerEntry {
+                    address,
+                    soul,
+                    value_type: ValueType::Direct,
+                    proof: None,
+                    aliases: vec![],
+                });
+                self.entries.len() - 1
             }
         };
+        index
+    }
+    
+    /// Point `from` at `to` - `from` stops holding a value of its own
+    pub fn redirect(&mut self, from: String, to: String) {
+        self.clock += 1;
+        self.detach(&from);
+        let targets = self.redirects.entry(from).or_default();
+        if !targets.contains(&to) {
+            targets.push(to);
+        }
+    }
+    
+    /// Follow redirects (shortest path) to the entry that holds the value
+    pub fn resolve(&self, address: &str) -> Option<(&LedgerEntry, usize)> {
+        let mut frontier = vec![String::from(address)];
+        let mut seen = BTreeSet::new();
+        seen.insert(String::from(address));
         
-        self.entries.push(entry.clone());
-        entry
+        for hops in 0..=self.redirects.len() {
+            let mut next = Vec::new();
+            for addr in &frontier {
+                if let Some(i) = self.holder(addr) {
+                    return Some((&self.entries[i], hops));
+                }
+                for target in self.redirects.get(addr).into_iter().flatten() {
+                    if seen.insert(target.clone()) {
+                        next.push(target.clone());
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        None
+    }
+    
+    /// Entry whose soul is held at this address (as primary or alias)
+    fn holder(&self, address: &str) -> Option<usize> {
+        self.entries.iter()
+            .position(|e| e.address == address || e.aliases.iter().any(|a| a == address))
+    }
+    
+    /// Remove an address from whatever entry holds it
+    fn detach(&mut self, address: &str) {
+        let i = match self.holder(address) {
+            Some(i) => i,
+            None => return,
+        };
+        let entry = &mut self.entries[i];
+        
+        if entry.address == address {
+            if entry.aliases.is_empty() {
+                self.entries.remove(i);
+                return;
+            }
+            entry.address = entry.aliases.remove(0);
+        } else {
+            entry.aliases.retain(|a| a != address);
+        }
+        if entry.aliases.is_empty() && entry.value_type == ValueType::Merged {
+            entry.value_type = ValueType::Direct;
+        }
+    }
+    
+    /// Addresses that hold a value directly
+    fn value_addresses(&self) -> impl Iterator<Item = (&String, &LedgerEntry)> {
+        self.entries.iter()
+            .flat_map(|e| core::iter::once(&e.address).chain(e.aliases.iter()).map(move |a| (a, e)))
     }
     
     /// Compute distortion metrics
     pub fn compute_distortion(&self) -> Distortion {
-        let total = self.entries.len() as f32;
+        let values = self.value_addresses().count() as f32;
+        let total = values + self.redirects.len() as f32;
         if total == 0.0 {
             return Distortion::zero();
         }
         
         // δ₁: Unpredictability - how often address doesn't match expected soul
-        let unexpected = self.entries.iter()
-            .filter(|e| !self.is_predictable(&e.address, &e.soul))
+        let unexpected = self.value_addresses()
+            .filter(|(address, e)| !self.is_predictable(address, &e.soul))
             .count() as f32;
-        let unpredictability = unexpected / total;
+        let unpredictability = if values > 0.0 { unexpected / values } else { 0.0 };
         
-        // δ₂: Instability - would track changes over time
-        // For now, simplified
-        let instability = 0.0;
+        // δ₂: Instability - fraction of writes that changed the soul, per address
+        let instability = if self.history.is_empty() {
+            0.0
+        } else {
+            self.history.values()
+                .map(|versions| {
+                    let changes = versions.windows(2).filter(|w| w[0].soul != w[1].soul).count();
+                    if versions.len() > 1 { changes as f32 / (versions.len() - 1) as f32 } else { 0.0 }
+                })
+                .sum::<f32>() / self.history.len() as f32
+        };
         
-        // δ₃: Nonlocality - indirection levels
-        let total_hops: f32 = self.entries.iter()
-            .map(|e| self.count_hops(e) as f32)
+        // δ₃: Nonlocality - shortest redirect path to the value (+1 through a lens)
+        let total_hops: f32 = self.value_addresses().map(|(a, _)| a)
+            .chain(self.redirects.keys())
+            .map(|a| self.count_hops(a) as f32)
             .sum();
         let nonlocality = total_hops / total;
         
         // δ₄: Redundancy - multiple addresses per soul
-        let souls: alloc::collections::BTreeSet<_> = 
-            self.entries.iter().map(|e| &e.soul).collect();
-        let redundancy = if souls.len() > 0 {
-            (total - souls.len() as f32) / total
+        let redundancy = if values > 0.0 {
+            (values - self.entries.len() as f32) / values
         } else {
             0.0
         };
@@ -171,17 +340,72 @@ impl AddressLedger {
         }
     }
     
+    /// Itemize the address pairs behind each component of `compute_distortion`
+    pub fn contributions(&self) -> Vec<Contribution> {
+        let values = self.value_addresses().count() as f32;
+        let total = values + self.redirects.len() as f32;
+        let mut items = Vec::new();
+        
+        for (address, entry) in self.value_addresses() {
+            if !self.is_predictable(address, &entry.soul) {
+                items.push(Contribution {
+                    component: Component::Unpredictability,
+                    address: address.clone(),
+                    target: entry.soul.clone(),
+                    share: 1.0 / values,
+                });
+            }
+            if *address != entry.address {
+                items.push(Contribution {
+                    component: Component::Redundancy,
+                    address: address.clone(),
+                    target: entry.address.clone(),
+                    share: 1.0 / values,
+                });
+            }
+        }
+        
+        for (address, versions) in &self.history {
+            let changes = versions.windows(2).filter(|w| w[0].soul != w[1].soul).count();
+            if changes > 0 {
+                items.push(Contribution {
+                    component: Component::Instability,
+                    address: address.clone(),
+                    target: versions[versions.len() - 1].soul.clone(),
+                    share: changes as f32 / (versions.len() - 1) as f32 / self.history.len() as f32,
+                });
+            }
+        }
+        
+        for address in self.value_addresses().map(|(a, _)| a).chain(self.redirects.keys()) {
+            let hops = self.count_hops(address);
+            if hops > 0 {
+                let target = self.resolve(address)
+                    .map(|(entry, _)| entry.address.clone())
+                    .unwrap_or_default();
+                items.push(Contribution {
+                    component: Component::Nonlocality,
+                    address: address.clone(),
+                    target,
+                    share: hops as f32 / total,
+                });
+            }
+        }
+        
+        items
+    }
+    
     fn is_predictable(&self, address: &str, soul: &str) -> bool {
         // Simple heuristic: address should hint at soul
-        address.contains(&soul[..8]) || soul.contains(&address[..3.min(address.len())])
+        let prefix: String = address.chars().take(3).collect();
+        address.contains(soul_prefix(soul)) || soul.contains(prefix.as_str())
     }
     
-    fn count_hops(&self, entry: &LedgerEntry) -> usize {
-        match entry.value_type {
-            ValueType::Direct => 0,
-            ValueType::Lens => 1,
-            ValueType::Redirect => 2,
-            ValueType::Merged => entry.aliases.len(),
+    fn count_hops(&self, address: &str) -> usize {
+        match self.resolve(address) {
+            Some((entry, hops)) => hops + (entry.value_type == ValueType::Lens) as usize,
+            // Dangling or cyclic: every address on the way is a wasted hop
+            None => self.redirects.len().max(1),
         }
     }
     
@@ -197,47 +421,59 @@ impl AddressLedger {
         self.align_names();
     }
     
+    /// Aliases stop holding copies and point at the canonical address
     fn merge_redundant(&mut self) {
-        // Group by soul
-        let mut by_soul: HashMap<String, Vec<usize>> = 
-            HashMap::new();
-            
-        for (i, entry) in self.entries.iter().enumerate() {
-            by_soul.entry(entry.soul.clone()).or_default().push(i);
-        }
-        
-        // Merge entries with same soul
-        for (soul, indices) in by_soul {
-            if indices.len() > 1 {
-                // Keep first, mark others as merged
-                for &i in &indices[1..] {
-                    self.entries[i].value_type = ValueType::Merged;
-                }
-            }
+        let merges: Vec<(String, String)> = self.entries.iter()
+            .flat_map(|e| e.aliases.iter().map(move |a| (a.clone(), e.address.clone())))
+            .collect();
+        for (alias, canonical) in merges {
+            self.redirect(alias, canonical);
         }
     }
     
+    /// Every redirect points straight at the address that holds the value
     fn flatten_redirects(&mut self) {
-        // Convert multi-hop redirects to direct
-        for entry in &mut self.entries {
-            if matches!(entry.value_type, ValueType::Redirect) {
-                entry.value_type = ValueType::Direct;
+        let sources: Vec<String> = self.redirects.keys().cloned().collect();
+        for source in sources {
+            if let Some((entry, hops)) = self.resolve(&source) {
+                if hops > 1 {
+                    let target = entry.address.clone();
+                    self.redirects.insert(source, vec![target]);
+                }
             }
         }
     }
     
+    /// Rename to include soul prefix; the old name redirects to the new one
     fn align_names(&mut self) {
-        // Rename to include soul prefix
-        for entry in &mut self.entries {
-            if !entry.address.contains(&entry.soul[..8]) {
-                let aligned = format!("{}_{}", &entry.soul[..8], entry.address);
-                entry.aliases.push(entry.address.clone());
-                entry.address = aligned;
+        let renames: Vec<(String, String, String)> = self.entries.iter()
+            .filter(|e| !e.address.contains(soul_prefix(&e.soul)))
+            .map(|e| (e.address.clone(), format!("{}_{}", soul_prefix(&e.soul), e.address), e.soul.clone()))
+            .collect();
+        
+        for (old, aligned, soul) in renames {
+            self.record_soul(aligned.clone(), soul);
+            self.redirect(old.clone(), aligned.clone());
+            // Keep redirect sources pointing one hop from the value
+            for targets in self.redirects.values_mut() {
+                for target in targets.iter_mut() {
+                    if *target == old {
+                        *target = aligned.clone();
+                    }
+                }
             }
         }
     }
 }
 
+/// First 8 characters of a soul (souls may start with a multi-byte λ)
+fn soul_prefix(soul: &str) -> &str {
+    match soul.char_indices().nth(8) {
+        Some((i, _)) => &soul[..i],
+        None => soul,
+    }
+}
+
 /// Distortion meter - measures before/after
 pub struct DistortionMeter {
     pub before: Distortion,
@@ -291,49 +527,29 @@ impl DistortionMeter {
     }
 }
 
-/// How our optimization rules reduce distortion
-pub fn distortion_impact(rule: &str) -> Distortion {
-    match rule {
-        "fold_sink" => Distortion {
-            unpredictability: -0.1,  // More direct computation
-            instability: -0.05,      // Less intermediate state
-            nonlocality: -0.3,       // Removes intermediate addresses
-            redundancy: -0.2,        // Merges operations
-        },
-        "focus_fusion" => Distortion {
-            unpredictability: -0.05,
-            instability: 0.0,
-            nonlocality: -0.4,       // Direct ROI access
-            redundancy: -0.1,
-        },
-        "early_stop" => Distortion {
-            unpredictability: -0.02,
-            instability: -0.3,       // Stops value drift
-            nonlocality: 0.0,
-            redundancy: 0.0,
-        },
-        "content_address" => Distortion {
-            unpredictability: -0.5,  // Perfect predictability
-            instability: -0.4,       // Immutable by hash
-            nonlocality: -0.2,       // Direct access
-            redundancy: -0.3,        // Automatic dedup
-        },
-        _ => Distortion::zero(),
-    }
+/// Measured impact of a rewrite: rebuild the ledger from the rewritten souls and diff
+///
+/// `rewritten` holds (address, soul after the rewrite); positive components
+/// mean the rewrite made distortion worse.
+pub fn distortion_impact(ledger: &AddressLedger, rewritten: &[(String, String)]) -> Distortion {
+    ledger.compute_distortion().delta(&ledger.rewrite(rewritten).compute_distortion())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::Symbol;
+    use alloc::string::ToString;
     
     #[test]
     fn test_distortion_measurement() {
+        let mut arena = Arena::new();
         let mut ledger = AddressLedger::new();
         
         // Add some entries
-        ledger.record("map".to_string(), &IR::Var(42));
-        ledger.record("filter".to_string(), &IR::Var(42)); // Same soul!
-        ledger.record("fold".to_string(), &IR::Var(23));
+        ledger.record("map".to_string(), &IR::Var(Symbol(42)), &mut arena);
+        ledger.record("filter".to_string(), &IR::Var(Symbol(42)), &mut arena); // Same soul!
+        ledger.record("fold".to_string(), &IR::Var(Symbol(23)), &mut arena);
         
         let distortion = ledger.compute_distortion();
         
@@ -343,9 +559,10 @@ mod tests {
     
     #[test]
     fn test_distortion_reduction() {
+        let mut arena = Arena::new();
         let mut before = AddressLedger::new();
-        before.record("f1".to_string(), &IR::Var(1));
-        before.record("f2".to_string(), &IR::Var(1)); // Redundant
+        before.record("f1".to_string(), &IR::Var(Symbol(1)), &mut arena);
+        before.record("f2".to_string(), &IR::Var(Symbol(1)), &mut arena); // Redundant
         
         let mut after = before.clone();
         after.reduce_distortion();
@@ -353,4 +570,102 @@ mod tests {
         let meter = DistortionMeter::measure(&before, &after);
         assert!(meter.after.redundancy < meter.before.redundancy);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_aliases_stored_once() {
+        let mut ledger = AddressLedger::new();
+        ledger.record_soul("map".to_string(), "λ00000001".to_string());
+        ledger.record_soul("collect".to_string(), "λ00000001".to_string());
+        ledger.record_soul("collect".to_string(), "λ00000001".to_string());
+        
+        assert_eq!(ledger.entries().len(), 1);
+        assert_eq!(ledger.entries()[0].aliases, vec!["collect".to_string()]);
+        assert_eq!(ledger.history("collect").len(), 2);
+    }
+    
+    #[test]
+    fn test_instability() {
+        let mut ledger = AddressLedger::new();
+        ledger.record_soul("latest".to_string(), "aaaaaaaa".to_string());
+        ledger.record_soul("latest".to_string(), "bbbbbbbb".to_string());
+        ledger.record_soul("latest".to_string(), "bbbbbbbb".to_string());
+        
+        // One change in two rewrites
+        assert_eq!(ledger.compute_distortion().instability, 0.5);
+        assert_eq!(ledger.history("latest")[1], Version { time: 2, soul: "bbbbbbbb".to_string() });
+        assert_eq!(ledger.entries().len(), 1);
+    }
+    
+    #[test]
+    fn test_redirect_shortest_path() {
+        let mut ledger = AddressLedger::new();
+        ledger.record_soul("map".to_string(), "λ00000001".to_string());
+        ledger.redirect("a".to_string(), "b".to_string());
+        ledger.redirect("b".to_string(), "c".to_string());
+        ledger.redirect("c".to_string(), "map".to_string());
+        ledger.redirect("a".to_string(), "map".to_string());  // Shortcut
+        
+        assert_eq!(ledger.resolve("b").map(|(_, hops)| hops), Some(2));
+        assert_eq!(ledger.resolve("a").map(|(_, hops)| hops), Some(1));
+        
+        let before = ledger.compute_distortion().nonlocality;
+        ledger.flatten_redirects();
+        assert_eq!(ledger.resolve("b").map(|(_, hops)| hops), Some(1));
+        assert!(ledger.compute_distortion().nonlocality < before);
+    }
+    
+    #[test]
+    fn test_contributions_sum_to_distortion() {
+        let mut ledger = AddressLedger::new();
+        ledger.record_soul("map".to_string(), "λ00000001".to_string());
+        ledger.record_soul("collect".to_string(), "λ00000001".to_string());
+        ledger.record_soul("latest".to_string(), "λ00000002".to_string());
+        ledger.record_soul("latest".to_string(), "λ00000003".to_string());
+        ledger.redirect("a".to_string(), "b".to_string());
+        ledger.redirect("b".to_string(), "map".to_string());
+        
+        let distortion = ledger.compute_distortion();
+        let items = ledger.contributions();
+        let sum = |c: Component| items.iter().filter(|i| i.component == c).map(|i| i.share).sum::<f32>();
+        
+        assert!((sum(Component::Unpredictability) - distortion.unpredictability).abs() < 1e-6);
+        assert!((sum(Component::Instability) - distortion.instability).abs() < 1e-6);
+        assert!((sum(Component::Nonlocality) - distortion.nonlocality).abs() < 1e-6);
+        assert!((sum(Component::Redundancy) - distortion.redundancy).abs() < 1e-6);
+        assert!(items.contains(&Contribution {
+            component: Component::Redundancy,
+            address: "collect".to_string(),
+            target: "map".to_string(),
+            share: 1.0 / 3.0,
+        }));
+    }
+    
+    #[test]
+    fn test_measured_impact() {
+        let mut ledger = AddressLedger::new();
+        ledger.record_soul("double".to_string(), "λ00000001".to_string());
+        ledger.record_soul("twice".to_string(), "λ00000002".to_string());
+        ledger.record_soul("doubled".to_string(), "λ00000002".to_string());
+        
+        // Rewriting all three to one normal form: every address stays, as an
+        // alias of the one entry, and keeps its history
+        let fused = [
+            ("double".to_string(), "λ00000003".to_string()),
+            ("twice".to_string(), "λ00000003".to_string()),
+            ("doubled".to_string(), "λ00000003".to_string()),
+        ];
+        let after = ledger.rewrite(&fused);
+        assert_eq!(after.entries().len(), 1);
+        assert_eq!(after.entries()[0].aliases, vec!["twice".to_string(), "doubled".to_string()]);
+        assert_eq!(after.history("twice"), &[Version { time: 2, soul: "λ00000003".to_string() }]);
+        let delta = distortion_impact(&ledger, &fused);
+        assert!((delta.redundancy - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(delta.instability, 0.0);
+        assert_eq!(delta.nonlocality, 0.0);
+        
+        // A rewrite that leaves souls alone changes nothing
+        let unchanged = [("double".to_string(), "λ00000001".to_string())];
+        let delta = distortion_impact(&ledger, &unchanged);
+        assert_eq!(delta.total(&DistortionWeights::default()), 0.0);
+    }
+}
@@ -0,0 +1,485 @@
+/// Evaluator - Run λ-IR on concrete values
+/// Laws are checked by observation: same input, compare what comes out
+///
+/// eval(ir, env) → Value, bounded by fuel so recursion cannot hang us
+
+use crate::ir::{IR, Symbol, Arena};
+use crate::focus::{Focus, FocusMode};
+use alloc::vec::Vec;
+use alloc::vec;
+use alloc::boxed::Box;
+
+/// Default step budget for a single evaluation
+pub const DEFAULT_FUEL: usize = 10_000;
+
+/// Runtime values
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Num(i64),
+    Bool(bool),
+    List(Vec<Value>),
+    Closure(Closure),
+    Identity,
+    Drop,
+    Compose(Box<Value>, Box<Value>),  // f ∘ g
+    Both(Box<Value>, Box<Value>),     // p ∧ q as a predicate
+}
+
+impl Value {
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Value::List(items) => Some(items.len()),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    pub fn truthy(&self) -> Result<bool, EvalError> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            Value::Num(n) => Ok(*n != 0),  // Fixed-point weights: 0 = out
+            _ => Err(EvalError::Type("expected bool")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub param: Symbol,
+    pub body: u32,
+    pub env: Env,
+}
+
+/// Variable bindings, innermost last
+pub type Env = Vec<(Symbol, Value)>;
+
+/// One generated input: a value for every free variable
+pub type Input = Vec<(Symbol, Value)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    Unbound(Symbol),
+    Type(&'static str),
+    DivByZero,
+    OutOfFuel,
+    Unsupported(&'static str),
+}
+
+pub struct Evaluator<'a> {
+    arena: &'a Arena,
+    fuel: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(arena: &'a Arena) -> Self {
+        Evaluator { arena, fuel: DEFAULT_FUEL }
+    }
+
+    pub fn with_fuel(arena: &'a Arena, fuel: usize) -> Self {
+        Evaluator { arena, fuel }
+    }
+
+    pub fn eval(&mut self, ir: &IR, env: &Env) -> Result<Value, EvalError> {
+        if self.fuel == 0 {
+            return Err(EvalError::OutOfFuel);
+        }
+        self.fuel -= 1;
+
+        match ir {
+            IR::Var(x) => env.iter()
+                .rev()
+                .find(|(s, _)| s == x)
+                .map(|(_, v)| v.clone())
+                .ok_or(EvalError::Unbound(*x)),
+            IR::Lam(x, body) => Ok(Value::Closure(Closure {
+                param: *x,
+                body: *body,
+                env: env.clone(),
+            })),
+            IR::App(f, a) => {
+                let f = self.eval_idx(*f, env)?;
+                let a = self.eval_idx(*a, env)?;
+                self.apply(&f, a)
+            }
+
+            IR::Num(n) => Ok(Value::Num(*n)),
+            IR::Bool(b) => Ok(Value::Bool(*b)),
+            IR::Nil => Ok(Value::List(vec![])),
+
+            IR::Add(a, b) => self.arith(*a, *b, env, |x, y| Ok(x.wrapping_add(y))),
+            IR::Sub(a, b) => self.arith(*a, *b, env, |x, y| Ok(x.wrapping_sub(y))),
+            IR::Mul(a, b) => self.arith(*a, *b, env, |x, y| Ok(x.wrapping_mul(y))),
+            IR::Div(a, b) => self.arith(*a, *b, env, |x, y| {
+                if y == 0 { Err(EvalError::DivByZero) } else { Ok(x.wrapping_div(y)) }
+            }),
+            IR::Eq(a, b) => {
+                let a = self.eval_idx(*a, env)?;
+                let b = self.eval_idx(*b, env)?;
+                Ok(Value::Bool(a == b))
+            }
+            IR::Lt(a, b) => self.compare(*a, *b, env, |x, y| x < y),
+            IR::Gt(a, b) => self.compare(*a, *b, env, |x, y| x > y),
+
+            IR::And(a, b) => {
+                let a = self.eval_idx(*a, env)?;
+                match a {
+                    Value::Bool(false) => Ok(Value::Bool(false)),
+                    Value::Bool(true) => {
+                        let b = self.eval_idx(*b, env)?;
+                        Ok(Value::Bool(b.truthy()?))
+                    }
+                    // w1 ∧ w2 over predicates (FOCUS fusion)
+                    p => {
+                        let q = self.eval_idx(*b, env)?;
+                        Ok(Value::Both(Box::new(p), Box::new(q)))
+                    }
+                }
+            }
+            IR::Or(a, b) => {
+                if self.eval_idx(*a, env)?.truthy()? {
+                    Ok(Value::Bool(true))
+                } else {
+                    Ok(Value::Bool(self.eval_idx(*b, env)?.truthy()?))
+                }
+            }
+            IR::Not(x) => Ok(Value::Bool(!self.eval_idx(*x, env)?.truthy()?)),
+
+            IR::If(c, t, e) => {
+                if self.eval_idx(*c, env)?.truthy()? {
+                    self.eval_idx(*t, env)
+                } else {
+                    self.eval_idx(*e, env)
+                }
+            }
+            IR::Let(x, e, body) => {
+                let value = self.eval_idx(*e, env)?;
+                let mut inner = env.clone();
+                inner.push((*x, value));
+                self.eval_idx(*body, &inner)
+            }
+            IR::Ref(idx) => self.eval_idx(*idx, env),
+
+            IR::Focus(focus) => self.eval_focus(focus, env),
+            IR::Map(f, xs) => {
+                let f = self.eval_idx(*f, env)?;
+                let items = self.eval_list(*xs, env)?;
+                let mut out = Vec::with_capacity(items.len());
+                for x in items {
+                    out.push(self.apply(&f, x)?);
+                }
+                Ok(Value::List(out))
+            }
+            IR::Filter(p, xs) => {
+                let p = self.eval_idx(*p, env)?;
+                let items = self.eval_list(*xs, env)?;
+                let mut out = Vec::new();
+                for x in items {
+                    if self.apply(&p, x.clone())?.truthy()? {
+                        out.push(x);
+                    }
+                }
+                Ok(Value::List(out))
+            }
+            IR::Compose(f, g) => {
+                let f = self.eval_idx(*f, env)?;
+                let g = self.eval_idx(*g, env)?;
+                Ok(Value::Compose(Box::new(f), Box::new(g)))
+            }
+            IR::Drop => Ok(Value::Drop),
+            IR::Identity => Ok(Value::Identity),
+
+            IR::Observe(_) => Err(EvalError::Unsupported("observe")),
+        }
+    }
+
+    /// Apply a function value to an argument
+    pub fn apply(&mut self, f: &Value, arg: Value) -> Result<Value, EvalError> {
+        match f {
+            Value::Closure(c) => {
+                let mut env = c.env.clone();
+                env.push((c.param, arg));
+                self.eval_idx(c.body, &env)
+            }
+            Value::Identity => Ok(arg),
+            Value::Compose(f, g) => {
+                let inner = self.apply(g, arg)?;
+                self.apply(f, inner)
+            }
+            Value::Both(p, q) => {
+                let left = self.apply(p, arg.clone())?.truthy()?;
+                Ok(Value::Bool(left && self.apply(q, arg)?.truthy()?))
+            }
+            Value::Drop => Err(EvalError::Type("drop has no value")),
+            _ => Err(EvalError::Type("not a function")),
+        }
+    }
+
+    fn eval_idx(&mut self, idx: u32, env: &Env) -> Result<Value, EvalError> {
+        let ir = self.arena.get(idx);
+        self.eval(&ir, env)
+    }
+
+    fn eval_list(&mut self, idx: u32, env: &Env) -> Result<Vec<Value>, EvalError> {
+        match self.eval_idx(idx, env)? {
+            Value::List(items) => Ok(items),
+            _ => Err(EvalError::Type("expected list")),
+        }
+    }
+
+    fn arith(
+        &mut self,
+        a: u32,
+        b: u32,
+        env: &Env,
+        op: fn(i64, i64) -> Result<i64, EvalError>,
+    ) -> Result<Value, EvalError> {
+        match (self.eval_idx(a, env)?, self.eval_idx(b, env)?) {
+            (Value::Num(x), Value::Num(y)) => Ok(Value::Num(op(x, y)?)),
+            _ => Err(EvalError::Type("expected numbers")),
+        }
+    }
+
+    fn compare(&mut self, a: u32, b: u32, env: &Env, op: fn(i64, i64) -> bool) -> Result<Value, EvalError> {
+        match (self.eval_idx(a, env)?, self.eval_idx(b, env)?) {
+            (Value::Num(x), Value::Num(y)) => Ok(Value::Bool(op(x, y))),
+            _ => Err(EvalError::Type("expected numbers")),
+        }
+    }
+
+    /// FOCUS semantics:
+    /// - Hard: w(x) gates, f inside, g outside (DROP removes)
+    /// - Soft: w(x) ∈ [0,256] blends f(x) and g(x) in fixed-point
+    /// - Spatial: w(i) over the element index, f inside, g outside
+    fn eval_focus(&mut self, focus: &Focus, env: &Env) -> Result<Value, EvalError> {
+        let items = self.eval_list(focus.xs, env)?;
+        let w = self.eval_idx(focus.w, env)?;
+        let f = self.eval_idx(focus.f, env)?;
+        let g = self.eval_idx(focus.g, env)?;
+
+        let mut out = Vec::with_capacity(items.len());
+        for (i, x) in items.into_iter().enumerate() {
+            match focus.mode {
+                FocusMode::Hard | FocusMode::Spatial => {
+                    let gate = if focus.mode == FocusMode::Hard {
+                        self.apply(&w, x.clone())?
+                    } else {
+                        self.apply(&w, Value::Num(i as i64))?
+                    };
+                    if gate.truthy()? {
+                        out.push(self.apply(&f, x)?);
+                    } else if g != Value::Drop {
+                        out.push(self.apply(&g, x)?);
+                    }
+                }
+                FocusMode::Soft => {
+                    let weight = match self.apply(&w, x.clone())? {
+                        Value::Num(n) => n.clamp(0, 256),
+                        Value::Bool(b) => if b { 256 } else { 0 },
+                        _ => return Err(EvalError::Type("expected weight")),
+                    };
+                    let inside = self.apply(&f, x.clone())?;
+                    let outside = self.apply(&g, x)?;
+                    match (inside, outside) {
+                        (Value::Num(a), Value::Num(b)) => {
+                            out.push(Value::Num((weight * a + (256 - weight) * b) / 256));
+                        }
+                        _ => return Err(EvalError::Type("soft focus blends numbers")),
+                    }
+                }
+            }
+        }
+        Ok(Value::List(out))
+    }
+}
+
+/// Evaluate a closed term under the given input bindings
+pub fn eval(ir: &IR, arena: &Arena, input: &Input) -> Result<Value, EvalError> {
+    Evaluator::new(arena).eval(ir, input)
+}
+
+/// Free variables of a term, in order of first occurrence
+pub fn free_vars(ir: &IR, arena: &Arena) -> Vec<Symbol> {
+    let mut bound = Vec::new();
+    let mut free = Vec::new();
+    collect_free(ir, arena, &mut bound, &mut free);
+    free
+}
+
+fn collect_free(ir: &IR, arena: &Arena, bound: &mut Vec<Symbol>, free: &mut Vec<Symbol>) {
+    let visit = |idx: u32, bound: &mut Vec<Symbol>, free: &mut Vec<Symbol>| {
+        collect_free(&arena.get(idx), arena, bound, free)
+    };
+
+    match ir {
+        IR::Var(x) => {
+            if !bound.contains(x) && !free.contains(x) {
+                free.push(*x);
+            }
+        }
+        IR::Lam(x, body) => {
+            bound.push(*x);
+            visit(*body, bound, free);
+            bound.pop();
+        }
+        IR::Let(x, e, body) => {
+            visit(*e, bound, free);
+            bound.push(*x);
+            visit(*body, bound, free);
+            bound.pop();
+        }
+        IR::App(a, b) | IR::Add(a, b) | IR::Sub(a, b) | IR::Mul(a, b) |
+        IR::Div(a, b) | IR::Eq(a, b) | IR::Lt(a, b) | IR::Gt(a, b) |
+        IR::And(a, b) | IR::Or(a, b) | IR::Map(a, b) | IR::Filter(a, b) |
+        IR::Compose(a, b) => {
+            visit(*a, bound, free);
+            visit(*b, bound, free);
+        }
+        IR::Not(a) | IR::Ref(a) => visit(*a, bound, free),
+        IR::If(c, t, e) => {
+            visit(*c, bound, free);
+            visit(*t, bound, free);
+            visit(*e, bound, free);
+        }
+        IR::Focus(focus) => {
+            for idx in [focus.xs, focus.w, focus.f, focus.g] {
+                visit(idx, bound, free);
+            }
+        }
+        IR::Observe(observe) => visit(observe.file, bound, free),
+        IR::Num(_) | IR::Bool(_) | IR::Nil | IR::Drop | IR::Identity => {}
+    }
+}
+
+/// Free variables that are consumed as data streams (xs of MAP/FILTER/FOCUS)
+fn stream_vars(ir: &IR, arena: &Arena, streams: &mut Vec<Symbol>) {
+    let xs = match ir {
+        IR::Map(_, xs) | IR::Filter(_, xs) => Some(*xs),
+        IR::Focus(focus) => Some(focus.xs),
+        _ => None,
+    };
+    if let Some(IR::Var(x)) = xs.map(|idx| arena.get(idx)) {
+        if !streams.contains(&x) {
+            streams.push(x);
+        }
+    }
+
+    for child in children(ir) {
+        stream_vars(&arena.get(child), arena, streams);
+    }
+}
+
+/// Child indices of a node
+pub fn children(ir: &IR) -> Vec<u32> {
+    match ir {
+        IR::Lam(_, a) | IR::Not(a) | IR::Ref(a) => vec![*a],
+        IR::App(a, b) | IR::Add(a, b) | IR::Sub(a, b) | IR::Mul(a, b) |
+        IR::Div(a, b) | IR::Eq(a, b) | IR::Lt(a, b) | IR::Gt(a, b) |
+        IR::And(a, b) | IR::Or(a, b) | IR::Map(a, b) | IR::Filter(a, b) |
+        IR::Compose(a, b) | IR::Let(_, a, b) => vec![*a, *b],
+        IR::If(c, t, e) => vec![*c, *t, *e],
+        IR::Focus(focus) => vec![focus.xs, focus.w, focus.f, focus.g],
+        IR::Observe(observe) => vec![observe.file],
+        IR::Var(_) | IR::Num(_) | IR::Bool(_) | IR::Nil | IR::Drop | IR::Identity => vec![],
+    }
+}
+
+/// Deterministic input generator (xorshift64*) - no_std, no entropy
+pub struct Sampler {
+    state: u64,
+}
+
+impl Sampler {
+    pub fn new(seed: u64) -> Self {
+        Sampler { state: seed | 1 }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    pub fn num(&mut self) -> Value {
+        Value::Num((self.next() % 33) as i64 - 16)
+    }
+
+    pub fn list(&mut self) -> Value {
+        let len = (self.next() % 9) as usize;
+        Value::List((0..len).map(|_| self.num()).collect())
+    }
+}
+
+/// Generate `n` inputs for a term: lists for streams, numbers elsewhere
+/// The first inputs are edge cases (empty and singleton streams)
+pub fn sample_inputs(ir: &IR, arena: &Arena, n: usize, seed: u64) -> Vec<Input> {
+    let free = free_vars(ir, arena);
+    let mut streams = Vec::new();
+    stream_vars(ir, arena, &mut streams);
+
+    let mut sampler = Sampler::new(seed);
+    let mut inputs = Vec::with_capacity(n);
+
+    for i in 0..n {
+        let input = free.iter()
+            .map(|x| {
+                let value = if !streams.contains(x) {
+                    sampler.num()
+                } else if i == 0 {
+                    Value::List(vec![])
+                } else if i == 1 {
+                    Value::List(vec![sampler.num()])
+                } else {
+                    sampler.list()
+                };
+                (*x, value)
+            })
+            .collect();
+        inputs.push(input);
+    }
+
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_filter() {
+        let mut arena = Arena::new();
+        let x = Symbol(1);
+        let xs = Symbol(2);
+
+        // map (λx. x * 2) (filter (λx. x > 0) xs)
+        let var_x = arena.alloc(IR::Var(x));
+        let zero = arena.alloc(IR::Num(0));
+        let two = arena.alloc(IR::Num(2));
+        let gt = arena.alloc(IR::Gt(var_x, zero));
+        let pred = arena.alloc(IR::Lam(x, gt));
+        let mul = arena.alloc(IR::Mul(var_x, two));
+        let double = arena.alloc(IR::Lam(x, mul));
+        let data = arena.alloc(IR::Var(xs));
+        let filtered = arena.alloc(IR::Filter(pred, data));
+        let ir = IR::Map(double, filtered);
+
+        let input = vec![(xs, Value::List(vec![Value::Num(-1), Value::Num(3)]))];
+        assert_eq!(eval(&ir, &arena, &input), Ok(Value::List(vec![Value::Num(6)])));
+    }
+
+    #[test]
+    fn test_samples_are_deterministic() {
+        let mut arena = Arena::new();
+        let xs = arena.alloc(IR::Var(Symbol(7)));
+        let id = arena.alloc(IR::Identity);
+        let ir = IR::Map(id, xs);
+
+        let a = sample_inputs(&ir, &arena, 8, 42);
+        let b = sample_inputs(&ir, &arena, 8, 42);
+        assert_eq!(a, b);
+        assert_eq!(a[0], vec![(Symbol(7), Value::List(vec![]))]);
+    }
+}
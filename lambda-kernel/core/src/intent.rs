@@ -3,13 +3,20 @@
 ///
 /// Intent(state) → {weights, rules, laws, focus, epsilon, caps}
 
-use crate::ir::IR;
-use crate::soul::compute_soul;
+use crate::ir::{IR, Arena, Symbol};
+use crate::soul::{compute_soul, hash_ir};
+use crate::focus;
+use crate::eval::{self, Value, Input};
+use crate::protein::ProteinVector;
 use alloc::vec::Vec;
 use alloc::vec;
 use alloc::boxed::Box;
 use alloc::string::String;
-use alloc::format;
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+/// Generated inputs per law check
+pub const LAW_SAMPLES: usize = 32;
 
 /// Cost weights determine optimization direction
 #[derive(Debug, Clone)]
@@ -59,15 +66,54 @@ pub enum RuleSet {
 }
 
 /// Laws that must be preserved
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Law {
     Identity,         // soul(before) == soul(after)
     Fusion,          // FOCUS fusion law
     LengthPreserved, // |output| == |input|
-    RoundTrip,       // f(f⁻¹(x)) == x
+    Equivalence,     // after(x) == before(x)
     Custom(String),  // User-defined invariant
 }
 
+/// One observation of before/after on the same generated input
+pub struct Sample<'a> {
+    pub input: &'a Input,
+    pub before: &'a Value,
+    pub after: &'a Value,
+}
+
+impl Sample<'_> {
+    /// The stream being transformed - first list among the inputs
+    pub fn primary(&self) -> Option<&Value> {
+        self.input.iter()
+            .map(|(_, v)| v)
+            .find(|v| matches!(v, Value::List(_)))
+    }
+}
+
+/// Predicate backing a `Law::Custom` - e.g. "len(out) == len(in)"
+pub type LawPredicate = fn(&Sample) -> bool;
+
+/// Rewrite an intent evolves by in place of its rule set
+pub type Rewrite = fn(&IR, &mut Arena) -> IR;
+
+/// A law that failed, with the input that broke it (when one exists)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub law: Law,
+    pub counterexample: Option<Input>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Law {:?} violated", self.law)?;
+        if let Some(input) = &self.counterexample {
+            write!(f, " at {:?}", input)?;
+        }
+        Ok(())
+    }
+}
+
 /// Focus configuration - where to apply attention
 #[derive(Debug, Clone)]
 pub struct Focus {
@@ -96,9 +142,25 @@ impl Focus {
 
 #[derive(Debug, Clone)]
 pub struct ROI {
+    pub subject: Symbol,     // The argument the predicate reads
     pub predicate: Box<IR>,  // λx. bool - defines region
 }
 
+impl ROI {
+    /// Does the input fall in this region? The predicate sees the subject's value
+    pub fn contains(&self, input: &Input, arena: &Arena) -> bool {
+        let value = match input.iter().find(|(name, _)| *name == self.subject) {
+            Some((_, value)) => value.clone(),
+            None => return false,
+        };
+        let mut evaluator = eval::Evaluator::new(arena);
+        evaluator.eval(&self.predicate, &Vec::new())
+            .and_then(|p| evaluator.apply(&p, value))
+            .and_then(|v| v.truthy())
+            .unwrap_or(false)  // Outside the predicate's domain = outside the region
+    }
+}
+
 /// Capabilities - what effects are allowed
 #[derive(Debug, Clone)]
 pub struct Capabilities {
@@ -132,6 +194,7 @@ impl Capabilities {
 }
 
 /// The Intent - drives evolution while preserving soul
+#[derive(Debug, Clone)]
 pub struct Intent {
     pub weights: CostWeights,
     pub allowed_rules: RuleSet,
@@ -139,6 +202,9 @@ pub struct Intent {
     pub focus: Focus,
     pub epsilon: f64,  // "Good enough" threshold
     pub caps: Capabilities,
+    pub custom_laws: BTreeMap<String, LawPredicate>,
+    pub protein: Option<ProteinVector>,  // Fingerprint of the code it drives
+    pub rewrite: Option<Rewrite>,
 }
 
 impl Intent {
@@ -151,6 +217,9 @@ impl Intent {
             focus: Focus::laser(),
             epsilon: 1e-2,  // Stop early when good enough
             caps: Capabilities::pure(),
+            custom_laws: BTreeMap::new(),
+            protein: None,
+            rewrite: None,
         }
     }
     
@@ -163,40 +232,55 @@ impl Intent {
                 Law::Identity,
                 Law::Fusion,
                 Law::LengthPreserved,
-                Law::RoundTrip,
+                Law::Equivalence,
             ],
             focus: Focus::broad(),
             epsilon: 1e-6,  // Near perfection required
             caps: Capabilities::pure(),
+            custom_laws: BTreeMap::new(),
+            protein: None,
+            rewrite: None,
         }
     }
     
+    /// Attach the protein of the term this intent drives
+    pub fn with_protein(mut self, ir: &IR, arena: &Arena) -> Self {
+        self.protein = Some(ProteinVector::from_ir(ir, arena));
+        self
+    }
+    
+    /// Register the predicate behind `Law::Custom(name)`
+    pub fn register_law(&mut self, name: &str, predicate: LawPredicate) {
+        self.custom_laws.insert(String::from(name), predicate);
+    }
+    
     /// Apply intent to state - evolve while preserving soul
-    pub fn evolve(&self, ir: &IR) -> Result<IR, String> {
-        let soul_before = compute_soul(ir);
+    pub fn evolve(&self, ir: &IR, arena: &mut Arena) -> Result<IR, Violation> {
+        let soul_before = compute_soul(ir, arena);
         
         // Apply transformations guided by intent
-        let evolved = self.apply_rules(ir)?;
+        let evolved = self.apply_rules(ir, arena);
         
         // Verify soul preservation
-        let soul_after = compute_soul(&evolved);
+        let soul_after = compute_soul(&evolved, arena);
         if soul_before != soul_after {
-            return Err(format!(
-                "Soul changed! {} → {}",
-                soul_before, soul_after
-            ));
+            return Err(Violation { law: Law::Identity, counterexample: None });
         }
         
-        // Check all laws hold
-        for law in &self.laws {
-            if !self.check_law(&evolved, law) {
-                return Err(format!("Law {:?} violated", law));
-            }
-        }
+        // Check all laws hold against the pre-evolution form
+        let inputs = eval::sample_inputs(ir, arena, LAW_SAMPLES, soul_before);
+        self.check_laws(ir, &evolved, &inputs, arena)?;
         
         Ok(evolved)
     }
     
+    fn check_laws(&self, before: &IR, after: &IR, inputs: &[Input], arena: &mut Arena) -> Result<(), Violation> {
+        for law in &self.laws {
+            self.check_law(before, after, law, inputs, arena)?;
+        }
+        Ok(())
+    }
+    
     /// Check if we should stop (good enough)
     pub fn should_stop(&self, improvement: f64) -> bool {
         improvement.abs() < self.epsilon
@@ -213,6 +297,7 @@ impl Intent {
             combined.weights.bytes += intent.weights.bytes;
             // Union of laws (must satisfy all)
             combined.laws.extend(intent.laws);
+            combined.custom_laws.extend(intent.custom_laws);
             // Intersection of capabilities (most restrictive)
             combined.caps.io &= intent.caps.io;
         }
@@ -220,32 +305,195 @@ impl Intent {
         combined
     }
     
-    fn apply_rules(&self, ir: &IR) -> Result<IR, String> {
-        // Placeholder - would apply e-graph rules
-        Ok(ir.clone())
+    fn apply_rules(&self, ir: &IR, arena: &mut Arena) -> IR {
+        match self.rewrite {
+            Some(rewrite) => rewrite(ir, arena),
+            None => *ir,  // Placeholder - would apply e-graph rules
+        }
     }
     
-    fn check_law(&self, ir: &IR, law: &Law) -> bool {
+    fn check_law(
+        &self,
+        before: &IR,
+        after: &IR,
+        law: &Law,
+        inputs: &[Input],
+        arena: &mut Arena,
+    ) -> Result<(), Violation> {
         match law {
-            Law::Identity => true,  // Already checked in evolve()
-            Law::Fusion => self.check_fusion_law(ir),
-            Law::LengthPreserved => self.check_length_preserved(ir),
-            Law::RoundTrip => self.check_round_trip(ir),
-            Law::Custom(_) => true,  // Would call custom checker
+            Law::Identity => Ok(()),  // Already checked in evolve()
+            Law::Fusion => check_fusion_law(before, after, inputs, arena),
+            Law::LengthPreserved => check_observed(before, after, law, inputs, arena, |s| {
+                s.primary().and_then(Value::len) == s.after.len()
+            }),
+            Law::Equivalence => check_observed(before, after, law, inputs, arena, |s| {
+                s.before == s.after
+            }),
+            Law::Custom(name) => match self.custom_laws.get(name) {
+                Some(predicate) => check_observed(before, after, law, inputs, arena, *predicate),
+                // An unregistered law cannot be shown to hold
+                None => Err(Violation { law: law.clone(), counterexample: None }),
+            },
+        }
+    }
+}
+
+/// Run before and after on every input and hold them to `predicate`
+/// Inputs outside the domain of `before` say nothing about the law;
+/// `after` failing where `before` succeeds is always a violation
+fn check_observed(
+    before: &IR,
+    after: &IR,
+    law: &Law,
+    inputs: &[Input],
+    arena: &Arena,
+    predicate: LawPredicate,
+) -> Result<(), Violation> {
+    for input in inputs {
+        let expected = match eval::eval(before, arena, input) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        let holds = match eval::eval(after, arena, input) {
+            Ok(actual) => predicate(&Sample { input, before: &expected, after: &actual }),
+            Err(_) => false,
+        };
+        if !holds {
+            return Err(Violation { law: law.clone(), counterexample: Some(input.clone()) });
+        }
+    }
+    Ok(())
+}
+
+/// FOCUS(FOCUS(xs, w1, f1), w2, f2) == FOCUS(xs, w1∧w2, f2∘f1)
+/// Every chain of `before` the evolution fused away must agree with its
+/// fused form - chains still nested in `after` were left alone
+fn check_fusion_law(
+    before: &IR,
+    after: &IR,
+    inputs: &[Input],
+    arena: &mut Arena,
+) -> Result<(), Violation> {
+    let mut chains = Vec::new();
+    collect_focus_chains(before, arena, &mut chains);
+    let mut kept = Vec::new();
+    collect_focus_chains(after, arena, &mut kept);
+    chains.retain(|chain| !kept.contains(chain));
+    
+    for (outer, inner) in chains {
+        let fused = match focus::rules::fuse_focus_chain(arena, outer, inner) {
+            Some(fused) => fused,
+            None => continue,  // Mixed modes never fuse
+        };
+        let chain = IR::Focus(outer);
+        
+        for input in inputs {
+            let expected = match eval::eval(&chain, arena, input) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            if eval::eval(&fused, arena, input).as_ref() != Ok(&expected) {
+                return Err(Violation { law: Law::Fusion, counterexample: Some(input.clone()) });
+            }
         }
     }
+    Ok(())
+}
+
+fn collect_focus_chains(ir: &IR, arena: &Arena, chains: &mut Vec<(focus::Focus, focus::Focus)>) {
+    if let IR::Focus(outer) = ir {
+        if let IR::Focus(inner) = arena.get(outer.xs) {
+            chains.push((*outer, inner));
+        }
+    }
+    for child in eval::children(ir) {
+        collect_focus_chains(&arena.get(child), arena, chains);
+    }
+}
+
+/// One cell of a partitioned input space
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub roi: ROI,
+    pub intent: Intent,
+}
+
+/// Intents that coexist by owning disjoint regions of the input space
+/// An input belongs to the first region whose ROI contains it, else to `fallback`
+#[derive(Debug, Clone)]
+pub struct CompositeIntent {
+    pub regions: Vec<Region>,
+    pub fallback: Intent,
+}
+
+impl CompositeIntent {
+    pub fn single(intent: Intent) -> Self {
+        CompositeIntent { regions: Vec::new(), fallback: intent }
+    }
     
-    fn check_fusion_law(&self, _ir: &IR) -> bool {
-        // FOCUS(FOCUS(xs, w1, f1), w2, f2) == FOCUS(xs, w1∧w2, f2∘f1)
-        true  // Placeholder
+    /// Which intent owns this input (index into regions, None = fallback)
+    pub fn region_of(&self, input: &Input, arena: &Arena) -> Option<usize> {
+        self.regions.iter().position(|r| r.roi.contains(input, arena))
     }
     
-    fn check_length_preserved(&self, _ir: &IR) -> bool {
-        true  // Placeholder
+    pub fn intent_for(&self, input: &Input, arena: &Arena) -> &Intent {
+        match self.region_of(input, arena) {
+            Some(i) => &self.regions[i].intent,
+            None => &self.fallback,
+        }
     }
     
-    fn check_round_trip(&self, _ir: &IR) -> bool {
-        true  // Placeholder
+    /// Evolve under every region's intent, holding each to its laws only on
+    /// the inputs it owns. Where regions disagree on the result, dispatch on
+    /// the ROI predicates: if roi₁(x) then ir₁ else if roi₂(x) … else fallback
+    pub fn evolve(&self, ir: &IR, arena: &mut Arena) -> Result<IR, Violation> {
+        if self.regions.is_empty() {
+            return self.fallback.evolve(ir, arena);
+        }
+        
+        let soul_before = compute_soul(ir, arena);
+        let inputs = eval::sample_inputs(ir, arena, LAW_SAMPLES, soul_before);
+        
+        // Partition the samples
+        let mut owned: Vec<Vec<Input>> = vec![Vec::new(); self.regions.len() + 1];
+        for input in inputs {
+            let cell = self.region_of(&input, arena).unwrap_or(self.regions.len());
+            owned[cell].push(input);
+        }
+        
+        let mut evolved = Vec::with_capacity(self.regions.len() + 1);
+        let intents = self.regions.iter().map(|r| &r.intent).chain(core::iter::once(&self.fallback));
+        for (intent, inputs) in intents.zip(owned.iter()) {
+            let after = intent.apply_rules(ir, arena);
+            if compute_soul(&after, arena) != soul_before {
+                return Err(Violation { law: Law::Identity, counterexample: None });
+            }
+            intent.check_laws(ir, &after, inputs, arena)?;
+            evolved.push(after);
+        }
+        
+        // All regions agree - no dispatch needed. Souls always agree by now,
+        // so agreement is on the terms themselves
+        let fallback = evolved.pop().unwrap_or(*ir);
+        if evolved.iter().all(|e| hash_ir(e, arena) == hash_ir(&fallback, arena)) {
+            return Ok(fallback);
+        }
+        
+        // A region whose subject isn't an argument owns no input
+        let free = eval::free_vars(ir, arena);
+        let mut dispatch = fallback;
+        for (region, body) in self.regions.iter().zip(evolved).rev() {
+            if !free.contains(&region.roi.subject) {
+                continue;
+            }
+            let subject = arena.alloc(IR::Var(region.roi.subject));
+            let predicate = arena.alloc(*region.roi.predicate);
+            let test = arena.alloc(IR::App(predicate, subject));
+            let then = arena.alloc(body);
+            let otherwise = arena.alloc(dispatch);
+            dispatch = IR::If(test, then, otherwise);
+        }
+        Ok(dispatch)
     }
 }
 
@@ -259,17 +507,17 @@ pub struct Metrics {
 
 /// Example: Gene with intent
 pub struct Gene {
-    pub soul: String,
+    pub soul: u64,
     pub ir: IR,
     pub intent: Intent,
 }
 
 impl Gene {
-    pub fn evolve(&mut self) -> Result<(), String> {
-        let new_ir = self.intent.evolve(&self.ir)?;
+    pub fn evolve(&mut self, arena: &mut Arena) -> Result<(), Violation> {
+        let new_ir = self.intent.evolve(&self.ir, arena)?;
         
         // Soul must remain unchanged
-        assert_eq!(self.soul, compute_soul(&new_ir));
+        assert_eq!(self.soul, compute_soul(&new_ir, arena));
         
         self.ir = new_ir;
         Ok(())
@@ -282,11 +530,12 @@ mod tests {
     
     #[test]
     fn test_soul_preservation() {
-        let ir = IR::Num(42.0);
+        let mut arena = Arena::new();
+        let ir = IR::Num(42);
         let intent = Intent::explorer();
         
-        let evolved = intent.evolve(&ir).unwrap();
-        assert_eq!(compute_soul(&ir), compute_soul(&evolved));
+        let evolved = intent.evolve(&ir, &mut arena).unwrap();
+        assert_eq!(compute_soul(&ir, &mut arena), compute_soul(&evolved, &mut arena));
     }
     
     #[test]
@@ -297,4 +546,179 @@ mod tests {
         assert!(explorer.epsilon > guardian.epsilon);
         assert!(explorer.weights.io_risk < guardian.weights.io_risk);
     }
+    
+    /// map (λx. x + 1) xs
+    fn increment(arena: &mut Arena) -> IR {
+        let x = crate::ir::Symbol(1);
+        let var_x = arena.alloc(IR::Var(x));
+        let one = arena.alloc(IR::Num(1));
+        let add = arena.alloc(IR::Add(var_x, one));
+        let f = arena.alloc(IR::Lam(x, add));
+        let xs = arena.alloc(IR::Var(crate::ir::Symbol(2)));
+        IR::Map(f, xs)
+    }
+    
+    #[test]
+    fn test_guardian_laws_hold() {
+        let mut arena = Arena::new();
+        let ir = increment(&mut arena);
+        
+        assert!(Intent::guardian().evolve(&ir, &mut arena).is_ok());
+    }
+    
+    /// (x + 1) * y with its 1 spelled `op(a, b)` - folds back to the same soul
+    fn respell(ir: &IR, arena: &mut Arena, op: fn(u32, u32) -> IR, a: i64, b: i64) -> IR {
+        let IR::Mul(sum, y) = *ir else { return *ir };
+        let IR::Add(x, _) = arena.get(sum) else { return *ir };
+        let (a, b) = (arena.alloc(IR::Num(a)), arena.alloc(IR::Num(b)));
+        let one = arena.alloc(op(a, b));
+        IR::Mul(arena.alloc(IR::Add(x, one)), y)
+    }
+    
+    /// (x + (0 + 1)) * y
+    fn plus_zero(ir: &IR, arena: &mut Arena) -> IR {
+        respell(ir, arena, IR::Add, 0, 1)
+    }
+    
+    /// (x + (2 - 1)) * y
+    fn two_minus(ir: &IR, arena: &mut Arena) -> IR {
+        respell(ir, arena, IR::Sub, 2, 1)
+    }
+    
+    #[test]
+    fn test_regions_dispatch() {
+        let mut arena = Arena::new();
+        let (x, y) = (Symbol(1), Symbol(2));
+        let var_x = arena.alloc(IR::Var(x));
+        let var_y = arena.alloc(IR::Var(y));
+        let zero = arena.alloc(IR::Num(0));
+        let one = arena.alloc(IR::Num(1));
+        let sum = arena.alloc(IR::Add(var_x, one));
+        let ir = IR::Mul(sum, var_y);
+        
+        // y > 0 and x < 0 each spell the 1 their own way, the rest is left alone
+        let gt = arena.alloc(IR::Gt(var_y, zero));
+        let lt = arena.alloc(IR::Lt(var_x, zero));
+        let region = |subject, predicate, rewrite: Rewrite| {
+            let mut intent = Intent::explorer();
+            intent.rewrite = Some(rewrite);
+            Region { roi: ROI { subject, predicate: Box::new(IR::Lam(subject, predicate)) }, intent }
+        };
+        let composite = CompositeIntent {
+            regions: vec![region(y, gt, plus_zero), region(x, lt, two_minus)],
+            fallback: Intent::explorer(),
+        };
+        
+        // Each ROI reads its own argument, whatever order the input binds them in
+        let input = vec![(x, Value::Num(-5)), (y, Value::Num(3))];
+        assert_eq!(composite.region_of(&input, &arena), Some(0));
+        let input = vec![(y, Value::Num(-3)), (x, Value::Num(-5))];
+        assert_eq!(composite.region_of(&input, &arena), Some(1));
+        let input = vec![(y, Value::Num(-3)), (x, Value::Num(5))];
+        assert_eq!(composite.region_of(&input, &arena), None);
+        
+        // if y > 0 then (x + (0 + 1)) * y else if x < 0 then (x + (2 - 1)) * y else (x + 1) * y
+        let dispatch = composite.evolve(&ir, &mut arena).unwrap();
+        let IR::If(_, first, rest) = dispatch else { panic!("no dispatch: {:?}", dispatch) };
+        let IR::If(_, second, fallback) = arena.get(rest) else { panic!("one region dispatched") };
+        let spelled = |ir: IR, arena: &Arena| match ir {
+            IR::Mul(sum, _) => match arena.get(sum) {
+                IR::Add(_, one) => arena.get(one),
+                _ => ir,
+            },
+            _ => ir,
+        };
+        assert!(matches!(spelled(arena.get(first), &arena), IR::Add(..)));
+        assert!(matches!(spelled(arena.get(second), &arena), IR::Sub(..)));
+        assert_eq!(arena.get(fallback), ir);
+        
+        for input in eval::sample_inputs(&ir, &arena, LAW_SAMPLES, 7) {
+            assert_eq!(eval::eval(&dispatch, &arena, &input), eval::eval(&ir, &arena, &input));
+        }
+    }
+    
+    #[test]
+    fn test_length_preserved_reads_input() {
+        let mut arena = Arena::new();
+        let x = crate::ir::Symbol(1);
+        let var_x = arena.alloc(IR::Var(x));
+        let zero = arena.alloc(IR::Num(0));
+        let gt = arena.alloc(IR::Gt(var_x, zero));
+        let positive = arena.alloc(IR::Lam(x, gt));
+        let xs = arena.alloc(IR::Var(crate::ir::Symbol(2)));
+        
+        // filter keeps its own output length, but not its input's
+        let mut intent = Intent::explorer();
+        intent.laws = vec![Law::LengthPreserved];
+        let violation = intent.evolve(&IR::Filter(positive, xs), &mut arena).unwrap_err();
+        assert_eq!(violation.law, Law::LengthPreserved);
+        assert!(violation.counterexample.is_some());
+    }
+    
+    #[test]
+    fn test_custom_law() {
+        let mut arena = Arena::new();
+        let ir = increment(&mut arena);
+        
+        let mut intent = Intent::explorer();
+        intent.laws.push(Law::Custom(String::from("len(out) == len(in)")));
+        intent.laws.push(Law::Custom(String::from("out is empty")));
+        intent.register_law("len(out) == len(in)", |s| {
+            s.primary().and_then(Value::len) == s.after.len()
+        });
+        intent.register_law("out is empty", |s| s.after.len() == Some(0));
+        
+        // Fails on the first non-empty input, and says which one
+        let violation = intent.evolve(&ir, &mut arena).unwrap_err();
+        assert_eq!(violation.law, Law::Custom(String::from("out is empty")));
+        let input = violation.counterexample.unwrap();
+        assert_ne!(input[0].1.len(), Some(0));
+    }
+    
+    #[test]
+    fn test_unregistered_law_is_violated() {
+        let mut arena = Arena::new();
+        let ir = increment(&mut arena);
+        
+        let mut intent = Intent::explorer();
+        intent.laws.push(Law::Custom(String::from("unknown")));
+        
+        let violation = intent.evolve(&ir, &mut arena).unwrap_err();
+        assert_eq!(violation.counterexample, None);
+    }
+    
+    #[test]
+    fn test_fusion_counterexample() {
+        let mut arena = Arena::new();
+        let x = crate::ir::Symbol(1);
+        let var_x = arena.alloc(IR::Var(x));
+        let zero = arena.alloc(IR::Num(0));
+        let ten = arena.alloc(IR::Num(10));
+        let id = arena.alloc(IR::Identity);
+        
+        // inner: keep x > 0, add 10; outer: keep y < 10
+        let gt = arena.alloc(IR::Gt(var_x, zero));
+        let positive = arena.alloc(IR::Lam(x, gt));
+        let add = arena.alloc(IR::Add(var_x, ten));
+        let shift = arena.alloc(IR::Lam(x, add));
+        let lt = arena.alloc(IR::Lt(var_x, ten));
+        let small = arena.alloc(IR::Lam(x, lt));
+        let xs = arena.alloc(IR::Var(crate::ir::Symbol(2)));
+        
+        let inner = crate::focus::Focus::hard(xs, positive, shift, &mut arena);
+        let inner = arena.alloc(inner);
+        let outer = crate::focus::Focus::hard(inner, small, id, &mut arena);
+        
+        let inputs = eval::sample_inputs(&outer, &arena, LAW_SAMPLES, 0);
+        
+        // Left nested, the chain isn't the evolution's doing
+        assert!(check_fusion_law(&outer, &outer, &inputs, &mut arena).is_ok());
+        
+        // Fused, w2 reads f1's output, so w1∧w2 on the raw input disagrees
+        let (IR::Focus(chain), IR::Focus(nested)) = (outer, arena.get(inner)) else { unreachable!() };
+        let fused = crate::focus::rules::fuse_focus_chain(&mut arena, chain, nested).unwrap();
+        let violation = check_fusion_law(&outer, &fused, &inputs, &mut arena).unwrap_err();
+        assert_eq!(violation.law, Law::Fusion);
+        assert!(violation.counterexample.is_some());
+    }
 }
\ No newline at end of file
@@ -13,12 +13,18 @@ pub mod observe;
 #[cfg(feature = "alloc")]
 pub mod poetry;
 
+#[cfg(feature = "alloc")]
+pub mod eval;
 #[cfg(feature = "alloc")]
 pub mod intent;
 #[cfg(feature = "alloc")]
 pub mod love;
 #[cfg(feature = "alloc")]
 pub mod distortion;
+#[cfg(feature = "alloc")]
+pub mod protein;
+#[cfg(feature = "alloc")]
+pub mod distance;
 
 #[cfg(feature = "alloc")]
 pub mod rewriter;
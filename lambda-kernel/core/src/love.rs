@@ -3,13 +3,11 @@
 ///
 /// L(i,j) = cos(protein_i, protein_j) × sim(intent_i, intent_j)
 
-use crate::intent::{Intent, CostWeights};
-use crate::ir::IR;
+use crate::intent::{Intent, CostWeights, CompositeIntent, Region};
+use crate::ir::{IR, Arena};
+use crate::distance;
 use alloc::vec::Vec;
 use alloc::vec;
-use alloc::format;
-#[cfg(feature = "alloc")]
-use libm::F32Ext;
 
 /// Love kernel - measures alignment between intents
 pub struct LoveKernel {
@@ -29,19 +27,24 @@ impl LoveKernel {
     
     /// Compute love between two intents
     pub fn compute(&self, i1: &Intent, i2: &Intent) -> f32 {
-        let protein_sim = self.protein_similarity(i1, i2);
         let intent_align = self.intent_alignment(i1, i2);
         let angle_factor = self.angle_factor(i1, i2);
+        let love = intent_align * self.intent_weight + angle_factor * self.angle_penalty;
         
-        protein_sim * self.protein_weight +
-        intent_align * self.intent_weight +
-        angle_factor * self.angle_penalty
+        // Without both fingerprints the protein term says nothing either way,
+        // so the other terms carry its weight
+        match self.protein_similarity(i1, i2) {
+            Some(protein_sim) => love + protein_sim * self.protein_weight,
+            None => love * (self.protein_weight + self.intent_weight + self.angle_penalty)
+                / (self.intent_weight + self.angle_penalty),
+        }
     }
     
-    fn protein_similarity(&self, _i1: &Intent, _i2: &Intent) -> f32 {
-        // Would compute semantic similarity of protein vectors
-        // For now, simplified
-        0.8
+    fn protein_similarity(&self, i1: &Intent, i2: &Intent) -> Option<f32> {
+        match (&i1.protein, &i2.protein) {
+            (Some(p1), Some(p2)) => Some(p1.cosine(p2).max(0.0)),
+            _ => None,
+        }
     }
     
     fn intent_alignment(&self, i1: &Intent, i2: &Intent) -> f32 {
@@ -53,8 +56,8 @@ impl LoveKernel {
                   w1.bytes * w2.bytes +
                   w1.allocs * w2.allocs;
         
-        let norm1 = (w1.cycles * w1.cycles + w1.bytes * w1.bytes + w1.allocs * w1.allocs).sqrt();
-        let norm2 = (w2.cycles * w2.cycles + w2.bytes * w2.bytes + w2.allocs * w2.allocs).sqrt();
+        let norm1 = libm::sqrtf(w1.cycles * w1.cycles + w1.bytes * w1.bytes + w1.allocs * w1.allocs);
+        let norm2 = libm::sqrtf(w2.cycles * w2.cycles + w2.bytes * w2.bytes + w2.allocs * w2.allocs);
         
         if norm1 * norm2 > 0.0 {
             dot / (norm1 * norm2)
@@ -66,7 +69,7 @@ impl LoveKernel {
     fn angle_factor(&self, i1: &Intent, i2: &Intent) -> f32 {
         let alignment = self.intent_alignment(i1, i2);
         // cos(θ) = alignment, so θ = acos(alignment)
-        let angle = alignment.acos();
+        let angle = libm::acosf(alignment);
         1.0 - (angle / core::f32::consts::PI)
     }
 }
@@ -85,8 +88,25 @@ impl Reconciler {
         }
     }
     
-    /// Reconcile multiple intents into one
-    pub fn reconcile(&self, intents: Vec<Intent>) -> Intent {
+    /// Reconcile multiple intents into a partition of the input space
+    /// Intents that carry an ROI own that region (first listed wins overlaps);
+    /// the rest are reconciled into one intent for everything else
+    pub fn reconcile(&self, intents: Vec<Intent>) -> CompositeIntent {
+        let (scoped, global): (Vec<Intent>, Vec<Intent>) = intents.into_iter()
+            .partition(|i| i.focus.roi.is_some());
+        
+        let regions = scoped.into_iter()
+            .filter_map(|intent| Some(Region { roi: intent.focus.roi.clone()?, intent }))
+            .collect();
+        
+        CompositeIntent {
+            regions,
+            fallback: self.reconcile_global(global),
+        }
+    }
+    
+    /// Reconcile intents that share the whole input space into one
+    fn reconcile_global(&self, intents: Vec<Intent>) -> Intent {
         if intents.is_empty() {
             return Intent::guardian();  // Safe default
         }
@@ -128,7 +148,7 @@ impl Reconciler {
     
     fn is_conflicting(&self, i1: &Intent, i2: &Intent) -> bool {
         let alignment = self.love_kernel.intent_alignment(i1, i2);
-        let angle = alignment.acos();
+        let angle = libm::acosf(alignment);
         angle > self.conflict_threshold
     }
     
@@ -146,12 +166,7 @@ impl Reconciler {
             }
         }
         
-        // Strategy 2: Split by focus (ROI)
-        if self.can_split_by_focus(&intents) {
-            return self.split_by_focus(intents);
-        }
-        
-        // Strategy 3: Pareto optimal selection
+        // Strategy 2: Pareto optimal selection
         self.pareto_select(intents)
     }
     
@@ -206,39 +221,54 @@ impl Reconciler {
         aligned
     }
     
-    fn can_split_by_focus(&self, intents: &[Intent]) -> bool {
-        intents.iter().any(|i| i.focus.sparsity > 0.0)
-    }
-    
-    fn split_by_focus(&self, intents: Vec<Intent>) -> Intent {
-        // Apply different intents to different regions
-        // This would use ROI to partition space
-        intents.into_iter()
-            .find(|i| i.focus.sparsity > 0.9)
-            .unwrap_or_else(Intent::guardian)
-    }
-    
     fn pareto_select(&self, intents: Vec<Intent>) -> Intent {
-        // Select from Pareto front based on global preferences
-        // For now, pick the most conservative
-        intents.into_iter()
-            .min_by_key(|i| (i.epsilon * 1000.0) as i32)
-            .unwrap_or_else(Intent::guardian)
+        // Most conservative member of the Pareto front
+        let front = pareto_front(&intents);
+        let pick = front.into_iter()
+            .min_by(|&a, &b| intents[a].epsilon.total_cmp(&intents[b].epsilon));
+        match pick {
+            Some(i) => intents.into_iter().nth(i).unwrap_or_else(Intent::guardian),
+            None => Intent::guardian(),
+        }
     }
 }
 
+/// Does `a` dominate `b`? It weighs every cost at least as heavily and one
+/// strictly more, so every concern of `b` is already covered by `a`
+pub fn dominates(a: &CostWeights, b: &CostWeights) -> bool {
+    let pairs = [
+        (a.cycles, b.cycles),
+        (a.bytes, b.bytes),
+        (a.allocs, b.allocs),
+        (a.io_risk, b.io_risk),
+    ];
+    pairs.iter().all(|(x, y)| x >= y) && pairs.iter().any(|(x, y)| x > y)
+}
+
+/// Indices of intents whose weight vectors no other intent dominates
+pub fn pareto_front(intents: &[Intent]) -> Vec<usize> {
+    (0..intents.len())
+        .filter(|&i| {
+            !intents.iter().enumerate()
+                .any(|(j, other)| j != i && dominates(&other.weights, &intents[i].weights))
+        })
+        .collect()
+}
+
 /// Ouroboros point - where all forces converge to zero
 pub struct OuroborosPoint {
     pub ir: IR,
     pub residual: f32,
     pub iterations: usize,
+    pub trajectory: Vec<f32>,  // Residual after each iteration
 }
 
 impl OuroborosPoint {
     /// Find fixed point where R(x*) = 0
-    pub fn find(initial: IR, intent: Intent) -> Self {
+    pub fn find(initial: IR, intent: Intent, arena: &mut Arena) -> Self {
         let mut current = initial;
         let mut iterations = 0;
+        let mut trajectory = Vec::new();
         const MAX_ITER: usize = 1000;
         const TOLERANCE: f32 = 1e-6;
         
@@ -246,18 +276,20 @@ impl OuroborosPoint {
             iterations += 1;
             
             // Apply intent
-            let next = match intent.evolve(&current) {
+            let next = match intent.evolve(&current, arena) {
                 Ok(evolved) => evolved,
                 Err(_) => break,
             };
             
             // Check convergence
-            let residual = compute_residual(&current, &next);
+            let residual = compute_residual(&current, &next, arena);
+            trajectory.push(residual);
             if residual < TOLERANCE || iterations >= MAX_ITER {
                 return OuroborosPoint {
                     ir: next,
                     residual,
                     iterations,
+                    trajectory,
                 };
             }
             
@@ -268,17 +300,19 @@ impl OuroborosPoint {
             ir: current,
             residual: f32::INFINITY,
             iterations,
+            trajectory,
         }
     }
+    
+    /// Did every step move less than the one before?
+    pub fn is_contracting(&self) -> bool {
+        self.trajectory.windows(2).all(|w| w[1] <= w[0])
+    }
 }
 
-fn compute_residual(ir1: &IR, ir2: &IR) -> f32 {
-    // Simplified - would compute actual distance
-    if format!("{:?}", ir1) == format!("{:?}", ir2) {
-        0.0
-    } else {
-        1.0
-    }
+/// Residual R = normalized tree edit distance between successive forms
+fn compute_residual(ir1: &IR, ir2: &IR, arena: &Arena) -> f32 {
+    distance::ir_distance(ir1, ir2, arena)
 }
 
 #[cfg(test)]
@@ -296,6 +330,31 @@ mod tests {
         assert!(love >= 0.0 && love <= 1.0);
     }
     
+    #[test]
+    fn test_protein_similarity() {
+        let mut arena = Arena::new();
+        let xs = arena.alloc(IR::Var(crate::ir::Symbol(1)));
+        let id = arena.alloc(IR::Identity);
+        let map = IR::Map(id, xs);
+        let one = arena.alloc(IR::Num(1));
+        let add = IR::Add(xs, one);
+        
+        let kernel = LoveKernel::new();
+        let a = Intent::explorer().with_protein(&map, &arena);
+        let same = Intent::explorer().with_protein(&map, &arena);
+        let other = Intent::explorer().with_protein(&add, &arena);
+        
+        assert!((kernel.protein_similarity(&a, &same).unwrap() - 1.0).abs() < 1e-5);
+        assert!(kernel.protein_similarity(&a, &other).unwrap() < 1.0);
+        assert!(kernel.compute(&a, &same) > kernel.compute(&a, &other));
+        
+        // A missing fingerprint is no evidence either way
+        let blank = Intent::explorer();
+        assert_eq!(kernel.protein_similarity(&a, &blank), None);
+        assert!(kernel.compute(&a, &blank) < kernel.compute(&a, &same));
+        assert!(kernel.compute(&a, &blank) > kernel.compute(&a, &other));
+    }
+    
     #[test]
     fn test_reconciliation() {
         let intents = vec![
@@ -307,6 +366,59 @@ mod tests {
         let reconciled = reconciler.reconcile(intents);
         
         // Should produce valid intent
-        assert!(reconciled.epsilon > 0.0);
+        assert!(reconciled.fallback.epsilon > 0.0);
+        assert!(reconciled.regions.is_empty());
+    }
+    
+    #[test]
+    fn test_ouroboros_trajectory() {
+        let mut arena = Arena::new();
+        let xs = arena.alloc(IR::Var(crate::ir::Symbol(1)));
+        let id = arena.alloc(IR::Identity);
+        
+        let point = OuroborosPoint::find(IR::Map(id, xs), Intent::explorer(), &mut arena);
+        assert_eq!(point.iterations, 1);
+        assert_eq!(point.trajectory, vec![0.0]);
+        assert!(point.is_contracting());
+    }
+    
+    #[test]
+    fn test_region_partition() {
+        let mut arena = Arena::new();
+        let x = crate::ir::Symbol(1);
+        let var_x = arena.alloc(IR::Var(x));
+        let hundred = arena.alloc(IR::Num(100));
+        let gt = arena.alloc(IR::Gt(var_x, hundred));
+        
+        // Guardian on the hot path (x > 100), explorer elsewhere
+        let mut guardian = Intent::guardian();
+        guardian.focus.roi = Some(crate::intent::ROI { subject: x, predicate: alloc::boxed::Box::new(IR::Lam(x, gt)) });
+        
+        let composite = Reconciler::new().reconcile(vec![guardian, Intent::explorer()]);
+        assert_eq!(composite.regions.len(), 1);
+        
+        let hot = vec![(x, crate::eval::Value::Num(500))];
+        let cold = vec![(x, crate::eval::Value::Num(3))];
+        assert_eq!(composite.region_of(&hot, &arena), Some(0));
+        assert_eq!(composite.region_of(&cold, &arena), None);
+        assert!(composite.intent_for(&hot, &arena).epsilon < composite.intent_for(&cold, &arena).epsilon);
+        
+        // Each region's laws hold on its own inputs
+        let one = arena.alloc(IR::Num(1));
+        let inc = IR::Add(var_x, one);
+        assert!(composite.evolve(&inc, &mut arena).is_ok());
+    }
+    
+    #[test]
+    fn test_pareto_front() {
+        let mut cheap = Intent::explorer();
+        cheap.weights = CostWeights { cycles: 1.0, bytes: 0.0, allocs: 0.0, io_risk: 0.0 };
+        let mut lean = Intent::explorer();
+        lean.weights = CostWeights { cycles: 0.0, bytes: 1.0, allocs: 0.0, io_risk: 0.0 };
+        let mut weak = Intent::explorer();
+        weak.weights = CostWeights { cycles: 0.5, bytes: 0.0, allocs: 0.0, io_risk: 0.0 };
+        
+        // weak is dominated by cheap; cheap and lean trade off
+        assert_eq!(pareto_front(&[cheap, lean, weak]), vec![0, 1]);
     }
 }
\ No newline at end of file
@@ -0,0 +1,419 @@
+/// Protein - Spectral fingerprint of an IR term
+/// Shape of the graph (Laplacian spectrum) + what it is made of (operators)
+///
+/// protein(ir) = L2([λ₁..λ₁₆ of L = D - A] ⊕ [histogram of 16 op classes])
+
+use crate::ir::{IR, Arena};
+use crate::eval::children;
+use alloc::vec::Vec;
+use alloc::vec;
+
+/// Embedding length
+pub const K: usize = 32;
+
+/// Spectral half / histogram half of the embedding
+const SPECTRAL: usize = 16;
+const HISTOGRAM: usize = K - SPECTRAL;
+
+/// Graphs larger than this are fingerprinted by their first nodes (pre-order)
+pub const MAX_NODES: usize = 96;
+
+/// Jacobi sweeps before giving up on convergence
+const MAX_SWEEPS: usize = 64;
+
+/// Operator classes shared by every IR flavour (arena, surgeon, JSON canon)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpClass {
+    Var = 0,
+    Lam,
+    App,
+    Literal,
+    Arith,
+    Compare,
+    Logic,
+    Branch,
+    Bind,
+    Map,
+    Filter,
+    Fold,
+    Focus,
+    Compose,
+    Identity,
+    Other,
+}
+
+impl OpClass {
+    pub fn of(ir: &IR) -> Self {
+        match ir {
+            IR::Var(_) => OpClass::Var,
+            IR::Lam(_, _) => OpClass::Lam,
+            IR::App(_, _) => OpClass::App,
+            IR::Num(_) | IR::Bool(_) | IR::Nil => OpClass::Literal,
+            IR::Add(_, _) | IR::Sub(_, _) | IR::Mul(_, _) | IR::Div(_, _) => OpClass::Arith,
+            IR::Eq(_, _) | IR::Lt(_, _) | IR::Gt(_, _) => OpClass::Compare,
+            IR::And(_, _) | IR::Or(_, _) | IR::Not(_) => OpClass::Logic,
+            IR::If(_, _, _) => OpClass::Branch,
+            IR::Let(_, _, _) | IR::Ref(_) => OpClass::Bind,
+            IR::Map(_, _) => OpClass::Map,
+            IR::Filter(_, _) => OpClass::Filter,
+            IR::Focus(_) => OpClass::Focus,
+            IR::Compose(_, _) => OpClass::Compose,
+            IR::Identity | IR::Drop => OpClass::Identity,
+            IR::Observe(_) => OpClass::Other,
+        }
+    }
+
+    /// Classify an operator by its textual head (s-expressions, JSON canon)
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "λ" | "lam" | "lambda" | "fn" => OpClass::Lam,
+            "app" | "apply" | "call" => OpClass::App,
+            "nil" | "true" | "false" => OpClass::Literal,
+            "+" | "-" | "*" | "/" | "%" | "add" | "sub" | "mul" | "div" => OpClass::Arith,
+            "=" | "==" | "<" | ">" | "<=" | ">=" | "eq" | "lt" | "gt" => OpClass::Compare,
+            "and" | "or" | "not" | "&&" | "||" | "!" => OpClass::Logic,
+            "if" | "case" | "cond" => OpClass::Branch,
+            "let" | "ref" => OpClass::Bind,
+            "map" => OpClass::Map,
+            "filter" => OpClass::Filter,
+            "reduce" | "fold" | "foldl" | "foldr" => OpClass::Fold,
+            "focus" | "FOCUS" => OpClass::Focus,
+            "∘" | "compose" | "|>" | "pipe" => OpClass::Compose,
+            "id" | "identity" | "const" | "drop" => OpClass::Identity,
+            _ if name.parse::<f64>().is_ok() || name.starts_with('"') => OpClass::Literal,
+            _ if name.chars().all(|c| c.is_alphanumeric() || c == '_') => OpClass::Var,
+            _ => OpClass::Other,
+        }
+    }
+}
+
+/// Labelled undirected graph of an IR term
+#[derive(Debug, Clone, Default)]
+pub struct IrGraph {
+    pub labels: Vec<OpClass>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl IrGraph {
+    pub fn new() -> Self {
+        IrGraph::default()
+    }
+
+    /// Add a node, returns its index (None once MAX_NODES is reached)
+    pub fn add_node(&mut self, label: OpClass) -> Option<usize> {
+        if self.labels.len() >= MAX_NODES {
+            return None;
+        }
+        self.labels.push(label);
+        Some(self.labels.len() - 1)
+    }
+
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        self.edges.push((a, b));
+    }
+
+    /// Tree of an arena term (shared sub-terms are unfolded)
+    pub fn from_ir(ir: &IR, arena: &Arena) -> Self {
+        let mut graph = IrGraph::new();
+        graph.visit(ir, arena, None);
+        graph
+    }
+
+    fn visit(&mut self, ir: &IR, arena: &Arena, parent: Option<usize>) {
+        let node = match self.add_node(OpClass::of(ir)) {
+            Some(node) => node,
+            None => return,
+        };
+        if let Some(parent) = parent {
+            self.add_edge(parent, node);
+        }
+        for child in children(ir) {
+            self.visit(&arena.get(child), arena, Some(node));
+        }
+    }
+
+    /// Tree of a canonical s-expression like `(map xs λx.(+ x 1))`
+    pub fn from_sexpr(text: &str) -> Self {
+        let tokens = tokenize(text);
+        let mut graph = IrGraph::new();
+        let mut pos = 0;
+        while pos < tokens.len() {
+            graph.parse_term(&tokens, &mut pos, None);
+        }
+        graph
+    }
+
+    fn parse_term(&mut self, tokens: &[&str], pos: &mut usize, parent: Option<usize>) {
+        let token = tokens[*pos];
+        *pos += 1;
+
+        let link = |graph: &mut IrGraph, node: Option<usize>| {
+            if let (Some(p), Some(n)) = (parent, node) {
+                graph.add_edge(p, n);
+            }
+        };
+
+        match token {
+            "(" => {
+                let head = match tokens.get(*pos) {
+                    Some(&")") | None => OpClass::Literal,  // () is nil
+                    Some(head) if *head != "(" && !head.starts_with('λ') => {
+                        *pos += 1;
+                        OpClass::from_name(head)
+                    }
+                    Some(_) => OpClass::App,  // ((f x) y)
+                };
+                let node = self.add_node(head);
+                link(self, node);
+                while *pos < tokens.len() && tokens[*pos] != ")" {
+                    self.parse_term(tokens, pos, node.or(parent));
+                }
+                *pos += 1;  // ")"
+            }
+            ")" => {}  // Unbalanced - ignore
+            token if token.starts_with('λ') => {
+                // λx.body - the binder is part of the node, like IR::Lam
+                let node = self.add_node(OpClass::Lam);
+                link(self, node);
+                if *pos < tokens.len() && tokens[*pos] != ")" {
+                    self.parse_term(tokens, pos, node.or(parent));
+                }
+            }
+            atom => {
+                let node = self.add_node(OpClass::from_name(atom));
+                link(self, node);
+            }
+        }
+    }
+}
+
+/// Split on whitespace and parens; `λx.` stays glued to its binder
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        let boundary = c.is_whitespace() || c == '(' || c == ')';
+        if boundary {
+            if let Some(s) = start.take() {
+                tokens.push(&text[s..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&text[i..i + 1]);
+            }
+        } else {
+            if start.is_none() {
+                start = Some(i);
+            }
+            // λx.body → "λx." then body
+            if c == '.' && text[start.unwrap()..].starts_with('λ') {
+                tokens.push(&text[start.unwrap()..i + 1]);
+                start = None;
+            }
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&text[s..]);
+    }
+
+    tokens
+}
+
+/// Fixed-length, L2-normalized fingerprint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProteinVector(pub [f32; K]);
+
+impl ProteinVector {
+    pub fn from_ir(ir: &IR, arena: &Arena) -> Self {
+        Self::from_graph(&IrGraph::from_ir(ir, arena))
+    }
+
+    pub fn from_sexpr(text: &str) -> Self {
+        Self::from_graph(&IrGraph::from_sexpr(text))
+    }
+
+    /// Each half is normalized on its own so shape and content weigh the same
+    pub fn from_graph(graph: &IrGraph) -> Self {
+        let mut v = [0.0f32; K];
+        if graph.labels.is_empty() {
+            return ProteinVector(v);
+        }
+
+        // Largest Laplacian eigenvalues, descending
+        let spectrum = laplacian_spectrum(graph);
+        for (slot, value) in v[..SPECTRAL].iter_mut().zip(spectrum.iter()) {
+            *slot = *value as f32;
+        }
+
+        // Operator histogram
+        for label in &graph.labels {
+            v[SPECTRAL + *label as usize] += 1.0;
+        }
+
+        normalize(&mut v[..SPECTRAL]);
+        normalize(&mut v[SPECTRAL..SPECTRAL + HISTOGRAM]);
+        normalize(&mut v);
+        ProteinVector(v)
+    }
+
+    pub fn norm(&self) -> f32 {
+        libm::sqrtf(self.0.iter().map(|x| x * x).sum())
+    }
+
+    /// cos(θ) between two proteins; 0 when either is empty
+    pub fn cosine(&self, other: &ProteinVector) -> f32 {
+        let dot: f32 = self.0.iter().zip(other.0.iter()).map(|(a, b)| a * b).sum();
+        let norms = self.norm() * other.norm();
+        if norms > 0.0 {
+            (dot / norms).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Little-endian f32 bytes (the `protein.vec` format)
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+}
+
+fn normalize(v: &mut [f32]) {
+    let norm = libm::sqrtf(v.iter().map(|x| x * x).sum());
+    if norm > 0.0 {
+        for x in v.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+/// Eigenvalues of L = D - A, sorted descending (cyclic Jacobi, fixed order)
+pub fn laplacian_spectrum(graph: &IrGraph) -> Vec<f64> {
+    let n = graph.labels.len();
+    let mut m = vec![0.0f64; n * n];
+
+    for &(a, b) in &graph.edges {
+        if a == b || a >= n || b >= n {
+            continue;
+        }
+        m[a * n + b] -= 1.0;
+        m[b * n + a] -= 1.0;
+        m[a * n + a] += 1.0;
+        m[b * n + b] += 1.0;
+    }
+
+    jacobi_eigenvalues(&mut m, n);
+
+    let mut eigenvalues: Vec<f64> = (0..n).map(|i| m[i * n + i]).collect();
+    eigenvalues.sort_by(|a, b| b.partial_cmp(a).unwrap_or(core::cmp::Ordering::Equal));
+    eigenvalues
+}
+
+/// In-place symmetric Jacobi rotation; the diagonal ends up holding the eigenvalues
+fn jacobi_eigenvalues(m: &mut [f64], n: usize) {
+    for _ in 0..MAX_SWEEPS {
+        let off: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| m[i * n + j] * m[i * n + j])
+            .sum();
+        if off < 1e-18 {
+            return;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                let apq = m[p * n + q];
+                if libm::fabs(apq) < 1e-12 {
+                    continue;
+                }
+                let app = m[p * n + p];
+                let aqq = m[q * n + q];
+                let theta = (aqq - app) / (2.0 * apq);
+                let t = libm::copysign(1.0, theta) / (libm::fabs(theta) + libm::sqrt(theta * theta + 1.0));
+                let c = 1.0 / libm::sqrt(t * t + 1.0);
+                let s = t * c;
+
+                for k in 0..n {
+                    let akp = m[k * n + p];
+                    let akq = m[k * n + q];
+                    m[k * n + p] = c * akp - s * akq;
+                    m[k * n + q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let apk = m[p * n + k];
+                    let aqk = m[q * n + k];
+                    m[p * n + k] = c * apk - s * aqk;
+                    m[q * n + k] = s * apk + c * aqk;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Symbol;
+
+    fn map_inc(arena: &mut Arena, var: u32) -> IR {
+        let x = Symbol(var);
+        let var_x = arena.alloc(IR::Var(x));
+        let one = arena.alloc(IR::Num(1));
+        let add = arena.alloc(IR::Add(var_x, one));
+        let f = arena.alloc(IR::Lam(x, add));
+        let xs = arena.alloc(IR::Var(Symbol(var + 1)));
+        IR::Map(f, xs)
+    }
+
+    #[test]
+    fn test_normalized_and_deterministic() {
+        let mut arena = Arena::new();
+        let ir = map_inc(&mut arena, 1);
+
+        let p1 = ProteinVector::from_ir(&ir, &arena);
+        let p2 = ProteinVector::from_ir(&ir, &arena);
+        assert_eq!(p1, p2);
+        assert!((p1.norm() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_similarity() {
+        let mut arena = Arena::new();
+        let a = map_inc(&mut arena, 1);
+        let b = map_inc(&mut arena, 7);  // Alpha-variant
+        let xs = arena.alloc(IR::Var(Symbol(9)));
+        let id = arena.alloc(IR::Identity);
+        let c = IR::Filter(id, xs);
+
+        let pa = ProteinVector::from_ir(&a, &arena);
+        let pb = ProteinVector::from_ir(&b, &arena);
+        let pc = ProteinVector::from_ir(&c, &arena);
+        assert!((pa.cosine(&pb) - 1.0).abs() < 1e-5);
+        assert!(pa.cosine(&pc) < 0.9);
+    }
+
+    #[test]
+    fn test_sexpr_matches_arena() {
+        let mut arena = Arena::new();
+        let ir = map_inc(&mut arena, 1);
+
+        let from_arena = ProteinVector::from_ir(&ir, &arena);
+        let from_text = ProteinVector::from_sexpr("(map λx.(+ x 1) xs)");
+        assert!((from_arena.cosine(&from_text) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_path_spectrum() {
+        // P3: eigenvalues 3, 1, 0
+        let mut graph = IrGraph::new();
+        for _ in 0..3 {
+            graph.add_node(OpClass::Var);
+        }
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+
+        let spectrum = laplacian_spectrum(&graph);
+        assert!((spectrum[0] - 3.0).abs() < 1e-9);
+        assert!((spectrum[1] - 1.0).abs() < 1e-9);
+        assert!(spectrum[2].abs() < 1e-9);
+    }
+}
@@ -6,96 +6,103 @@ use crate::normalize::normalize;
 const FNV_PRIME: u64 = 1099511628211;
 const FNV_OFFSET: u64 = 14695981039346656037;
 
+/// Soul of a term: hash of its normal form by content. Souls were once
+/// hashes of arena positions, and every soul recorded before changes - the
+/// same term built twice now gets one soul, as soul preservation needs.
 pub fn compute_soul(ir: &IR, arena: &mut Arena) -> u64 {
     let normalized = normalize(ir, arena);
-    hash_ir(&normalized)
+    hash_ir(&normalized, arena)
 }
 
-fn hash_ir(ir: &IR) -> u64 {
+/// Structural hash - children are hashed by content, not arena position,
+/// so equal terms built in different orders share a soul
+pub(crate) fn hash_ir(ir: &IR, arena: &Arena) -> u64 {
+    let h = |idx: u32| hash_ir(&arena.get(idx), arena);
+
     match ir {
         IR::Var(s) => hash_combine(1, s.0 as u64),
         IR::Lam(s, body_idx) => {
             let h1 = hash_combine(2, s.0 as u64);
-            hash_combine(h1, *body_idx as u64)
+            hash_combine(h1, h(*body_idx))
         }
         IR::App(f_idx, a_idx) => {
-            let h1 = hash_combine(3, *f_idx as u64);
-            hash_combine(h1, *a_idx as u64)
+            let h1 = hash_combine(3, h(*f_idx));
+            hash_combine(h1, h(*a_idx))
         }
         IR::Num(n) => hash_combine(4, *n as u64),
         IR::Bool(b) => hash_combine(5, if *b { 1 } else { 0 }),
         IR::Nil => 6,
         IR::If(c_idx, t_idx, f_idx) => {
-            let h1 = hash_combine(13, *c_idx as u64);
-            let h2 = hash_combine(h1, *t_idx as u64);
-            hash_combine(h2, *f_idx as u64)
+            let h1 = hash_combine(13, h(*c_idx));
+            let h2 = hash_combine(h1, h(*t_idx));
+            hash_combine(h2, h(*f_idx))
         }
         IR::Let(s, e_idx, b_idx) => {
             let h1 = hash_combine(14, s.0 as u64);
-            let h2 = hash_combine(h1, *e_idx as u64);
-            hash_combine(h2, *b_idx as u64)
+            let h2 = hash_combine(h1, h(*e_idx));
+            hash_combine(h2, h(*b_idx))
         }
         IR::Add(a_idx, b_idx) => {
-            let h1 = hash_combine(16, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(16, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Sub(a_idx, b_idx) => {
-            let h1 = hash_combine(17, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(17, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Mul(a_idx, b_idx) => {
-            let h1 = hash_combine(18, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(18, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Div(a_idx, b_idx) => {
-            let h1 = hash_combine(19, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(19, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Eq(a_idx, b_idx) => {
-            let h1 = hash_combine(20, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(20, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Lt(a_idx, b_idx) => {
-            let h1 = hash_combine(21, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(21, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Gt(a_idx, b_idx) => {
-            let h1 = hash_combine(22, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(22, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::And(a_idx, b_idx) => {
-            let h1 = hash_combine(23, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(23, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
         IR::Or(a_idx, b_idx) => {
-            let h1 = hash_combine(24, *a_idx as u64);
-            hash_combine(h1, *b_idx as u64)
+            let h1 = hash_combine(24, h(*a_idx));
+            hash_combine(h1, h(*b_idx))
         }
-        IR::Not(x_idx) => hash_combine(25, *x_idx as u64),
-        IR::Ref(idx) => hash_combine(26, *idx as u64),
+        IR::Not(x_idx) => hash_combine(25, h(*x_idx)),
+        IR::Ref(idx) => hash_combine(26, h(*idx)),
         IR::Focus(focus) => {
             let h1 = hash_combine(27, focus.mode as u64);
-            let h2 = hash_combine(h1, focus.xs as u64);
-            let h3 = hash_combine(h2, focus.w as u64);
-            let h4 = hash_combine(h3, focus.f as u64);
-            hash_combine(h4, focus.g as u64)
+            let h2 = hash_combine(h1, h(focus.xs));
+            let h3 = hash_combine(h2, h(focus.w));
+            let h4 = hash_combine(h3, h(focus.f));
+            hash_combine(h4, h(focus.g))
         }
         IR::Map(f_idx, xs_idx) => {
-            let h1 = hash_combine(28, *f_idx as u64);
-            hash_combine(h1, *xs_idx as u64)
+            let h1 = hash_combine(28, h(*f_idx));
+            hash_combine(h1, h(*xs_idx))
         }
         IR::Filter(p_idx, xs_idx) => {
-            let h1 = hash_combine(29, *p_idx as u64);
-            hash_combine(h1, *xs_idx as u64)
+            let h1 = hash_combine(29, h(*p_idx));
+            hash_combine(h1, h(*xs_idx))
         }
         IR::Compose(f_idx, g_idx) => {
-            let h1 = hash_combine(30, *f_idx as u64);
-            hash_combine(h1, *g_idx as u64)
+            let h1 = hash_combine(30, h(*f_idx));
+            hash_combine(h1, h(*g_idx))
         }
         IR::Drop => 31,
         IR::Identity => 32,
         IR::Observe(observe) => {
-            let h1 = hash_combine(33, observe.file as u64);
+            let h1 = hash_combine(33, h(observe.file));
             let h2 = hash_combine(h1, observe.theta as u64);
             let h3 = hash_combine(h2, observe.phase as u64);
             hash_combine(h3, observe.mapping as u64)
@@ -116,4 +123,22 @@ fn fnv_hash(data: &[u8]) -> u64 {
         hash = hash.wrapping_mul(FNV_PRIME);
     }
     hash
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soul_is_content() {
+        let mut arena = Arena::new();
+        let first = arena.alloc(IR::Num(1));
+        let two = arena.alloc(IR::Num(2));
+        let second = arena.alloc(IR::Num(1));
+        let a = IR::Add(first, two);
+        let b = IR::Add(second, two);
+        assert_eq!(compute_soul(&a, &mut arena), compute_soul(&b, &mut arena));
+        assert_eq!(compute_soul(&a, &mut arena), compute_soul(&a, &mut arena));
+        assert_ne!(compute_soul(&IR::Sub(first, two), &mut arena), compute_soul(&IR::Sub(two, first), &mut arena));
+    }
+}